@@ -57,6 +57,113 @@ struct Gate {
 #include "U32RangeCheckGate.cuh"
 #include "U32SubtractionGate.cuh"
 
+// Layout of the gate table built by `plonky2::backend::gates::GateTable`; the values below must
+// match the constants of that module.
+#define GATE_PARAMS_LEN 3
+#define GATE_TABLE_HEADER_LEN 3
+#define GATE_DESCRIPTOR_LEN (GATE_PARAMS_LEN + 5)
+#define MAX_GATE_CONSTRAINTS 256
+
+enum GateKind : uint32_t {
+    GATE_NOOP = 0,
+    GATE_CONSTANT = 1,
+    GATE_PUBLIC_INPUT = 2,
+    GATE_BASE_SUM = 3,
+    GATE_ARITHMETIC = 4,
+    GATE_COMPARISON = 5,
+    GATE_U32_ADD_MANY = 6,
+    GATE_U32_ARITHMETIC = 7,
+    GATE_U32_RANGE_CHECK = 8,
+    GATE_U32_SUBTRACTION = 9,
+    GATE_RANDOM_ACCESS = 10,
+    GATE_POSEIDON = 11,
+};
+
+struct GateDescriptor {
+    uint32_t kind;
+    uint32_t params[GATE_PARAMS_LEN];
+    uint32_t selector_index;
+    uint32_t group_start;
+    uint32_t group_end;
+    uint32_t constraint_offset;
+    uint32_t num_constraints;
+
+    __device__ inline
+    usize param(int i) const {
+        return params[i];
+    }
+
+    __device__ inline
+    void eval_unfiltered_base_packed(
+            EvaluationVarsBasePacked vars,
+            StridedConstraintConsumer yield_constr) const {
+        switch (kind) {
+            case GATE_NOOP:
+                NoopGate{}.eval_unfiltered_base_packed(vars, yield_constr);
+                break;
+            case GATE_CONSTANT:
+                ConstantGate{.num_consts = param(0)}.eval_unfiltered_base_packed(vars, yield_constr);
+                break;
+            case GATE_PUBLIC_INPUT:
+                PublicInputGate{}.eval_unfiltered_base_packed(vars, yield_constr);
+                break;
+            case GATE_BASE_SUM:
+                if (param(0) == 2)
+                    BaseSumGate<2>{.num_limbs = param(1)}.eval_unfiltered_base_packed(vars, yield_constr);
+                else
+                    BaseSumGate<4>{.num_limbs = param(1)}.eval_unfiltered_base_packed(vars, yield_constr);
+                break;
+            case GATE_ARITHMETIC:
+                ArithmeticGate{.num_ops = (int)param(0)}.eval_unfiltered_base_packed(vars, yield_constr);
+                break;
+            case GATE_COMPARISON:
+                ComparisonGate{.num_bits = param(0), .num_chunks = param(1)}
+                        .eval_unfiltered_base_packed(vars, yield_constr);
+                break;
+            case GATE_U32_ADD_MANY:
+                U32AddManyGate{.num_addends = param(0), .num_ops = param(1)}
+                        .eval_unfiltered_base_packed(vars, yield_constr);
+                break;
+            case GATE_U32_ARITHMETIC:
+                U32ArithmeticGate{.num_ops = param(0)}.eval_unfiltered_base_packed(vars, yield_constr);
+                break;
+            case GATE_U32_RANGE_CHECK:
+                U32RangeCheckGate{.num_input_limbs = param(0)}.eval_unfiltered_base_packed(vars, yield_constr);
+                break;
+            case GATE_U32_SUBTRACTION:
+                U32SubtractionGate{.num_ops = param(0)}.eval_unfiltered_base_packed(vars, yield_constr);
+                break;
+            case GATE_RANDOM_ACCESS:
+                RandomAccessGate{.bits = param(0), .num_copies = param(1), .num_extra_constants = param(2)}
+                        .eval_unfiltered_base_packed(vars, yield_constr);
+                break;
+            case GATE_POSEIDON:
+                PoseidonGate{}.eval_unfiltered_base_packed(vars, yield_constr);
+                break;
+            default:
+                assert(false);
+        }
+    }
+};
+static_assert(sizeof(GateDescriptor) == GATE_DESCRIPTOR_LEN * sizeof(uint32_t), "GateDescriptor layout");
+
+struct GateTable {
+    uint32_t num_gates;
+    uint32_t num_selectors;
+    uint32_t num_gate_constraints;
+    const GateDescriptor* descriptors;
+
+    __device__ inline
+    static GateTable from_words(const uint32_t* words) {
+        return GateTable{
+                .num_gates = words[0],
+                .num_selectors = words[1],
+                .num_gate_constraints = words[2],
+                .descriptors = (const GateDescriptor*)(words + GATE_TABLE_HEADER_LEN),
+        };
+    }
+};
+
 
 
 #endif
@@ -186,6 +186,7 @@ extern "C" {
                                                                                           num_digests);
         cudaStreamSynchronize(stream);
         printf("reduce_digests_kernel elapsed: %.2lf\n", (double) (clock() - start) / CLOCKS_PER_SEC * 1000);
+        return RustError{cudaSuccess};
     }
 
 
@@ -606,6 +607,156 @@ extern "C" {
     }
 
 
+    // Computes the LDE of `poly_num` polynomials whose coefficients are at `d_coeffs`, in
+    // `d_lde`, and writes it bit-reversed into the columns starting at `first_column` of the
+    // row-major leaves at `d_leaves`. Used to build a commitment a chunk of polynomials at a time,
+    // when the LDE of the whole batch does not fit in device memory.
+    RustError lde_columns(
+            GoldilocksField* d_coeffs,
+            GoldilocksField* d_lde,
+            int poly_num, int values_num_per_poly, int log_len,
+            const GoldilocksField* d_root_table2, const GoldilocksField* d_shift_powers,
+            int rate_bits,
+            GoldilocksField* d_leaves,
+            int leaf_len, int first_column,
+            CudaInvContext* ctx
+    ) {
+        auto stream = ctx->stream;
+        int values_num_per_extpoly = values_num_per_poly*(1<<rate_bits);
+
+        int thcnt = values_num_per_poly*poly_num;
+        int nthreads = 32;
+        lde_kernel<<<(thcnt+nthreads-1)/nthreads, nthreads, 0, stream>>>(d_coeffs, d_lde, poly_num, values_num_per_poly, rate_bits);
+        init_lde_kernel<<<(thcnt+nthreads-1)/nthreads, nthreads, 0, stream>>>(d_lde, poly_num, values_num_per_poly, rate_bits);
+        mul_shift_kernel<<<(thcnt+nthreads-1)/nthreads, nthreads, 0, stream>>>(d_lde, poly_num, values_num_per_poly, rate_bits, d_shift_powers);
+        fft_kernel<<<poly_num, 32*8, 0, stream>>>(d_lde, poly_num, values_num_per_extpoly, log_len+rate_bits, d_root_table2, rate_bits);
+
+        thcnt = values_num_per_extpoly*poly_num;
+        reverse_index_bits_kernel<<<(thcnt+nthreads-1)/nthreads, nthreads, 0, stream>>>(d_lde, poly_num, values_num_per_extpoly, log_len+rate_bits);
+        transpose_columns_kernel<<<(thcnt+nthreads-1)/nthreads, nthreads, 0, stream>>>(d_lde, d_leaves, poly_num, values_num_per_extpoly, leaf_len, first_column);
+        cudaStreamSynchronize(stream);
+
+        return RustError{cudaGetLastError()};
+    }
+
+    // Writes the poly-major `d_values` into the columns starting at `first_column` of the
+    // row-major leaves at `d_leaves`, as is. Used for the salt of chunked commitments.
+    RustError write_columns(
+            const GoldilocksField* d_values,
+            int poly_num, int values_num_per_poly,
+            GoldilocksField* d_leaves,
+            int leaf_len, int first_column,
+            CudaInvContext* ctx
+    ) {
+        auto stream = ctx->stream;
+
+        int thcnt = values_num_per_poly*poly_num;
+        int nthreads = 32;
+        transpose_columns_kernel<<<(thcnt+nthreads-1)/nthreads, nthreads, 0, stream>>>(d_values, d_leaves, poly_num, values_num_per_poly, leaf_len, first_column);
+        cudaStreamSynchronize(stream);
+
+        return RustError{cudaGetLastError()};
+    }
+
+    // Builds the digests and cap of the tree whose leaves are the `leaves_len` rows of `leaf_len`
+    // elements at `d_leaves`, and writes them at `d_digests`.
+    RustError hash_leaves(
+            GoldilocksField* d_leaves,
+            int leaf_len, int leaves_len,
+            int cap_height,
+            GoldilocksField* d_digests,
+            CudaInvContext* ctx
+    ) {
+        auto stream = ctx->stream;
+        int len_cap = 1 << cap_height;
+        int num_digests = 2 * (leaves_len - len_cap);
+        auto *d_digest_buf = (PoseidonHasher::HashOut*)d_digests;
+
+        int thcnt = leaves_len;
+        int nthreads = 32;
+        hash_rows_kernel<<<(thcnt+nthreads-1)/nthreads, nthreads, 0, stream>>>(d_leaves, leaf_len, leaves_len, d_digest_buf, len_cap, num_digests);
+
+        nthreads = 32*8;
+        thcnt = len_cap * nthreads;
+        reduce_digests_kernel<<<(thcnt+nthreads-1)/nthreads, nthreads, 0, stream>>>(leaves_len, d_digest_buf, len_cap, num_digests);
+        cudaStreamSynchronize(stream);
+
+        return RustError{cudaGetLastError()};
+    }
+
+    // The FRI entry points below work on elements of the quadratic extension, stored as pairs of
+    // u64s like `QuadraticExtension<GoldilocksField>`. Challenges are read from host memory.
+
+    // Folds the `out_len * arity` coefficients at `d_coeffs` with `beta` into `d_out`.
+    RustError fri_fold(
+            const GoldilocksField* d_coeffs,
+            GoldilocksField* d_out,
+            int out_len, int arity,
+            const GoldilocksField* beta,
+            CudaInvContext* ctx
+    ) {
+        auto stream = ctx->stream;
+        GoldilocksExt2 beta_ext = {beta[0], beta[1]};
+
+        int thcnt = out_len;
+        int nthreads = 32;
+        fri_fold_kernel<<<(thcnt+nthreads-1)/nthreads, nthreads, 0, stream>>>(
+                (const GoldilocksExt2*)d_coeffs, (GoldilocksExt2*)d_out, out_len, arity, beta_ext);
+        cudaStreamSynchronize(stream);
+
+        return RustError{cudaGetLastError()};
+    }
+
+    // Adds `alpha_start * sum_j alpha^j * polys[j]` to the `values_num_per_poly` coefficients at
+    // `d_acc`, for the poly-major polynomials at `d_polys`. A batch too large for the device is
+    // reduced a chunk of polynomials at a time, with `alpha_start` the power of `alpha` of the
+    // first polynomial of the chunk.
+    RustError reduce_polys(
+            const GoldilocksField* d_polys,
+            int poly_num, int values_num_per_poly,
+            const GoldilocksField* alpha,
+            const GoldilocksField* alpha_start,
+            GoldilocksField* d_acc,
+            CudaInvContext* ctx
+    ) {
+        auto stream = ctx->stream;
+        GoldilocksExt2 alpha_ext = {alpha[0], alpha[1]};
+        GoldilocksExt2 alpha_start_ext = {alpha_start[0], alpha_start[1]};
+
+        int thcnt = values_num_per_poly;
+        int nthreads = 32;
+        reduce_polys_kernel<<<(thcnt+nthreads-1)/nthreads, nthreads, 0, stream>>>(
+                d_polys, poly_num, values_num_per_poly, alpha_ext, alpha_start_ext, (GoldilocksExt2*)d_acc);
+        cudaStreamSynchronize(stream);
+
+        return RustError{cudaGetLastError()};
+    }
+
+    // Evaluates the `values_num_per_poly` coefficients at `d_values` on the coset shifted by
+    // `shift`, in place and in natural order. `d_scratch` holds `2 * values_num_per_poly`
+    // elements. The roots of unity of smaller subgroups are a prefix of `d_root_table2`.
+    RustError coset_fft_ext2(
+            GoldilocksField* d_values,
+            GoldilocksField* d_scratch,
+            int values_num_per_poly, int log_len,
+            const GoldilocksField* d_root_table2,
+            const GoldilocksField* shift,
+            CudaInvContext* ctx
+    ) {
+        auto stream = ctx->stream;
+
+        int thcnt = values_num_per_poly;
+        int nthreads = 32;
+        ext2_to_columns_kernel<<<(thcnt+nthreads-1)/nthreads, nthreads, 0, stream>>>(
+                (const GoldilocksExt2*)d_values, d_scratch, values_num_per_poly, *shift);
+        fft_kernel<<<2, 32*8, 0, stream>>>(d_scratch, 2, values_num_per_poly, log_len, d_root_table2, 0);
+        columns_to_ext2_kernel<<<(thcnt+nthreads-1)/nthreads, nthreads, 0, stream>>>(
+                d_scratch, (GoldilocksExt2*)d_values, values_num_per_poly);
+        cudaStreamSynchronize(stream);
+
+        return RustError{cudaGetLastError()};
+    }
+
     RustError compute_quotient_polys(
             GoldilocksField* d_ext_values_flatten,
             int poly_num, int values_num_per_poly, int log_len,
@@ -626,6 +777,12 @@ extern "C" {
             DataSlice<GoldilocksField>* betas,
             DataSlice<GoldilocksField>* gammas,
 
+            DataSlice<uint32_t>* gate_table,
+
+            int num_challenges, int num_constants, int num_routed_wires,
+            int quotient_degree_factor, int num_partial_products,
+            const GoldilocksField* public_inputs_hash_elements,
+
             CudaInvContext* ctx
     ) {
 
@@ -662,31 +819,30 @@ extern "C" {
 //        size_t total_dev_use = start_p-(uint8_t*)d_ext_values_flatten;
 //        printf("total_dev_use: %fG\n", (double )total_dev_use/1024/1024/1024);
 
-        int num_challenges = 2;
-        int num_gate_constraints = 231;
-        int num_constants = 8;
-        int num_routed_wires = 80;
-        int quotient_degree_factor = 8;
-        int num_partial_products = 9;
-        int constants_sigmas_commitment_leaf_len = 88;
-        int zs_partial_products_commitment_leaf_len = 20;
-        int wires_commitment_leaf_len = 234;
-
-//    printf("%d, %d\n", constants_sigmas_commitment_leaves->len, values_num_per_extpoly*constants_sigmas_commitment_leaf_len);
-        assert(constants_sigmas_commitment_leaves->len    == values_num_per_extpoly*constants_sigmas_commitment_leaf_len);
-        assert(zs_partial_products_commitment_leaves->len == values_num_per_extpoly*zs_partial_products_commitment_leaf_len);
-        assert(points->len == values_num_per_extpoly);
-        assert(alphas->len == num_challenges);
-        assert(betas->len == num_challenges);
-        assert(gammas->len == num_challenges);
+        int constants_sigmas_commitment_leaf_len = constants_sigmas_commitment_leaves->len / values_num_per_extpoly;
+        int zs_partial_products_commitment_leaf_len = zs_partial_products_commitment_leaves->len / values_num_per_extpoly;
+        int wires_commitment_leaf_len = ext_poly_num;
+
+        if (num_challenges > MAX_NUM_CHALLENGES
+            || constants_sigmas_commitment_leaves->len    != values_num_per_extpoly*constants_sigmas_commitment_leaf_len
+            || zs_partial_products_commitment_leaves->len != values_num_per_extpoly*zs_partial_products_commitment_leaf_len
+            || constants_sigmas_commitment_leaf_len < num_constants + num_routed_wires
+            || zs_partial_products_commitment_leaf_len < num_challenges * (1 + num_partial_products)
+            || points->len != values_num_per_extpoly
+            || k_is->len < num_routed_wires
+            || alphas->len != num_challenges
+            || betas->len != num_challenges
+            || gammas->len != num_challenges) {
+            return RustError{cudaErrorInvalidValue};
+        }
 
         start = clock();
         thcnt = 300000;
         nthreads = 32;
-        PoseidonHasher::HashOut public_inputs_hash = {
-                GoldilocksField{0x672c5e6c12ad3476}, GoldilocksField{0xca5c2e49acfad27e},
-                GoldilocksField{0x296be18388d15f70}, GoldilocksField{0x66b42e146a70d96d}
-        };
+        PoseidonHasher::HashOut public_inputs_hash;
+        for (int i = 0; i < 4; ++i) {
+            public_inputs_hash.elements[i] = public_inputs_hash_elements[i];
+        }
         compute_quotient_values_kernel<<<(thcnt+nthreads-1)/nthreads, nthreads, 0, stream>>>(
                 log_len, rate_bits,
                 points->ptr,
@@ -698,7 +854,7 @@ extern "C" {
                 d_ext_values_flatten,                wires_commitment_leaf_len,
                 num_constants, num_routed_wires,
                 num_challenges,
-                num_gate_constraints,
+                gate_table->ptr,
 
                 quotient_degree_factor,
                 num_partial_products,
@@ -713,6 +869,7 @@ extern "C" {
         );
         if (auto code = cudaGetLastError(); code != cudaSuccess) {
             printf("compute quotient error: %s\n", cudaGetErrorString(code));
+            return RustError{code};
         }
 
         cudaStreamSynchronize(stream);
@@ -743,7 +900,8 @@ extern "C" {
         printf("transpose_kernel elapsed: %.2lf\n", (double )(clock()-start) / CLOCKS_PER_SEC * 1000);
 
         start = clock();
-        GoldilocksField n_inv_ext = {.data = 0xfffff7ff00000801ULL};
+        // The inverse of `2^lg_n` is `p - (p - 1) / 2^lg_n`.
+        GoldilocksField n_inv_ext = {.data = 0xffffffff00000001ULL - (0xffffffff00000000ULL >> (log_len+rate_bits))};
         ifft_kernel<<<num_challenges, 32*8, 0, stream>>>(d_quotient_polys, num_challenges, values_num_per_extpoly, log_len+rate_bits, d_root_table2, n_inv_ext);
         cudaStreamSynchronize(stream);
         printf("ifft_kernel elapsed: %.2lf\n", (double )(clock()-start) / CLOCKS_PER_SEC * 1000);
@@ -779,7 +937,7 @@ extern "C" {
 //
 ////        printf("v1: %lx, v2: %lx\n", outs[2086137].data, outs[2086137 + values_num_per_extpoly].data);
 //        }
-        return RustError{cudaSuccess};
+        return RustError{cudaGetLastError()};
     }
 
 }
@@ -478,10 +478,68 @@ void transpose_kernel(GoldilocksField* src_values_flatten, GoldilocksField* dst_
     }
 }
 
+// Writes the poly-major `src_values_flatten` into the columns `first_column..first_column+poly_num`
+// of the row-major leaves `dst_leaves`, `leaf_len` elements each.
+__global__
+void transpose_columns_kernel(const GoldilocksField* src_values_flatten, GoldilocksField* dst_leaves, int poly_num, int values_num_per_poly,
+                              int leaf_len, int first_column)
+{
+    int thCnt = get_global_thcnt();
+    int gid = get_global_id();
+
+    for (int i = gid; i < poly_num*values_num_per_poly; i += thCnt) {
+        unsigned val_idx = i / poly_num;
+        unsigned poly_idx = i % poly_num;
+
+        dst_leaves[(uint64_t)val_idx * leaf_len + first_column + poly_idx] = src_values_flatten[(uint64_t)poly_idx * values_num_per_poly + val_idx];
+    }
+}
+
+// Same as hash_leaves_kernel, for row-major leaves.
+__global__
+void hash_rows_kernel(const GoldilocksField* leaves, int leaf_len, int leaves_len,
+                      PoseidonHasher::HashOut* digest_buf, int len_cap, int num_digests)
+{
+    int thCnt = get_global_thcnt();
+    int gid = get_global_id();
+
+    assert(num_digests % len_cap == 0);
+
+    const int cap_len = leaves_len/len_cap;
+    const int digest_len = num_digests/len_cap;
+
+    for (int i = gid; i < leaves_len; i += thCnt) {
+        GoldilocksField state[SPONGE_WIDTH] = {0};
+        const GoldilocksField* leaf = leaves + (uint64_t)i * leaf_len;
+
+        if (leaf_len <= 4) {
+            // Like `hash_or_noop`, leaves which fit in a hash are their own digest.
+            for (int k = 0; k < leaf_len; ++k)
+                state[k] = leaf[k];
+        } else {
+            for (int j = 0; j < leaf_len; j += SPONGE_RATE) {
+                for (int k = 0; k < SPONGE_RATE && (j+k)<leaf_len; ++k)
+                    state[k] = leaf[j+k];
+                PoseidonHasher::permute_poseidon(state);
+            }
+        }
+
+        const int ith_cap = i / cap_len;
+        const int idx = i % cap_len;
+        int d_idx = find_digest_index(0, idx, cap_len, digest_len);
+
+        assert((d_idx < digest_len));
+        digest_buf[d_idx + ith_cap*digest_len] = *(PoseidonHasher::HashOut*)state;
+    }
+}
+
 
 #include "gates-def.cuh"
 
 
+// Size of the accumulators of the quotient kernel, i.e. the largest supported `num_challenges`.
+#define MAX_NUM_CHALLENGES 4
+
 __global__
 void compute_quotient_values_kernel(
         int degree_log, int rate_bits, GoldilocksField* points, GoldilocksField* outs,
@@ -491,11 +549,11 @@ void compute_quotient_values_kernel(
         GoldilocksField* constants_sigmas_commitment_leaves,     int constants_sigmas_commitment_leaf_len,
         GoldilocksField* zs_partial_products_commitment_leaves,  int zs_partial_products_commitment_leaf_len,
         GoldilocksField* wires_commitment_leaves,                int wires_commitment_leaf_len,
-        int num_constants, int _num_routed_wires,
-        int _num_challenges,
-        int _num_gate_constraints,
+        int num_constants, int num_routed_wires,
+        int num_challenges,
+        const uint32_t* gate_table_words,
 
-        int _quotient_degree_factor,
+        int quotient_degree_factor,
         int num_partial_products,
 
         GoldilocksField* z_h_on_coset_evals,
@@ -508,10 +566,10 @@ void compute_quotient_values_kernel(
 
 )
 {
-    constexpr int num_challenges = 2;
-    constexpr int num_gate_constraints = 231;
-    assert(num_gate_constraints == _num_gate_constraints);
-    assert(num_challenges == _num_challenges);
+    assert(num_challenges <= MAX_NUM_CHALLENGES);
+    const GateTable gate_table = GateTable::from_words(gate_table_words);
+    const int num_gate_constraints = gate_table.num_gate_constraints;
+    assert(num_gate_constraints <= MAX_GATE_CONSTRAINTS);
 
     int thCnt = get_global_thcnt();
     int gid = get_global_id();
@@ -528,10 +586,12 @@ void compute_quotient_values_kernel(
 //    int values_num_per_extpoly = 1;
     int lde_size  = values_num_per_extpoly;
 
-    constexpr int quotient_degree_factor = 8;
-    constexpr int num_routed_wires = 80;
-    constexpr int max_degree = quotient_degree_factor;
-    int num_prods = num_partial_products;
+    const int max_degree = quotient_degree_factor;
+    const int num_prods = num_partial_products;
+    // The last chunk of the partial products may be shorter than `max_degree`.
+    const int partial_product_rounds = num_prods + 1;
+    assert((partial_product_rounds-1) * max_degree < num_routed_wires);
+    assert(num_routed_wires <= partial_product_rounds * max_degree);
 
 //    if (gid == 0) {
 //        GoldilocksFieldView{alphas, num_challenges}.print_hex("alphas");
@@ -577,11 +637,9 @@ void compute_quotient_values_kernel(
 //        let constraint_terms_batch =
 //        evaluate_gate_constraints_base_batch::<F, C, D>(common_data, vars_batch);
 
-        assert(num_routed_wires % max_degree == 0);
-
 //        let constraint_terms = PackedStridedView::new(&constraint_terms_batch, n, k);
 
-        GoldilocksField res[num_challenges] = {0};
+        GoldilocksField res[MAX_NUM_CHALLENGES] = {0};
 
         auto reduce_with_powers = [&res, &alphas, num_challenges](GoldilocksField term) {
             for (int i = 0; i < num_challenges; ++i) {
@@ -589,171 +647,57 @@ void compute_quotient_values_kernel(
             }
         };
 
-        GoldilocksField constraint_terms_batch[num_gate_constraints] = {0};
+        GoldilocksField constraint_terms_batch[MAX_GATE_CONSTRAINTS] = {0};
         auto evaluate_gate_constraints_base_batch = [&]()
         {
-            struct SelectorsInfo {
-                int *selector_indices;
-                Range<int>* groups;
-            };
+            auto compute_filter = [](int row, Range<int> group_range, GoldilocksField s,
+                                     bool many_selector) -> GoldilocksField {
+                assert(group_range.contains(row));
+                GoldilocksField res = {1};
+                for (int i = group_range.first; i < group_range.second; ++i) {
+                    if (i == row)
+                        continue;
+                    res *= GoldilocksField::from_canonical_u64(i) - s;
+                }
 
-            int selector_indices[25] = {
-                    0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 4, 4, 4, 5
-            };
+                const uint32_t UNUSED_SELECTOR = UINT32_MAX;
 
-            constexpr  int num_selectors = 6;
-            Range<int> groups[num_selectors] = {
-                    Range<int>{0,6},
-                    Range<int>{6,11},
-                    Range<int>{11,16},
-                    Range<int>{16,21},
-                    Range<int>{21,24},
-                    Range<int>{24,25}
-            };
-            SelectorsInfo selectors_info = {
-                    .selector_indices = selector_indices,
-                    .groups = groups
+                if (many_selector) {
+                    res *= GoldilocksField::from_canonical_u64(UNUSED_SELECTOR) - s;
+                }
+                return res;
             };
 
-            struct BaseGate {};
-            using FUNC = void (BaseGate::*)(EvaluationVarsBasePacked, StridedConstraintConsumer yield_constr);
-
-            struct GateFUNC {
-                BaseGate* gate;
-                FUNC func;
-                int num_constraints;
-            };
-            constexpr const int num_gates = 25;
-            GateFUNC gate_objs[num_gates];
-
-#define DECL_GATE_NAME(TYPE, NAME, INDEX) \
-        gate_objs[INDEX] = GateFUNC{.gate = (BaseGate*)&NAME, .func = (FUNC)&TYPE::eval_unfiltered_base_packed, .num_constraints = NAME.num_constraints()};
-
-            NoopGate NoopGate_ins;
-            DECL_GATE_NAME(NoopGate,NoopGate_ins, 0);
-            ConstantGate ConstantGate_ins{ .num_consts = 2 };
-            DECL_GATE_NAME(ConstantGate, ConstantGate_ins, 1);
-
-            PublicInputGate PublicInputGate_ins;
-            DECL_GATE_NAME(PublicInputGate,PublicInputGate_ins, 2);
-
-            BaseSumGate<2> BaseSumGate_ins{ .num_limbs = 32 };
-            DECL_GATE_NAME(BaseSumGate<2>,BaseSumGate_ins, 3);
-            BaseSumGate<2> BaseSumGate_ins2{ .num_limbs = 63 };
-            DECL_GATE_NAME(BaseSumGate<2>,BaseSumGate_ins2, 4);
-            ArithmeticGate ArithmeticGate_ins{ .num_ops = 20 };
-            DECL_GATE_NAME(ArithmeticGate,ArithmeticGate_ins, 5);
-            BaseSumGate<4> BaseSumGate_ins3{ .num_limbs = 16 };
-            DECL_GATE_NAME(BaseSumGate<4>,BaseSumGate_ins3, 6);
-
-            ComparisonGate ComparisonGate_ins{ .num_bits = 32, .num_chunks = 16};
-            DECL_GATE_NAME(ComparisonGate,ComparisonGate_ins, 7);
-
-            U32AddManyGate U32AddManyGate_ins{ .num_addends = 0, .num_ops = 11};
-            DECL_GATE_NAME(U32AddManyGate,U32AddManyGate_ins, 8);
-            U32AddManyGate U32AddManyGate_ins2{ .num_addends = 11, .num_ops = 5};
-            DECL_GATE_NAME(U32AddManyGate,U32AddManyGate_ins2, 9);
-            U32AddManyGate U32AddManyGate_ins3{ .num_addends = 13, .num_ops = 5};
-            DECL_GATE_NAME(U32AddManyGate,U32AddManyGate_ins3, 10);
-            U32AddManyGate U32AddManyGate_ins4{ .num_addends = 15, .num_ops = 4};
-            DECL_GATE_NAME(U32AddManyGate,U32AddManyGate_ins4, 11);
-            U32AddManyGate U32AddManyGate_ins5{ .num_addends = 16, .num_ops = 4};
-            DECL_GATE_NAME(U32AddManyGate,U32AddManyGate_ins5, 12);
-            U32AddManyGate U32AddManyGate_ins6{ .num_addends = 2, .num_ops = 10};
-            DECL_GATE_NAME(U32AddManyGate,U32AddManyGate_ins6, 13);
-            U32AddManyGate U32AddManyGate_ins7{ .num_addends = 3, .num_ops = 9};
-            DECL_GATE_NAME(U32AddManyGate,U32AddManyGate_ins7, 14);
-            U32AddManyGate U32AddManyGate_ins8{ .num_addends = 5, .num_ops = 9};
-            DECL_GATE_NAME(U32AddManyGate,U32AddManyGate_ins8, 15);
-            U32AddManyGate U32AddManyGate_ins9{ .num_addends = 7, .num_ops = 8};
-            DECL_GATE_NAME(U32AddManyGate,U32AddManyGate_ins9, 16);
-            U32AddManyGate U32AddManyGate_ins10{ .num_addends = 9, .num_ops = 6};
-            DECL_GATE_NAME(U32AddManyGate,U32AddManyGate_ins10, 17);
-            U32ArithmeticGate U32ArithmeticGate_ins{ .num_ops = 6};
-            DECL_GATE_NAME(U32ArithmeticGate,U32ArithmeticGate_ins, 18);
-            U32RangeCheckGate U32RangeCheckGate_ins2{ .num_input_limbs = 0};
-            DECL_GATE_NAME(U32RangeCheckGate,U32RangeCheckGate_ins2, 19);
-            U32RangeCheckGate U32RangeCheckGate_ins3{ .num_input_limbs = 1};
-            DECL_GATE_NAME(U32RangeCheckGate,U32RangeCheckGate_ins3, 20);
-            U32RangeCheckGate U32RangeCheckGate_ins4{ .num_input_limbs = 8};
-            DECL_GATE_NAME(U32RangeCheckGate,U32RangeCheckGate_ins4, 21);
-            U32SubtractionGate U32SubtractionGate_ins{ .num_ops = 11};
-            DECL_GATE_NAME(U32SubtractionGate,U32SubtractionGate_ins, 22);
-            RandomAccessGate RandomAccessGate_ins{ .bits = 4, .num_copies = 4, .num_extra_constants = 2};
-            DECL_GATE_NAME(RandomAccessGate,RandomAccessGate_ins, 23);
-            PoseidonGate PoseidonGate_ins;
-            DECL_GATE_NAME(PoseidonGate,PoseidonGate_ins, 24);
-
-//            if (index == 1048576) {
-//                printf("i: %d, local_constants: ", index);
-//                local_constants.print_hex();
-//                printf("i: %d, local_wires: ", index);
-//                local_wires.print_hex();
-//            }
+            GoldilocksField terms[MAX_GATE_CONSTRAINTS];
+            for (int row = 0; row < gate_table.num_gates; ++row) {
+                const GateDescriptor& gate = gate_table.descriptors[row];
+                int selector_index = gate.selector_index;
+
+                auto filter = compute_filter(
+                        row,
+                        Range<int>{(int)gate.group_start, (int)gate.group_end},
+                        local_constants[selector_index],
+                        gate_table.num_selectors > 1
+                );
+
+                EvaluationVarsBasePacked vars = {
+                        .local_constants = local_constants.view(gate_table.num_selectors, local_constants.len),
+                        .local_wires = local_wires,
+                        .public_inputs_hash = public_inputs_hash,
+                        .index = index
+                };
+
+                for (int i = 0; i < gate.num_constraints; ++i) {
+                    terms[i] = GoldilocksField{0};
+                }
 
-            GoldilocksField terms[num_gate_constraints];
-            auto evaluate_gate_constraints_base_batch = [index, public_inputs_hash, &constraint_terms_batch, &terms, gate_objs, selectors_info, local_constants, local_wires]() {
-                for (int row = 0; row < num_gates; ++row) {
-                    int selector_index = selectors_info.selector_indices[row];
-                    auto gate = gate_objs[row];
-
-                    auto compute_filter = [](int row, Range<int> group_range, GoldilocksField s,
-                                             bool many_selector) -> GoldilocksField {
-                        assert(group_range.contains(row));
-                        GoldilocksField res = {1};
-                        for (int i = group_range.first; i < group_range.second; ++i) {
-                            if (i == row)
-                                continue;
-                            res *= GoldilocksField::from_canonical_u64(i) - s;
-                        }
-
-                        const uint32_t UNUSED_SELECTOR = UINT32_MAX;
-
-                        if (many_selector) {
-                            res *= GoldilocksField::from_canonical_u64(UNUSED_SELECTOR) - s;
-                        }
-                        return res;
-                    };
-
-                    auto filter = compute_filter(
-                            row,
-                            selectors_info.groups[selector_index],
-                            local_constants[selector_index],
-                            num_selectors > 1
-                    );
-
-                    EvaluationVarsBasePacked vars = {
-                            .local_constants = local_constants.view(num_selectors, local_constants.len),
-                            .local_wires = local_wires,
-                            .public_inputs_hash = public_inputs_hash,
-                            .index = index
-                    };
-
-//                    if (index == 1048576) {
-//                        printf("i: %d, row: %d, filter: ", index, row);
-//                        filter.print_hex(nullptr, GoldilocksField::newline);
-//                    }
-
-                    for (int i = 0; i < gate.num_constraints; ++i) {
-                        terms[i] = GoldilocksField{0};
-                    }
-
-                    auto fn = gate.func;
-                    auto yield_constr =  StridedConstraintConsumer{terms, &terms[gate.num_constraints]};
-                    ((gate.gate)->*fn)(vars, yield_constr);
-//                    if (index == 1048576) {
-//                        printf("i: %d, row: %d, terms: ", index, row);
-//                        GoldilocksFieldView{terms, gate.num_constraints}.print_hex();
-//                    }
-
-                    for (int i = 0; i < gate.num_constraints; ++i) {
-                        constraint_terms_batch[i] += terms[i] * filter;
-                    }
+                auto yield_constr =  StridedConstraintConsumer{terms, &terms[gate.num_constraints]};
+                gate.eval_unfiltered_base_packed(vars, yield_constr);
 
+                for (int i = 0; i < gate.num_constraints; ++i) {
+                    constraint_terms_batch[gate.constraint_offset + i] += terms[i] * filter;
                 }
-            };
-
-            evaluate_gate_constraints_base_batch();
+            }
         };
         evaluate_gate_constraints_base_batch();
 //        if (index == 1048576) {
@@ -764,75 +708,38 @@ void compute_quotient_values_kernel(
             reduce_with_powers(constraint_terms_batch[i]);
         }
 
-        constexpr int vanishing_partial_products_terms_len = num_challenges * num_routed_wires/max_degree;
-        GoldilocksField vanishing_partial_products_terms[vanishing_partial_products_terms_len];
-        for (int i = 0; i < num_challenges; ++i) {
+        // The terms of `check_partial_products` for all challenges, `i * partial_product_rounds + k`
+        // being the `k`-th chunk of the `i`-th challenge. They are reduced in reverse order, so
+        // each of them is computed from its accumulators rather than from the previous chunk.
+        const int vanishing_partial_products_terms_len = num_challenges * partial_product_rounds;
+        for (int t = vanishing_partial_products_terms_len-1; t >= 0; --t) {
+            int i = t / partial_product_rounds;
+            int k = t % partial_product_rounds;
             auto z_x = local_zs[i];
             auto z_gx = next_zs[i];
 
             // The partial products considered for this iteration of `i`.
             auto current_partial_products = partial_products.view(i * num_prods, (i + 1) * num_prods);
-            // Check the numerator partial products.
-//            let partial_product_checks = check_partial_products(
-//                    &numerator_values,
-//                    &denominator_values,
-//                    current_partial_products,
-////                    z_x,
-////                    z_gx,
-//                    max_degree,
-//            );
-
-            GoldilocksField prev_acc, next_acc;
-            constexpr int partial_product_rounds = num_routed_wires/max_degree;
-            assert(current_partial_products.len == partial_product_rounds-1);
-            for (int k = 0; k < partial_product_rounds; ++k) {
-                GoldilocksField num_chunk_product = GoldilocksField::from_canonical_u64(1);
-                for (int j = k*max_degree; j < (k+1)*max_degree; ++j) {
-                    auto wire_value = local_wires[j];
-                    auto k_i = k_is[j];
-                    auto s_id = k_i * x;
-                    auto v = wire_value + betas[i] * s_id + gammas[i];
-                    num_chunk_product *= v;
-//                    if (index == 1048576) {
-//                        printf("i: %d, wi: %d, ", index, j);
-//                        wire_value.print_hex("wire_value", GoldilocksField::colum_space);
-//                        k_i.print_hex("k_i", GoldilocksField::colum_space);
-//                        x.print_hex("x", GoldilocksField::newline);
-//                        v.print_hex("v", GoldilocksField::newline);
-//                    }
-                }
-                GoldilocksField den_chunk_product = GoldilocksField::from_canonical_u64(1);
-                for (int j = k*max_degree; j < (k+1)*max_degree; ++j) {
-                    auto wire_value = local_wires[j];
-                    auto s_sigma = s_sigmas[j];
-                    den_chunk_product *= wire_value + betas[i] * s_sigma + gammas[i];
-                }
-                if (k == 0) {
-                    prev_acc = z_x;
-                } else {
-                    prev_acc = next_acc;
-                }
-
-                if (k == partial_product_rounds-1)
-                    next_acc = z_gx;
-                else
-                    next_acc = current_partial_products[k];
-
-                vanishing_partial_products_terms[i * partial_product_rounds + k] = (prev_acc * num_chunk_product - next_acc * den_chunk_product);
-
-//                if (index == 1048576) {
-//                    printf("i: %d, partial_product_checks: ", index);
-//                    GoldilocksFieldView{vanishing_partial_products_terms, vanishing_partial_products_terms_len}.print_hex();
-//                }
-
+            assert(current_partial_products.len == num_prods);
+
+            int chunk_end = min((k+1)*max_degree, num_routed_wires);
+            GoldilocksField num_chunk_product = GoldilocksField::from_canonical_u64(1);
+            for (int j = k*max_degree; j < chunk_end; ++j) {
+                auto wire_value = local_wires[j];
+                auto k_i = k_is[j];
+                auto s_id = k_i * x;
+                num_chunk_product *= wire_value + betas[i] * s_id + gammas[i];
             }
-        }
-//        if (index == 1048576) {
-//            printf("i: %d, term: ", index);
-//            GoldilocksFieldView{vanishing_partial_products_terms, vanishing_partial_products_terms_len}.print_hex();
-//        }
-        for (int i = vanishing_partial_products_terms_len-1; i >= 0; --i) {
-            reduce_with_powers(vanishing_partial_products_terms[i]);
+            GoldilocksField den_chunk_product = GoldilocksField::from_canonical_u64(1);
+            for (int j = k*max_degree; j < chunk_end; ++j) {
+                auto wire_value = local_wires[j];
+                auto s_sigma = s_sigmas[j];
+                den_chunk_product *= wire_value + betas[i] * s_sigma + gammas[i];
+            }
+
+            auto prev_acc = k == 0 ? z_x : current_partial_products[k-1];
+            auto next_acc = k == partial_product_rounds-1 ? z_gx : current_partial_products[k];
+            reduce_with_powers(prev_acc * num_chunk_product - next_acc * den_chunk_product);
         }
 
         auto eval_l_0 = [z_h_on_coset_evals, rate_bits, degree_log](int index, GoldilocksField x) -> GoldilocksField {
@@ -871,8 +778,9 @@ void compute_quotient_values_kernel(
 //            GoldilocksFieldView{res, num_challenges}.print_hex();
 //        }
 
-        outs[index*2]   = res[0];
-        outs[index*2+1] = res[1];
+        for (int i = 0; i < num_challenges; ++i) {
+            outs[index*num_challenges + i] = res[i];
+        }
     }
 
 }
@@ -897,4 +805,87 @@ void mul_kernel(GoldilocksField* values_flatten, int poly_num, int values_num_pe
     }
 }
 
+// The quadratic extension of Goldilocks, F[X]/(X^2 - 7), laid out like
+// `QuadraticExtension<GoldilocksField>`. Only the FRI kernels use it.
+struct GoldilocksExt2 {
+    GoldilocksField c0;
+    GoldilocksField c1;
+};
+
+#define EXT2_W 7
+
+__device__ inline
+GoldilocksExt2 ext2_add(const GoldilocksExt2& a, const GoldilocksExt2& b) {
+    return GoldilocksExt2{a.c0 + b.c0, a.c1 + b.c1};
+}
+
+__device__ inline
+GoldilocksExt2 ext2_mul(const GoldilocksExt2& a, const GoldilocksExt2& b) {
+    return GoldilocksExt2{
+            a.c0 * b.c0 + GoldilocksField::from_canonical_u64(EXT2_W) * (a.c1 * b.c1),
+            a.c0 * b.c1 + a.c1 * b.c0
+    };
+}
+
+// Folds a FRI layer: `out[i] = sum_j beta^j * coeffs[i*arity + j]`, like `reduce_with_powers`.
+__global__
+void fri_fold_kernel(const GoldilocksExt2* coeffs, GoldilocksExt2* out, int out_len, int arity, GoldilocksExt2 beta)
+{
+    int thCnt = get_global_thcnt();
+    int gid = get_global_id();
+
+    for (int i = gid; i < out_len; i += thCnt) {
+        GoldilocksExt2 acc = {GoldilocksField{0}, GoldilocksField{0}};
+        for (int j = arity-1; j >= 0; --j)
+            acc = ext2_add(ext2_mul(acc, beta), coeffs[(uint64_t)i*arity + j]);
+        out[i] = acc;
+    }
+}
+
+// Adds `alpha_start * sum_j alpha^j * polys[j]` to `acc`, for the `poly_num` poly-major
+// polynomials `polys` of `values_num_per_poly` coefficients each.
+__global__
+void reduce_polys_kernel(const GoldilocksField* polys, int poly_num, int values_num_per_poly,
+                         GoldilocksExt2 alpha, GoldilocksExt2 alpha_start, GoldilocksExt2* acc)
+{
+    int thCnt = get_global_thcnt();
+    int gid = get_global_id();
+
+    for (int i = gid; i < values_num_per_poly; i += thCnt) {
+        GoldilocksExt2 sum = {GoldilocksField{0}, GoldilocksField{0}};
+        for (int j = poly_num-1; j >= 0; --j) {
+            sum = ext2_mul(sum, alpha);
+            sum.c0 += polys[(uint64_t)j*values_num_per_poly + i];
+        }
+        acc[i] = ext2_add(acc[i], ext2_mul(sum, alpha_start));
+    }
+}
+
+// Splits the coefficients over the extension into the two poly-major polynomials of their
+// components, multiplying the `i`-th coefficient by `shift^i` so that an FFT evaluates them on
+// the coset.
+__global__
+void ext2_to_columns_kernel(const GoldilocksExt2* coeffs, GoldilocksField* columns, int values_num_per_poly, GoldilocksField shift)
+{
+    int thCnt = get_global_thcnt();
+    int gid = get_global_id();
+
+    for (int i = gid; i < values_num_per_poly; i += thCnt) {
+        GoldilocksField shift_power = shift.exp_u64(i);
+        columns[i] = coeffs[i].c0 * shift_power;
+        columns[values_num_per_poly + i] = coeffs[i].c1 * shift_power;
+    }
+}
+
+// Interleaves the two poly-major component polynomials back into values over the extension.
+__global__
+void columns_to_ext2_kernel(const GoldilocksField* columns, GoldilocksExt2* values, int values_num_per_poly)
+{
+    int thCnt = get_global_thcnt();
+    int gid = get_global_id();
+
+    for (int i = gid; i < values_num_per_poly; i += thCnt)
+        values[i] = GoldilocksExt2{columns[i], columns[values_num_per_poly + i]};
+}
+
 #endif
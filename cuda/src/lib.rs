@@ -113,6 +113,69 @@ extern "C" {
         ctx: *mut c_void,
     ) -> cuda::Error;
 
+    pub fn lde_columns(
+        coeffs: *mut u64,
+        lde: *mut u64,
+        poly_num: i32,
+        values_num_per_poly: i32,
+        log_len: i32,
+        root_table2: *const u64,
+        shift_powers: *const u64,
+        rate_bits: i32,
+        leaves: *mut u64,
+        leaf_len: i32,
+        first_column: i32,
+        ctx: *mut c_void,
+    ) -> cuda::Error;
+
+    pub fn write_columns(
+        values: *const u64,
+        poly_num: i32,
+        values_num_per_poly: i32,
+        leaves: *mut u64,
+        leaf_len: i32,
+        first_column: i32,
+        ctx: *mut c_void,
+    ) -> cuda::Error;
+
+    pub fn hash_leaves(
+        leaves: *mut u64,
+        leaf_len: i32,
+        leaves_len: i32,
+        cap_height: i32,
+        digests: *mut u64,
+        ctx: *mut c_void,
+    ) -> cuda::Error;
+
+
+    pub fn fri_fold(
+        coeffs: *const u64,
+        out: *mut u64,
+        out_len: i32,
+        arity: i32,
+        beta: *const u64,
+        ctx: *mut c_void,
+    ) -> cuda::Error;
+
+    pub fn reduce_polys(
+        polys: *const u64,
+        poly_num: i32,
+        values_num_per_poly: i32,
+        alpha: *const u64,
+        alpha_start: *const u64,
+        acc: *mut u64,
+        ctx: *mut c_void,
+    ) -> cuda::Error;
+
+    pub fn coset_fft_ext2(
+        values: *mut u64,
+        scratch: *mut u64,
+        values_num_per_poly: i32,
+        log_len: i32,
+        root_table2: *const u64,
+        shift: *const u64,
+        ctx: *mut c_void,
+    ) -> cuda::Error;
 
     pub fn compute_quotient_polys(
         ext_values_flatten: *const u64,
@@ -139,6 +202,15 @@ extern "C" {
         betas: *const DataSlice,
         gammas: *const DataSlice,
 
+        gate_table: *const DataSlice,
+
+        num_challenges: i32,
+        num_constants: i32,
+        num_routed_wires: i32,
+        quotient_degree_factor: i32,
+        num_partial_products: i32,
+        public_inputs_hash: *const u64,
+
         ctx: *mut c_void,
     ) -> cuda::Error;
 
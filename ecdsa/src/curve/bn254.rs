@@ -0,0 +1,142 @@
+use num::BigUint;
+use plonky2::field::bn254_base::Bn254Base;
+use plonky2::field::bn254_scalar::Bn254Scalar;
+use plonky2::field::types::{Field, PrimeField};
+use serde::{Deserialize, Serialize};
+
+use crate::curve::curve_types::{AffinePoint, Curve};
+
+/// The group `G1` of the BN254 curve, also known as alt_bn128, of the Ethereum precompiles.
+#[derive(Debug, Copy, Clone, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Bn254;
+
+impl Curve for Bn254 {
+    type BaseField = Bn254Base;
+    type ScalarField = Bn254Scalar;
+
+    const A: Bn254Base = Bn254Base::ZERO;
+    const B: Bn254Base = Bn254Base([3, 0, 0, 0]);
+    const GENERATOR_AFFINE: AffinePoint<Self> = AffinePoint {
+        x: Bn254Base::ONE,
+        y: Bn254Base::TWO,
+        zero: false,
+    };
+}
+
+/// Decodes a point as in the inputs of the `ECADD` and `ECMUL` precompiles, i.e. its big-endian
+/// coordinates, with `(0, 0)` for the point at infinity. Returns `None` if a coordinate is not
+/// canonical, or if the point is not on the curve.
+pub fn point_from_be_bytes(bytes: &[u8; 64]) -> Option<AffinePoint<Bn254>> {
+    let coordinate = |bytes: &[u8]| {
+        let c = BigUint::from_bytes_be(bytes);
+        (c < Bn254Base::order()).then(|| Bn254Base::from_noncanonical_biguint(c))
+    };
+    let x = coordinate(&bytes[..32])?;
+    let y = coordinate(&bytes[32..])?;
+    if x.is_zero() && y.is_zero() {
+        return Some(AffinePoint::ZERO);
+    }
+
+    let point = AffinePoint { x, y, zero: false };
+    point.is_valid().then_some(point)
+}
+
+/// Encodes a point as in the outputs of the `ECADD` and `ECMUL` precompiles.
+pub fn point_to_be_bytes(point: AffinePoint<Bn254>) -> [u8; 64] {
+    let mut bytes = [0; 64];
+    if !point.zero {
+        for (chunk, c) in bytes.chunks_mut(32).zip([point.x, point.y]) {
+            let c = c.to_canonical_biguint().to_bytes_be();
+            chunk[32 - c.len()..].copy_from_slice(&c);
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use hex::FromHex;
+    use num::BigUint;
+    use plonky2::field::bn254_scalar::Bn254Scalar;
+    use plonky2::field::types::{Field, PrimeField};
+
+    use crate::curve::bn254::{point_from_be_bytes, point_to_be_bytes, Bn254};
+    use crate::curve::curve_types::{AffinePoint, Curve, CurveScalar, ProjectivePoint};
+
+    #[test]
+    fn test_generator() {
+        let g = Bn254::GENERATOR_AFFINE;
+        assert!(g.is_valid());
+        assert!(Bn254::is_safe_curve());
+
+        let neg_g = AffinePoint::<Bn254> {
+            x: g.x,
+            y: -g.y,
+            zero: g.zero,
+        };
+        assert!(neg_g.is_valid());
+
+        // `G1` has prime order, so `(r - 1) G = -G`.
+        let g = Bn254::GENERATOR_PROJECTIVE;
+        assert_eq!(CurveScalar(Bn254Scalar::NEG_ONE) * g, -g);
+    }
+
+    #[test]
+    fn test_naive_multiplication() {
+        let g = Bn254::GENERATOR_PROJECTIVE;
+        let ten = Bn254Scalar::from_canonical_u64(10);
+        let product = mul_naive(ten, g);
+        let sum = g + g + g + g + g + g + g + g + g + g;
+        assert_eq!(product, sum);
+    }
+
+    #[test]
+    fn test_g1_multiplication() {
+        let lhs = Bn254Scalar::from_noncanonical_biguint(BigUint::from_slice(&[
+            1111, 2222, 3333, 4444, 5555, 6666, 7777, 8888,
+        ]));
+        assert_eq!(
+            Bn254::convert(lhs) * Bn254::GENERATOR_PROJECTIVE,
+            mul_naive(lhs, Bn254::GENERATOR_PROJECTIVE)
+        );
+    }
+
+    #[test]
+    fn test_precompile_encoding() {
+        let double_g = <[u8; 64]>::from_hex(concat!(
+            "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3",
+            "15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4",
+        ))
+        .unwrap();
+        let g = Bn254::GENERATOR_AFFINE;
+        assert_eq!(point_from_be_bytes(&double_g), Some(g.double()));
+        assert_eq!(point_to_be_bytes(g.double()), double_g);
+
+        assert_eq!(point_from_be_bytes(&[0; 64]), Some(AffinePoint::ZERO));
+        assert_eq!(point_to_be_bytes(AffinePoint::ZERO), [0; 64]);
+
+        // `(1, 3)` is not on the curve.
+        let mut bytes = [0; 64];
+        bytes[31] = 1;
+        bytes[63] = 3;
+        assert_eq!(point_from_be_bytes(&bytes), None);
+        // Coordinates must be below the order of the base field.
+        assert_eq!(point_from_be_bytes(&[0xFF; 64]), None);
+    }
+
+    /// A simple, somewhat inefficient implementation of multiplication which is used as a reference
+    /// for correctness.
+    fn mul_naive(lhs: Bn254Scalar, rhs: ProjectivePoint<Bn254>) -> ProjectivePoint<Bn254> {
+        let mut g = rhs;
+        let mut sum = ProjectivePoint::ZERO;
+        for limb in lhs.to_canonical_biguint().to_u64_digits().iter() {
+            for j in 0..64 {
+                if (limb >> j & 1u64) != 0u64 {
+                    sum = sum + g;
+                }
+                g = g.double();
+            }
+        }
+        sum
+    }
+}
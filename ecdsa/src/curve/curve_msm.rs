@@ -166,7 +166,7 @@ pub(crate) fn to_digits<C: Curve>(x: &C::ScalarField, w: usize) -> Vec<usize> {
         .to_u64_digits()
         .iter()
         .cloned()
-        .pad_using(scalar_bits / 64, |_| 0)
+        .pad_using((scalar_bits + 63) / 64, |_| 0)
         .collect();
     let mut x_bits = Vec::with_capacity(scalar_bits);
     for i in 0..scalar_bits {
@@ -0,0 +1,186 @@
+use num::BigUint;
+use plonky2::field::ed25519_base::Ed25519Base;
+use plonky2::field::ed25519_scalar::Ed25519Scalar;
+use plonky2::field::types::{Field, PrimeField};
+use serde::{Deserialize, Serialize};
+
+use crate::curve::curve_types::{AffinePoint, Curve};
+
+/// Curve25519 in short Weierstrass form, also known as Wei25519, so that ed25519 signatures can be
+/// checked with the short Weierstrass arithmetic and gadgets of this crate. The points of the
+/// twisted Edwards curve of ed25519 are mapped to it by `edwards_to_weierstrass`.
+#[derive(Debug, Copy, Clone, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Ed25519;
+
+impl Curve for Ed25519 {
+    type BaseField = Ed25519Base;
+    type ScalarField = Ed25519Scalar;
+
+    // 19298681539552699237261830834781317975544997444273427339909597334573241639236
+    const A: Ed25519Base = Ed25519Base([
+        0xAAAAAA984914A144,
+        0xAAAAAAAAAAAAAAAA,
+        0xAAAAAAAAAAAAAAAA,
+        0x2AAAAAAAAAAAAAAA,
+    ]);
+    // 55751746669818908907645289078257140818241103727901012315294400837956729358436
+    const B: Ed25519Base = Ed25519Base([
+        0x260B5E9C7710C864,
+        0xED097B425ED097B4,
+        0x097B425ED097B425,
+        0x7B425ED097B425ED,
+    ]);
+    const GENERATOR_AFFINE: AffinePoint<Self> = AffinePoint {
+        x: ED25519_GENERATOR_X,
+        y: ED25519_GENERATOR_Y,
+        zero: false,
+    };
+}
+
+/// The image of the ed25519 base point, i.e. the point with `u = 9` of the Montgomery form.
+// 19298681539552699237261830834781317975544997444273427339909597334652188435546
+const ED25519_GENERATOR_X: Ed25519Base = Ed25519Base([
+    0xAAAAAAAAAAAD245A,
+    0xAAAAAAAAAAAAAAAA,
+    0xAAAAAAAAAAAAAAAA,
+    0x2AAAAAAAAAAAAAAA,
+]);
+
+// 14781619447589544791020593568409986887264606134616475288964881837755586237401
+const ED25519_GENERATOR_Y: Ed25519Base = Ed25519Base([
+    0x29E9C5A27ECED3D9,
+    0x923D4D7E6D7C61B2,
+    0xE01EDD2C7748D14C,
+    0x20AE19A1B8A086B4,
+]);
+
+/// `A / 3`, where `A = 486662` is the coefficient of the Montgomery form `v^2 = u^3 + A u^2 + u`.
+pub(crate) const MONTGOMERY_A_THIRD: Ed25519Base = Ed25519Base([
+    0xAAAAAAAAAAAD2451,
+    0xAAAAAAAAAAAAAAAA,
+    0xAAAAAAAAAAAAAAAA,
+    0x2AAAAAAAAAAAAAAA,
+]);
+
+/// The square root of `-(A + 2)` of the birational map of RFC 7748 from the Edwards form.
+pub(crate) const SQRT_NEG_A_PLUS_TWO: Ed25519Base = Ed25519Base([
+    0x3391FB5500BA81E7,
+    0x3A5E2C2EB482E57D,
+    0x2D84F723FC03B081,
+    0x70D9120B9F5FF944,
+]);
+
+/// The `d` of the Edwards form `-x^2 + y^2 = 1 + d x^2 y^2`.
+const EDWARDS_D: Ed25519Base = Ed25519Base([
+    0x75EB4DCA135978A3,
+    0x00700A4D4141D8AB,
+    0x8CC740797779E898,
+    0x52036CEE2B6FFE73,
+]);
+
+/// Maps the point `(x, y)` of the Edwards form of ed25519 to `Ed25519`.
+pub fn edwards_to_weierstrass(x: Ed25519Base, y: Ed25519Base) -> AffinePoint<Ed25519> {
+    if x.is_zero() && y.is_one() {
+        return AffinePoint::ZERO;
+    }
+
+    // The Montgomery form has `u = (1 + y) / (1 - y)` and `v = sqrt(-(A + 2)) u / x`, except for
+    // the point `(0, -1)` of order 2, which maps to `(0, 0)`.
+    let u = (Ed25519Base::ONE + y) / (Ed25519Base::ONE - y);
+    let v = if x.is_zero() {
+        Ed25519Base::ZERO
+    } else {
+        SQRT_NEG_A_PLUS_TWO * u / x
+    };
+    AffinePoint::nonzero(u + MONTGOMERY_A_THIRD, v)
+}
+
+/// Decodes a point encoded as in RFC 8032, or returns `None` if the encoding is invalid.
+pub fn decode_point(bytes: &[u8; 32]) -> Option<AffinePoint<Ed25519>> {
+    let mut y_bytes = *bytes;
+    let x_is_odd = y_bytes[31] >> 7 == 1;
+    y_bytes[31] &= 0x7F;
+    let y = BigUint::from_bytes_le(&y_bytes);
+    if y >= Ed25519Base::order() {
+        return None;
+    }
+
+    let y = Ed25519Base::from_noncanonical_biguint(y);
+    let y_squared = y * y;
+    // `d` is not a square, so the denominator is nonzero.
+    let x = ((y_squared - Ed25519Base::ONE) / (EDWARDS_D * y_squared + Ed25519Base::ONE)).sqrt()?;
+    if x.is_zero() && x_is_odd {
+        return None;
+    }
+    let x = if x.to_canonical_biguint().bit(0) == x_is_odd {
+        x
+    } else {
+        -x
+    };
+    Some(edwards_to_weierstrass(x, y))
+}
+
+/// Decodes a scalar encoded as in RFC 8032, or returns `None` if it is not canonical.
+pub fn decode_scalar(bytes: &[u8; 32]) -> Option<Ed25519Scalar> {
+    let s = BigUint::from_bytes_le(bytes);
+    (s < Ed25519Scalar::order()).then(|| Ed25519Scalar::from_noncanonical_biguint(s))
+}
+
+/// The scalar of a 64-byte hash, read in little-endian order as the SHA-512 hashes of ed25519.
+pub fn hash_to_scalar(hash: &[u8; 64]) -> Ed25519Scalar {
+    Ed25519Scalar::from_noncanonical_biguint(BigUint::from_bytes_le(hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::ed25519_scalar::Ed25519Scalar;
+    use plonky2::field::types::{Field, Sample};
+
+    use crate::curve::curve_types::{AffinePoint, Curve, CurveScalar};
+    use crate::curve::ed25519::{decode_point, Ed25519};
+
+    #[test]
+    fn test_generator() {
+        let g = Ed25519::GENERATOR_AFFINE;
+        assert!(g.is_valid());
+        assert!(Ed25519::is_safe_curve());
+
+        // The encoding of the ed25519 base point, whose `y` is `4 / 5`.
+        let mut encoded = [0x66; 32];
+        encoded[0] = 0x58;
+        assert_eq!(decode_point(&encoded), Some(g));
+    }
+
+    #[test]
+    fn test_generator_order() {
+        let g = Ed25519::GENERATOR_PROJECTIVE;
+        let neg_g = CurveScalar(Ed25519Scalar::NEG_ONE) * g;
+        assert_eq!(neg_g, -g);
+
+        let x = Ed25519Scalar::rand();
+        let y = Ed25519Scalar::rand();
+        let lhs = CurveScalar(x) * g + CurveScalar(y) * g;
+        assert_eq!(lhs, CurveScalar(x + y) * g);
+    }
+
+    #[test]
+    fn test_decode_invalid_points() {
+        // `y = p` is not canonical.
+        let mut encoded = [0xFF; 32];
+        encoded[0] = 0xED;
+        encoded[31] = 0x7F;
+        assert_eq!(decode_point(&encoded), None);
+
+        // The identity, with `y = 1`, maps to the point at infinity, but `x = 0` cannot be odd.
+        let mut encoded = [0; 32];
+        encoded[0] = 1;
+        assert_eq!(decode_point(&encoded), Some(AffinePoint::ZERO));
+        encoded[31] = 0x80;
+        assert_eq!(decode_point(&encoded), None);
+
+        // `y = 2` is not the coordinate of a point.
+        let mut encoded = [0; 32];
+        encoded[0] = 2;
+        assert_eq!(decode_point(&encoded), None);
+    }
+}
@@ -0,0 +1,141 @@
+use plonky2::field::types::Sample;
+use serde::{Deserialize, Serialize};
+
+use crate::curve::curve_types::{AffinePoint, Curve, CurveScalar};
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct EDDSASignature<C: Curve> {
+    pub r: AffinePoint<C>,
+    pub s: C::ScalarField,
+}
+
+/// The secret scalar of a key. For ed25519, it is derived from the hash of the secret seed.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct EDDSASecretKey<C: Curve>(pub C::ScalarField);
+
+impl<C: Curve> EDDSASecretKey<C> {
+    pub fn to_public(&self) -> EDDSAPublicKey<C> {
+        EDDSAPublicKey((CurveScalar(self.0) * C::GENERATOR_PROJECTIVE).to_affine())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct EDDSAPublicKey<C: Curve>(pub AffinePoint<C>);
+
+/// Signs with a random nonce, where `challenge` returns the hash `h` of the nonce point `R`, the
+/// public key and the message. The signature satisfies `s G = R + h A`.
+pub fn sign_message<C: Curve>(
+    sk: EDDSASecretKey<C>,
+    challenge: impl FnOnce(AffinePoint<C>) -> C::ScalarField,
+) -> EDDSASignature<C> {
+    let k = C::ScalarField::rand();
+    let r = (CurveScalar(k) * C::GENERATOR_PROJECTIVE).to_affine();
+    let s = k + challenge(r) * sk.0;
+    EDDSASignature { r, s }
+}
+
+/// Checks that `s G = R + h A`, where `h` is the hash of `R`, the public key and the message. For
+/// ed25519, it is the SHA-512 hash of their encodings, reduced with `ed25519::hash_to_scalar`.
+pub fn verify_message<C: Curve>(
+    h: C::ScalarField,
+    sig: EDDSASignature<C>,
+    pk: EDDSAPublicKey<C>,
+) -> bool {
+    let EDDSASignature { r, s } = sig;
+
+    assert!(pk.0.is_valid());
+    assert!(r.is_valid());
+
+    let lhs = CurveScalar(s) * C::GENERATOR_PROJECTIVE;
+    let rhs = CurveScalar(h) * pk.0.to_projective() + r;
+    lhs == rhs
+}
+
+#[cfg(test)]
+mod tests {
+    use hex::FromHex;
+    use plonky2::field::ed25519_scalar::Ed25519Scalar;
+    use plonky2::field::types::{Field, Sample};
+    use sha2::{Digest, Sha512};
+
+    use crate::curve::ed25519::{decode_point, decode_scalar, hash_to_scalar, Ed25519};
+    use crate::curve::eddsa::{
+        sign_message, verify_message, EDDSAPublicKey, EDDSASecretKey, EDDSASignature,
+    };
+
+    /// Decodes a public key and a signature of ed25519, and computes the hash of the message.
+    fn decode_signature(
+        pk: &str,
+        msg: &[u8],
+        sig: &str,
+    ) -> (
+        Ed25519Scalar,
+        EDDSASignature<Ed25519>,
+        EDDSAPublicKey<Ed25519>,
+    ) {
+        let pk = <[u8; 32]>::from_hex(pk).unwrap();
+        let sig = <[u8; 64]>::from_hex(sig).unwrap();
+        let r = sig[..32].try_into().unwrap();
+        let s = sig[32..].try_into().unwrap();
+
+        let mut hasher = Sha512::new();
+        hasher.update(r);
+        hasher.update(pk);
+        hasher.update(msg);
+        let h = hash_to_scalar(hasher.finalize().as_slice().try_into().unwrap());
+
+        let sig = EDDSASignature {
+            r: decode_point(&r).unwrap(),
+            s: decode_scalar(&s).unwrap(),
+        };
+        (h, sig, EDDSAPublicKey(decode_point(&pk).unwrap()))
+    }
+
+    #[test]
+    fn test_eddsa_native() {
+        type C = Ed25519;
+
+        let sk = EDDSASecretKey::<C>(Ed25519Scalar::rand());
+        let pk = sk.to_public();
+        let h = Ed25519Scalar::rand();
+
+        let sig = sign_message(sk, |_| h);
+        assert!(verify_message(h, sig, pk));
+        assert!(!verify_message(h + Ed25519Scalar::ONE, sig, pk));
+    }
+
+    #[test]
+    fn test_ed25519_vectors() {
+        // The first two tests of RFC 8032, section 7.1.
+        let (h, sig, pk) = decode_signature(
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            b"",
+            concat!(
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155",
+                "5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            ),
+        );
+        assert!(verify_message(h, sig, pk));
+
+        let (h, sig, pk) = decode_signature(
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            &[0x72],
+            concat!(
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da",
+                "085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+            ),
+        );
+        assert!(verify_message(h, sig, pk));
+
+        // The signature of the first test does not match the second message.
+        let (h, sig, pk) = decode_signature(
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            &[0x72],
+            concat!(
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155",
+                "5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            ),
+        );
+        assert!(!verify_message(h, sig, pk));
+    }
+}
@@ -10,6 +10,7 @@ use plonky2::iop::generator::{GeneratedValues, SimpleGenerator};
 use plonky2::iop::target::{BoolTarget, Target};
 use plonky2::iop::witness::{PartitionWitness, Witness};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
 use plonky2_u32::gadgets::arithmetic_u32::{CircuitBuilderU32, U32Target};
 use plonky2_u32::gadgets::multiple_comparison::list_le_u32_circuit;
 use plonky2_u32::witness::{GeneratedValuesU32, WitnessU32};
@@ -313,8 +314,30 @@ impl<F: PrimeField> GeneratedValuesBigUint<F> for GeneratedValues<F> {
     }
 }
 
+pub trait WriteBigUint {
+    fn write_target_biguint(&mut self, x: &BigUintTarget) -> IoResult<()>;
+}
+
+impl WriteBigUint for Vec<u8> {
+    fn write_target_biguint(&mut self, x: &BigUintTarget) -> IoResult<()> {
+        let limbs: Vec<Target> = x.limbs.iter().map(|l| l.0).collect();
+        self.write_target_vec(&limbs)
+    }
+}
+
+pub trait ReadBigUint {
+    fn read_target_biguint(&mut self) -> IoResult<BigUintTarget>;
+}
+
+impl ReadBigUint for Buffer {
+    fn read_target_biguint(&mut self) -> IoResult<BigUintTarget> {
+        let limbs = self.read_target_vec()?.into_iter().map(U32Target).collect();
+        Ok(BigUintTarget { limbs })
+    }
+}
+
 #[derive(Debug)]
-struct BigUintDivRemGenerator<F: RichField + Extendable<D>, const D: usize> {
+pub(crate) struct BigUintDivRemGenerator<F: RichField + Extendable<D>, const D: usize> {
     a: BigUintTarget,
     b: BigUintTarget,
     div: BigUintTarget,
@@ -342,6 +365,23 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
         out_buffer.set_biguint_target(&self.div, &div);
         out_buffer.set_biguint_target(&self.rem, &rem);
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_biguint(&self.a)?;
+        dst.write_target_biguint(&self.b)?;
+        dst.write_target_biguint(&self.div)?;
+        dst.write_target_biguint(&self.rem)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            a: src.read_target_biguint()?,
+            b: src.read_target_biguint()?,
+            div: src.read_target_biguint()?,
+            rem: src.read_target_biguint()?,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -10,6 +10,7 @@ use plonky2::hash::keccak::KeccakHash;
 use plonky2::iop::target::{BoolTarget, Target};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2::plonk::config::{GenericHashOut, Hasher};
+use plonky2::util::ceil_div_usize;
 use plonky2_u32::gadgets::arithmetic_u32::{CircuitBuilderU32, U32Target};
 
 use crate::curve::curve_types::{Curve, CurveScalar};
@@ -75,7 +76,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilderWindowedMul<F,
         access_index: Target,
         v: Vec<AffinePointTarget<C>>,
     ) -> AffinePointTarget<C> {
-        let num_limbs = C::BaseField::BITS / 32;
+        let num_limbs = ceil_div_usize(C::BaseField::BITS, 32);
         let zero = self.zero_u32();
         let x_limbs: Vec<Vec<_>> = (0..num_limbs)
             .map(|i| {
@@ -139,7 +140,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilderWindowedMul<F,
         let starting_point = CurveScalar(hash_0_scalar) * C::GENERATOR_PROJECTIVE;
         let starting_point_multiplied = {
             let mut cur = starting_point;
-            for _ in 0..C::ScalarField::BITS {
+            // The result is doubled `WINDOW_SIZE` times per window, i.e. once per bit of the limbs.
+            for _ in 0..n.value.limbs.len() * 32 {
                 cur = cur.double();
             }
             cur
@@ -174,7 +176,9 @@ mod tests {
     use core::ops::Neg;
 
     use anyhow::Result;
+    use plonky2::field::bn254_scalar::Bn254Scalar;
     use plonky2::field::secp256k1_scalar::Secp256K1Scalar;
+    use plonky2::field::types::PrimeField;
     use plonky2::iop::witness::PartialWitness;
     use plonky2::plonk::circuit_data::CircuitConfig;
     use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
@@ -182,7 +186,9 @@ mod tests {
     use rand::Rng;
 
     use super::*;
+    use crate::curve::bn254::Bn254;
     use crate::curve::secp256k1::Secp256K1;
+    use crate::gadgets::biguint::WitnessBigUint;
 
     #[test]
     fn test_random_access_curve_points() -> Result<()> {
@@ -251,4 +257,35 @@ mod tests {
 
         data.verify(proof)
     }
+
+    #[test]
+    #[ignore]
+    fn test_curve_windowed_mul_bn254() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_ecc_config();
+
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let g = (CurveScalar(Bn254Scalar::rand()) * Bn254::GENERATOR_PROJECTIVE).to_affine();
+        let n = Bn254Scalar::rand();
+        let n_g = (CurveScalar(n) * g.to_projective()).to_affine();
+        let n_g_expected = builder.constant_affine_point(n_g);
+
+        let g_target = builder.constant_affine_point(g);
+        let n_target = builder.add_virtual_nonnative_target::<Bn254Scalar>();
+        pw.set_biguint_target(&n_target.value, &n.to_canonical_biguint());
+        let n_g_actual = builder.curve_scalar_mul_windowed(&g_target, &n_target);
+        builder.curve_assert_valid(&n_g_actual);
+
+        builder.connect_affine_point(&n_g_expected, &n_g_actual);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+
+        data.verify(proof)
+    }
 }
@@ -0,0 +1,204 @@
+use alloc::vec::Vec;
+
+use plonky2::field::ed25519_base::Ed25519Base;
+use plonky2::field::ed25519_scalar::Ed25519Scalar;
+use plonky2::field::extension::Extendable;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2_u32::gadgets::arithmetic_u32::U32Target;
+use plonky2_u32::gadgets::bytes::U8Target;
+use plonky2_u32::gadgets::sha512::CircuitBuilderSha512;
+
+use crate::curve::curve_types::Curve;
+use crate::curve::ed25519::{Ed25519, MONTGOMERY_A_THIRD, SQRT_NEG_A_PLUS_TWO};
+use crate::gadgets::biguint::{BigUintTarget, CircuitBuilderBiguint};
+use crate::gadgets::curve::{AffinePointTarget, CircuitBuilderCurve};
+use crate::gadgets::curve_fixed_base::fixed_base_curve_mul_circuit;
+use crate::gadgets::curve_windowed_mul::CircuitBuilderWindowedMul;
+use crate::gadgets::nonnative::{CircuitBuilderNonNative, NonNativeTarget};
+
+#[derive(Clone, Debug)]
+pub struct EDDSAPublicKeyTarget<C: Curve>(pub AffinePointTarget<C>);
+
+#[derive(Clone, Debug)]
+pub struct EDDSASignatureTarget<C: Curve> {
+    pub r: AffinePointTarget<C>,
+    pub s: NonNativeTarget<C::ScalarField>,
+}
+
+/// Checks an ed25519 signature of `msg`. The hash `h` of `R`, the public key and the message is
+/// computed in the circuit, as the SHA-512 hash of their encodings reduced as in
+/// `ed25519::hash_to_scalar`. The points must be nonzero, like any `AffinePointTarget`, and must
+/// not be the point of order 2, which has no `x` of the Edwards form to encode. As in RFC 8032,
+/// `s` must be below the group order.
+pub fn verify_message_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    msg: &[U8Target],
+    sig: EDDSASignatureTarget<Ed25519>,
+    pk: EDDSAPublicKeyTarget<Ed25519>,
+) {
+    let EDDSASignatureTarget { r, s } = sig;
+
+    builder.curve_assert_valid(&pk.0);
+    builder.curve_assert_valid(&r);
+
+    // Otherwise `s + L` would also be a valid signature.
+    let max_s = builder.constant_biguint(&(Ed25519Scalar::order() - 1u32));
+    let s_is_canonical = builder.cmp_biguint(&s.value, &max_s);
+    builder.assert_one(s_is_canonical.target);
+
+    let mut hash_input = encode_point_circuit(builder, &r);
+    hash_input.extend(encode_point_circuit(builder, &pk.0));
+    hash_input.extend_from_slice(msg);
+    let hash = builder.sha512(&hash_input);
+    let h = hash_to_scalar_circuit(builder, &hash);
+
+    // `s G = R + h A`.
+    let lhs = fixed_base_curve_mul_circuit(builder, Ed25519::GENERATOR_AFFINE, &s);
+    let h_pk = builder.curve_scalar_mul_windowed(&pk.0, &h);
+    let rhs = builder.curve_add(&r, &h_pk);
+    builder.connect_affine_point(&lhs, &rhs);
+}
+
+/// The RFC 8032 encoding of a point, i.e. the `y` of the Edwards form in little-endian order,
+/// with the parity of `x` as its top bit. This inverts `ed25519::edwards_to_weierstrass`.
+fn encode_point_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    p: &AffinePointTarget<Ed25519>,
+) -> Vec<U8Target> {
+    // The Montgomery form has `u = x - A / 3` and `v = y`.
+    let a_third = builder.constant_nonnative(MONTGOMERY_A_THIRD);
+    let u = builder.sub_nonnative(&p.x, &a_third);
+
+    // The Edwards form has `y = (u - 1) / (u + 1)` and `x = sqrt(-(A + 2)) u / v`.
+    let one = builder.constant_nonnative(Ed25519Base::ONE);
+    let u_minus_one = builder.sub_nonnative(&u, &one);
+    let u_plus_one = builder.add_nonnative(&u, &one);
+    let u_plus_one_inv = builder.inv_nonnative(&u_plus_one);
+    let y = builder.mul_nonnative(&u_minus_one, &u_plus_one_inv);
+    let sqrt_neg_a_plus_two = builder.constant_nonnative(SQRT_NEG_A_PLUS_TWO);
+    let scaled_u = builder.mul_nonnative(&sqrt_neg_a_plus_two, &u);
+    let v_inv = builder.inv_nonnative(&p.y);
+    let x = builder.mul_nonnative(&scaled_u, &v_inv);
+
+    // The products are only reduced modulo the order, and the encoding is of canonical values.
+    let max = builder.constant_biguint(&(Ed25519Base::order() - 1u32));
+    for coord in [&x, &y] {
+        let is_canonical = builder.cmp_biguint(&coord.value, &max);
+        builder.assert_one(is_canonical.target);
+    }
+
+    let y_bits = builder.split_nonnative_to_bits(&y);
+    let mut bytes = y_bits
+        .chunks(8)
+        .map(|bits| {
+            let zero = builder.zero();
+            let byte = bits.iter().rev().fold(zero, |acc, bit| {
+                builder.mul_const_add(F::TWO, acc, bit.target)
+            });
+            U8Target(byte)
+        })
+        .collect::<Vec<_>>();
+    // `y < 2^255`, so its top bit is free.
+    let x_is_odd = builder.split_le(x.value.get_limb(0).0, 32)[0];
+    let last = bytes[31].0;
+    bytes[31] = U8Target(builder.mul_const_add(F::from_canonical_u8(0x80), x_is_odd.target, last));
+    bytes
+}
+
+/// The scalar of a 64-byte hash, read in little-endian order as in `ed25519::hash_to_scalar`.
+fn hash_to_scalar_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    hash: &[U8Target; 64],
+) -> NonNativeTarget<Ed25519Scalar> {
+    let limbs = hash
+        .chunks(4)
+        .map(|bytes| {
+            let zero = builder.zero();
+            let limb = bytes.iter().rev().fold(zero, |acc, byte| {
+                builder.mul_const_add(F::from_canonical_u16(256), acc, byte.0)
+            });
+            U32Target(limb)
+        })
+        .collect();
+    builder.reduce(&BigUintTarget { limbs })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use hex::FromHex;
+    use num::BigUint;
+    use plonky2::iop::witness::PartialWitness;
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2_u32::gadgets::bytes::CircuitBuilderU8;
+    use plonky2_u32::witness::WitnessU8;
+
+    use super::*;
+    use crate::curve::ed25519::decode_point;
+    use crate::gadgets::biguint::WitnessBigUint;
+
+    fn test_eddsa_circuit_with_config(config: CircuitConfig) -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        // The second test of RFC 8032, section 7.1.
+        let pk = <[u8; 32]>::from_hex(
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+        )
+        .unwrap();
+        let sig = <[u8; 64]>::from_hex(concat!(
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da",
+            "085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        ))
+        .unwrap();
+        let msg = [0x72];
+        let s = BigUint::from_bytes_le(&sig[32..]);
+
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let pk_target =
+            EDDSAPublicKeyTarget(builder.constant_affine_point(decode_point(&pk).unwrap()));
+        let s_target = builder.add_virtual_nonnative_target::<Ed25519Scalar>();
+        let sig_target = EDDSASignatureTarget {
+            r: builder.constant_affine_point(decode_point(sig[..32].try_into().unwrap()).unwrap()),
+            s: s_target.clone(),
+        };
+        let msg_target = builder.add_virtual_u8_targets(msg.len());
+        verify_message_circuit(&mut builder, &msg_target, sig_target, pk_target);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_biguint_target(&s_target.value, &s);
+        pw.set_u8_target(msg_target[0], msg[0]);
+        let proof = data.prove(pw)?;
+        data.verify(proof)?;
+
+        // The signature does not match another message.
+        let mut pw = PartialWitness::new();
+        pw.set_biguint_target(&s_target.value, &s);
+        pw.set_u8_target(msg_target[0], msg[0] ^ 1);
+        assert!(data.prove(pw).is_err());
+
+        // `s + L` is rejected, although `(s + L) G = s G`.
+        let mut pw = PartialWitness::new();
+        pw.set_biguint_target(&s_target.value, &(s + Ed25519Scalar::order()));
+        pw.set_u8_target(msg_target[0], msg[0]);
+        assert!(data.prove(pw).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn test_eddsa_circuit_narrow() -> Result<()> {
+        test_eddsa_circuit_with_config(CircuitConfig::standard_ecc_config())
+    }
+
+    #[test]
+    #[ignore]
+    fn test_eddsa_circuit_wide() -> Result<()> {
+        test_eddsa_circuit_with_config(CircuitConfig::wide_ecc_config())
+    }
+}
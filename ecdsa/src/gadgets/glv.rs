@@ -10,13 +10,16 @@ use plonky2::iop::generator::{GeneratedValues, SimpleGenerator};
 use plonky2::iop::target::{BoolTarget, Target};
 use plonky2::iop::witness::{PartitionWitness, WitnessWrite};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
 
 use crate::curve::glv::{decompose_secp256k1_scalar, GLV_BETA, GLV_S};
 use crate::curve::secp256k1::Secp256K1;
 use crate::gadgets::biguint::{GeneratedValuesBigUint, WitnessBigUint};
 use crate::gadgets::curve::{AffinePointTarget, CircuitBuilderCurve};
 use crate::gadgets::curve_msm::curve_msm_circuit;
-use crate::gadgets::nonnative::{CircuitBuilderNonNative, NonNativeTarget};
+use crate::gadgets::nonnative::{
+    CircuitBuilderNonNative, NonNativeTarget, ReadNonNative, WriteNonNative,
+};
 
 pub trait CircuitBuilderGlv<F: RichField + Extendable<D>, const D: usize> {
     fn secp256k1_glv_beta(&mut self) -> NonNativeTarget<Secp256K1Base>;
@@ -100,7 +103,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilderGlv<F, D>
 }
 
 #[derive(Debug)]
-struct GLVDecompositionGenerator<F: RichField + Extendable<D>, const D: usize> {
+pub(crate) struct GLVDecompositionGenerator<F: RichField + Extendable<D>, const D: usize> {
     k: NonNativeTarget<Secp256K1Scalar>,
     k1: NonNativeTarget<Secp256K1Scalar>,
     k2: NonNativeTarget<Secp256K1Scalar>,
@@ -128,6 +131,25 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
         out_buffer.set_bool_target(self.k1_neg, k1_neg);
         out_buffer.set_bool_target(self.k2_neg, k2_neg);
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_nonnative(&self.k)?;
+        dst.write_target_nonnative(&self.k1)?;
+        dst.write_target_nonnative(&self.k2)?;
+        dst.write_bool_target(self.k1_neg)?;
+        dst.write_bool_target(self.k2_neg)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            k: src.read_target_nonnative()?,
+            k1: src.read_target_nonnative()?,
+            k2: src.read_target_nonnative()?,
+            k1_neg: src.read_bool_target()?,
+            k2_neg: src.read_bool_target()?,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[cfg(test)]
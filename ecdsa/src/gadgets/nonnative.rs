@@ -11,12 +11,14 @@ use plonky2::iop::target::{BoolTarget, Target};
 use plonky2::iop::witness::{PartitionWitness, WitnessWrite};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2::util::ceil_div_usize;
+use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
 use plonky2_u32::gadgets::arithmetic_u32::{CircuitBuilderU32, U32Target};
 use plonky2_u32::gadgets::range_check::range_check_u32_circuit;
 use plonky2_u32::witness::GeneratedValuesU32;
 
 use crate::gadgets::biguint::{
-    BigUintTarget, CircuitBuilderBiguint, GeneratedValuesBigUint, WitnessBigUint,
+    BigUintTarget, CircuitBuilderBiguint, GeneratedValuesBigUint, ReadBigUint, WitnessBigUint,
+    WriteBigUint,
 };
 
 #[derive(Clone, Debug)]
@@ -25,6 +27,29 @@ pub struct NonNativeTarget<FF: Field> {
     pub(crate) _phantom: PhantomData<FF>,
 }
 
+pub trait WriteNonNative {
+    fn write_target_nonnative<FF: Field>(&mut self, x: &NonNativeTarget<FF>) -> IoResult<()>;
+}
+
+impl WriteNonNative for Vec<u8> {
+    fn write_target_nonnative<FF: Field>(&mut self, x: &NonNativeTarget<FF>) -> IoResult<()> {
+        self.write_target_biguint(&x.value)
+    }
+}
+
+pub trait ReadNonNative {
+    fn read_target_nonnative<FF: Field>(&mut self) -> IoResult<NonNativeTarget<FF>>;
+}
+
+impl ReadNonNative for Buffer {
+    fn read_target_nonnative<FF: Field>(&mut self) -> IoResult<NonNativeTarget<FF>> {
+        Ok(NonNativeTarget {
+            value: self.read_target_biguint()?,
+            _phantom: PhantomData,
+        })
+    }
+}
+
 pub trait CircuitBuilderNonNative<F: RichField + Extendable<D>, const D: usize> {
     fn num_nonnative_limbs<FF: Field>() -> usize {
         ceil_div_usize(FF::BITS, 32)
@@ -446,7 +471,11 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilderNonNative<F, D>
 }
 
 #[derive(Debug)]
-struct NonNativeAdditionGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> {
+pub(crate) struct NonNativeAdditionGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    FF: PrimeField,
+> {
     a: NonNativeTarget<FF>,
     b: NonNativeTarget<FF>,
     sum: NonNativeTarget<FF>,
@@ -484,11 +513,31 @@ impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerat
         out_buffer.set_biguint_target(&self.sum.value, &sum_reduced);
         out_buffer.set_bool_target(self.overflow, overflow);
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_nonnative(&self.a)?;
+        dst.write_target_nonnative(&self.b)?;
+        dst.write_target_nonnative(&self.sum)?;
+        dst.write_bool_target(self.overflow)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            a: src.read_target_nonnative()?,
+            b: src.read_target_nonnative()?,
+            sum: src.read_target_nonnative()?,
+            overflow: src.read_bool_target()?,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[derive(Debug)]
-struct NonNativeMultipleAddsGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField>
-{
+pub(crate) struct NonNativeMultipleAddsGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    FF: PrimeField,
+> {
     summands: Vec<NonNativeTarget<FF>>,
     sum: NonNativeTarget<FF>,
     overflow: U32Target,
@@ -529,10 +578,34 @@ impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerat
         out_buffer.set_biguint_target(&self.sum.value, &sum_reduced);
         out_buffer.set_u32_target(self.overflow, overflow);
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_usize(self.summands.len())?;
+        for summand in &self.summands {
+            dst.write_target_nonnative(summand)?;
+        }
+        dst.write_target_nonnative(&self.sum)?;
+        dst.write_target(self.overflow.0)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            summands: (0..src.read_usize()?)
+                .map(|_| src.read_target_nonnative())
+                .collect::<IoResult<_>>()?,
+            sum: src.read_target_nonnative()?,
+            overflow: U32Target(src.read_target()?),
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[derive(Debug)]
-struct NonNativeSubtractionGenerator<F: RichField + Extendable<D>, const D: usize, FF: Field> {
+pub(crate) struct NonNativeSubtractionGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    FF: Field,
+> {
     a: NonNativeTarget<FF>,
     b: NonNativeTarget<FF>,
     diff: NonNativeTarget<FF>,
@@ -570,10 +643,31 @@ impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerat
         out_buffer.set_biguint_target(&self.diff.value, &diff_biguint);
         out_buffer.set_bool_target(self.overflow, overflow);
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_nonnative(&self.a)?;
+        dst.write_target_nonnative(&self.b)?;
+        dst.write_target_nonnative(&self.diff)?;
+        dst.write_bool_target(self.overflow)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            a: src.read_target_nonnative()?,
+            b: src.read_target_nonnative()?,
+            diff: src.read_target_nonnative()?,
+            overflow: src.read_bool_target()?,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[derive(Debug)]
-struct NonNativeMultiplicationGenerator<F: RichField + Extendable<D>, const D: usize, FF: Field> {
+pub(crate) struct NonNativeMultiplicationGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    FF: Field,
+> {
     a: NonNativeTarget<FF>,
     b: NonNativeTarget<FF>,
     prod: NonNativeTarget<FF>,
@@ -609,10 +703,31 @@ impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerat
         out_buffer.set_biguint_target(&self.prod.value, &prod_reduced);
         out_buffer.set_biguint_target(&self.overflow, &overflow_biguint);
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_nonnative(&self.a)?;
+        dst.write_target_nonnative(&self.b)?;
+        dst.write_target_nonnative(&self.prod)?;
+        dst.write_target_biguint(&self.overflow)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            a: src.read_target_nonnative()?,
+            b: src.read_target_nonnative()?,
+            prod: src.read_target_nonnative()?,
+            overflow: src.read_target_biguint()?,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[derive(Debug)]
-struct NonNativeInverseGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> {
+pub(crate) struct NonNativeInverseGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    FF: PrimeField,
+> {
     x: NonNativeTarget<FF>,
     inv: BigUintTarget,
     div: BigUintTarget,
@@ -639,6 +754,21 @@ impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerat
         out_buffer.set_biguint_target(&self.div, &div);
         out_buffer.set_biguint_target(&self.inv, &inv_biguint);
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_nonnative(&self.x)?;
+        dst.write_target_biguint(&self.inv)?;
+        dst.write_target_biguint(&self.div)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            x: src.read_target_nonnative()?,
+            inv: src.read_target_biguint()?,
+            div: src.read_target_biguint()?,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,148 @@
+use plonky2::field::bn254_base::Bn254Base;
+use plonky2::field::bn254_scalar::Bn254Scalar;
+use plonky2::field::ed25519_base::Ed25519Base;
+use plonky2::field::ed25519_scalar::Ed25519Scalar;
+use plonky2::field::extension::Extendable;
+use plonky2::field::secp256k1_base::Secp256K1Base;
+use plonky2::field::secp256k1_scalar::Secp256K1Scalar;
+use plonky2::field::types::PrimeField;
+use plonky2::gates::base_sum::{BaseSplitGenerator, BaseSumGate};
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::registry::CircuitRegistry;
+
+use crate::gadgets::biguint::BigUintDivRemGenerator;
+use crate::gadgets::glv::GLVDecompositionGenerator;
+use crate::gadgets::nonnative::{
+    NonNativeAdditionGenerator, NonNativeInverseGenerator, NonNativeMultipleAddsGenerator,
+    NonNativeMultiplicationGenerator, NonNativeSubtractionGenerator,
+};
+
+/// Registers the gates and generators of this crate and of `plonky2_u32`, so that circuits using
+/// them can be serialized. The non-native generators are registered for the base and scalar
+/// fields of secp256k1, ed25519 and BN254.
+pub fn register<F: RichField + Extendable<D>, const D: usize>(
+    registry: &mut CircuitRegistry<F, D>,
+) {
+    plonky2_u32::serialization::register(registry);
+    registry.register_gate::<BaseSumGate<4>>("BaseSumGate<4>");
+    registry
+        .register_simple_generator::<BaseSplitGenerator<4>>("BaseSplitGenerator<4>")
+        .register_simple_generator::<BigUintDivRemGenerator<F, D>>("BigUintDivRemGenerator")
+        .register_simple_generator::<GLVDecompositionGenerator<F, D>>("GLVDecompositionGenerator");
+    register_nonnative::<F, D, Secp256K1Base>(
+        registry,
+        [
+            "NonNativeAdditionGenerator<Secp256K1Base>",
+            "NonNativeMultipleAddsGenerator<Secp256K1Base>",
+            "NonNativeSubtractionGenerator<Secp256K1Base>",
+            "NonNativeMultiplicationGenerator<Secp256K1Base>",
+            "NonNativeInverseGenerator<Secp256K1Base>",
+        ],
+    );
+    register_nonnative::<F, D, Secp256K1Scalar>(
+        registry,
+        [
+            "NonNativeAdditionGenerator<Secp256K1Scalar>",
+            "NonNativeMultipleAddsGenerator<Secp256K1Scalar>",
+            "NonNativeSubtractionGenerator<Secp256K1Scalar>",
+            "NonNativeMultiplicationGenerator<Secp256K1Scalar>",
+            "NonNativeInverseGenerator<Secp256K1Scalar>",
+        ],
+    );
+    register_nonnative::<F, D, Ed25519Base>(
+        registry,
+        [
+            "NonNativeAdditionGenerator<Ed25519Base>",
+            "NonNativeMultipleAddsGenerator<Ed25519Base>",
+            "NonNativeSubtractionGenerator<Ed25519Base>",
+            "NonNativeMultiplicationGenerator<Ed25519Base>",
+            "NonNativeInverseGenerator<Ed25519Base>",
+        ],
+    );
+    register_nonnative::<F, D, Ed25519Scalar>(
+        registry,
+        [
+            "NonNativeAdditionGenerator<Ed25519Scalar>",
+            "NonNativeMultipleAddsGenerator<Ed25519Scalar>",
+            "NonNativeSubtractionGenerator<Ed25519Scalar>",
+            "NonNativeMultiplicationGenerator<Ed25519Scalar>",
+            "NonNativeInverseGenerator<Ed25519Scalar>",
+        ],
+    );
+    register_nonnative::<F, D, Bn254Base>(
+        registry,
+        [
+            "NonNativeAdditionGenerator<Bn254Base>",
+            "NonNativeMultipleAddsGenerator<Bn254Base>",
+            "NonNativeSubtractionGenerator<Bn254Base>",
+            "NonNativeMultiplicationGenerator<Bn254Base>",
+            "NonNativeInverseGenerator<Bn254Base>",
+        ],
+    );
+    register_nonnative::<F, D, Bn254Scalar>(
+        registry,
+        [
+            "NonNativeAdditionGenerator<Bn254Scalar>",
+            "NonNativeMultipleAddsGenerator<Bn254Scalar>",
+            "NonNativeSubtractionGenerator<Bn254Scalar>",
+            "NonNativeMultiplicationGenerator<Bn254Scalar>",
+            "NonNativeInverseGenerator<Bn254Scalar>",
+        ],
+    );
+}
+
+fn register_nonnative<F: RichField + Extendable<D>, const D: usize, FF: PrimeField>(
+    registry: &mut CircuitRegistry<F, D>,
+    ids: [&'static str; 5],
+) {
+    registry
+        .register_simple_generator::<NonNativeAdditionGenerator<F, D, FF>>(ids[0])
+        .register_simple_generator::<NonNativeMultipleAddsGenerator<F, D, FF>>(ids[1])
+        .register_simple_generator::<NonNativeSubtractionGenerator<F, D, FF>>(ids[2])
+        .register_simple_generator::<NonNativeMultiplicationGenerator<F, D, FF>>(ids[3])
+        .register_simple_generator::<NonNativeInverseGenerator<F, D, FF>>(ids[4]);
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::field::types::Sample;
+    use plonky2::iop::witness::PartialWitness;
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::{CircuitConfig, CircuitData};
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    use super::*;
+    use crate::gadgets::glv::CircuitBuilderGlv;
+    use crate::gadgets::nonnative::CircuitBuilderNonNative;
+    use crate::gadgets::split_nonnative::CircuitBuilderSplit;
+
+    #[test]
+    fn test_circuit_data_round_trip() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_ecc_config());
+        let x = builder.constant_nonnative(Secp256K1Scalar::rand());
+        let y = builder.constant_nonnative(Secp256K1Scalar::rand());
+        let sum = builder.add_nonnative(&x, &y);
+        let diff = builder.sub_nonnative(&sum, &y);
+        builder.connect_nonnative(&diff, &x);
+        let prod = builder.mul_nonnative(&x, &y);
+        let inv = builder.inv_nonnative(&prod);
+        let total = builder.add_many_nonnative(&[x.clone(), y, inv]);
+        builder.decompose_secp256k1_scalar(&total);
+        builder.split_nonnative_to_4_bit_limbs(&x);
+        let data = builder.build::<C>();
+
+        let mut registry = CircuitRegistry::new();
+        register(&mut registry);
+        let bytes = data.to_bytes(&registry)?;
+        let deserialized = CircuitData::<F, C, D>::from_bytes(bytes, &registry)?;
+
+        let proof = data.prove(PartialWitness::new())?;
+        assert_eq!(deserialized.prove(PartialWitness::new())?, proof);
+        deserialized.verify(proof)
+    }
+}
@@ -133,7 +133,10 @@ impl EvmField {
                 U256::from_str("0x30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47")
                     .unwrap()
             }
-            EvmField::Bn254Scalar => todo!(),
+            EvmField::Bn254Scalar => {
+                U256::from_str("0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001")
+                    .unwrap()
+            }
             EvmField::Secp256k1Base => {
                 U256::from_str("0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f")
                     .unwrap()
@@ -408,8 +408,9 @@ where
             challenger,
             &fri_params,
             timing,
+            None,
         )
-    );
+    )?;
 
     Ok(StarkProof {
         trace_cap: trace_commitment.merkle_tree.cap.clone(),
@@ -5,6 +5,7 @@ use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAss
 
 use num::{BigUint, Integer};
 use plonky2_util::{assume, branch_hint};
+#[cfg(feature = "cuda")]
 use rustacuda::DeviceCopy;
 use serde::{Deserialize, Serialize};
 
@@ -21,7 +22,8 @@ const EPSILON: u64 = (1 << 32) - 1;
 ///   = 2**64 - 2**32 + 1
 ///   = 2**32 * (2**32 - 1) + 1
 /// ```
-#[derive(Copy, Clone, Serialize, Deserialize, DeviceCopy)]
+#[derive(Copy, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "cuda", derive(DeviceCopy))]
 #[repr(transparent)]
 pub struct GoldilocksField(pub u64);
 
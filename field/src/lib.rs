@@ -17,7 +17,11 @@ mod inversion;
 pub(crate) mod arch;
 
 pub mod batch_util;
+pub mod bn254_base;
+pub mod bn254_scalar;
 pub mod cosets;
+pub mod ed25519_base;
+pub mod ed25519_scalar;
 pub mod extension;
 pub mod fft;
 pub mod goldilocks_extensions;
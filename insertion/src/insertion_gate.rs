@@ -17,6 +17,7 @@ use plonky2::iop::wire::Wire;
 use plonky2::iop::witness::{PartitionWitness, Witness, WitnessWrite};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBase};
+use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
 
 /// A gate for inserting a value into a list at a non-deterministic location.
 #[derive(Clone, Debug)]
@@ -80,6 +81,14 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for InsertionGate<
         format!("{self:?}<D={D}>")
     }
 
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_usize(self.vec_size)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self::new(src.read_usize()?))
+    }
+
     fn export_circom_verification_code(&self) -> String {
         todo!()
     }
@@ -250,7 +259,7 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for InsertionGate<
 }
 
 #[derive(Debug)]
-struct InsertionGenerator<F: RichField + Extendable<D>, const D: usize> {
+pub(crate) struct InsertionGenerator<F: RichField + Extendable<D>, const D: usize> {
     row: usize,
     gate: InsertionGate<F, D>,
 }
@@ -324,6 +333,18 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F> for Insert
             out_buffer.set_wire(insert_here_wire, insert_here_vals[i]);
         }
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        self.gate.serialize(dst)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            row: src.read_usize()?,
+            gate: InsertionGate::<F, D>::deserialize(src)?,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,54 @@
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::registry::CircuitRegistry;
+
+use crate::insertion_gate::{InsertionGate, InsertionGenerator};
+
+/// Registers the gate and generator of this crate, so that circuits using them can be serialized.
+pub fn register<F: RichField + Extendable<D>, const D: usize>(
+    registry: &mut CircuitRegistry<F, D>,
+) {
+    registry.register_gate::<InsertionGate<F, D>>("InsertionGate");
+    registry.register_simple_generator::<InsertionGenerator<F, D>>("InsertionGenerator");
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use anyhow::Result;
+    use plonky2::field::types::{Field, Sample};
+    use plonky2::iop::witness::PartialWitness;
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::{CircuitConfig, CircuitData};
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    use super::*;
+    use crate::insert_gadget::CircuitBuilderInsert;
+
+    #[test]
+    fn test_circuit_data_round_trip() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let v = (0..3)
+            .map(|_| builder.constant_extension(FF::rand()))
+            .collect::<Vec<_>>();
+        let index = builder.constant(F::ONE);
+        let element = builder.constant_extension(FF::rand());
+        builder.insert(index, element, v);
+        let data = builder.build::<C>();
+
+        let mut registry = CircuitRegistry::new();
+        register(&mut registry);
+        let bytes = data.to_bytes(&registry)?;
+        let deserialized = CircuitData::<F, C, D>::from_bytes(bytes, &registry)?;
+
+        let proof = data.prove(PartialWitness::new())?;
+        assert_eq!(deserialized.prove(PartialWitness::new())?, proof);
+        deserialized.verify(proof)
+    }
+}
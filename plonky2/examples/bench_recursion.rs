@@ -19,7 +19,7 @@ use plonky2::plonk::circuit_data::{
 };
 use plonky2::plonk::config::{AlgebraicHasher, GenericConfig, PoseidonGoldilocksConfig};
 use plonky2::plonk::proof::{CompressedProofWithPublicInputs, ProofWithPublicInputs};
-use plonky2::plonk::prover::prove;
+use plonky2::plonk::prover::{prove, ProvingStrategy};
 use plonky2::util::timing::TimingTree;
 use plonky2_field::extension::Extendable;
 use rand::rngs::OsRng;
@@ -83,7 +83,7 @@ fn dummy_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D
     let inputs = PartialWitness::new();
 
     let mut timing = TimingTree::new("prove", Level::Debug);
-    let proof = prove(&data.prover_only, &data.common, inputs, &mut timing)?;
+    let proof = prove(&data.prover_only, &data.common, inputs, &mut timing, ProvingStrategy::Cpu)?;
     timing.print();
     data.verify(proof.clone())?;
 
@@ -133,7 +133,7 @@ where
     pw.set_verifier_data_target(&inner_data, inner_vd);
 
     let mut timing = TimingTree::new("prove", Level::Debug);
-    let proof = prove(&data.prover_only, &data.common, pw, &mut timing)?;
+    let proof = prove(&data.prover_only, &data.common, pw, &mut timing, ProvingStrategy::Cpu)?;
     timing.print();
 
     data.verify(proof.clone())?;
@@ -0,0 +1,885 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use maybe_rayon::*;
+
+use crate::backend::quotient::compute_quotient_polys_packed;
+use crate::backend::{
+    ensure_flat_hash, hash_to_elements, CircuitTables, FlatTreeParams, ProofLayout, ProverBackend,
+    QuotientPolysJob,
+};
+use crate::field::extension::Extendable;
+use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
+use crate::field::types::{Field, Sample};
+use crate::field::zero_poly_coset::ZeroPolyOnCoset;
+use crate::fri::prover::{grind_proof_of_work, PowJob};
+use crate::hash::hash_types::{HashOut, RichField, NUM_HASH_OUT_ELTS};
+use crate::hash::merkle_tree::MerkleTree;
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::error::{ensure_shape, ProverError, ProverResult};
+use crate::plonk::prover::BATCH_SIZE;
+use crate::plonk::vanishing_poly::eval_vanishing_poly_base_batch;
+use crate::plonk::vars::EvaluationVarsBaseBatch;
+use crate::util::{ceil_div_usize, log2_ceil, reverse_bits, reverse_index_bits_in_place};
+
+/// Pure-Rust implementation of the `plonky2_cuda` entry points, operating on a host buffer with
+/// the same layout as the device memory used by the kernels.
+pub struct CpuBackend<F: RichField> {
+    buffer: Vec<F>,
+    second_stage_offset: usize,
+    chunk_polys: Option<usize>,
+    tables: Option<CircuitTables<F>>,
+    cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl<F: RichField> CpuBackend<F> {
+    /// A backend with a zeroed working buffer of `buffer_len` elements and no preprocessed
+    /// polynomials, which is enough to build commitments.
+    pub fn new(buffer_len: usize, second_stage_offset: usize) -> Self {
+        Self {
+            buffer: vec![F::ZERO; buffer_len],
+            second_stage_offset,
+            chunk_polys: None,
+            tables: None,
+            cancelled: None,
+        }
+    }
+
+    /// A backend whose working buffer fits the three commitments of a proof of the given circuit,
+    /// holding the leaves of its constants and sigmas commitment. Fails with
+    /// `ProverError::Unsupported` if the circuit does not fit the flattened layout, e.g. if its
+    /// hashes are not made of field elements.
+    pub fn for_circuit<C: GenericConfig<D, F = F>, const D: usize>(
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> ProverResult<Self>
+    where
+        F: Extendable<D>,
+    {
+        Self::with_layout(prover_data, common_data, ProofLayout::new(common_data)?)
+    }
+
+    /// Like `for_circuit`, with a working buffer laid out by `layout`, e.g. a layout from
+    /// `ProofLayout::with_budget` to exercise chunked commitments.
+    pub fn with_layout<C: GenericConfig<D, F = F>, const D: usize>(
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        layout: ProofLayout,
+    ) -> ProverResult<Self>
+    where
+        F: Extendable<D>,
+    {
+        ensure_flat_hash::<F, C::Hasher>()?;
+        Ok(Self {
+            buffer: vec![F::ZERO; layout.buffer_len],
+            second_stage_offset: layout.zs_partial_products_offset,
+            chunk_polys: layout.chunk_polys,
+            tables: Some(CircuitTables::new(prover_data, common_data)),
+            cancelled: None,
+        })
+    }
+
+    /// Makes the proof-of-work search stop with `ProverError::Cancelled` once `cancelled` is set,
+    /// e.g. from another thread.
+    pub fn with_cancellation(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.cancelled = Some(cancelled);
+        self
+    }
+
+    /// The working buffer.
+    pub fn buffer(&self) -> &[F] {
+        &self.buffer
+    }
+
+    fn range(&self, offset: usize, len: usize) -> ProverResult<core::ops::Range<usize>> {
+        ensure_shape!(
+            offset + len <= self.buffer.len(),
+            "Access to [{}, {}) is out of the working buffer of {} elements",
+            offset,
+            offset + len,
+            self.buffer.len()
+        );
+        Ok(offset..offset + len)
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    ProverBackend<F, C, D> for CpuBackend<F>
+{
+    fn buffer_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn second_stage_offset(&self) -> usize {
+        self.second_stage_offset
+    }
+
+    fn chunk_polys(&self) -> Option<usize> {
+        self.chunk_polys
+    }
+
+    fn write(&mut self, offset: usize, values: &[F]) -> ProverResult<()> {
+        let range = self.range(offset, values.len())?;
+        self.buffer[range].copy_from_slice(values);
+        Ok(())
+    }
+
+    fn read(&self, offset: usize, out: &mut [F]) -> ProverResult<()> {
+        let range = self.range(offset, out.len())?;
+        out.copy_from_slice(&self.buffer[range]);
+        Ok(())
+    }
+
+    fn ifft(
+        &mut self,
+        offset: usize,
+        poly_num: usize,
+        values_num_per_poly: usize,
+    ) -> ProverResult<()> {
+        let range = self.range(offset, poly_num * values_num_per_poly)?;
+        ifft_flat(&mut self.buffer[range], values_num_per_poly);
+        Ok(())
+    }
+
+    fn merkle_tree_from_values(
+        &mut self,
+        offset: usize,
+        params: &FlatTreeParams,
+        coeffs: &mut [F],
+    ) -> ProverResult<()> {
+        <Self as ProverBackend<F, C, D>>::ifft(
+            self,
+            offset,
+            params.poly_num,
+            params.values_num_per_poly,
+        )?;
+        <Self as ProverBackend<F, C, D>>::merkle_tree_from_coeffs(self, offset, params, coeffs)
+    }
+
+    fn merkle_tree_from_coeffs(
+        &mut self,
+        offset: usize,
+        params: &FlatTreeParams,
+        coeffs: &mut [F],
+    ) -> ProverResult<()> {
+        ensure_shape!(coeffs.len() == params.values_len());
+        let range = self.range(offset, params.footprint())?;
+        let region = &mut self.buffer[range];
+        coeffs.copy_from_slice(&region[..params.values_len()]);
+
+        let (leaves, rest) = region.split_at_mut(params.ext_values_len());
+        let (scratch, digests_and_caps) = rest.split_at_mut(params.scratch_len());
+        if params.chunk_polys.is_some() {
+            chunked_lde_flat(coeffs, leaves, scratch, params);
+            hash_leaves_flat::<F, C::Hasher>(leaves, digests_and_caps, params);
+        } else {
+            lde_flat(coeffs, scratch, params);
+            build_merkle_tree_flat::<F, C::Hasher>(scratch, digests_and_caps, params);
+            transpose_flat(scratch, leaves, params.leaf_len(), params.lde_size());
+        }
+        Ok(())
+    }
+
+    fn lde_from_values(
+        &mut self,
+        offset: usize,
+        params: &FlatTreeParams,
+        coeffs: &mut [F],
+    ) -> ProverResult<()> {
+        ensure_shape!(coeffs.len() == params.values_len());
+        <Self as ProverBackend<F, C, D>>::ifft(
+            self,
+            offset,
+            params.poly_num,
+            params.values_num_per_poly,
+        )?;
+        let range = self.range(offset, params.ext_values_len() + params.scratch_len())?;
+        let region = &mut self.buffer[range];
+        coeffs.copy_from_slice(&region[..params.values_len()]);
+
+        let (leaves, scratch) = region.split_at_mut(params.ext_values_len());
+        if params.chunk_polys.is_some() {
+            chunked_lde_flat(coeffs, leaves, scratch, params);
+        } else {
+            lde_flat(coeffs, scratch, params);
+            scratch[..params.poly_num * params.lde_size()]
+                .par_chunks_exact_mut(params.lde_size())
+                .for_each(reverse_index_bits_in_place);
+            transpose_flat(scratch, leaves, params.leaf_len(), params.lde_size());
+        }
+        Ok(())
+    }
+
+    fn hash_leaves(&mut self, offset: usize, params: &FlatTreeParams) -> ProverResult<()> {
+        let range = self.range(offset, params.footprint())?;
+        let (leaves, rest) = self.buffer[range].split_at_mut(params.ext_values_len());
+        let digests_and_caps = &mut rest[params.scratch_len()..];
+        hash_leaves_flat::<F, C::Hasher>(leaves, digests_and_caps, params);
+        Ok(())
+    }
+
+    fn build_merkle_tree(&mut self, offset: usize, params: &FlatTreeParams) -> ProverResult<()> {
+        ensure_shape!(
+            params.chunk_polys.is_none(),
+            "build_merkle_tree needs the LDE of the whole batch"
+        );
+        let range = self.range(offset, params.footprint())?;
+        let region = &mut self.buffer[range];
+        let (lde, digests_and_caps) =
+            region[params.ext_values_len()..].split_at_mut(params.ext_values_len());
+        build_merkle_tree_flat::<F, C::Hasher>(lde, digests_and_caps, params);
+        Ok(())
+    }
+
+    fn compute_quotient_polys(&mut self, job: &QuotientPolysJob<F, D>) -> ProverResult<()> {
+        let wires = self.range(job.wires_offset, job.wires.ext_values_len())?;
+        let zs_partial_products = self.range(
+            job.zs_partial_products_offset,
+            job.zs_partial_products.ext_values_len(),
+        )?;
+        let tables = self.tables.as_ref().ok_or_else(|| {
+            ProverError::Shape(String::from("The backend holds no circuit tables"))
+        })?;
+
+        let mut quotient_polys =
+            vec![F::ZERO; job.alphas.len() * job.zs_partial_products.lde_size()];
+        compute_quotient_polys_packed::<F, C, D>(
+            job.common_data,
+            job.public_inputs_hash,
+            &self.buffer[wires],
+            &job.wires,
+            &tables.shift_inv_powers,
+            &self.buffer[zs_partial_products],
+            &tables.constants_sigmas_leaves,
+            &mut quotient_polys,
+            &tables.points,
+            &tables.z_h_on_coset_evals,
+            &tables.z_h_on_coset_inverses,
+            &tables.k_is,
+            job.alphas,
+            job.betas,
+            job.gammas,
+        )?;
+        <Self as ProverBackend<F, C, D>>::write(self, job.quotient_polys_offset, &quotient_polys)
+    }
+
+    fn host_leaves(&self, offset: usize, params: &FlatTreeParams) -> ProverResult<Arc<Vec<F>>> {
+        let range = self.range(offset, params.ext_values_len())?;
+        Ok(Arc::new(self.buffer[range].to_vec()))
+    }
+
+    fn grind_proof_of_work(&mut self, job: &PowJob<F>) -> ProverResult<F> {
+        let cancelled = self.cancelled.as_deref();
+        grind_proof_of_work::<F, <C::Hasher as Hasher<F>>::Permutation>(job, &|| {
+            cancelled.map_or(false, |cancelled| cancelled.load(Ordering::Relaxed))
+        })
+    }
+}
+
+/// Interpolates each `values_num_per_poly`-long chunk of `values` in place, like `ifft_kernel`.
+pub fn ifft_flat<F: Field>(values: &mut [F], values_num_per_poly: usize) {
+    values
+        .par_chunks_mut(values_num_per_poly)
+        .for_each(|chunk| {
+            let coeffs = PolynomialValues::new(chunk.to_vec()).ifft();
+            chunk.copy_from_slice(&coeffs.coeffs);
+        });
+}
+
+/// Writes the coset LDE of the poly-major `coeffs`, poly-major and in natural order, into `lde`,
+/// followed by `salt_size` random columns. This is what `lde_kernel`, `init_lde_kernel`,
+/// `mul_shift_kernel` and `fft_kernel` compute together; the kernels leave the salt columns
+/// untouched instead.
+pub fn lde_flat<F: RichField>(coeffs: &[F], lde: &mut [F], params: &FlatTreeParams) {
+    let lde_size = params.lde_size();
+    let (values, salt) = lde.split_at_mut(params.poly_num * lde_size);
+    lde_polys_flat(coeffs, values, params);
+    salt.copy_from_slice(&F::rand_vec(params.salt_size * lde_size));
+}
+
+/// Writes the coset LDE of each polynomial of the poly-major `coeffs` into `lde`.
+fn lde_polys_flat<F: RichField>(coeffs: &[F], lde: &mut [F], params: &FlatTreeParams) {
+    lde.par_chunks_exact_mut(params.lde_size())
+        .zip(coeffs.par_chunks_exact(params.values_num_per_poly))
+        .for_each(|(out, coeffs)| {
+            let lde_values = PolynomialCoeffs::new(coeffs.to_vec())
+                .lde(params.rate_bits)
+                .coset_fft_with_options(F::coset_shift(), Some(params.rate_bits), None)
+                .values;
+            out.copy_from_slice(&lde_values);
+        });
+}
+
+/// Writes the bit-reversed coset LDE of the poly-major `coeffs` into the columns of the row-major
+/// `leaves`, `params.chunk_polys` polynomials at a time, then fills the salt columns with random
+/// values. `scratch` holds the coefficients of a chunk followed by their LDE, like the device
+/// memory used by `plonky2_cuda::lde_columns`.
+pub fn chunked_lde_flat<F: RichField>(
+    coeffs: &[F],
+    leaves: &mut [F],
+    scratch: &mut [F],
+    params: &FlatTreeParams,
+) {
+    let n = params.values_num_per_poly;
+    let lde_size = params.lde_size();
+    let leaf_len = params.leaf_len();
+    let chunk_polys = params.chunk_polys.unwrap_or(params.poly_num);
+    let (chunk_coeffs, chunk_lde) = scratch.split_at_mut(chunk_polys * n);
+
+    for (i, coeffs) in coeffs.chunks(chunk_polys * n).enumerate() {
+        let polys = coeffs.len() / n;
+        chunk_coeffs[..coeffs.len()].copy_from_slice(coeffs);
+        let chunk_lde = &mut chunk_lde[..polys * lde_size];
+        lde_polys_flat(&chunk_coeffs[..coeffs.len()], chunk_lde, params);
+        chunk_lde
+            .par_chunks_exact_mut(lde_size)
+            .for_each(reverse_index_bits_in_place);
+
+        let first_column = i * chunk_polys;
+        leaves
+            .par_chunks_exact_mut(leaf_len)
+            .enumerate()
+            .for_each(|(row, leaf)| {
+                for (j, x) in leaf[first_column..first_column + polys].iter_mut().enumerate() {
+                    *x = chunk_lde[j * lde_size + row];
+                }
+            });
+    }
+
+    if params.salt_size > 0 {
+        let salt = F::rand_vec(params.salt_size * lde_size);
+        leaves
+            .par_chunks_exact_mut(leaf_len)
+            .zip(salt.par_chunks_exact(params.salt_size))
+            .for_each(|(leaf, salt)| leaf[params.poly_num..].copy_from_slice(salt));
+    }
+}
+
+/// Writes the digests and cap of the tree whose leaves are the rows of the row-major `leaves`
+/// into `digests_and_caps`. Mirrors `plonky2_cuda::hash_leaves`.
+pub fn hash_leaves_flat<F: RichField, H: Hasher<F>>(
+    leaves: &[F],
+    digests_and_caps: &mut [F],
+    params: &FlatTreeParams,
+) {
+    let leaves = leaves
+        .par_chunks_exact(params.leaf_len())
+        .map(|leaf| leaf.to_vec())
+        .collect::<Vec<_>>();
+    write_digests_and_caps::<F, H>(
+        &MerkleTree::<F, H>::new(leaves, params.cap_height),
+        digests_and_caps,
+    );
+}
+
+fn write_digests_and_caps<F: RichField, H: Hasher<F>>(
+    tree: &MerkleTree<F, H>,
+    digests_and_caps: &mut [F],
+) {
+    tree.digests
+        .iter()
+        .chain(&tree.cap.0)
+        .zip(digests_and_caps.chunks_exact_mut(NUM_HASH_OUT_ELTS))
+        .for_each(|(hash, out)| hash_to_elements::<F, H>(hash, out));
+}
+
+/// Bit-reverses the first `poly_num` columns of the poly-major `lde` in place, then writes the
+/// digests of the tree whose leaves are the rows of `lde`, followed by its cap, into
+/// `digests_and_caps`. Mirrors `reverse_index_bits_kernel`, `hash_leaves_kernel` and
+/// `reduce_digests_kernel`.
+pub fn build_merkle_tree_flat<F: RichField, H: Hasher<F>>(
+    lde: &mut [F],
+    digests_and_caps: &mut [F],
+    params: &FlatTreeParams,
+) {
+    let lde_size = params.lde_size();
+    lde[..params.poly_num * lde_size]
+        .par_chunks_exact_mut(lde_size)
+        .for_each(reverse_index_bits_in_place);
+
+    let leaf_len = params.leaf_len();
+    let leaves = (0..lde_size)
+        .into_par_iter()
+        .map(|i| (0..leaf_len).map(|j| lde[j * lde_size + i]).collect())
+        .collect::<Vec<Vec<F>>>();
+    let tree = MerkleTree::<F, H>::new(leaves, params.cap_height);
+    write_digests_and_caps(&tree, digests_and_caps);
+}
+
+/// Transposes the poly-major `lde` into row-major `leaves`, like `transpose_kernel`.
+pub fn transpose_flat<F: Field>(lde: &[F], leaves: &mut [F], leaf_len: usize, lde_size: usize) {
+    leaves
+        .par_chunks_exact_mut(leaf_len)
+        .enumerate()
+        .for_each(|(i, leaf)| {
+            for (j, x) in leaf.iter_mut().enumerate() {
+                *x = lde[j * lde_size + i];
+            }
+        });
+}
+
+/// The leaf of the flattened commitment `leaves` holding the `i`-th point of the LDE.
+pub(crate) fn flat_leaf<F>(leaves: &[F], leaf_len: usize, lde_bits: usize, i: usize) -> &[F] {
+    let index = reverse_bits(i, lde_bits);
+    &leaves[index * leaf_len..(index + 1) * leaf_len]
+}
+
+/// Evaluates the quotient polynomials at every point of the LDE coset from the flattened leaves of
+/// the three first oracles, one point at a time, then interpolates them. Mirrors
+/// `compute_quotient_values_kernel` followed by the transposition and coset IFFT done in
+/// `compute_quotient_polys`. Returns the `num_challenges` quotient polynomials in coefficient form,
+/// poly-major. This is the reference for `compute_quotient_polys_packed`, which the backend uses.
+pub fn quotient_polys_flat<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    common_data: &CommonCircuitData<F, D>,
+    public_inputs_hash: &HashOut<F>,
+    constants_sigmas_leaves: &[F],
+    constants_sigmas_leaf_len: usize,
+    wires_leaves: &[F],
+    wires_leaf_len: usize,
+    zs_partial_products_leaves: &[F],
+    zs_partial_products_leaf_len: usize,
+    alphas: &[F],
+    betas: &[F],
+    gammas: &[F],
+) -> ProverResult<Vec<F>> {
+    let config = &common_data.config;
+    let num_challenges = config.num_challenges;
+    let rate_bits = config.fri_config.rate_bits;
+    let degree_bits = common_data.degree_bits();
+    let quotient_degree_bits = log2_ceil(common_data.quotient_degree_factor);
+    ensure_shape!(
+        quotient_degree_bits == rate_bits,
+        "The flattened layout needs a quotient degree factor of 2^rate_bits, got {} with rate_bits = {}",
+        common_data.quotient_degree_factor,
+        rate_bits
+    );
+
+    let lde_bits = degree_bits + rate_bits;
+    let lde_size = 1 << lde_bits;
+    // The quotient is computed on the whole LDE, so the "next" point is `next_step` points away.
+    let next_step = 1 << quotient_degree_bits;
+    let points = F::two_adic_subgroup(lde_bits);
+    let z_h_on_coset = ZeroPolyOnCoset::new(degree_bits, quotient_degree_bits);
+    let num_batches = ceil_div_usize(lde_size, BATCH_SIZE);
+
+    let quotient_values: Vec<Vec<F>> = points
+        .par_chunks(BATCH_SIZE)
+        .enumerate()
+        .flat_map(|(batch_i, xs_batch)| {
+            // Each batch must be the same size, except the last one, which may be smaller.
+            debug_assert!(
+                xs_batch.len() == BATCH_SIZE
+                    || (batch_i == num_batches - 1 && xs_batch.len() <= BATCH_SIZE)
+            );
+            let indices_batch: Vec<usize> =
+                (BATCH_SIZE * batch_i..BATCH_SIZE * batch_i + xs_batch.len()).collect();
+
+            let mut shifted_xs_batch = Vec::with_capacity(xs_batch.len());
+            let mut local_zs_batch = Vec::with_capacity(xs_batch.len());
+            let mut next_zs_batch = Vec::with_capacity(xs_batch.len());
+            let mut partial_products_batch = Vec::with_capacity(xs_batch.len());
+            let mut s_sigmas_batch = Vec::with_capacity(xs_batch.len());
+            let mut local_constants_batch_refs = Vec::with_capacity(xs_batch.len());
+            let mut local_wires_batch_refs = Vec::with_capacity(xs_batch.len());
+
+            for (&i, &x) in indices_batch.iter().zip(xs_batch) {
+                let i_next = (i + next_step) % lde_size;
+                let local_constants_sigmas =
+                    flat_leaf(constants_sigmas_leaves, constants_sigmas_leaf_len, lde_bits, i);
+                let local_wires = &flat_leaf(wires_leaves, wires_leaf_len, lde_bits, i)
+                    [..config.num_wires];
+                let local_zs_partial_products = flat_leaf(
+                    zs_partial_products_leaves,
+                    zs_partial_products_leaf_len,
+                    lde_bits,
+                    i,
+                );
+                let next_zs_partial_products = flat_leaf(
+                    zs_partial_products_leaves,
+                    zs_partial_products_leaf_len,
+                    lde_bits,
+                    i_next,
+                );
+
+                local_constants_batch_refs
+                    .push(&local_constants_sigmas[common_data.constants_range()]);
+                s_sigmas_batch.push(&local_constants_sigmas[common_data.sigmas_range()]);
+                local_wires_batch_refs.push(local_wires);
+                shifted_xs_batch.push(F::coset_shift() * x);
+                local_zs_batch.push(&local_zs_partial_products[common_data.zs_range()]);
+                next_zs_batch.push(&next_zs_partial_products[common_data.zs_range()]);
+                partial_products_batch.push(
+                    &local_zs_partial_products[common_data.partial_products_range()],
+                );
+            }
+
+            let local_constants_batch = transpose_batch(&local_constants_batch_refs);
+            let local_wires_batch = transpose_batch(&local_wires_batch_refs);
+            let vars_batch = EvaluationVarsBaseBatch::new(
+                xs_batch.len(),
+                &local_constants_batch,
+                &local_wires_batch,
+                public_inputs_hash,
+            );
+
+            let mut quotient_values_batch = eval_vanishing_poly_base_batch::<F, C, D>(
+                common_data,
+                &indices_batch,
+                &shifted_xs_batch,
+                vars_batch,
+                &local_zs_batch,
+                &next_zs_batch,
+                &partial_products_batch,
+                &s_sigmas_batch,
+                betas,
+                gammas,
+                alphas,
+                &z_h_on_coset,
+            );
+
+            for (&i, quotient_values) in indices_batch.iter().zip(quotient_values_batch.iter_mut())
+            {
+                let denominator_inv = z_h_on_coset.eval_inverse(i);
+                quotient_values
+                    .iter_mut()
+                    .for_each(|v| *v *= denominator_inv);
+            }
+            quotient_values_batch
+        })
+        .collect();
+
+    let mut quotient_polys = vec![F::ZERO; num_challenges * lde_size];
+    quotient_polys
+        .par_chunks_exact_mut(lde_size)
+        .enumerate()
+        .for_each(|(i, out)| {
+            let values = quotient_values.iter().map(|v| v[i]).collect();
+            let coeffs = PolynomialValues::new(values).coset_ifft(F::coset_shift());
+            out.copy_from_slice(&coeffs.coeffs);
+        });
+    Ok(quotient_polys)
+}
+
+/// Lays out `rows` column-major, as expected by `EvaluationVarsBaseBatch`.
+fn transpose_batch<F: Field>(rows: &[&[F]]) -> Vec<F> {
+    let batch_size = rows.len();
+    let mut res = vec![F::ZERO; batch_size * rows[0].len()];
+    for (j, row) in rows.iter().enumerate() {
+        for (i, &x) in row.iter().enumerate() {
+            res[i * batch_size + j] = x;
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::fri::oracle::PolynomialBatch;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+    use crate::plonk::config::{KeccakGoldilocksConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::prover::ProvingStrategy;
+    use crate::plonk::verifier::verify;
+    use crate::util::timing::TimingTree;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_flat_commitment_matches_classic() -> Result<()> {
+        let params = FlatTreeParams {
+            poly_num: 5,
+            values_num_per_poly: 1 << 4,
+            rate_bits: 2,
+            salt_size: 0,
+            cap_height: 1,
+            chunk_polys: None,
+        };
+        let values = (0..params.poly_num)
+            .map(|_| PolynomialValues::new(F::rand_vec(params.values_num_per_poly)))
+            .collect::<Vec<_>>();
+        let values_flatten = values
+            .iter()
+            .flat_map(|v| v.values.clone())
+            .collect::<Vec<_>>();
+
+        let offset = 3;
+        let mut backend = CpuBackend::<F>::new(offset + params.footprint(), 0);
+        let mut timing = TimingTree::default();
+        let flat = PolynomialBatch::<F, C, D>::from_values_with_gpu(
+            &values_flatten,
+            params.poly_num,
+            params.values_num_per_poly,
+            params.rate_bits,
+            false,
+            params.cap_height,
+            offset,
+            &mut timing,
+            &mut backend,
+        )?;
+        let classic = PolynomialBatch::<F, C, D>::from_values(
+            values,
+            params.rate_bits,
+            false,
+            params.cap_height,
+            &mut timing,
+            None,
+        );
+
+        assert_eq!(flat.polynomials, classic.polynomials);
+        assert_eq!(flat.merkle_tree.cap, classic.merkle_tree.cap);
+        for i in 0..params.lde_size() {
+            assert_eq!(flat.get_lde_values(i, 1), classic.get_lde_values(i, 1));
+            assert_eq!(
+                flat.merkle_tree.prove(i).siblings,
+                classic.merkle_tree.prove(i).siblings
+            );
+        }
+        Ok(())
+    }
+
+    fn mul_add_circuit() -> (CircuitData<F, C, D>, PartialWitness<F>) {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        let z = builder.mul_add(x, y, x);
+        builder.register_public_input(z);
+        pw.set_target(x, F::rand());
+        pw.set_target(y, F::rand());
+
+        (builder.build::<C>(), pw)
+    }
+
+    /// Proves with `strategy` and checks that the proof is the one of the classic prover.
+    fn check_strategy(
+        data: &CircuitData<F, C, D>,
+        pw: PartialWitness<F>,
+        strategy: ProvingStrategy<F, C, D>,
+    ) -> Result<()> {
+        let proof = data.prove_with_strategy(pw.clone(), strategy)?;
+        let classic = data.prove(pw)?;
+        assert_eq!(proof, classic);
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_prove_cpu_flat() -> Result<()> {
+        let (data, pw) = mul_add_circuit();
+        check_strategy(&data, pw, ProvingStrategy::CpuFlat)
+    }
+
+    #[test]
+    fn test_cpu_flat_rejects_byte_hashes() {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        let data = builder.build::<KeccakGoldilocksConfig>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::rand());
+        let err = data
+            .prove_with_strategy(pw, ProvingStrategy::CpuFlat)
+            .unwrap_err();
+        assert!(matches!(err, ProverError::Unsupported(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_prove_with_cpu_backend() -> Result<()> {
+        let (data, pw) = mul_add_circuit();
+        let mut backend = CpuBackend::for_circuit(&data.prover_only, &data.common)?;
+        check_strategy(&data, pw, ProvingStrategy::Accelerated(&mut backend))
+    }
+
+    #[test]
+    fn test_cancelled_proof_of_work() -> Result<()> {
+        let (data, pw) = mul_add_circuit();
+        let cancelled = Arc::new(AtomicBool::new(true));
+        let mut backend =
+            CpuBackend::for_circuit(&data.prover_only, &data.common)?.with_cancellation(cancelled);
+        let err = data
+            .prove_with_strategy(pw, ProvingStrategy::Accelerated(&mut backend))
+            .unwrap_err();
+        assert_eq!(err, ProverError::Cancelled);
+        assert!(!err.can_retry_on_cpu());
+        Ok(())
+    }
+
+    /// Counts the FRI operations run through the backend, on top of a `CpuBackend`.
+    struct FriCounting {
+        inner: CpuBackend<F>,
+        reductions: usize,
+        ffts: usize,
+        layers: usize,
+        folds: usize,
+    }
+
+    impl ProverBackend<F, C, D> for FriCounting {
+        fn buffer_len(&self) -> usize {
+            ProverBackend::<F, C, D>::buffer_len(&self.inner)
+        }
+
+        fn second_stage_offset(&self) -> usize {
+            ProverBackend::<F, C, D>::second_stage_offset(&self.inner)
+        }
+
+        fn write(&mut self, offset: usize, values: &[F]) -> ProverResult<()> {
+            ProverBackend::<F, C, D>::write(&mut self.inner, offset, values)
+        }
+
+        fn read(&self, offset: usize, out: &mut [F]) -> ProverResult<()> {
+            ProverBackend::<F, C, D>::read(&self.inner, offset, out)
+        }
+
+        fn ifft(
+            &mut self,
+            offset: usize,
+            poly_num: usize,
+            values_num_per_poly: usize,
+        ) -> ProverResult<()> {
+            ProverBackend::<F, C, D>::ifft(&mut self.inner, offset, poly_num, values_num_per_poly)
+        }
+
+        fn merkle_tree_from_values(
+            &mut self,
+            offset: usize,
+            params: &FlatTreeParams,
+            coeffs: &mut [F],
+        ) -> ProverResult<()> {
+            ProverBackend::<F, C, D>::merkle_tree_from_values(
+                &mut self.inner,
+                offset,
+                params,
+                coeffs,
+            )
+        }
+
+        fn merkle_tree_from_coeffs(
+            &mut self,
+            offset: usize,
+            params: &FlatTreeParams,
+            coeffs: &mut [F],
+        ) -> ProverResult<()> {
+            ProverBackend::<F, C, D>::merkle_tree_from_coeffs(
+                &mut self.inner,
+                offset,
+                params,
+                coeffs,
+            )
+        }
+
+        fn hash_leaves(&mut self, offset: usize, params: &FlatTreeParams) -> ProverResult<()> {
+            ProverBackend::<F, C, D>::hash_leaves(&mut self.inner, offset, params)
+        }
+
+        fn build_merkle_tree(
+            &mut self,
+            offset: usize,
+            params: &FlatTreeParams,
+        ) -> ProverResult<()> {
+            ProverBackend::<F, C, D>::build_merkle_tree(&mut self.inner, offset, params)
+        }
+
+        fn compute_quotient_polys(&mut self, job: &QuotientPolysJob<F, D>) -> ProverResult<()> {
+            ProverBackend::<F, C, D>::compute_quotient_polys(&mut self.inner, job)
+        }
+
+        fn host_leaves(
+            &self,
+            offset: usize,
+            params: &FlatTreeParams,
+        ) -> ProverResult<Arc<Vec<F>>> {
+            ProverBackend::<F, C, D>::host_leaves(&self.inner, offset, params)
+        }
+
+        fn reduce_opening_batch(
+            &mut self,
+            polys: &[&PolynomialCoeffs<F>],
+            alpha: <F as Extendable<D>>::Extension,
+            point: <F as Extendable<D>>::Extension,
+        ) -> ProverResult<PolynomialCoeffs<<F as Extendable<D>>::Extension>> {
+            self.reductions += 1;
+            ProverBackend::<F, C, D>::reduce_opening_batch(&mut self.inner, polys, alpha, point)
+        }
+
+        fn coset_fft_extension(
+            &mut self,
+            coeffs: &PolynomialCoeffs<<F as Extendable<D>>::Extension>,
+            shift: F,
+        ) -> ProverResult<PolynomialValues<<F as Extendable<D>>::Extension>> {
+            self.ffts += 1;
+            ProverBackend::<F, C, D>::coset_fft_extension(&mut self.inner, coeffs, shift)
+        }
+
+        fn commit_fri_layer(
+            &mut self,
+            values: &mut PolynomialValues<<F as Extendable<D>>::Extension>,
+            arity_bits: usize,
+            cap_height: usize,
+        ) -> ProverResult<MerkleTree<F, <C as GenericConfig<D>>::Hasher>> {
+            self.layers += 1;
+            ProverBackend::<F, C, D>::commit_fri_layer(
+                &mut self.inner,
+                values,
+                arity_bits,
+                cap_height,
+            )
+        }
+
+        fn fold_fri_layer(
+            &mut self,
+            coeffs: &PolynomialCoeffs<<F as Extendable<D>>::Extension>,
+            arity_bits: usize,
+            beta: <F as Extendable<D>>::Extension,
+        ) -> ProverResult<PolynomialCoeffs<<F as Extendable<D>>::Extension>> {
+            self.folds += 1;
+            ProverBackend::<F, C, D>::fold_fri_layer(&mut self.inner, coeffs, arity_bits, beta)
+        }
+    }
+
+    #[test]
+    fn test_fri_runs_through_backend() -> Result<()> {
+        let (data, pw) = mul_add_circuit();
+        let mut backend = FriCounting {
+            inner: CpuBackend::for_circuit(&data.prover_only, &data.common)?,
+            reductions: 0,
+            ffts: 0,
+            layers: 0,
+            folds: 0,
+        };
+        check_strategy(&data, pw, ProvingStrategy::Accelerated(&mut backend))?;
+
+        let num_layers = data.common.fri_params.reduction_arity_bits.len();
+        assert_eq!(backend.reductions, 2);
+        assert_eq!(backend.ffts, num_layers + 1);
+        assert_eq!(backend.layers, num_layers);
+        assert_eq!(backend.folds, num_layers);
+        Ok(())
+    }
+
+    #[test]
+    fn test_prove_with_chunked_commitments() -> Result<()> {
+        let (data, pw) = mul_add_circuit();
+        // 135 wires make 8 full chunks and a partial one.
+        let layout = ProofLayout::with_chunk_polys(&data.common, Some(16))?;
+        assert!(layout.is_chunked());
+        let mut backend = CpuBackend::with_layout(&data.prover_only, &data.common, layout)?;
+        check_strategy(&data, pw, ProvingStrategy::Accelerated(&mut backend))
+    }
+}
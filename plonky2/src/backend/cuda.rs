@@ -0,0 +1,809 @@
+//! `ProverBackend` implementation on top of the `plonky2_cuda` kernels.
+//!
+//! A [`CudaInvContext`] is built once per circuit: it owns the CUDA context and streams, the
+//! working buffer sized by [`ProofLayout`], and the device copies of the [`CircuitTables`] and of
+//! the gate table. It can then be reused for any number of proofs of that circuit.
+//!
+//! The FRI commit phase and the reduction of the opening batches also run on the device, in the
+//! FRI region of the layout; the proof-of-work search and the query rounds run on the host.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::{type_name, TypeId};
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::mem::{size_of, transmute};
+
+use plonky2_cuda::DataSlice;
+use rustacuda::context::{Context, ContextFlags};
+use rustacuda::device::Device;
+use rustacuda::error::CudaError;
+use rustacuda::memory::{AsyncCopyDestination, DeviceBuffer, DeviceSlice};
+use rustacuda::stream::{Stream, StreamFlags};
+use rustacuda::CudaFlags;
+
+use crate::backend::gates::{GateTable, QuotientKernelParams};
+use crate::backend::layout::ELEMENT_BYTES;
+use crate::backend::{
+    hash_from_elements, CircuitTables, FlatTreeParams, ProofLayout, ProverBackend,
+    QuotientPolysJob,
+};
+use crate::field::extension::{flatten, unflatten, Extendable, FieldExtension};
+use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
+use crate::field::types::Field;
+use crate::fri::prover::{commit_fri_layer, fold_fri_layer, reduce_opening_batch};
+use crate::hash::hash_types::{RichField, NUM_HASH_OUT_ELTS};
+use crate::hash::merkle_tree::{MerkleCap, MerkleTree};
+use crate::hash::poseidon::PoseidonHash;
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::GenericConfig;
+use crate::plonk::error::{ensure_shape, ProverError, ProverResult};
+use crate::util::{log2_strict, reverse_index_bits_in_place};
+
+/// Calls a `plonky2_cuda` entry point and turns a non-zero status into a `ProverError::Kernel`.
+macro_rules! cuda_call {
+    ($name:expr, $call:expr) => {{
+        let status = unsafe { $call };
+        if status.code != 0 {
+            return Err(ProverError::Kernel {
+                name: $name,
+                message: String::from(status),
+            });
+        }
+    }};
+}
+
+fn device_error(e: CudaError) -> ProverError {
+    ProverError::Device(format!("{:?}", e))
+}
+
+fn allocation_error(
+    elements: usize,
+    element_bytes: usize,
+) -> impl FnOnce(CudaError) -> ProverError {
+    move |e| ProverError::Allocation {
+        bytes: elements * element_bytes,
+        message: format!("{:?}", e),
+    }
+}
+
+/// The streams of a context, as read by the `plonky2_cuda` entry points.
+#[repr(C)]
+pub struct CudaInnerContext {
+    pub stream: Stream,
+    pub stream2: Stream,
+}
+
+/// Device state for proving a given circuit. Field elements are stored as their `u64`
+/// representation, which is what the kernels operate on.
+pub struct CudaInvContext<F: RichField> {
+    inner: CudaInnerContext,
+    layout: ProofLayout,
+    quotient_params: QuotientKernelParams,
+    cache_mem_device: DeviceBuffer<u64>,
+
+    root_table_device: DeviceBuffer<u64>,
+    root_table_device2: DeviceBuffer<u64>,
+    shift_powers_device: DeviceBuffer<u64>,
+    shift_inv_powers_device: DeviceBuffer<u64>,
+    constants_sigmas_commitment_leaves_device: DeviceBuffer<u64>,
+    points_device: DeviceBuffer<u64>,
+    z_h_on_coset_evals_device: DeviceBuffer<u64>,
+    z_h_on_coset_inverses_device: DeviceBuffer<u64>,
+    k_is_device: DeviceBuffer<u64>,
+    gate_table_device: DeviceBuffer<u32>,
+
+    _phantom: PhantomData<F>,
+    // Declared last so that it is dropped after every buffer and stream allocated in it.
+    _ctx: Context,
+}
+
+/// Builder of a [`CudaInvContext`]. See [`CudaInvContext::builder`].
+pub struct CudaInvContextBuilder<
+    'a,
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+    common_data: &'a CommonCircuitData<F, D>,
+    device_ordinal: u32,
+    memory_budget: Option<usize>,
+}
+
+impl<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    CudaInvContextBuilder<'a, F, C, D>
+{
+    /// Selects the device to run on. Defaults to device 0.
+    pub fn device(mut self, ordinal: u32) -> Self {
+        self.device_ordinal = ordinal;
+        self
+    }
+
+    /// Limits the device memory used for proving to `bytes`, which may make commitments compute
+    /// their LDE in chunks. Defaults to the total memory of the device.
+    pub fn memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = Some(bytes);
+        self
+    }
+
+    /// Checks that the circuit is supported by the kernels and fits in the memory budget, creates
+    /// a context on the device, allocates the working buffer and uploads the constant tables.
+    ///
+    /// Circuits the kernels cannot prove, such as circuits with unsupported gates, lookups or too
+    /// many challenges, are rejected with `ProverError::Unsupported` before the device is touched.
+    /// So are configurations whose `Hasher` is not `PoseidonHash`, the only hash of the Merkle
+    /// tree kernels.
+    pub fn build(self) -> ProverResult<CudaInvContext<F>>
+    where
+        C::Hasher: 'static,
+    {
+        if TypeId::of::<C::Hasher>() != TypeId::of::<PoseidonHash>() {
+            return Err(ProverError::Unsupported(format!(
+                "The CUDA kernels build Merkle trees with PoseidonHash, not {}",
+                type_name::<C::Hasher>()
+            )));
+        }
+        let gate_table = GateTable::new(self.common_data)?.to_u32s();
+        let quotient_params = QuotientKernelParams::new(self.common_data)?;
+
+        rustacuda::init(CudaFlags::empty()).map_err(device_error)?;
+        let device = Device::get_device(self.device_ordinal).map_err(device_error)?;
+        let memory_budget = match self.memory_budget {
+            Some(bytes) => bytes,
+            None => device.total_memory().map_err(device_error)?,
+        };
+        let layout = ProofLayout::with_budget(self.common_data, memory_budget)?;
+        let tables = CircuitTables::new(self.prover_data, self.common_data);
+        ensure_shape!(
+            tables.constants_sigmas_leaf_len
+                >= quotient_params.num_constants + quotient_params.num_routed_wires
+                && tables.constants_sigmas_leaves.len()
+                    == tables.constants_sigmas_leaf_len * layout.wires.lde_size(),
+            "The constants and sigmas commitment does not match the circuit"
+        );
+        let ctx =
+            Context::create_and_push(ContextFlags::MAP_HOST | ContextFlags::SCHED_AUTO, device)
+                .map_err(device_error)?;
+        let new_stream = || Stream::new(StreamFlags::NON_BLOCKING, None).map_err(device_error);
+        let inner = CudaInnerContext {
+            stream: new_stream()?,
+            stream2: new_stream()?,
+        };
+
+        let upload = |values: &[F]| {
+            DeviceBuffer::from_slice(as_u64s(values))
+                .map_err(allocation_error(values.len(), ELEMENT_BYTES))
+        };
+        Ok(CudaInvContext {
+            inner,
+            layout,
+            quotient_params,
+            cache_mem_device: unsafe { DeviceBuffer::zeroed(layout.buffer_len) }
+                .map_err(allocation_error(layout.buffer_len, ELEMENT_BYTES))?,
+            root_table_device: upload(&tables.root_table)?,
+            root_table_device2: upload(&tables.root_table_lde)?,
+            shift_powers_device: upload(&tables.shift_powers)?,
+            shift_inv_powers_device: upload(&tables.shift_inv_powers)?,
+            constants_sigmas_commitment_leaves_device: upload(&tables.constants_sigmas_leaves)?,
+            points_device: upload(&tables.points)?,
+            z_h_on_coset_evals_device: upload(&tables.z_h_on_coset_evals)?,
+            z_h_on_coset_inverses_device: upload(&tables.z_h_on_coset_inverses)?,
+            k_is_device: upload(&tables.k_is)?,
+            gate_table_device: DeviceBuffer::from_slice(&gate_table)
+                .map_err(allocation_error(gate_table.len(), size_of::<u32>()))?,
+            _phantom: PhantomData,
+            _ctx: ctx,
+        })
+    }
+}
+
+fn as_u64s<F: Field>(values: &[F]) -> &[u64] {
+    unsafe { transmute::<&[F], &[u64]>(values) }
+}
+
+fn as_u64s_mut<F: Field>(values: &mut [F]) -> &mut [u64] {
+    unsafe { transmute::<&mut [F], &mut [u64]>(values) }
+}
+
+fn data_slice<T>(slice: &DeviceSlice<T>) -> DataSlice {
+    DataSlice {
+        ptr: slice.as_ptr() as *const c_void,
+        len: slice.len() as i32,
+    }
+}
+
+impl<F: RichField> CudaInvContext<F> {
+    /// Starts building a context for proving the given circuit.
+    pub fn builder<'a, C: GenericConfig<D, F = F>, const D: usize>(
+        prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+        common_data: &'a CommonCircuitData<F, D>,
+    ) -> CudaInvContextBuilder<'a, F, C, D>
+    where
+        F: Extendable<D>,
+    {
+        CudaInvContextBuilder {
+            prover_data,
+            common_data,
+            device_ordinal: 0,
+            memory_budget: None,
+        }
+    }
+
+    /// Layout of the working buffer.
+    pub fn layout(&self) -> &ProofLayout {
+        &self.layout
+    }
+
+    fn ctx_ptr(&mut self) -> *mut c_void {
+        let ctx_ptr: *mut CudaInnerContext = &mut self.inner;
+        ctx_ptr as *mut c_void
+    }
+
+    fn write_elements(&mut self, offset: usize, values: &[F]) -> ProverResult<()> {
+        self.check_range(offset, values.len())?;
+        let dst = &mut self.cache_mem_device[offset..offset + values.len()];
+        unsafe { dst.async_copy_from(as_u64s(values), &self.inner.stream) }
+            .map_err(device_error)?;
+        self.inner
+            .stream
+            .synchronize()
+            .map_err(device_error)
+    }
+
+    fn read_elements(&self, offset: usize, out: &mut [F]) -> ProverResult<()> {
+        self.check_range(offset, out.len())?;
+        let src = &self.cache_mem_device[offset..offset + out.len()];
+        unsafe { src.async_copy_to(as_u64s_mut(out), &self.inner.stream) }
+            .map_err(device_error)?;
+        self.inner
+            .stream
+            .synchronize()
+            .map_err(device_error)
+    }
+
+    /// Commits to polynomials whose LDE does not fit in the working buffer at once. The
+    /// coefficients, already copied to `coeffs`, are uploaded again one chunk at a time into the
+    /// scratch area, and the LDE of each chunk is written into its columns of the leaves, followed
+    /// by the salt.
+    fn chunked_merkle_tree_from_coeffs(
+        &mut self,
+        offset: usize,
+        params: &FlatTreeParams,
+        coeffs: &[F],
+    ) -> ProverResult<()> {
+        let n = params.values_num_per_poly;
+        let chunk_polys = params.chunk_polys.unwrap_or(params.poly_num);
+        let scratch_offset = offset + params.ext_values_len();
+        let lde_offset = scratch_offset + chunk_polys * n;
+
+        for (i, chunk) in coeffs.chunks(chunk_polys * n).enumerate() {
+            self.write_elements(scratch_offset, chunk)?;
+            let coeffs_ptr = self.cache_mem_device[scratch_offset..].as_mut_ptr();
+            let lde_ptr = self.cache_mem_device[lde_offset..].as_mut_ptr();
+            let leaves_ptr = self.cache_mem_device[offset..].as_mut_ptr();
+            let ctx_ptr = self.ctx_ptr();
+            cuda_call!(
+                "lde_columns",
+                plonky2_cuda::lde_columns(
+                    coeffs_ptr,
+                    lde_ptr,
+                    (chunk.len() / n) as i32,
+                    n as i32,
+                    params.log_len() as i32,
+                    self.root_table_device2.as_ptr(),
+                    self.shift_powers_device.as_ptr(),
+                    params.rate_bits as i32,
+                    leaves_ptr,
+                    params.leaf_len() as i32,
+                    (i * chunk_polys) as i32,
+                    ctx_ptr,
+                )
+            );
+        }
+
+        self.write_salt_columns(offset, params)?;
+        self.hash_leaves_at(offset, params)
+    }
+
+    /// Fills the salt columns of the row-major leaves at `offset` with random values, going
+    /// through the scratch area as many columns at a time as it holds.
+    fn write_salt_columns(&mut self, offset: usize, params: &FlatTreeParams) -> ProverResult<()> {
+        if params.salt_size == 0 {
+            return Ok(());
+        }
+        let lde_size = params.lde_size();
+        let scratch_offset = offset + params.ext_values_len();
+        let group_columns = params.scratch_len() / lde_size;
+        let salt = F::rand_vec(params.salt_size * lde_size);
+
+        for (i, columns) in salt.chunks(group_columns * lde_size).enumerate() {
+            self.write_elements(scratch_offset, columns)?;
+            let values_ptr = self.cache_mem_device[scratch_offset..].as_ptr();
+            let leaves_ptr = self.cache_mem_device[offset..].as_mut_ptr();
+            let ctx_ptr = self.ctx_ptr();
+            cuda_call!(
+                "write_columns",
+                plonky2_cuda::write_columns(
+                    values_ptr,
+                    (columns.len() / lde_size) as i32,
+                    lde_size as i32,
+                    leaves_ptr,
+                    params.leaf_len() as i32,
+                    (params.poly_num + i * group_columns) as i32,
+                    ctx_ptr,
+                )
+            );
+        }
+        Ok(())
+    }
+
+    fn hash_leaves_at(&mut self, offset: usize, params: &FlatTreeParams) -> ProverResult<()> {
+        self.hash_rows(
+            offset,
+            params.leaf_len(),
+            params.lde_size(),
+            params.cap_height,
+            offset + params.digests_offset(),
+        )
+    }
+
+    /// Writes the digests and cap of the `leaves_len` rows of `leaf_len` elements at
+    /// `leaves_offset` at `digests_offset`.
+    fn hash_rows(
+        &mut self,
+        leaves_offset: usize,
+        leaf_len: usize,
+        leaves_len: usize,
+        cap_height: usize,
+        digests_offset: usize,
+    ) -> ProverResult<()> {
+        let leaves_ptr = self.cache_mem_device[leaves_offset..].as_mut_ptr();
+        let digests_ptr = self.cache_mem_device[digests_offset..].as_mut_ptr();
+        let ctx_ptr = self.ctx_ptr();
+        cuda_call!(
+            "hash_leaves",
+            plonky2_cuda::hash_leaves(
+                leaves_ptr,
+                leaf_len as i32,
+                leaves_len as i32,
+                cap_height as i32,
+                digests_ptr,
+                ctx_ptr,
+            )
+        );
+        Ok(())
+    }
+
+    /// Whether the FRI region of the working buffer holds `len` elements. The FRI kernels only
+    /// support the quadratic extension; other extensions, and FRI layers which do not fit, are
+    /// handled on the host.
+    fn fri_fits<const D: usize>(&self, len: usize) -> bool {
+        D == 2 && len <= self.layout.fri_len
+    }
+
+    fn check_range(&self, offset: usize, len: usize) -> ProverResult<()> {
+        ensure_shape!(
+            offset + len <= self.cache_mem_device.len(),
+            "range {}..{} is outside of the device buffer of {} elements",
+            offset,
+            offset + len,
+            self.cache_mem_device.len()
+        );
+        Ok(())
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    ProverBackend<F, C, D> for CudaInvContext<F>
+{
+    fn buffer_len(&self) -> usize {
+        self.cache_mem_device.len()
+    }
+
+    fn second_stage_offset(&self) -> usize {
+        self.layout.zs_partial_products_offset
+    }
+
+    fn chunk_polys(&self) -> Option<usize> {
+        self.layout.chunk_polys
+    }
+
+    fn write(&mut self, offset: usize, values: &[F]) -> ProverResult<()> {
+        self.write_elements(offset, values)
+    }
+
+    fn read(&self, offset: usize, out: &mut [F]) -> ProverResult<()> {
+        self.read_elements(offset, out)
+    }
+
+    fn ifft(
+        &mut self,
+        offset: usize,
+        poly_num: usize,
+        values_num_per_poly: usize,
+    ) -> ProverResult<()> {
+        self.check_range(offset, poly_num * values_num_per_poly)?;
+        let lg_n = crate::util::log2_strict(values_num_per_poly);
+        let n_inv = F::inverse_2exp(lg_n);
+        let n_inv_ptr: *const F = &n_inv;
+        let ctx_ptr = self.ctx_ptr();
+        cuda_call!(
+            "ifft",
+            plonky2_cuda::ifft(
+                self.cache_mem_device[offset..].as_mut_ptr(),
+                poly_num as i32,
+                values_num_per_poly as i32,
+                lg_n as i32,
+                self.root_table_device.as_ptr(),
+                n_inv_ptr as *const u64,
+                ctx_ptr,
+            )
+        );
+        Ok(())
+    }
+
+    fn merkle_tree_from_values(
+        &mut self,
+        offset: usize,
+        params: &FlatTreeParams,
+        coeffs: &mut [F],
+    ) -> ProverResult<()> {
+        <Self as ProverBackend<F, C, D>>::ifft(
+            self,
+            offset,
+            params.poly_num,
+            params.values_num_per_poly,
+        )?;
+        <Self as ProverBackend<F, C, D>>::merkle_tree_from_coeffs(self, offset, params, coeffs)
+    }
+
+    fn merkle_tree_from_coeffs(
+        &mut self,
+        offset: usize,
+        params: &FlatTreeParams,
+        coeffs: &mut [F],
+    ) -> ProverResult<()> {
+        ensure_shape!(coeffs.len() == params.values_len());
+        self.check_range(offset, params.footprint())?;
+
+        // The coefficients are overwritten by the leaves, so they are copied out first.
+        let src = &self.cache_mem_device[offset..offset + coeffs.len()];
+        unsafe { src.async_copy_to(as_u64s_mut(coeffs), &self.inner.stream2) }
+            .map_err(device_error)?;
+        self.inner
+            .stream2
+            .synchronize()
+            .map_err(device_error)?;
+
+        if params.chunk_polys.is_some() {
+            return self.chunked_merkle_tree_from_coeffs(offset, params, coeffs);
+        }
+
+        // The kernel hashes and transposes the salt columns of the poly-major LDE in the scratch
+        // area, right after the LDE of the polynomials, but does not fill them.
+        if params.salt_size > 0 {
+            let salt_offset =
+                offset + params.ext_values_len() + params.poly_num * params.lde_size();
+            self.write_elements(salt_offset, &F::rand_vec(params.salt_size * params.lde_size()))?;
+        }
+
+        let values_ptr = self.cache_mem_device[offset..].as_mut_ptr();
+        let ctx_ptr = self.ctx_ptr();
+        cuda_call!(
+            "merkle_tree_from_coeffs",
+            plonky2_cuda::merkle_tree_from_coeffs(
+                values_ptr,
+                values_ptr,
+                params.poly_num as i32,
+                params.values_num_per_poly as i32,
+                params.log_len() as i32,
+                self.root_table_device.as_ptr(),
+                self.root_table_device2.as_ptr(),
+                self.shift_powers_device.as_ptr(),
+                params.rate_bits as i32,
+                params.salt_size as i32,
+                params.cap_height as i32,
+                params.ext_values_len() as i32,
+                ctx_ptr,
+            )
+        );
+        Ok(())
+    }
+
+    fn hash_leaves(&mut self, offset: usize, params: &FlatTreeParams) -> ProverResult<()> {
+        self.check_range(offset, params.footprint())?;
+        self.hash_leaves_at(offset, params)
+    }
+
+    fn build_merkle_tree(&mut self, offset: usize, params: &FlatTreeParams) -> ProverResult<()> {
+        ensure_shape!(
+            params.chunk_polys.is_none(),
+            "build_merkle_tree needs the LDE of the whole batch"
+        );
+        self.check_range(offset, params.footprint())?;
+        let ctx_ptr = self.ctx_ptr();
+        cuda_call!(
+            "build_merkle_tree",
+            plonky2_cuda::build_merkle_tree(
+                self.cache_mem_device[offset..].as_mut_ptr(),
+                params.poly_num as i32,
+                params.values_num_per_poly as i32,
+                params.log_len() as i32,
+                params.rate_bits as i32,
+                params.salt_size as i32,
+                params.cap_height as i32,
+                params.ext_values_len() as i32,
+                ctx_ptr,
+            )
+        );
+        Ok(())
+    }
+
+    fn compute_quotient_polys(&mut self, job: &QuotientPolysJob<F, D>) -> ProverResult<()> {
+        let num_challenges = job.alphas.len();
+        ensure_shape!(job.betas.len() == num_challenges && job.gammas.len() == num_challenges);
+
+        // The kernel writes the quotient polynomials at `quotient_polys_offset`, and uses the
+        // space right after them for its evaluations and the challenges, as planned by the layout.
+        let params = self.quotient_params;
+        let layout = self.layout;
+        ensure_shape!(
+            job.wires == layout.wires
+                && job.zs_partial_products == layout.zs_partial_products
+                && job.quotient_polys_offset == layout.quotient_polys_offset
+                && num_challenges == layout.num_challenges
+                && QuotientKernelParams::new(job.common_data)? == params,
+            "The quotient job does not match the circuit the context was built for"
+        );
+        let outs_offset = layout.quotient_outs_offset();
+        let challenges_offset = layout.challenges_offset();
+        self.check_range(job.wires_offset, job.wires.ext_values_len())?;
+        self.check_range(
+            job.zs_partial_products_offset,
+            job.zs_partial_products.ext_values_len(),
+        )?;
+        self.check_range(challenges_offset, 3 * num_challenges)?;
+
+        for (i, challenges) in [job.alphas, job.betas, job.gammas].into_iter().enumerate() {
+            self.write_elements(challenges_offset + i * num_challenges, challenges)?;
+        }
+
+        let device_slice =
+            |offset: usize, len: usize| data_slice(&self.cache_mem_device[offset..offset + len]);
+        let zs_partial_products_leaves = device_slice(
+            job.zs_partial_products_offset,
+            job.zs_partial_products.ext_values_len(),
+        );
+        let alphas = device_slice(challenges_offset, num_challenges);
+        let betas = device_slice(challenges_offset + num_challenges, num_challenges);
+        let gammas = device_slice(challenges_offset + 2 * num_challenges, num_challenges);
+        let constants_sigmas_leaves = data_slice(&self.constants_sigmas_commitment_leaves_device);
+        let points = data_slice(&self.points_device);
+        let z_h_on_coset_evals = data_slice(&self.z_h_on_coset_evals_device);
+        let z_h_on_coset_inverses = data_slice(&self.z_h_on_coset_inverses_device);
+        let k_is = data_slice(&self.k_is_device);
+        let gate_table = data_slice(&self.gate_table_device);
+        let public_inputs_hash_ptr = job.public_inputs_hash.elements.as_ptr();
+
+        let wires_ptr = self.cache_mem_device[job.wires_offset..].as_ptr();
+        let quotient_polys_ptr =
+            self.cache_mem_device[job.quotient_polys_offset..].as_mut_ptr() as *mut c_void;
+        let outs_ptr = self.cache_mem_device[outs_offset..].as_mut_ptr() as *mut c_void;
+        let ctx_ptr = self.ctx_ptr();
+        cuda_call!(
+            "compute_quotient_polys",
+            plonky2_cuda::compute_quotient_polys(
+                wires_ptr,
+                job.wires.poly_num as i32,
+                job.wires.values_num_per_poly as i32,
+                job.wires.log_len() as i32,
+                self.root_table_device2.as_ptr(),
+                self.shift_inv_powers_device.as_ptr(),
+                job.wires.rate_bits as i32,
+                job.wires.salt_size as i32,
+                &zs_partial_products_leaves,
+                &constants_sigmas_leaves,
+                outs_ptr,
+                quotient_polys_ptr,
+                &points,
+                &z_h_on_coset_evals,
+                &z_h_on_coset_inverses,
+                &k_is,
+                &alphas,
+                &betas,
+                &gammas,
+                &gate_table,
+                params.num_challenges as i32,
+                params.num_constants as i32,
+                params.num_routed_wires as i32,
+                params.quotient_degree_factor as i32,
+                params.num_partial_products as i32,
+                public_inputs_hash_ptr as *const u64,
+                ctx_ptr,
+            )
+        );
+        Ok(())
+    }
+
+    fn host_leaves(&self, _offset: usize, _params: &FlatTreeParams) -> ProverResult<Arc<Vec<F>>> {
+        // The leaves stay on the device; FRI query rounds read them through `lde_leaf`.
+        Ok(Arc::new(Vec::new()))
+    }
+
+    fn reduce_opening_batch(
+        &mut self,
+        polys: &[&PolynomialCoeffs<F>],
+        alpha: F::Extension,
+        point: F::Extension,
+    ) -> ProverResult<PolynomialCoeffs<F::Extension>> {
+        // The coefficients are reduced into an accumulator at the start of the FRI region, and
+        // the polynomials uploaded after it, as many at a time as fit.
+        let n = polys.iter().map(|p| p.len()).max().unwrap_or(0);
+        if n == 0 || !self.fri_fits::<D>((D + 1) * n) {
+            return Ok(reduce_opening_batch::<F, D>(polys, alpha, point));
+        }
+        let acc_offset = self.layout.fri_offset;
+        let polys_offset = acc_offset + D * n;
+        let chunk_polys = (self.layout.fri_len - D * n) / n;
+
+        self.write_elements(acc_offset, &vec![F::ZERO; D * n])?;
+        let alpha_elements = alpha.to_basefield_array();
+        let alpha_chunk = alpha.exp_u64(chunk_polys as u64);
+        let mut alpha_start = F::Extension::ONE;
+        for chunk in polys.chunks(chunk_polys) {
+            let mut values = vec![F::ZERO; chunk.len() * n];
+            for (dst, poly) in values.chunks_exact_mut(n).zip(chunk) {
+                dst[..poly.len()].copy_from_slice(&poly.coeffs);
+            }
+            self.write_elements(polys_offset, &values)?;
+
+            let alpha_start_elements = alpha_start.to_basefield_array();
+            let polys_ptr = self.cache_mem_device[polys_offset..].as_ptr();
+            let acc_ptr = self.cache_mem_device[acc_offset..].as_mut_ptr();
+            let ctx_ptr = self.ctx_ptr();
+            cuda_call!(
+                "reduce_polys",
+                plonky2_cuda::reduce_polys(
+                    polys_ptr,
+                    chunk.len() as i32,
+                    n as i32,
+                    alpha_elements.as_ptr() as *const u64,
+                    alpha_start_elements.as_ptr() as *const u64,
+                    acc_ptr,
+                    ctx_ptr,
+                )
+            );
+            alpha_start *= alpha_chunk;
+        }
+
+        let mut acc = vec![F::ZERO; D * n];
+        self.read_elements(acc_offset, &mut acc)?;
+        Ok(PolynomialCoeffs::new(unflatten::<F, D>(&acc)).divide_by_linear(point))
+    }
+
+    fn coset_fft_extension(
+        &mut self,
+        coeffs: &PolynomialCoeffs<F::Extension>,
+        shift: F,
+    ) -> ProverResult<PolynomialValues<F::Extension>> {
+        // The roots of unity of the FFT are read from the LDE table, which covers every layer.
+        let n = coeffs.len();
+        if !n.is_power_of_two() || n > self.layout.lde_size() || !self.fri_fits::<D>(2 * D * n) {
+            return Ok(coeffs.coset_fft(shift.into()));
+        }
+        let values_offset = self.layout.fri_offset;
+        let scratch_offset = values_offset + D * n;
+        self.write_elements(values_offset, &flatten::<F, D>(&coeffs.coeffs))?;
+
+        let shift_ptr: *const F = &shift;
+        let values_ptr = self.cache_mem_device[values_offset..].as_mut_ptr();
+        let scratch_ptr = self.cache_mem_device[scratch_offset..].as_mut_ptr();
+        let ctx_ptr = self.ctx_ptr();
+        cuda_call!(
+            "coset_fft_ext2",
+            plonky2_cuda::coset_fft_ext2(
+                values_ptr,
+                scratch_ptr,
+                n as i32,
+                log2_strict(n) as i32,
+                self.root_table_device2.as_ptr(),
+                shift_ptr as *const u64,
+                ctx_ptr,
+            )
+        );
+
+        let mut values = vec![F::ZERO; D * n];
+        self.read_elements(values_offset, &mut values)?;
+        Ok(PolynomialValues::new(unflatten::<F, D>(&values)))
+    }
+
+    fn commit_fri_layer(
+        &mut self,
+        values: &mut PolynomialValues<F::Extension>,
+        arity_bits: usize,
+        cap_height: usize,
+    ) -> ProverResult<MerkleTree<F, C::Hasher>> {
+        let leaf_len = D << arity_bits;
+        let num_leaves = values.len() >> arity_bits;
+        ensure_shape!(
+            num_leaves << arity_bits == values.len() && cap_height <= log2_strict(num_leaves),
+            "a FRI layer of {} values does not fit leaves of arity 2^{} under a cap of height {}",
+            values.len(),
+            arity_bits,
+            cap_height
+        );
+        let num_digests = 2 * (num_leaves - (1 << cap_height));
+        let num_digests_and_caps = num_digests + (1 << cap_height);
+        let leaves_len = leaf_len * num_leaves;
+        if !self.fri_fits::<D>(leaves_len + num_digests_and_caps * NUM_HASH_OUT_ELTS) {
+            return Ok(commit_fri_layer::<F, C::Hasher, D>(values, arity_bits, cap_height));
+        }
+
+        // Each leaf holds the evaluations folded together, which are consecutive once the layer
+        // is bit-reversed.
+        reverse_index_bits_in_place(&mut values.values);
+        let leaves = flatten::<F, D>(&values.values);
+        let leaves_offset = self.layout.fri_offset;
+        let digests_offset = leaves_offset + leaves_len;
+        self.write_elements(leaves_offset, &leaves)?;
+        self.hash_rows(leaves_offset, leaf_len, num_leaves, cap_height, digests_offset)?;
+
+        let mut elements = vec![F::ZERO; num_digests_and_caps * NUM_HASH_OUT_ELTS];
+        self.read_elements(digests_offset, &mut elements)?;
+        let digests = elements
+            .chunks_exact(NUM_HASH_OUT_ELTS)
+            .map(hash_from_elements::<F, C::Hasher>)
+            .collect::<Vec<_>>();
+        Ok(MerkleTree {
+            leaves: vec![],
+            digests: vec![],
+            cap: MerkleCap(digests[num_digests..].to_vec()),
+            my_leaf_len: leaf_len,
+            my_leaves_len: leaves.len(),
+            my_leaves: Arc::new(leaves),
+            my_leaves_dev_offset: -1,
+            my_digests: Arc::new(digests),
+        })
+    }
+
+    fn fold_fri_layer(
+        &mut self,
+        coeffs: &PolynomialCoeffs<F::Extension>,
+        arity_bits: usize,
+        beta: F::Extension,
+    ) -> ProverResult<PolynomialCoeffs<F::Extension>> {
+        let out_len = coeffs.len() >> arity_bits;
+        if out_len << arity_bits != coeffs.len()
+            || !self.fri_fits::<D>(D * (coeffs.len() + out_len))
+        {
+            return Ok(fold_fri_layer(coeffs, arity_bits, beta));
+        }
+        let coeffs_offset = self.layout.fri_offset;
+        let out_offset = coeffs_offset + D * coeffs.len();
+        self.write_elements(coeffs_offset, &flatten::<F, D>(&coeffs.coeffs))?;
+
+        let beta_elements = beta.to_basefield_array();
+        let coeffs_ptr = self.cache_mem_device[coeffs_offset..].as_ptr();
+        let out_ptr = self.cache_mem_device[out_offset..].as_mut_ptr();
+        let ctx_ptr = self.ctx_ptr();
+        cuda_call!(
+            "fri_fold",
+            plonky2_cuda::fri_fold(
+                coeffs_ptr,
+                out_ptr,
+                out_len as i32,
+                1 << arity_bits,
+                beta_elements.as_ptr() as *const u64,
+                ctx_ptr,
+            )
+        );
+
+        let mut out = vec![F::ZERO; D * out_len];
+        self.read_elements(out_offset, &mut out)?;
+        Ok(PolynomialCoeffs::new(unflatten::<F, D>(&out)))
+    }
+}
@@ -0,0 +1,457 @@
+//! Descriptor table of the gates of a circuit, read by the `plonky2_cuda` quotient kernel to
+//! evaluate the gate constraints without a hardcoded list of gate instances.
+//!
+//! The table is a sequence of `u32` words: a header of `GATE_TABLE_HEADER_LEN` words
+//! (`num_gates`, `num_selectors`, `num_gate_constraints`), followed by one descriptor of
+//! `GATE_DESCRIPTOR_LEN` words per gate, in the order of `CommonCircuitData::gates`:
+//! `kind`, `GATE_PARAMS_LEN` parameters, `selector_index`, the start and end of the selector
+//! group, `constraint_offset` and `num_constraints`.
+//!
+//! The rest of the shape of the circuit is passed to the kernel as [`QuotientKernelParams`].
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use anyhow::{anyhow, ensure, Result};
+
+use crate::field::extension::Extendable;
+use crate::field::goldilocks_field::GoldilocksField;
+use crate::field::types::Field64;
+use crate::hash::hash_types::RichField;
+use crate::plonk::circuit_data::CommonCircuitData;
+use crate::plonk::error::{ProverError, ProverResult};
+use crate::util::partial_products::num_partial_products;
+
+/// Number of parameters of a gate descriptor. Unused parameters are zero.
+pub const GATE_PARAMS_LEN: usize = 3;
+
+/// Number of words of the table header.
+pub const GATE_TABLE_HEADER_LEN: usize = 3;
+
+/// Number of words of a gate descriptor.
+pub const GATE_DESCRIPTOR_LEN: usize = GATE_PARAMS_LEN + 5;
+
+/// Size of the constraint buffers of the quotient kernel, i.e. the largest supported
+/// `num_gate_constraints`.
+pub const MAX_GPU_GATE_CONSTRAINTS: usize = 256;
+
+/// Size of the accumulators of the quotient kernel, i.e. the largest supported `num_challenges`.
+/// This is `MAX_NUM_CHALLENGES` in `plonky2_gpu_impl.cuh`.
+pub const MAX_GPU_CHALLENGES: usize = 4;
+
+/// Gates with an implementation in `plonky2_cuda`. The discriminants are the `GATE_*` values of
+/// `plonky2_gpu_impl.cuh`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum GpuGateKind {
+    /// No parameters.
+    Noop = 0,
+    /// Parameters: `num_consts`.
+    Constant = 1,
+    /// No parameters.
+    PublicInput = 2,
+    /// Parameters: `B`, `num_limbs`. Only bases 2 and 4 are implemented.
+    BaseSum = 3,
+    /// Parameters: `num_ops`.
+    Arithmetic = 4,
+    /// Parameters: `num_bits`, `num_chunks`.
+    Comparison = 5,
+    /// Parameters: `num_addends`, `num_ops`.
+    U32AddMany = 6,
+    /// Parameters: `num_ops`.
+    U32Arithmetic = 7,
+    /// Parameters: `num_input_limbs`.
+    U32RangeCheck = 8,
+    /// Parameters: `num_ops`.
+    U32Subtraction = 9,
+    /// Parameters: `bits`, `num_copies`, `num_extra_constants`.
+    RandomAccess = 10,
+    /// No parameters.
+    Poseidon = 11,
+}
+
+impl GpuGateKind {
+    const ALL: [Self; 12] = [
+        Self::Noop,
+        Self::Constant,
+        Self::PublicInput,
+        Self::BaseSum,
+        Self::Arithmetic,
+        Self::Comparison,
+        Self::U32AddMany,
+        Self::U32Arithmetic,
+        Self::U32RangeCheck,
+        Self::U32Subtraction,
+        Self::RandomAccess,
+        Self::Poseidon,
+    ];
+
+    pub fn from_u32(kind: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| *k as u32 == kind)
+    }
+}
+
+/// A gate kind along with the parameters of the instance, as returned by `Gate::gpu_gate`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct GpuGate {
+    pub kind: GpuGateKind,
+    pub params: [u32; GATE_PARAMS_LEN],
+}
+
+impl GpuGate {
+    /// Fails with `ProverError::Unsupported` if a descriptor cannot hold the parameters, i.e. if
+    /// there are more than `GATE_PARAMS_LEN` of them or one does not fit in a `u32`.
+    pub fn new(kind: GpuGateKind, params: &[usize]) -> ProverResult<Self> {
+        if params.len() > GATE_PARAMS_LEN {
+            return Err(ProverError::Unsupported(format!(
+                "{:?} gates have {} parameters, but descriptors hold at most {}",
+                kind,
+                params.len(),
+                GATE_PARAMS_LEN
+            )));
+        }
+        let mut res = Self {
+            kind,
+            params: [0; GATE_PARAMS_LEN],
+        };
+        for (p, &x) in res.params.iter_mut().zip(params) {
+            *p = x.try_into().map_err(|_| {
+                ProverError::Unsupported(format!(
+                    "Parameter {} of a {:?} gate does not fit in a u32",
+                    x, kind
+                ))
+            })?;
+        }
+        Ok(res)
+    }
+
+    /// Checks that the kernel implements this instance, beyond its kind.
+    fn check_supported(&self) -> Result<()> {
+        if self.kind == GpuGateKind::BaseSum {
+            ensure!(
+                matches!(self.params[0], 2 | 4),
+                "BaseSumGate is only accelerated for bases 2 and 4, got base {}",
+                self.params[0]
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Descriptor of the `i`-th gate of a circuit, `i` being the `row` used to compute its filter.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GateDescriptor {
+    pub gate: GpuGate,
+    pub selector_index: usize,
+    pub group_range: Range<usize>,
+    /// Index of the first combined gate constraint this gate's filtered constraints are added to.
+    pub constraint_offset: usize,
+    pub num_constraints: usize,
+}
+
+impl GateDescriptor {
+    fn encode(&self, out: &mut Vec<u32>) {
+        out.push(self.gate.kind as u32);
+        out.extend_from_slice(&self.gate.params);
+        out.extend(
+            [
+                self.selector_index,
+                self.group_range.start,
+                self.group_range.end,
+                self.constraint_offset,
+                self.num_constraints,
+            ]
+            .map(|x| x as u32),
+        );
+    }
+
+    fn decode(words: &[u32]) -> Result<Self> {
+        debug_assert_eq!(words.len(), GATE_DESCRIPTOR_LEN);
+        let kind = GpuGateKind::from_u32(words[0])
+            .ok_or_else(|| anyhow!("Unknown gate kind {}", words[0]))?;
+        let mut params = [0; GATE_PARAMS_LEN];
+        params.copy_from_slice(&words[1..1 + GATE_PARAMS_LEN]);
+        let rest = &words[1 + GATE_PARAMS_LEN..];
+        Ok(Self {
+            gate: GpuGate { kind, params },
+            selector_index: rest[0] as usize,
+            group_range: rest[1] as usize..rest[2] as usize,
+            constraint_offset: rest[3] as usize,
+            num_constraints: rest[4] as usize,
+        })
+    }
+}
+
+/// The gates of a circuit, in the form consumed by the quotient kernel.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GateTable {
+    pub descriptors: Vec<GateDescriptor>,
+    pub num_selectors: usize,
+    pub num_gate_constraints: usize,
+}
+
+impl GateTable {
+    /// Builds the table of `common_data.gates`, failing if one of them has no accelerated
+    /// implementation or parameters a descriptor cannot hold.
+    pub fn new<F: RichField + Extendable<D>, const D: usize>(
+        common_data: &CommonCircuitData<F, D>,
+    ) -> ProverResult<Self> {
+        let selectors_info = &common_data.selectors_info;
+        if common_data.num_gate_constraints > MAX_GPU_GATE_CONSTRAINTS {
+            return Err(ProverError::Unsupported(format!(
+                "The circuit has {} gate constraints, but the GPU quotient kernel supports at most {}",
+                common_data.num_gate_constraints, MAX_GPU_GATE_CONSTRAINTS
+            )));
+        }
+
+        let descriptors = common_data
+            .gates
+            .iter()
+            .enumerate()
+            .map(|(i, gate)| {
+                let gpu_gate = gate.0.gpu_gate().ok_or_else(|| {
+                    ProverError::Unsupported(format!(
+                        "Gate {} has no accelerated implementation",
+                        gate.0.id()
+                    ))
+                })??;
+                gpu_gate.check_supported().map_err(|e| {
+                    ProverError::Unsupported(format!("Gate {}: {}", gate.0.id(), e))
+                })?;
+                let selector_index = selectors_info.selector_indices[i];
+                Ok(GateDescriptor {
+                    gate: gpu_gate,
+                    selector_index,
+                    group_range: selectors_info.groups[selector_index].clone(),
+                    // The filtered constraints of all gates are summed from the first one.
+                    constraint_offset: 0,
+                    num_constraints: gate.0.num_constraints(),
+                })
+            })
+            .collect::<ProverResult<Vec<_>>>()?;
+
+        Ok(Self {
+            descriptors,
+            num_selectors: selectors_info.num_selectors(),
+            num_gate_constraints: common_data.num_gate_constraints,
+        })
+    }
+
+    pub fn to_u32s(&self) -> Vec<u32> {
+        let mut words =
+            Vec::with_capacity(GATE_TABLE_HEADER_LEN + self.descriptors.len() * GATE_DESCRIPTOR_LEN);
+        words.extend([
+            self.descriptors.len() as u32,
+            self.num_selectors as u32,
+            self.num_gate_constraints as u32,
+        ]);
+        for descriptor in &self.descriptors {
+            descriptor.encode(&mut words);
+        }
+        words
+    }
+
+    pub fn from_u32s(words: &[u32]) -> Result<Self> {
+        ensure!(words.len() >= GATE_TABLE_HEADER_LEN, "Gate table too short");
+        let num_gates = words[0] as usize;
+        let (header, body) = words.split_at(GATE_TABLE_HEADER_LEN);
+        ensure!(
+            body.len() == num_gates * GATE_DESCRIPTOR_LEN,
+            "Gate table of {} gates has {} descriptor words",
+            num_gates,
+            body.len()
+        );
+        let descriptors = body
+            .chunks_exact(GATE_DESCRIPTOR_LEN)
+            .map(GateDescriptor::decode)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            descriptors,
+            num_selectors: header[1] as usize,
+            num_gate_constraints: header[2] as usize,
+        })
+    }
+}
+
+/// The scalar parameters of the quotient kernel besides the gate table.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct QuotientKernelParams {
+    pub num_challenges: usize,
+    pub num_constants: usize,
+    pub num_routed_wires: usize,
+    pub quotient_degree_factor: usize,
+    pub num_partial_products: usize,
+}
+
+impl QuotientKernelParams {
+    /// Reads the parameters of `common_data`, failing if the kernel cannot evaluate its quotient.
+    pub fn new<F: RichField + Extendable<D>, const D: usize>(
+        common_data: &CommonCircuitData<F, D>,
+    ) -> ProverResult<Self> {
+        let config = &common_data.config;
+        let unsupported = |message: String| Err(ProverError::Unsupported(message));
+        if F::ORDER != GoldilocksField::ORDER {
+            return unsupported(format!(
+                "The GPU quotient kernel only supports Goldilocks, not a field of order {}",
+                F::ORDER
+            ));
+        }
+        if config.num_challenges > MAX_GPU_CHALLENGES {
+            return unsupported(format!(
+                "The circuit has {} challenges, but the GPU quotient kernel supports at most {}",
+                config.num_challenges, MAX_GPU_CHALLENGES
+            ));
+        }
+        if common_data.has_lookups() {
+            return unsupported("The GPU quotient kernel does not support lookups".into());
+        }
+        if common_data.num_partial_products
+            != num_partial_products(config.num_routed_wires, common_data.quotient_degree_factor)
+        {
+            return Err(ProverError::Shape(format!(
+                "{} partial products do not match {} routed wires of quotient degree factor {}",
+                common_data.num_partial_products,
+                config.num_routed_wires,
+                common_data.quotient_degree_factor
+            )));
+        }
+
+        Ok(Self {
+            num_challenges: config.num_challenges,
+            num_constants: common_data.num_constants,
+            num_routed_wires: config.num_routed_wires,
+            quotient_degree_factor: common_data.quotient_degree_factor,
+            num_partial_products: common_data.num_partial_products,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::hash::poseidon::PoseidonHash;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_gate_table_round_trip() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        let y = builder.constant(F::from_canonical_u64(7));
+        let z = builder.mul_add(x, y, x);
+        builder.split_le(z, 64);
+        let h = builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![x, z]);
+        builder.register_public_inputs(&h.elements);
+        let data = builder.build::<C>();
+
+        let table = GateTable::new(&data.common)?;
+        assert_eq!(table.descriptors.len(), data.common.gates.len());
+        assert_eq!(
+            table.num_selectors,
+            data.common.selectors_info.num_selectors()
+        );
+        for (i, (descriptor, gate)) in table.descriptors.iter().zip(&data.common.gates).enumerate()
+        {
+            let selector_index = data.common.selectors_info.selector_indices[i];
+            assert_eq!(descriptor.selector_index, selector_index);
+            assert_eq!(
+                descriptor.group_range,
+                data.common.selectors_info.groups[selector_index]
+            );
+            assert_eq!(descriptor.num_constraints, gate.0.num_constraints());
+        }
+        assert!(table
+            .descriptors
+            .iter()
+            .any(|d| d.gate == GpuGate::new(GpuGateKind::BaseSum, &[2, 63]).unwrap()));
+
+        let words = table.to_u32s();
+        assert_eq!(
+            words.len(),
+            GATE_TABLE_HEADER_LEN + table.descriptors.len() * GATE_DESCRIPTOR_LEN
+        );
+        assert_eq!(GateTable::from_u32s(&words)?, table);
+        Ok(())
+    }
+
+    #[test]
+    fn test_gpu_gate_rejects_large_parameters() {
+        assert!(matches!(
+            GpuGate::new(GpuGateKind::BaseSum, &[2, u32::MAX as usize + 1]),
+            Err(ProverError::Unsupported(_))
+        ));
+        assert!(matches!(
+            GpuGate::new(GpuGateKind::RandomAccess, &[1; GATE_PARAMS_LEN + 1]),
+            Err(ProverError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_gate_table_rejects_unsupported_gate() {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_extension_target();
+        let y = builder.add_virtual_extension_target();
+        builder.mul_extension(x, y);
+        let data = builder.build::<C>();
+
+        let err = GateTable::new(&data.common).unwrap_err().to_string();
+        assert!(err.contains("ExtensionGate"), "{}", err);
+        assert!(err.contains("no accelerated implementation"), "{}", err);
+    }
+
+    #[test]
+    fn test_quotient_kernel_params() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let x = builder.add_virtual_target();
+        builder.mul(x, x);
+        let data = builder.build::<C>();
+        assert_eq!(
+            QuotientKernelParams::new(&data.common)?,
+            QuotientKernelParams {
+                num_challenges: config.num_challenges,
+                num_constants: data.common.num_constants,
+                num_routed_wires: config.num_routed_wires,
+                quotient_degree_factor: data.common.quotient_degree_factor,
+                num_partial_products: data.common.num_partial_products,
+            }
+        );
+
+        let mut common = data.common.clone();
+        common.config.num_challenges = MAX_GPU_CHALLENGES + 1;
+        assert!(matches!(
+            QuotientKernelParams::new(&common),
+            Err(ProverError::Unsupported(_))
+        ));
+
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let table = builder.add_lookup_table(&[(0, 1), (1, 2)]);
+        let x = builder.add_virtual_target();
+        builder.add_lookup(x, table);
+        let data = builder.build::<C>();
+        assert!(matches!(
+            QuotientKernelParams::new(&data.common),
+            Err(ProverError::Unsupported(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_gate_table_rejects_unknown_kind() {
+        let mut words = vec![1, 1, 0];
+        words.extend([42; GATE_DESCRIPTOR_LEN]);
+        assert!(GateTable::from_u32s(&words).is_err());
+    }
+}
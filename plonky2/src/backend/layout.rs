@@ -0,0 +1,520 @@
+//! Host-side planning of the working buffer and of the constant tables used by the flattened
+//! proving path. Nothing here touches a device, so the sizes and offsets of a circuit, and whether
+//! it fits in a memory budget, can be checked before any memory is allocated.
+
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cmp::max;
+use core::ops::Range;
+
+use crate::backend::FlatTreeParams;
+use crate::field::extension::Extendable;
+use crate::field::fft::fft_root_table;
+use crate::field::types::Field;
+use crate::field::zero_poly_coset::ZeroPolyOnCoset;
+use crate::hash::hash_types::{RichField, NUM_HASH_OUT_ELTS};
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::GenericConfig;
+use crate::plonk::error::{ProverError, ProverResult};
+use crate::plonk::plonk_common::{salt_size, PlonkOracle};
+
+/// Number of bytes of a field element in device memory; the kernels operate on `u64`s.
+pub const ELEMENT_BYTES: usize = 8;
+
+/// The stages of a proof, in the order in which they use the working buffer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ProvingStage {
+    /// The wires commitment.
+    WiresLde,
+    /// The Z's and partial products commitment.
+    ZsPartialProductsLde,
+    /// The evaluation of the quotient polynomials, and their commitment.
+    QuotientEvaluations,
+    /// The commit phase of FRI.
+    FriFolding,
+}
+
+impl ProvingStage {
+    pub const ALL: [Self; 4] = [
+        Self::WiresLde,
+        Self::ZsPartialProductsLde,
+        Self::QuotientEvaluations,
+        Self::FriFolding,
+    ];
+}
+
+/// Sizes and offsets of the proving stages in the working buffer.
+///
+/// The wires commitment starts at 0 and the Z's and partial products commitment right after it.
+/// The quotient polynomials are written after the leaves of the latter, over its scratch area,
+/// which is no longer needed once its Merkle tree is built. The quotient kernel also uses the
+/// space after the quotient polynomials for its evaluations and the challenges. The FRI commit
+/// phase only needs the leaves of the earlier commitments, so it starts after the quotient leaves.
+///
+/// A layout built with [`ProofLayout::with_budget`] may compute the LDE of each commitment in
+/// chunks of `chunk_polys` polynomials, in which case the `FlatTreeParams` are chunked too.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ProofLayout {
+    pub num_challenges: usize,
+    pub chunk_polys: Option<usize>,
+    pub wires: FlatTreeParams,
+    pub zs_partial_products: FlatTreeParams,
+    pub quotient_polys: FlatTreeParams,
+    pub zs_partial_products_offset: usize,
+    pub quotient_polys_offset: usize,
+    pub fri_offset: usize,
+    pub fri_len: usize,
+    /// Number of field elements in the working buffer.
+    pub buffer_len: usize,
+    /// Number of field elements in the [`CircuitTables`], which live next to the working buffer.
+    pub tables_len: usize,
+}
+
+impl ProofLayout {
+    /// The layout holding the LDE of whole commitments, regardless of its size.
+    pub fn new<F: RichField + Extendable<D>, const D: usize>(
+        common_data: &CommonCircuitData<F, D>,
+    ) -> ProverResult<Self> {
+        Self::with_chunk_polys(common_data, None)
+    }
+
+    /// The layout with the largest chunks whose peak memory fits in `budget_bytes`, or an error if
+    /// the circuit does not fit even one polynomial at a time.
+    pub fn with_budget<F: RichField + Extendable<D>, const D: usize>(
+        common_data: &CommonCircuitData<F, D>,
+        budget_bytes: usize,
+    ) -> ProverResult<Self> {
+        let unchunked = Self::new(common_data)?;
+        if unchunked.peak_bytes() <= budget_bytes {
+            return Ok(unchunked);
+        }
+
+        let smallest = Self::with_chunk_polys(common_data, Some(1))?;
+        if smallest.peak_bytes() > budget_bytes {
+            return Err(ProverError::Allocation {
+                bytes: smallest.peak_bytes(),
+                message: format!(
+                    "{} bytes are needed without chunking, but the budget is {} bytes",
+                    unchunked.peak_bytes(),
+                    budget_bytes
+                ),
+            });
+        }
+
+        // The peak grows with the chunk size, and chunks of the largest batch are no chunking.
+        let max_chunk_polys = unchunked
+            .commitments()
+            .iter()
+            .map(|params| params.poly_num)
+            .max()
+            .unwrap();
+        let (mut lo, mut hi) = (1, max_chunk_polys);
+        let mut best = smallest;
+        while hi - lo > 1 {
+            let mid = (lo + hi) / 2;
+            let layout = Self::with_chunk_polys(common_data, Some(mid))?;
+            if layout.peak_bytes() <= budget_bytes {
+                lo = mid;
+                best = layout;
+            } else {
+                hi = mid;
+            }
+        }
+        Ok(best)
+    }
+
+    /// The layout computing the LDE of each commitment `chunk_polys` polynomials at a time.
+    pub fn with_chunk_polys<F: RichField + Extendable<D>, const D: usize>(
+        common_data: &CommonCircuitData<F, D>,
+        chunk_polys: Option<usize>,
+    ) -> ProverResult<Self> {
+        let config = &common_data.config;
+        let wires = FlatTreeParams::wires(common_data).with_chunk_polys(chunk_polys);
+        let zs_partial_products =
+            FlatTreeParams::zs_partial_products(common_data).with_chunk_polys(chunk_polys);
+        let quotient_polys =
+            FlatTreeParams::quotient_polys(common_data).with_chunk_polys(chunk_polys);
+
+        if common_data.has_lookups() {
+            return Err(ProverError::Unsupported(
+                "The flattened layout does not support the lookup argument".to_string(),
+            ));
+        }
+        // The quotient polynomials are computed on the whole LDE and split in place into their
+        // degree-`n` chunks, so there must be exactly `1 << rate_bits` chunks per challenge.
+        if common_data.quotient_degree() != common_data.degree() << wires.rate_bits {
+            return Err(ProverError::Unsupported(format!(
+                "The flattened layout needs a quotient degree factor of 2^rate_bits, got {} with rate_bits = {}",
+                common_data.quotient_degree_factor, wires.rate_bits
+            )));
+        }
+        if wires.cap_height > wires.log_len() + wires.rate_bits {
+            return Err(ProverError::Unsupported(format!(
+                "Cap height {} is larger than the LDE of 2^{} points",
+                wires.cap_height,
+                wires.log_len() + wires.rate_bits
+            )));
+        }
+
+        let zs_partial_products_offset = wires.footprint();
+        let quotient_polys_offset =
+            zs_partial_products_offset + zs_partial_products.ext_values_len();
+        let fri_offset = quotient_polys_offset + quotient_polys.ext_values_len();
+        let mut layout = Self {
+            num_challenges: config.num_challenges,
+            chunk_polys,
+            wires,
+            zs_partial_products,
+            quotient_polys,
+            zs_partial_products_offset,
+            quotient_polys_offset,
+            fri_offset,
+            fri_len: fri_folding_len(common_data),
+            buffer_len: 0,
+            tables_len: tables_len(common_data),
+        };
+        layout.buffer_len = ProvingStage::ALL
+            .into_iter()
+            .map(|stage| layout.stage_range(stage).end)
+            .max()
+            .unwrap();
+        Ok(layout)
+    }
+
+    pub fn lde_size(&self) -> usize {
+        self.wires.lde_size()
+    }
+
+    /// The wires, Z's and partial products, and quotient commitments.
+    pub fn commitments(&self) -> [FlatTreeParams; 3] {
+        [self.wires, self.zs_partial_products, self.quotient_polys]
+    }
+
+    /// Whether the LDE of at least one commitment is computed in chunks.
+    pub fn is_chunked(&self) -> bool {
+        self.commitments()
+            .iter()
+            .any(|params| params.chunk_polys.is_some())
+    }
+
+    /// The part of the working buffer used by `stage`.
+    pub fn stage_range(&self, stage: ProvingStage) -> Range<usize> {
+        let start = match stage {
+            ProvingStage::WiresLde => 0,
+            ProvingStage::ZsPartialProductsLde => self.zs_partial_products_offset,
+            ProvingStage::QuotientEvaluations => self.quotient_polys_offset,
+            ProvingStage::FriFolding => self.fri_offset,
+        };
+        let len = match stage {
+            ProvingStage::WiresLde => self.wires.footprint(),
+            ProvingStage::ZsPartialProductsLde => self.zs_partial_products.footprint(),
+            ProvingStage::QuotientEvaluations => {
+                max(self.quotient_polys.footprint(), self.quotient_scratch_len())
+            }
+            ProvingStage::FriFolding => self.fri_len,
+        };
+        start..start + len
+    }
+
+    /// Device memory needed to prove the circuit: the working buffer and the constant tables.
+    /// The gate table, a few words per gate, is not counted.
+    pub fn peak_bytes(&self) -> usize {
+        (self.buffer_len + self.tables_len) * ELEMENT_BYTES
+    }
+
+    /// Space used by the quotient kernel from `quotient_polys_offset`: the quotient polynomials,
+    /// their evaluations, then the alphas, betas and gammas.
+    pub fn quotient_scratch_len(&self) -> usize {
+        2 * self.num_challenges * self.lde_size() + 3 * self.num_challenges
+    }
+
+    /// Offset of the quotient evaluations written by the kernel.
+    pub fn quotient_outs_offset(&self) -> usize {
+        self.quotient_polys_offset + self.num_challenges * self.lde_size()
+    }
+
+    /// Offset of the alphas, betas and gammas read by the kernel, in that order.
+    pub fn challenges_offset(&self) -> usize {
+        self.quotient_outs_offset() + self.num_challenges * self.lde_size()
+    }
+}
+
+/// Space for the FRI commit phase: the evaluations of the combined polynomial over the extension
+/// field, the same values grouped into the leaves of the first folded layer, and the digests and
+/// cap of that layer. Later layers are smaller and reuse this space, as does the reduction of the
+/// opening batches, which comes first.
+fn fri_folding_len<F: RichField + Extendable<D>, const D: usize>(
+    common_data: &CommonCircuitData<F, D>,
+) -> usize {
+    let lde_size = common_data.lde_size();
+    let num_caps = 1 << common_data.config.fri_config.cap_height;
+    match common_data.fri_params.reduction_arity_bits.first() {
+        None => D * lde_size,
+        Some(&arity_bits) => {
+            let num_leaves = lde_size >> arity_bits;
+            let num_digests_and_caps = 2 * num_leaves.saturating_sub(num_caps) + num_caps;
+            2 * D * lde_size + num_digests_and_caps * NUM_HASH_OUT_ELTS
+        }
+    }
+}
+
+/// Number of elements of the [`CircuitTables`] of a circuit.
+fn tables_len<F: RichField + Extendable<D>, const D: usize>(
+    common_data: &CommonCircuitData<F, D>,
+) -> usize {
+    let n = common_data.degree();
+    let lde_size = common_data.lde_size();
+    let rate = 1 << common_data.config.fri_config.rate_bits;
+    let constants_sigmas_leaf_len = common_data.num_constants
+        + common_data.config.num_routed_wires
+        + salt_size(common_data.config.zero_knowledge && PlonkOracle::CONSTANTS_SIGMAS.blinding);
+    // Root tables, shift powers, points, Z_H evaluations and inverses, k_is and leaves.
+    2 * (n + lde_size)
+        + lde_size
+        + 2 * rate
+        + common_data.k_is.len()
+        + constants_sigmas_leaf_len * lde_size
+}
+
+/// Precomputed tables read by the kernels, which only depend on the circuit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CircuitTables<F: Field> {
+    /// `fft_root_table(n)`, flattened.
+    pub root_table: Vec<F>,
+    /// `fft_root_table(n << rate_bits)`, flattened.
+    pub root_table_lde: Vec<F>,
+    /// `shift^i` for `i` in `0..n`, with `shift` the coset shift.
+    pub shift_powers: Vec<F>,
+    /// `shift^-i` for `i` in `0..n << rate_bits`.
+    pub shift_inv_powers: Vec<F>,
+    /// The subgroup of order `n << rate_bits`, in natural order.
+    pub points: Vec<F>,
+    pub z_h_on_coset_evals: Vec<F>,
+    pub z_h_on_coset_inverses: Vec<F>,
+    pub k_is: Vec<F>,
+    /// Leaves of the constants and sigmas commitment, row after row.
+    pub constants_sigmas_leaves: Vec<F>,
+    pub constants_sigmas_leaf_len: usize,
+}
+
+impl<F: RichField> CircuitTables<F> {
+    pub fn new<C: GenericConfig<D, F = F>, const D: usize>(
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Self
+    where
+        F: Extendable<D>,
+    {
+        let degree_bits = common_data.degree_bits();
+        let rate_bits = common_data.config.fri_config.rate_bits;
+        let lde_bits = degree_bits + rate_bits;
+        let shift = F::coset_shift();
+        let z_h_on_coset = ZeroPolyOnCoset::new(degree_bits, rate_bits);
+
+        let tree = &prover_data.constants_sigmas_commitment.merkle_tree;
+        let (constants_sigmas_leaves, constants_sigmas_leaf_len) = if tree.my_leaves.is_empty() {
+            (tree.leaves.concat(), tree.leaves[0].len())
+        } else {
+            (tree.my_leaves.to_vec(), tree.my_leaf_len)
+        };
+
+        Self {
+            root_table: prover_data.fft_root_table_deg.clone(),
+            root_table_lde: fft_root_table(1 << lde_bits).concat(),
+            shift_powers: shift.powers().take(1 << degree_bits).collect(),
+            shift_inv_powers: shift.inverse().powers().take(1 << lde_bits).collect(),
+            points: F::two_adic_subgroup(lde_bits),
+            z_h_on_coset_evals: z_h_on_coset.evals,
+            z_h_on_coset_inverses: z_h_on_coset.inverses,
+            k_is: common_data.k_is.clone(),
+            constants_sigmas_leaves,
+            constants_sigmas_leaf_len,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::gates::noop::NoopGate;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn common_data(config: CircuitConfig, num_gates: usize) -> CommonCircuitData<F, D> {
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        for _ in 0..num_gates {
+            builder.add_gate(NoopGate, vec![]);
+        }
+        builder.build::<C>().common
+    }
+
+    fn configs() -> Vec<(CircuitConfig, usize)> {
+        let mut zk_config = CircuitConfig::standard_recursion_zk_config();
+        zk_config.fri_config.cap_height = 2;
+        vec![
+            (CircuitConfig::standard_recursion_config(), 0),
+            (CircuitConfig::standard_recursion_config(), 1000),
+            (CircuitConfig::standard_ecc_config(), 300),
+            (CircuitConfig::wide_ecc_config(), 100),
+            (zk_config, 100),
+        ]
+    }
+
+    fn check_layout(layout: &ProofLayout) {
+        let range = |stage| layout.stage_range(stage);
+        assert_eq!(range(ProvingStage::WiresLde).start, 0);
+        assert!(
+            range(ProvingStage::WiresLde).end <= range(ProvingStage::ZsPartialProductsLde).start
+        );
+        // Each stage may reuse the scratch area of the previous commitment, but not its leaves.
+        assert_eq!(
+            range(ProvingStage::ZsPartialProductsLde).start
+                + layout.zs_partial_products.ext_values_len(),
+            range(ProvingStage::QuotientEvaluations).start
+        );
+        assert_eq!(
+            range(ProvingStage::QuotientEvaluations).start
+                + layout.quotient_polys.ext_values_len(),
+            range(ProvingStage::FriFolding).start
+        );
+        for stage in ProvingStage::ALL {
+            assert!(range(stage).end <= layout.buffer_len);
+        }
+        assert_eq!(
+            layout.challenges_offset() + 3 * layout.num_challenges,
+            layout.quotient_polys_offset + layout.quotient_scratch_len()
+        );
+        assert_eq!(
+            layout.peak_bytes(),
+            (layout.buffer_len + layout.tables_len) * ELEMENT_BYTES
+        );
+    }
+
+    #[test]
+    fn test_layout_is_consistent() -> Result<()> {
+        for (config, num_gates) in configs() {
+            let common_data = common_data(config, num_gates);
+            let layout = ProofLayout::new(&common_data)?;
+            check_layout(&layout);
+            assert!(!layout.is_chunked());
+            assert_eq!(layout.lde_size(), common_data.lde_size());
+            assert_eq!(
+                layout.quotient_polys.poly_num,
+                layout.num_challenges << layout.wires.rate_bits
+            );
+            assert_eq!(
+                layout.quotient_polys.values_len(),
+                layout.num_challenges * layout.lde_size()
+            );
+
+            for chunk_polys in [1, 7, 50] {
+                let chunked = ProofLayout::with_chunk_polys(&common_data, Some(chunk_polys))?;
+                check_layout(&chunked);
+                assert!(chunked.is_chunked());
+                assert!(chunked.peak_bytes() < layout.peak_bytes());
+                for (params, full) in chunked.commitments().iter().zip(layout.commitments()) {
+                    assert!(params.scratch_len() <= full.scratch_len());
+                    assert_eq!(params.ext_values_len(), full.ext_values_len());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_layout_with_budget() -> Result<()> {
+        for (config, num_gates) in configs() {
+            let common_data = common_data(config, num_gates);
+            let unchunked = ProofLayout::new(&common_data)?;
+            let smallest = ProofLayout::with_chunk_polys(&common_data, Some(1))?;
+
+            assert_eq!(
+                ProofLayout::with_budget(&common_data, unchunked.peak_bytes())?,
+                unchunked
+            );
+            assert!(matches!(
+                ProofLayout::with_budget(&common_data, smallest.peak_bytes() - 1),
+                Err(ProverError::Allocation { .. })
+            ));
+
+            for budget in [
+                unchunked.peak_bytes() - 1,
+                (smallest.peak_bytes() + unchunked.peak_bytes()) / 2,
+                smallest.peak_bytes(),
+            ] {
+                let layout = ProofLayout::with_budget(&common_data, budget)?;
+                check_layout(&layout);
+                assert!(layout.is_chunked());
+                assert!(layout.peak_bytes() <= budget);
+                // The chunks are as large as the budget allows.
+                let chunk_polys = layout.chunk_polys.unwrap();
+                let larger = ProofLayout::with_chunk_polys(&common_data, Some(chunk_polys + 1))?;
+                assert!(larger.peak_bytes() > budget);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_layout_rejects_unsupported_quotient_degree() {
+        let mut config = CircuitConfig::standard_recursion_config();
+        config.fri_config.rate_bits = 4;
+        let common_data = common_data(config, 0);
+        assert!(matches!(
+            ProofLayout::new(&common_data),
+            Err(ProverError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_circuit_tables() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        builder.add_gate(NoopGate, vec![]);
+        let data = builder.build::<C>();
+        let tables = CircuitTables::new(&data.prover_only, &data.common);
+
+        let n = data.common.degree();
+        let lde_size = data.common.lde_size();
+        assert_eq!(tables.root_table.len(), n);
+        assert_eq!(tables.root_table_lde.len(), lde_size);
+        assert_eq!(tables.shift_powers.len(), n);
+        assert_eq!(tables.shift_inv_powers.len(), lde_size);
+        assert_eq!(tables.points.len(), lde_size);
+        assert_eq!(
+            tables.z_h_on_coset_evals.len(),
+            1 << data.common.config.fri_config.rate_bits
+        );
+        assert_eq!(
+            tables.constants_sigmas_leaves.len(),
+            tables.constants_sigmas_leaf_len * lde_size
+        );
+        let tables_len = [
+            &tables.root_table,
+            &tables.root_table_lde,
+            &tables.shift_powers,
+            &tables.shift_inv_powers,
+            &tables.points,
+            &tables.z_h_on_coset_evals,
+            &tables.z_h_on_coset_inverses,
+            &tables.k_is,
+            &tables.constants_sigmas_leaves,
+        ]
+        .iter()
+        .map(|table| table.len())
+        .sum::<usize>();
+        assert_eq!(ProofLayout::new(&data.common)?.tables_len, tables_len);
+        for (x, y) in tables.shift_powers.iter().zip(&tables.shift_inv_powers) {
+            assert_eq!(*x * *y, F::ONE);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,373 @@
+//! Backends for the flattened proving path, used by `prove` with `ProvingStrategy::CpuFlat` and
+//! `ProvingStrategy::Accelerated`.
+//!
+//! The flattened path keeps every commitment in a single working buffer, addressed by element
+//! offsets, in the layout expected by the `plonky2_cuda` kernels. A commitment of `poly_num`
+//! polynomials starting at `offset` occupies
+//!
+//! - `[offset, offset + ext_values_len)`: the leaves, i.e. the bit-reversed LDE rows of
+//!   `poly_num + salt_size` elements each;
+//! - `[offset + ext_values_len, offset + 2 * ext_values_len)`: the poly-major LDE scratch area;
+//! - `offset + 2 * ext_values_len` onwards: the digests followed by the cap, `NUM_HASH_OUT_ELTS`
+//!   elements per hash.
+//!
+//! Before a commitment is built, the poly-major values (or coefficients) are expected at `offset`.
+//!
+//! When the working buffer is too small for the LDE of a whole batch, the LDE is computed
+//! `chunk_polys` polynomials at a time and the scratch area shrinks to the coefficients of one
+//! chunk followed by their LDE; see [`FlatTreeParams::scratch_len`]. The leaves and digests are
+//! the same either way.
+
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::type_name;
+
+use crate::field::extension::Extendable;
+use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
+use crate::field::types::Field;
+use crate::fri::prover::{
+    commit_fri_layer, fold_fri_layer, grind_proof_of_work, reduce_opening_batch, PowJob,
+};
+use crate::hash::hash_types::{HashOut, RichField, NUM_HASH_OUT_ELTS};
+use crate::hash::merkle_tree::MerkleTree;
+use crate::plonk::circuit_data::CommonCircuitData;
+use crate::plonk::config::{GenericConfig, GenericHashOut, Hasher};
+use crate::plonk::error::{ProverError, ProverResult};
+use crate::plonk::plonk_common::{salt_size, PlonkOracle};
+
+pub mod cpu;
+/// Only built with the opt-in `cuda` feature, which needs `nvcc` and the CUDA toolkit.
+#[cfg(feature = "cuda")]
+pub mod cuda;
+pub mod gates;
+pub mod layout;
+pub mod quotient;
+pub mod shard;
+
+pub use cpu::CpuBackend;
+pub use layout::{CircuitTables, ProofLayout};
+pub use shard::ShardPlan;
+
+/// Shape of a batch of polynomials committed through a [`ProverBackend`]. These are the scalar
+/// arguments shared by the `plonky2_cuda` Merkle tree entry points.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FlatTreeParams {
+    pub poly_num: usize,
+    pub values_num_per_poly: usize,
+    pub rate_bits: usize,
+    pub salt_size: usize,
+    pub cap_height: usize,
+    /// Number of polynomials whose LDE is computed at a time, or `None` to compute the LDE of the
+    /// whole batch at once.
+    pub chunk_polys: Option<usize>,
+}
+
+impl FlatTreeParams {
+    /// Parameters of the wires commitment.
+    pub fn wires<F: RichField + Extendable<D>, const D: usize>(
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Self {
+        Self::for_oracle(common_data, common_data.config.num_wires, PlonkOracle::WIRES)
+    }
+
+    /// Parameters of the Z's and partial products commitment.
+    pub fn zs_partial_products<F: RichField + Extendable<D>, const D: usize>(
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Self {
+        Self::for_oracle(
+            common_data,
+            common_data.num_zs_partial_products_polys(),
+            PlonkOracle::ZS_PARTIAL_PRODUCTS,
+        )
+    }
+
+    /// Parameters of the quotient polynomials commitment, whose polynomials are the degree-`n`
+    /// chunks of the quotient polynomials.
+    pub fn quotient_polys<F: RichField + Extendable<D>, const D: usize>(
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Self {
+        Self::for_oracle(
+            common_data,
+            common_data.num_quotient_polys(),
+            PlonkOracle::QUOTIENT,
+        )
+    }
+
+    fn for_oracle<F: RichField + Extendable<D>, const D: usize>(
+        common_data: &CommonCircuitData<F, D>,
+        poly_num: usize,
+        oracle: PlonkOracle,
+    ) -> Self {
+        let config = &common_data.config;
+        Self {
+            poly_num,
+            values_num_per_poly: common_data.degree(),
+            rate_bits: config.fri_config.rate_bits,
+            salt_size: salt_size(config.zero_knowledge && oracle.blinding),
+            cap_height: config.fri_config.cap_height,
+            chunk_polys: None,
+        }
+    }
+
+    /// Computes the LDE `chunk_polys` polynomials at a time. Chunks whose scratch area would not
+    /// be smaller than the LDE of the whole batch are the same as no chunking.
+    pub fn with_chunk_polys(mut self, chunk_polys: Option<usize>) -> Self {
+        assert_ne!(chunk_polys, Some(0), "chunks must hold at least one polynomial");
+        self.chunk_polys = chunk_polys.filter(|&c| c * self.chunk_stride() < self.ext_values_len());
+        self
+    }
+
+    pub fn log_len(&self) -> usize {
+        crate::util::log2_strict(self.values_num_per_poly)
+    }
+
+    /// Number of points in the LDE of each polynomial.
+    pub fn lde_size(&self) -> usize {
+        self.values_num_per_poly << self.rate_bits
+    }
+
+    /// Number of elements in a leaf, salt included.
+    pub fn leaf_len(&self) -> usize {
+        self.poly_num + self.salt_size
+    }
+
+    /// Number of elements in the poly-major input values or coefficients.
+    pub fn values_len(&self) -> usize {
+        self.poly_num * self.values_num_per_poly
+    }
+
+    /// Number of elements in all leaves. This is also the `pad_extvalues_len` passed to the
+    /// kernels, i.e. the offset of the LDE scratch area relative to the leaves.
+    pub fn ext_values_len(&self) -> usize {
+        self.leaf_len() * self.lde_size()
+    }
+
+    pub fn num_digests(&self) -> usize {
+        2 * (self.lde_size() - (1 << self.cap_height))
+    }
+
+    pub fn num_digests_and_caps(&self) -> usize {
+        self.num_digests() + (1 << self.cap_height)
+    }
+
+    /// Scratch space per polynomial of a chunk: its coefficients and its LDE.
+    fn chunk_stride(&self) -> usize {
+        self.values_num_per_poly + self.lde_size()
+    }
+
+    /// Number of elements of the scratch area following the leaves: the poly-major LDE of the
+    /// whole batch, salt included, or the coefficients and the LDE of one chunk.
+    pub fn scratch_len(&self) -> usize {
+        match self.chunk_polys {
+            None => self.ext_values_len(),
+            Some(c) => c * self.chunk_stride(),
+        }
+    }
+
+    /// Offset of the digests relative to the start of the commitment.
+    pub fn digests_offset(&self) -> usize {
+        self.ext_values_len() + self.scratch_len()
+    }
+
+    /// Number of elements the commitment occupies in the working buffer.
+    pub fn footprint(&self) -> usize {
+        self.digests_offset() + self.num_digests_and_caps() * NUM_HASH_OUT_ELTS
+    }
+}
+
+/// Inputs of [`ProverBackend::compute_quotient_polys`]. The wires and Z's leaves are read from the
+/// working buffer; the constants and sigmas leaves are owned by the backend.
+pub struct QuotientPolysJob<'a, F: RichField + Extendable<D>, const D: usize> {
+    pub common_data: &'a CommonCircuitData<F, D>,
+    pub public_inputs_hash: &'a HashOut<F>,
+    pub wires_offset: usize,
+    pub wires: FlatTreeParams,
+    pub zs_partial_products_offset: usize,
+    pub zs_partial_products: FlatTreeParams,
+    /// Where the `num_challenges` quotient polynomials are written, poly-major, in coefficient
+    /// form. Each of them is `lde_size` long, i.e. `1 << rate_bits` chunks of degree `n`.
+    pub quotient_polys_offset: usize,
+    pub alphas: &'a [F],
+    pub betas: &'a [F],
+    pub gammas: &'a [F],
+}
+
+/// The operations of the flattened proving path. `CpuBackend` is a pure-Rust reference which
+/// reproduces the memory layout of the `plonky2_cuda` kernels, so the data plumbing of the
+/// flattened path can run on machines without a GPU.
+///
+/// The FRI operations of `PolynomialBatch::prove_openings` run on the host unless a backend
+/// overrides them; `CpuBackend` only does for the proof-of-work search, to make it cancellable,
+/// and so gives the FRI proofs of the classic prover. `CudaInvContext` runs the reduction of the
+/// opening batches and the commit phase in the FRI region of its working buffer.
+///
+/// Failures are reported as `ProverError`s rather than panics, so that a caller can prove again
+/// with `ProvingStrategy::Cpu`.
+pub trait ProverBackend<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+{
+    /// Number of field elements in the working buffer.
+    fn buffer_len(&self) -> usize;
+
+    /// Offset of the Z's and partial products commitment. The wires commitment starts at 0.
+    fn second_stage_offset(&self) -> usize;
+
+    /// Number of polynomials whose LDE fits in the scratch area of a commitment, or `None` if the
+    /// LDE of a whole batch does. Commitments are built with
+    /// `FlatTreeParams::with_chunk_polys(backend.chunk_polys())`.
+    fn chunk_polys(&self) -> Option<usize> {
+        None
+    }
+
+    /// Copies `values` into the working buffer at `offset`.
+    fn write(&mut self, offset: usize, values: &[F]) -> ProverResult<()>;
+
+    /// Copies `out.len()` elements of the working buffer, starting at `offset`, into `out`.
+    fn read(&self, offset: usize, out: &mut [F]) -> ProverResult<()>;
+
+    /// Interpolates the poly-major values at `offset` in place. Mirrors `plonky2_cuda::ifft`.
+    fn ifft(
+        &mut self,
+        offset: usize,
+        poly_num: usize,
+        values_num_per_poly: usize,
+    ) -> ProverResult<()>;
+
+    /// Interpolates the poly-major values at `offset` and commits to the resulting polynomials,
+    /// whose coefficients are also copied into `coeffs`.
+    fn merkle_tree_from_values(
+        &mut self,
+        offset: usize,
+        params: &FlatTreeParams,
+        coeffs: &mut [F],
+    ) -> ProverResult<()>;
+
+    /// Commits to the poly-major coefficients at `offset`, which are also copied into `coeffs`.
+    /// Mirrors `plonky2_cuda::merkle_tree_from_coeffs`.
+    fn merkle_tree_from_coeffs(
+        &mut self,
+        offset: usize,
+        params: &FlatTreeParams,
+        coeffs: &mut [F],
+    ) -> ProverResult<()>;
+
+    /// Interpolates the poly-major values at `offset` and writes the leaves of their LDE, whose
+    /// coefficients are also copied into `coeffs`, without committing to them. By default, the
+    /// whole commitment is built and its digests are left unused.
+    fn lde_from_values(
+        &mut self,
+        offset: usize,
+        params: &FlatTreeParams,
+        coeffs: &mut [F],
+    ) -> ProverResult<()> {
+        self.merkle_tree_from_values(offset, params, coeffs)
+    }
+
+    /// Builds the digests and cap of the row-major leaves at `offset`. Mirrors
+    /// `plonky2_cuda::hash_leaves`.
+    fn hash_leaves(&mut self, offset: usize, params: &FlatTreeParams) -> ProverResult<()>;
+
+    /// Builds the digests and cap from LDE values already present, poly-major and in natural
+    /// order, in the scratch area of the commitment at `offset`. The leaves are not transposed.
+    /// Mirrors `plonky2_cuda::build_merkle_tree`, and so does not support chunked commitments.
+    fn build_merkle_tree(&mut self, offset: usize, params: &FlatTreeParams) -> ProverResult<()>;
+
+    /// Evaluates the quotient polynomials on the LDE coset and writes their coefficients at
+    /// `job.quotient_polys_offset`. Mirrors `plonky2_cuda::compute_quotient_polys`.
+    fn compute_quotient_polys(&mut self, job: &QuotientPolysJob<F, D>) -> ProverResult<()>;
+
+    /// Host copy of the leaves of the commitment at `offset`, or an empty vector if the backend
+    /// keeps them in device memory only; they can then be read with `lde_leaf`.
+    fn host_leaves(&self, offset: usize, params: &FlatTreeParams) -> ProverResult<Arc<Vec<F>>>;
+
+    /// Reads the `index`-th leaf of the commitment at `offset`.
+    fn lde_leaf(&self, offset: usize, leaf_len: usize, index: usize) -> ProverResult<Vec<F>> {
+        let mut leaf = vec![F::ZERO; leaf_len];
+        self.read(offset + index * leaf_len, &mut leaf)?;
+        Ok(leaf)
+    }
+
+    /// Returns `(F(X) - F(point)) / (X - point)` with `F = sum_j alpha^j polys[j]`, which is what
+    /// an opening batch contributes to the polynomial going into FRI.
+    fn reduce_opening_batch(
+        &mut self,
+        polys: &[&PolynomialCoeffs<F>],
+        alpha: F::Extension,
+        point: F::Extension,
+    ) -> ProverResult<PolynomialCoeffs<F::Extension>> {
+        Ok(reduce_opening_batch::<F, D>(polys, alpha, point))
+    }
+
+    /// Evaluates `coeffs` on the coset of the subgroup of size `coeffs.len()` shifted by `shift`.
+    fn coset_fft_extension(
+        &mut self,
+        coeffs: &PolynomialCoeffs<F::Extension>,
+        shift: F,
+    ) -> ProverResult<PolynomialValues<F::Extension>> {
+        Ok(coeffs.coset_fft(shift.into()))
+    }
+
+    /// Bit-reverses the evaluations of a FRI layer in place and commits to them, each leaf holding
+    /// the `1 << arity_bits` evaluations folded together in the next layer.
+    fn commit_fri_layer(
+        &mut self,
+        values: &mut PolynomialValues<F::Extension>,
+        arity_bits: usize,
+        cap_height: usize,
+    ) -> ProverResult<MerkleTree<F, C::Hasher>> {
+        Ok(commit_fri_layer::<F, C::Hasher, D>(values, arity_bits, cap_height))
+    }
+
+    /// Folds the coefficients of a FRI layer with the challenge `beta`.
+    fn fold_fri_layer(
+        &mut self,
+        coeffs: &PolynomialCoeffs<F::Extension>,
+        arity_bits: usize,
+        beta: F::Extension,
+    ) -> ProverResult<PolynomialCoeffs<F::Extension>> {
+        Ok(fold_fri_layer(coeffs, arity_bits, beta))
+    }
+
+    /// Returns the smallest witness of the FRI proof-of-work search `job`, whatever the number of
+    /// threads, so that proofs are reproducible.
+    fn grind_proof_of_work(&mut self, job: &PowJob<F>) -> ProverResult<F> {
+        grind_proof_of_work::<F, <C::Hasher as Hasher<F>>::Permutation>(job, &|| false)
+    }
+}
+
+/// Reads a hash stored as `NUM_HASH_OUT_ELTS` field elements in the working buffer.
+pub(crate) fn hash_from_elements<F: RichField, H: Hasher<F>>(elements: &[F]) -> H::Hash {
+    debug_assert_eq!(elements.len(), NUM_HASH_OUT_ELTS);
+    let bytes = elements
+        .iter()
+        .flat_map(|x| x.to_canonical_u64().to_le_bytes())
+        .collect::<Vec<_>>();
+    H::Hash::from_bytes(&bytes)
+}
+
+/// Writes `hash` as `NUM_HASH_OUT_ELTS` field elements. Only hashes made of exactly that many
+/// field elements, such as Poseidon's, fit the flattened layout; see [`ensure_flat_hash`].
+pub(crate) fn hash_to_elements<F: RichField, H: Hasher<F>>(hash: &H::Hash, out: &mut [F]) {
+    let elements = hash.to_vec();
+    debug_assert_eq!(elements.len(), NUM_HASH_OUT_ELTS);
+    out.copy_from_slice(&elements);
+}
+
+/// Fails with `ProverError::Unsupported` unless the hashes of `H` are `NUM_HASH_OUT_ELTS` field
+/// elements, the only hashes the flattened layout can store. Keccak's, for instance, are bytes.
+pub(crate) fn ensure_flat_hash<F: RichField, H: Hasher<F>>() -> ProverResult<()> {
+    let hash = H::hash_no_pad(&[]);
+    let elements = hash.to_vec();
+    if H::HASH_SIZE != NUM_HASH_OUT_ELTS * 8
+        || elements.len() != NUM_HASH_OUT_ELTS
+        || hash_from_elements::<F, H>(&elements) != hash
+    {
+        return Err(ProverError::Unsupported(format!(
+            "The flattened layout stores hashes as {} field elements, which {} does not produce",
+            NUM_HASH_OUT_ELTS,
+            type_name::<H>()
+        )));
+    }
+    Ok(())
+}
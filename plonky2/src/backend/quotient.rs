@@ -0,0 +1,420 @@
+//! Evaluation of the quotient polynomials on the flattened buffers read by
+//! `plonky2_cuda::compute_quotient_polys`, vectorized over `PackedField` rows.
+//!
+//! Each batch of `BATCH_SIZE` points of the LDE coset is first copied, column by column, out of the
+//! leaves of the wires, Z's and partial products, and constants and sigmas commitments. The
+//! vanishing polynomial is then evaluated `WIDTH` points at a time, each lane of a packed element
+//! holding one point, like each thread of the kernel.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::once;
+use core::ops::Range;
+
+use maybe_rayon::*;
+
+use crate::backend::cpu::flat_leaf;
+use crate::backend::FlatTreeParams;
+use crate::field::extension::Extendable;
+use crate::field::packable::Packable;
+use crate::field::packed::PackedField;
+use crate::field::polynomial::PolynomialValues;
+use crate::field::types::Field;
+use crate::hash::hash_types::{HashOut, RichField};
+use crate::plonk::circuit_data::CommonCircuitData;
+use crate::plonk::config::GenericConfig;
+use crate::plonk::error::{ensure_shape, ProverResult};
+use crate::plonk::prover::BATCH_SIZE;
+use crate::plonk::vanishing_poly::evaluate_gate_constraints_base_batch;
+use crate::plonk::vars::EvaluationVarsBaseBatch;
+use crate::util::log2_ceil;
+
+/// Evaluates the quotient polynomials at every point of the LDE coset and writes their
+/// coefficients, poly-major, into `quotient_polys`. Takes the same buffers as
+/// `plonky2_cuda::compute_quotient_polys`, on the host: the wires leaves described by `wires`, the
+/// Z's and partial products and the constants and sigmas leaves, `shift^-i` for the coset IFFT,
+/// the subgroup of the LDE in natural order, `Z_H` and its inverse on the coset, and the `k_i`s.
+/// The gates are read from `common_data` rather than from a gate table.
+pub fn compute_quotient_polys_packed<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    common_data: &CommonCircuitData<F, D>,
+    public_inputs_hash: &HashOut<F>,
+    wires_leaves: &[F],
+    wires: &FlatTreeParams,
+    shift_inv_powers: &[F],
+    zs_partial_products_leaves: &[F],
+    constants_sigmas_leaves: &[F],
+    quotient_polys: &mut [F],
+    points: &[F],
+    z_h_on_coset_evals: &[F],
+    z_h_on_coset_inverses: &[F],
+    k_is: &[F],
+    alphas: &[F],
+    betas: &[F],
+    gammas: &[F],
+) -> ProverResult<()> {
+    let config = &common_data.config;
+    let num_challenges = config.num_challenges;
+    let rate_bits = wires.rate_bits;
+    let lde_size = wires.lde_size();
+    ensure_shape!(
+        log2_ceil(common_data.quotient_degree_factor) == rate_bits,
+        "The flattened layout needs a quotient degree factor of 2^rate_bits, got {} with rate_bits = {}",
+        common_data.quotient_degree_factor,
+        rate_bits
+    );
+    ensure_shape!(
+        alphas.len() == num_challenges
+            && betas.len() == num_challenges
+            && gammas.len() == num_challenges,
+        "Expected {} challenges of each kind",
+        num_challenges
+    );
+    ensure_shape!(
+        wires.poly_num == config.num_wires && wires_leaves.len() == wires.ext_values_len(),
+        "The wires leaves do not match the circuit"
+    );
+    ensure_shape!(points.len() == lde_size && shift_inv_powers.len() == lde_size);
+    ensure_shape!(
+        z_h_on_coset_evals.len() == 1 << rate_bits && z_h_on_coset_inverses.len() == 1 << rate_bits
+    );
+    ensure_shape!(k_is.len() >= config.num_routed_wires);
+    ensure_shape!(quotient_polys.len() == num_challenges * lde_size);
+
+    let batch_size = BATCH_SIZE.min(lde_size);
+    ensure_shape!(batch_size % <F as Packable>::Packing::WIDTH == 0);
+    let inputs = QuotientInputs {
+        common_data,
+        public_inputs_hash,
+        lde_bits: wires.log_len() + rate_bits,
+        wires: (wires_leaves, wires.leaf_len()),
+        zs_partial_products: (
+            zs_partial_products_leaves,
+            leaf_len(
+                zs_partial_products_leaves,
+                lde_size,
+                common_data.partial_products_range().end,
+            )?,
+        ),
+        constants_sigmas: (
+            constants_sigmas_leaves,
+            leaf_len(
+                constants_sigmas_leaves,
+                lde_size,
+                common_data.sigmas_range().end,
+            )?,
+        ),
+        points,
+        z_h_on_coset_evals,
+        z_h_on_coset_inverses,
+        k_is,
+        alphas,
+        betas,
+        gammas,
+    };
+
+    // The quotient values, row-major, like the `outs` of the kernel.
+    let mut outs = vec![F::ZERO; num_challenges * lde_size];
+    outs.par_chunks_mut(num_challenges * batch_size)
+        .enumerate()
+        .for_each(|(batch_i, outs)| {
+            eval_quotient_batch::<F, C, D, <F as Packable>::Packing>(
+                &inputs,
+                batch_i * batch_size,
+                outs,
+            )
+        });
+
+    quotient_polys
+        .par_chunks_exact_mut(lde_size)
+        .enumerate()
+        .for_each(|(i, poly)| {
+            let values = (0..lde_size)
+                .map(|row| outs[row * num_challenges + i])
+                .collect();
+            let coeffs = PolynomialValues::new(values).ifft().coeffs;
+            for ((out, c), &shift_inv) in poly.iter_mut().zip(coeffs).zip(shift_inv_powers) {
+                *out = c * shift_inv;
+            }
+        });
+    Ok(())
+}
+
+/// Length of the rows of `leaves`, which must hold `lde_size` rows of at least `min_len` elements.
+fn leaf_len<F>(leaves: &[F], lde_size: usize, min_len: usize) -> ProverResult<usize> {
+    ensure_shape!(
+        leaves.len() % lde_size == 0 && leaves.len() / lde_size >= min_len,
+        "Expected {} leaves of at least {} elements, got {} elements",
+        lde_size,
+        min_len,
+        leaves.len()
+    );
+    Ok(leaves.len() / lde_size)
+}
+
+/// The buffers of [`compute_quotient_polys_packed`]. Leaves come with the length of their rows.
+struct QuotientInputs<'a, F: RichField + Extendable<D>, const D: usize> {
+    common_data: &'a CommonCircuitData<F, D>,
+    public_inputs_hash: &'a HashOut<F>,
+    lde_bits: usize,
+    wires: (&'a [F], usize),
+    zs_partial_products: (&'a [F], usize),
+    constants_sigmas: (&'a [F], usize),
+    points: &'a [F],
+    z_h_on_coset_evals: &'a [F],
+    z_h_on_coset_inverses: &'a [F],
+    k_is: &'a [F],
+    alphas: &'a [F],
+    betas: &'a [F],
+    gammas: &'a [F],
+}
+
+impl<'a, F: RichField + Extendable<D>, const D: usize> QuotientInputs<'a, F, D> {
+    /// Copies the `columns` of the leaves holding the points `start + step..start + step + len` of
+    /// the LDE, column-major: column `j` of the `r`-th point is at `j * len + r`.
+    fn columns(
+        &self,
+        (leaves, leaf_len): (&[F], usize),
+        columns: Range<usize>,
+        start: usize,
+        step: usize,
+        len: usize,
+    ) -> Vec<F> {
+        let lde_mask = (1 << self.lde_bits) - 1;
+        let mut res = vec![F::ZERO; columns.len() * len];
+        for r in 0..len {
+            let leaf = flat_leaf(
+                leaves,
+                leaf_len,
+                self.lde_bits,
+                (start + step + r) & lde_mask,
+            );
+            for (j, &x) in leaf[columns.clone()].iter().enumerate() {
+                res[j * len + r] = x;
+            }
+        }
+        res
+    }
+}
+
+/// Writes the quotient values of the points `start..start + outs.len() / num_challenges` into
+/// `outs`, row-major.
+fn eval_quotient_batch<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+    P: PackedField<Scalar = F>,
+>(
+    inputs: &QuotientInputs<F, D>,
+    start: usize,
+    outs: &mut [F],
+) {
+    let common_data = inputs.common_data;
+    let config = &common_data.config;
+    let num_challenges = config.num_challenges;
+    let num_routed_wires = config.num_routed_wires;
+    let num_prods = common_data.num_partial_products;
+    let max_degree = common_data.quotient_degree_factor;
+    let rate = inputs.z_h_on_coset_evals.len();
+    let len = outs.len() / num_challenges;
+
+    let constants = inputs.columns(
+        inputs.constants_sigmas,
+        common_data.constants_range(),
+        start,
+        0,
+        len,
+    );
+    let s_sigmas = inputs.columns(
+        inputs.constants_sigmas,
+        common_data.sigmas_range(),
+        start,
+        0,
+        len,
+    );
+    let wires = inputs.columns(inputs.wires, 0..config.num_wires, start, 0, len);
+    let zs = inputs.columns(
+        inputs.zs_partial_products,
+        common_data.zs_range(),
+        start,
+        0,
+        len,
+    );
+    // The "next" point is `rate` points away on the LDE.
+    let next_zs = inputs.columns(
+        inputs.zs_partial_products,
+        common_data.zs_range(),
+        start,
+        rate,
+        len,
+    );
+    let partial_products = inputs.columns(
+        inputs.zs_partial_products,
+        common_data.partial_products_range(),
+        start,
+        0,
+        len,
+    );
+
+    let indices = (start..start + len).collect::<Vec<_>>();
+    let vars_batch =
+        EvaluationVarsBaseBatch::new(len, &constants, &wires, inputs.public_inputs_hash);
+    let constraint_terms =
+        evaluate_gate_constraints_base_batch::<F, C, D>(common_data, vars_batch, &indices);
+
+    let xs = inputs.points[start..start + len]
+        .iter()
+        .map(|&x| F::coset_shift() * x)
+        .collect::<Vec<_>>();
+    // `L_0(x) = Z_H(x) / (n * (x - 1))`.
+    let n = F::from_canonical_usize(common_data.degree());
+    let l_0_denominator_invs =
+        F::batch_multiplicative_inverse(&xs.iter().map(|&x| n * (x - F::ONE)).collect::<Vec<_>>());
+
+    let mut terms = Vec::new();
+    for lanes in (0..len).step_by(P::WIDTH).map(|r| r..r + P::WIDTH) {
+        let load = |columns: &[F], j: usize| *P::from_slice(&columns[j * len..][lanes.clone()]);
+        let coset_table = |table: &[F]| {
+            let mut packed = P::ZEROS;
+            for (k, x) in packed.as_slice_mut().iter_mut().enumerate() {
+                *x = table[(start + lanes.start + k) % rate];
+            }
+            packed
+        };
+
+        let x = *P::from_slice(&xs[lanes.clone()]);
+        let l_0_x = coset_table(inputs.z_h_on_coset_evals)
+            * *P::from_slice(&l_0_denominator_invs[lanes.clone()]);
+
+        terms.clear();
+        // The L_0(x) (Z(x) - 1) vanishing terms.
+        terms.extend((0..num_challenges).map(|i| l_0_x * (load(&zs, i) - F::ONE)));
+        // The terms checking the partial products, as in `check_partial_products`.
+        for i in 0..num_challenges {
+            let (beta, gamma) = (inputs.betas[i], inputs.gammas[i]);
+            let accs = once(load(&zs, i))
+                .chain((0..num_prods).map(|k| load(&partial_products, i * num_prods + k)))
+                .chain(once(load(&next_zs, i)))
+                .collect::<Vec<_>>();
+            for (k, chunk_start) in (0..num_routed_wires).step_by(max_degree).enumerate() {
+                let chunk = chunk_start..(chunk_start + max_degree).min(num_routed_wires);
+                let numerator: P = chunk
+                    .clone()
+                    .map(|j| load(&wires, j) + x * (beta * inputs.k_is[j]) + gamma)
+                    .product();
+                let denominator: P = chunk
+                    .map(|j| load(&wires, j) + load(&s_sigmas, j) * beta + gamma)
+                    .product();
+                terms.push(accs[k] * numerator - accs[k + 1] * denominator);
+            }
+        }
+        terms.extend((0..common_data.num_gate_constraints).map(|j| load(&constraint_terms, j)));
+
+        let z_h_inverse = coset_table(inputs.z_h_on_coset_inverses);
+        for (i, &alpha) in inputs.alphas.iter().enumerate() {
+            let quotient = terms
+                .iter()
+                .rev()
+                .fold(P::ZEROS, |acc, &term| acc * alpha + term)
+                * z_h_inverse;
+            for (k, &q) in quotient.as_slice().iter().enumerate() {
+                outs[(lanes.start + k) * num_challenges + i] = q;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::backend::cpu::quotient_polys_flat;
+    use crate::backend::{CircuitTables, CpuBackend};
+    use crate::field::types::Sample;
+    use crate::fri::oracle::PolynomialBatch;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::util::timing::TimingTree;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    /// Commits to random wires and Z's and checks that both flat-buffer evaluations of the
+    /// quotient agree.
+    #[test]
+    fn test_packed_quotient_matches_scalar() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.exp_u64(x, 1 << 20);
+        let z = builder.mul_add(x, y, x);
+        builder.range_check(x, 32);
+        builder.register_public_input(z);
+        let data = builder.build::<C>();
+        let common_data = &data.common;
+        let tables = CircuitTables::new(&data.prover_only, common_data);
+
+        let commit = |params: FlatTreeParams| {
+            let values = F::rand_vec(params.values_len());
+            PolynomialBatch::<F, C, D>::from_values_with_gpu(
+                &values,
+                params.poly_num,
+                params.values_num_per_poly,
+                params.rate_bits,
+                false,
+                params.cap_height,
+                0,
+                &mut TimingTree::default(),
+                &mut CpuBackend::new(params.footprint(), 0),
+            )
+        };
+        let wires = FlatTreeParams::wires(common_data);
+        let zs_partial_products = FlatTreeParams::zs_partial_products(common_data);
+        let wires_leaves = commit(wires)?.merkle_tree.my_leaves.to_vec();
+        let zs_partial_products_leaves =
+            commit(zs_partial_products)?.merkle_tree.my_leaves.to_vec();
+
+        let num_challenges = common_data.config.num_challenges;
+        let [alphas, betas, gammas] = [(); 3].map(|_| F::rand_vec(num_challenges));
+        let public_inputs_hash = HashOut::rand();
+
+        let scalar = quotient_polys_flat::<F, C, D>(
+            common_data,
+            &public_inputs_hash,
+            &tables.constants_sigmas_leaves,
+            tables.constants_sigmas_leaf_len,
+            &wires_leaves,
+            wires.leaf_len(),
+            &zs_partial_products_leaves,
+            zs_partial_products.leaf_len(),
+            &alphas,
+            &betas,
+            &gammas,
+        )?;
+        let mut packed = vec![F::ZERO; scalar.len()];
+        compute_quotient_polys_packed::<F, C, D>(
+            common_data,
+            &public_inputs_hash,
+            &wires_leaves,
+            &wires,
+            &tables.shift_inv_powers,
+            &zs_partial_products_leaves,
+            &tables.constants_sigmas_leaves,
+            &mut packed,
+            &tables.points,
+            &tables.z_h_on_coset_evals,
+            &tables.z_h_on_coset_inverses,
+            &tables.k_is,
+            &alphas,
+            &betas,
+            &gammas,
+        )?;
+        assert_eq!(packed, scalar);
+        Ok(())
+    }
+}
@@ -0,0 +1,338 @@
+//! Commitments split across several backends, e.g. one per device.
+//!
+//! The polynomials of a batch are split column-wise: each backend interpolates and extends its
+//! share of the polynomials. The rows of the LDE are then gathered on the host and split across
+//! the backends, each of which hashes a contiguous range of leaves into whole subtrees of the
+//! final Merkle tree. The subtrees are merged on the host, so the commitment is the one a single
+//! backend computes.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use maybe_rayon::*;
+
+use crate::backend::{hash_from_elements, FlatTreeParams, ProverBackend};
+use crate::field::extension::Extendable;
+use crate::field::types::{Field, Sample};
+use crate::hash::hash_types::{RichField, NUM_HASH_OUT_ELTS};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::error::{ensure_shape, ProverResult};
+use crate::util::log2_strict;
+
+/// How the commitment of a batch of polynomials is split across backends.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ShardPlan {
+    /// Shape of the whole commitment.
+    pub params: FlatTreeParams,
+    /// The polynomials extended by each backend.
+    pub columns: Vec<Range<usize>>,
+    /// Number of backends hashing leaves, the largest power of two which is at most the number
+    /// of backends and the number of leaves.
+    pub row_shards: usize,
+}
+
+impl ShardPlan {
+    pub fn new(params: FlatTreeParams, num_backends: usize) -> Self {
+        assert!(num_backends > 0, "a commitment needs at least one backend");
+        let column_shards = num_backends.min(params.poly_num).max(1);
+        let columns = (0..column_shards)
+            .map(|i| params.poly_num * i / column_shards..params.poly_num * (i + 1) / column_shards)
+            .collect();
+        let max_row_shards = num_backends.min(params.lde_size());
+        Self {
+            params,
+            columns,
+            row_shards: 1 << (usize::BITS - 1 - max_row_shards.leading_zeros()),
+        }
+    }
+
+    /// Parameters of the LDE of the `shard`-th range of polynomials, which is not salted. Its
+    /// leaves are the columns of the final leaves.
+    pub fn column_params(&self, shard: usize) -> FlatTreeParams {
+        FlatTreeParams {
+            poly_num: self.columns[shard].len(),
+            salt_size: 0,
+            chunk_polys: None,
+            ..self.params
+        }
+    }
+
+    /// Parameters of the subtrees built by each backend, over `lde_size / row_shards` leaves.
+    pub fn row_params(&self) -> FlatTreeParams {
+        let shard_bits = log2_strict(self.row_shards);
+        FlatTreeParams {
+            values_num_per_poly: self.params.lde_size() / self.row_shards,
+            rate_bits: 0,
+            cap_height: self.params.cap_height.saturating_sub(shard_bits),
+            chunk_polys: None,
+            ..self.params
+        }
+    }
+
+    /// Number of elements of the working buffer of each backend needed after `offset`.
+    pub fn footprint(&self) -> usize {
+        (0..self.columns.len())
+            .map(|shard| self.column_params(shard).footprint())
+            .chain([self.row_params().footprint()])
+            .max()
+            .unwrap()
+    }
+}
+
+/// A commitment computed by several backends, in the layout of a host `MerkleTree`.
+pub struct ShardedCommitment<F: RichField, H: Hasher<F>> {
+    /// The coefficients of the polynomials, poly-major.
+    pub coeffs: Vec<F>,
+    /// The row-major leaves, salt included.
+    pub leaves: Vec<F>,
+    pub digests: Vec<H::Hash>,
+    pub cap: Vec<H::Hash>,
+}
+
+/// Commits to the poly-major `values` with `backends`, using the working buffer of each backend
+/// from `offset` on. The backends run in parallel, so they must be `Send`.
+///
+/// Unless the batch is salted, the commitment is the same as the one of a single backend; salted
+/// leaves only differ by their random salt.
+pub fn commit_sharded<F, C, B, const D: usize>(
+    values: &[F],
+    plan: &ShardPlan,
+    offset: usize,
+    backends: &mut [B],
+) -> ProverResult<ShardedCommitment<F, C::Hasher>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    B: ProverBackend<F, C, D> + Send,
+{
+    let params = &plan.params;
+    let n = params.values_num_per_poly;
+    ensure_shape!(values.len() == params.values_len());
+    ensure_shape!(
+        backends.len() >= plan.columns.len() && backends.len() >= plan.row_shards,
+        "{} backends for a plan of {} shards",
+        backends.len(),
+        plan.columns.len().max(plan.row_shards)
+    );
+
+    // Column-wise LDE.
+    let shards = backends
+        .par_iter_mut()
+        .zip(plan.columns.clone())
+        .enumerate()
+        .map(|(shard, (backend, columns))| {
+            let shard_params = plan
+                .column_params(shard)
+                .with_chunk_polys(backend.chunk_polys());
+            backend.write(offset, &values[columns.start * n..columns.end * n])?;
+            let mut coeffs = vec![F::ZERO; shard_params.values_len()];
+            backend.lde_from_values(offset, &shard_params, &mut coeffs)?;
+            let mut leaves = vec![F::ZERO; shard_params.ext_values_len()];
+            backend.read(offset, &mut leaves)?;
+            Ok((coeffs, leaves))
+        })
+        .collect::<ProverResult<Vec<_>>>()?;
+
+    let leaf_len = params.leaf_len();
+    let mut leaves = vec![F::ZERO; params.ext_values_len()];
+    let salt = F::rand_vec(params.salt_size * params.lde_size());
+    leaves
+        .par_chunks_exact_mut(leaf_len)
+        .enumerate()
+        .for_each(|(row, leaf)| {
+            for (columns, (_, shard_leaves)) in plan.columns.iter().zip(&shards) {
+                let shard_row = &shard_leaves[row * columns.len()..(row + 1) * columns.len()];
+                leaf[columns.clone()].copy_from_slice(shard_row);
+            }
+            leaf[params.poly_num..]
+                .copy_from_slice(&salt[row * params.salt_size..(row + 1) * params.salt_size]);
+        });
+    let coeffs = shards.into_iter().flat_map(|(coeffs, _)| coeffs).collect();
+
+    // Row-wise hashing.
+    let row_params = plan.row_params();
+    let shard_len = row_params.ext_values_len();
+    let subtrees = backends[..plan.row_shards]
+        .par_iter_mut()
+        .enumerate()
+        .map(|(shard, backend)| {
+            backend.write(offset, &leaves[shard * shard_len..(shard + 1) * shard_len])?;
+            backend.hash_leaves(offset, &row_params)?;
+            let mut elements = vec![F::ZERO; row_params.num_digests_and_caps() * NUM_HASH_OUT_ELTS];
+            backend.read(offset + row_params.digests_offset(), &mut elements)?;
+            let mut digests = elements
+                .chunks_exact(NUM_HASH_OUT_ELTS)
+                .map(hash_from_elements::<F, C::Hasher>)
+                .collect::<Vec<_>>();
+            let cap = digests.split_off(row_params.num_digests());
+            Ok((digests, cap))
+        })
+        .collect::<ProverResult<Vec<_>>>()?;
+
+    let (digests, cap) = merge_subtrees::<F, C::Hasher>(subtrees, params.cap_height);
+    Ok(ShardedCommitment {
+        coeffs,
+        leaves,
+        digests,
+        cap,
+    })
+}
+
+/// Merges the digests and caps of the trees built over consecutive ranges of leaves into those of
+/// the tree over all leaves, with a cap of height `cap_height`.
+fn merge_subtrees<F: RichField, H: Hasher<F>>(
+    subtrees: Vec<(Vec<H::Hash>, Vec<H::Hash>)>,
+    cap_height: usize,
+) -> (Vec<H::Hash>, Vec<H::Hash>) {
+    let shard_bits = log2_strict(subtrees.len());
+    if cap_height >= shard_bits {
+        // Each subtree of the final tree is a subtree of a shard.
+        let (digests, caps): (Vec<_>, Vec<_>) = subtrees.into_iter().unzip();
+        return (digests.concat(), caps.concat());
+    }
+
+    // Each shard is a single subtree, with a cap of height 0. The top layers of the subtrees of
+    // the final tree are rebuilt on the host.
+    let roots = subtrees
+        .into_iter()
+        .map(|(digests, cap)| (digests, cap[0]))
+        .collect::<Vec<_>>();
+    let (digests, cap): (Vec<_>, Vec<_>) = roots
+        .chunks(1 << (shard_bits - cap_height))
+        .map(merge_roots::<F, H>)
+        .unzip();
+    (digests.concat(), cap)
+}
+
+/// Merges consecutive subtrees, given with their roots, into their parent subtree, in the layout of
+/// `MerkleTree::digests`: left subtree, left root, right root, right subtree.
+fn merge_roots<F: RichField, H: Hasher<F>>(
+    subtrees: &[(Vec<H::Hash>, H::Hash)],
+) -> (Vec<H::Hash>, H::Hash) {
+    if let [subtree] = subtrees {
+        return subtree.clone();
+    }
+    let (left, right) = subtrees.split_at(subtrees.len() / 2);
+    let (left_digests, left_root) = merge_roots::<F, H>(left);
+    let (right_digests, right_root) = merge_roots::<F, H>(right);
+    let mut digests = left_digests;
+    digests.extend([left_root, right_root]);
+    digests.extend(right_digests);
+    (digests, H::two_to_one(left_root, right_root))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::backend::CpuBackend;
+    use crate::field::polynomial::PolynomialValues;
+    use crate::fri::oracle::PolynomialBatch;
+    use crate::hash::merkle_tree::MerkleTree;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::util::timing::TimingTree;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type H = <C as GenericConfig<D>>::Hasher;
+
+    fn commit(
+        values: &[F],
+        params: &FlatTreeParams,
+        num_backends: usize,
+    ) -> Result<PolynomialBatch<F, C, D>> {
+        let plan = ShardPlan::new(*params, num_backends);
+        let offset = 5;
+        let mut backends = (0..num_backends)
+            .map(|_| CpuBackend::<F>::new(offset + plan.footprint(), 0))
+            .collect::<Vec<_>>();
+        Ok(PolynomialBatch::from_values_sharded(
+            values,
+            params.poly_num,
+            params.values_num_per_poly,
+            params.rate_bits,
+            params.salt_size > 0,
+            params.cap_height,
+            offset,
+            &mut TimingTree::default(),
+            &mut backends,
+        )?)
+    }
+
+    #[test]
+    fn test_sharded_commitment_matches_single_backend() -> Result<()> {
+        for (cap_height, num_backends) in [(2, 1), (2, 2), (2, 3), (3, 4), (0, 4), (1, 8)] {
+            let params = FlatTreeParams {
+                poly_num: 7,
+                values_num_per_poly: 1 << 3,
+                rate_bits: 1,
+                salt_size: 0,
+                cap_height,
+                chunk_polys: None,
+            };
+            let values = F::rand_vec(params.values_len());
+
+            let mut backend = CpuBackend::<F>::new(params.footprint(), 0);
+            let single = PolynomialBatch::<F, C, D>::from_values_with_gpu(
+                &values,
+                params.poly_num,
+                params.values_num_per_poly,
+                params.rate_bits,
+                false,
+                cap_height,
+                0,
+                &mut TimingTree::default(),
+                &mut backend,
+            )?;
+            let sharded = commit(&values, &params, num_backends)?;
+
+            let (single_tree, sharded_tree) = (&single.merkle_tree, &sharded.merkle_tree);
+            assert_eq!(sharded_tree.my_leaves, single_tree.my_leaves);
+            assert_eq!(sharded_tree.my_digests, single_tree.my_digests);
+            assert_eq!(sharded_tree.cap, single_tree.cap);
+            assert_eq!(sharded.polynomials, single.polynomials);
+
+            let classic = PolynomialBatch::<F, C, D>::from_values(
+                values
+                    .chunks_exact(params.values_num_per_poly)
+                    .map(|v| PolynomialValues::new(v.to_vec()))
+                    .collect(),
+                params.rate_bits,
+                false,
+                cap_height,
+                &mut TimingTree::default(),
+                None,
+            );
+            assert_eq!(sharded_tree.cap, classic.merkle_tree.cap);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_sharded_salted_commitment() -> Result<()> {
+        let params = FlatTreeParams {
+            poly_num: 5,
+            values_num_per_poly: 1 << 4,
+            rate_bits: 2,
+            salt_size: 4,
+            cap_height: 1,
+            chunk_polys: None,
+        };
+        let values = F::rand_vec(params.values_len());
+        let sharded = commit(&values, &params, 3)?;
+
+        let tree = &sharded.merkle_tree;
+        let leaves = tree
+            .my_leaves
+            .chunks_exact(params.leaf_len())
+            .map(|leaf| leaf.to_vec())
+            .collect::<Vec<_>>();
+        let expected = MerkleTree::<F, H>::new(leaves, params.cap_height);
+        assert_eq!(tree.cap, expected.cap);
+        assert_eq!(tree.my_digests[..expected.digests.len()], expected.digests);
+        Ok(())
+    }
+}
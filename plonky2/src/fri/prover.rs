@@ -1,22 +1,24 @@
+use alloc::format;
 use alloc::vec::Vec;
-use std::intrinsics::transmute;
-use itertools::Itertools;
 
 use maybe_rayon::*;
-use rustacuda::memory::{AsyncCopyDestination, DeviceSlice};
 
+use crate::backend::ProverBackend;
 use crate::field::extension::{flatten, unflatten, Extendable};
 use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
+use crate::field::types::Field;
 use crate::fri::proof::{FriInitialTreeProof, FriProof, FriQueryRound, FriQueryStep};
 use crate::fri::{FriConfig, FriParams};
 use crate::hash::hash_types::RichField;
-use crate::hash::hashing::{PlonkyPermutation, SPONGE_RATE};
+use crate::hash::hashing::{PlonkyPermutation, SPONGE_RATE, SPONGE_WIDTH};
 use crate::hash::merkle_proofs::MerkleProof;
 use crate::hash::merkle_tree::MerkleTree;
 use crate::iop::challenger::Challenger;
 use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::error::{ProverError, ProverResult};
 use crate::plonk::plonk_common::reduce_with_powers;
 use crate::timed;
+use crate::util::reducing::ReducingFactor;
 use crate::util::reverse_index_bits_in_place;
 use crate::util::timing::TimingTree;
 
@@ -30,8 +32,8 @@ pub fn fri_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const
     challenger: &mut Challenger<F, C::Hasher>,
     fri_params: &FriParams,
     timing: &mut TimingTree,
-    ctx: &mut Option<&mut crate::fri::oracle::CudaInvContext<F, C, D>>,
-) -> FriProof<F, C::Hasher, D> {
+    backend: &mut dyn ProverBackend<F, C, D>,
+) -> ProverResult<FriProof<F, C::Hasher, D>> {
     let n = lde_polynomial_values.len();
     assert_eq!(lde_polynomial_coeffs.len(), n);
 
@@ -44,29 +46,37 @@ pub fn fri_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const
             lde_polynomial_values,
             challenger,
             fri_params,
+            backend,
         )
-    );
+    )?;
 
     // PoW phase
     let pow_witness = timed!(
         timing,
         "find proof-of-work witness",
-        fri_proof_of_work::<F, C, D>(challenger, &fri_params.config)
-    );
+        fri_proof_of_work::<F, C, D>(challenger, &fri_params.config, backend)
+    )?;
 
     // Query phase
     let query_round_proofs = timed!(
         timing,
         "fri_prover_query_rounds",
-        fri_prover_query_rounds::<F, C, D>(initial_merkle_trees, &trees, challenger, n, fri_params, ctx)
-    );
+        fri_prover_query_rounds::<F, C, D>(
+            initial_merkle_trees,
+            &trees,
+            challenger,
+            n,
+            fri_params,
+            backend,
+        )
+    )?;
 
-    FriProof {
+    Ok(FriProof {
         commit_phase_merkle_caps: trees.iter().map(|t| t.cap.clone()).collect(),
         query_round_proofs,
         final_poly: final_coeffs,
         pow_witness,
-    }
+    })
 }
 
 type FriCommitedTrees<F, C, const D: usize> = (
@@ -79,35 +89,22 @@ fn fri_committed_trees<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>,
     mut values: PolynomialValues<F::Extension>,
     challenger: &mut Challenger<F, C::Hasher>,
     fri_params: &FriParams,
-) -> FriCommitedTrees<F, C, D> {
+    backend: &mut dyn ProverBackend<F, C, D>,
+) -> ProverResult<FriCommitedTrees<F, C, D>> {
     let mut trees = Vec::new();
 
     let mut shift = F::MULTIPLICATIVE_GROUP_GENERATOR;
-    for arity_bits in &fri_params.reduction_arity_bits {
-        let arity = 1 << arity_bits;
-
-        reverse_index_bits_in_place(&mut values.values);
-        let chunked_values = values
-            .values
-            .par_chunks(arity)
-            .map(|chunk: &[F::Extension]| flatten(chunk))
-            .collect();
-        let tree = MerkleTree::<F, C::Hasher>::new(chunked_values, fri_params.config.cap_height);
+    for &arity_bits in &fri_params.reduction_arity_bits {
+        let tree =
+            backend.commit_fri_layer(&mut values, arity_bits, fri_params.config.cap_height)?;
 
         challenger.observe_cap(&tree.cap);
         trees.push(tree);
 
         let beta = challenger.get_extension_challenge::<D>();
-        // P(x) = sum_{i<r} x^i * P_i(x^r) becomes sum_{i<r} beta^i * P_i(x).
-        coeffs = PolynomialCoeffs::new(
-            coeffs
-                .coeffs
-                .par_chunks_exact(arity)
-                .map(|chunk| reduce_with_powers(chunk, beta))
-                .collect::<Vec<_>>(),
-        );
-        shift = shift.exp_u64(arity as u64);
-        values = coeffs.coset_fft(shift.into())
+        coeffs = backend.fold_fri_layer(&coeffs, arity_bits, beta)?;
+        shift = shift.exp_power_of_2(arity_bits);
+        values = backend.coset_fft_extension(&coeffs, shift)?;
     }
 
     // The coefficients being removed here should always be zero.
@@ -116,58 +113,195 @@ fn fri_committed_trees<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>,
         .truncate(coeffs.len() >> fri_params.config.rate_bits);
 
     challenger.observe_extension_elements(&coeffs.coeffs);
-    (trees, coeffs)
+    Ok((trees, coeffs))
+}
+
+/// Returns `(F(X) - F(point)) / (X - point)` with `F = sum_j alpha^j polys[j]`, which is what an
+/// opening batch contributes to the polynomial going into FRI.
+pub fn reduce_opening_batch<F: RichField + Extendable<D>, const D: usize>(
+    polys: &[&PolynomialCoeffs<F>],
+    alpha: F::Extension,
+    point: F::Extension,
+) -> PolynomialCoeffs<F::Extension> {
+    ReducingFactor::new(alpha)
+        .reduce_polys_base(polys.iter().copied())
+        .divide_by_linear(point)
+}
+
+/// Bit-reverses the evaluations of a FRI layer in place and commits to them, each leaf holding
+/// the `1 << arity_bits` evaluations folded together in the next layer.
+pub fn commit_fri_layer<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize>(
+    values: &mut PolynomialValues<F::Extension>,
+    arity_bits: usize,
+    cap_height: usize,
+) -> MerkleTree<F, H> {
+    reverse_index_bits_in_place(&mut values.values);
+    let chunked_values = values
+        .values
+        .par_chunks(1 << arity_bits)
+        .map(|chunk: &[F::Extension]| flatten(chunk))
+        .collect();
+    MerkleTree::new(chunked_values, cap_height)
+}
+
+/// Folds a FRI layer: with `r = 1 << arity_bits`, `P(x) = sum_{i<r} x^i * P_i(x^r)` becomes
+/// `sum_{i<r} beta^i * P_i(x)`.
+pub fn fold_fri_layer<F: Field>(
+    coeffs: &PolynomialCoeffs<F>,
+    arity_bits: usize,
+    beta: F,
+) -> PolynomialCoeffs<F> {
+    PolynomialCoeffs::new(
+        coeffs
+            .coeffs
+            .par_chunks_exact(1 << arity_bits)
+            .map(|chunk| reduce_with_powers(chunk, beta))
+            .collect(),
+    )
 }
 
 /// Performs the proof-of-work (a.k.a. grinding) step of the FRI protocol. Returns the PoW witness.
 fn fri_proof_of_work<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
     challenger: &mut Challenger<F, C::Hasher>,
     config: &FriConfig,
-) -> F {
-    let min_leading_zeros = config.proof_of_work_bits + (64 - F::order().bits()) as u32;
-
-    // The easiest implementation would be repeatedly clone our Challenger. With each clone, we'd
-    // observe an incrementing PoW witness, then get the PoW response. If it contained sufficient
-    // leading zeros, we'd end the search, and store this clone as our new challenger.
-    //
-    // However, performance is critical here. We want to avoid cloning Challenger, particularly
-    // since it stores vectors, which means allocations. We'd like a more compact state to clone.
-    //
-    // We know that a duplex will be performed right after we send the PoW witness, so we can ignore
-    // any output_buffer, which will be invalidated. We also know input_buffer.len() < SPONGE_WIDTH,
-    // an invariant of Challenger.
-    //
-    // We separate the duplex operation into two steps, one which can be performed now, and the
-    // other which depends on the PoW witness candidate. The first step is the overwrite our sponge
-    // state with any inputs (excluding the PoW witness candidate). The second step is to overwrite
-    // one more element of our sponge state with the candidate, then apply the permutation,
-    // obtaining our duplex's post-state which contains the PoW response.
-    let mut duplex_intermediate_state = challenger.sponge_state;
-    let witness_input_pos = challenger.input_buffer.len();
-    for (i, input) in challenger.input_buffer.iter().enumerate() {
-        duplex_intermediate_state[i] = *input;
-    }
-
-    let pow_witness = (0..=F::NEG_ONE.to_canonical_u64())
-        .into_par_iter()
-        .find_any(|&candidate| {
-            let mut duplex_state = duplex_intermediate_state;
-            duplex_state[witness_input_pos] = F::from_canonical_u64(candidate);
-            duplex_state =
-                <<C as GenericConfig<D>>::Hasher as Hasher<F>>::Permutation::permute(duplex_state);
-            let pow_response = duplex_state[SPONGE_RATE - 1];
-            let leading_zeros = pow_response.to_canonical_u64().leading_zeros();
-            leading_zeros >= min_leading_zeros
-        })
-        .map(F::from_canonical_u64)
-        .expect("Proof of work failed. This is highly unlikely!");
+    backend: &mut dyn ProverBackend<F, C, D>,
+) -> ProverResult<F> {
+    let job = PowJob::new(challenger, config);
+    let pow_witness = backend.grind_proof_of_work(&job)?;
 
     // Recompute pow_response using our normal Challenger code, and make sure it matches.
     challenger.observe_element(pow_witness);
     let pow_response = challenger.get_challenge();
     let leading_zeros = pow_response.to_canonical_u64().leading_zeros();
-    assert!(leading_zeros >= min_leading_zeros);
-    pow_witness
+    if leading_zeros < job.min_leading_zeros {
+        return Err(ProverError::Transcript(format!(
+            "the proof-of-work witness {} gives {} leading zeros, {} are required",
+            pow_witness, leading_zeros, job.min_leading_zeros
+        )));
+    }
+    Ok(pow_witness)
+}
+
+/// A proof-of-work search, i.e. the part of the next duplex of the challenger which does not
+/// depend on the witness.
+///
+/// The easiest implementation would be repeatedly clone our Challenger. With each clone, we'd
+/// observe an incrementing PoW witness, then get the PoW response. If it contained sufficient
+/// leading zeros, we'd end the search, and store this clone as our new challenger.
+///
+/// However, performance is critical here. We want to avoid cloning Challenger, particularly
+/// since it stores vectors, which means allocations. We'd like a more compact state to clone.
+///
+/// We know that a duplex will be performed right after we send the PoW witness, so we can ignore
+/// any output_buffer, which will be invalidated. We also know input_buffer.len() < SPONGE_WIDTH,
+/// an invariant of Challenger.
+///
+/// We separate the duplex operation into two steps, one which can be performed now, and the
+/// other which depends on the PoW witness candidate. The first step is the overwrite our sponge
+/// state with any inputs (excluding the PoW witness candidate). The second step is to overwrite
+/// one more element of our sponge state with the candidate, then apply the permutation,
+/// obtaining our duplex's post-state which contains the PoW response.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PowJob<F: Field> {
+    /// The sponge state overwritten with the pending inputs of the challenger.
+    pub duplex_intermediate_state: [F; SPONGE_WIDTH],
+    /// The position of the witness in the sponge state.
+    pub witness_input_pos: usize,
+    /// The number of leading zeros required of the canonical `u64` of the PoW response.
+    pub min_leading_zeros: u32,
+}
+
+impl<F: RichField> PowJob<F> {
+    pub fn new<H: Hasher<F>>(challenger: &Challenger<F, H>, config: &FriConfig) -> Self {
+        let mut duplex_intermediate_state = challenger.sponge_state;
+        let witness_input_pos = challenger.input_buffer.len();
+        for (i, input) in challenger.input_buffer.iter().enumerate() {
+            duplex_intermediate_state[i] = *input;
+        }
+        Self {
+            duplex_intermediate_state,
+            witness_input_pos,
+            min_leading_zeros: config.proof_of_work_bits + (64 - F::order().bits()) as u32,
+        }
+    }
+
+    /// Whether `candidate` is a valid PoW witness.
+    pub fn accepts<P: PlonkyPermutation<F>>(&self, candidate: u64) -> bool {
+        let mut duplex_state = self.duplex_intermediate_state;
+        duplex_state[self.witness_input_pos] = F::from_canonical_u64(candidate);
+        let pow_response = P::permute(duplex_state)[SPONGE_RATE - 1];
+        pow_response.to_canonical_u64().leading_zeros() >= self.min_leading_zeros
+    }
+
+    /// The smallest valid PoW witness in `start..=end`, if any. The candidates are permuted
+    /// `POW_GROUP_SIZE` at a time through `P::permute_many`.
+    pub fn first_accepted<P: PlonkyPermutation<F>>(&self, start: u64, end: u64) -> Option<u64> {
+        let mut states = [self.duplex_intermediate_state; POW_GROUP_SIZE];
+        let mut group_start = start;
+        while group_start <= end {
+            let group_len = (end - group_start).min(POW_GROUP_SIZE as u64 - 1) as usize + 1;
+            let states = &mut states[..group_len];
+            for (i, state) in states.iter_mut().enumerate() {
+                *state = self.duplex_intermediate_state;
+                state[self.witness_input_pos] = F::from_canonical_u64(group_start + i as u64);
+            }
+            P::permute_many(states);
+            let accepted = states.iter().position(|state| {
+                state[SPONGE_RATE - 1].to_canonical_u64().leading_zeros() >= self.min_leading_zeros
+            });
+            if let Some(i) = accepted {
+                return Some(group_start + i as u64);
+            }
+            group_start = group_start.checked_add(group_len as u64)?;
+        }
+        None
+    }
+}
+
+/// Number of candidates permuted together, enough to fill the widest packing of the field.
+const POW_GROUP_SIZE: usize = 16;
+
+/// Number of candidates checked one after the other by a task of the PoW search.
+const POW_BATCH_SIZE: u64 = 1 << 10;
+/// Number of batches searched in parallel before looking for a witness among them.
+const POW_BATCHES_PER_ROUND: u64 = 1 << 6;
+
+/// Searches the smallest PoW witness of `job` on the host. The candidates are scanned in rounds of
+/// `POW_BATCHES_PER_ROUND` batches which run in parallel, so the witness does not depend on the
+/// number of threads. `is_cancelled` is polled between rounds.
+///
+/// The permutations go through `P::permute_many`, which evaluates several candidates per call
+/// with the packed Poseidon of the field when the target has SIMD support.
+pub fn grind_proof_of_work<F: RichField, P: PlonkyPermutation<F>>(
+    job: &PowJob<F>,
+    is_cancelled: &dyn Fn() -> bool,
+) -> ProverResult<F> {
+    let num_candidates = F::NEG_ONE.to_canonical_u64();
+    let round_size = POW_BATCH_SIZE * POW_BATCHES_PER_ROUND;
+    let mut round_start = 0;
+    while round_start <= num_candidates {
+        if is_cancelled() {
+            return Err(ProverError::Cancelled);
+        }
+        let witness = (0..POW_BATCHES_PER_ROUND)
+            .into_par_iter()
+            .filter_map(|batch| {
+                let start = round_start.checked_add(batch * POW_BATCH_SIZE)?;
+                let end = start.saturating_add(POW_BATCH_SIZE - 1).min(num_candidates);
+                job.first_accepted::<P>(start, end)
+            })
+            .min();
+        if let Some(witness) = witness {
+            return Ok(F::from_canonical_u64(witness));
+        }
+        round_start = match round_start.checked_add(round_size) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    Err(ProverError::Transcript(
+        "no proof-of-work witness in the field".into(),
+    ))
 }
 
 fn fri_prover_query_rounds<
@@ -180,47 +314,38 @@ fn fri_prover_query_rounds<
     challenger: &mut Challenger<F, C::Hasher>,
     n: usize,
     fri_params: &FriParams,
-    ctx: &mut Option<&mut crate::fri::oracle::CudaInvContext<F, C, D>>,
-) -> Vec<FriQueryRound<F, C::Hasher, D>> {
+    backend: &dyn ProverBackend<F, C, D>,
+) -> ProverResult<Vec<FriQueryRound<F, C::Hasher, D>>> {
     let challs = challenger.get_n_challenges(fri_params.config.num_query_rounds);
 
     let proofs_vec = challs.iter()
         .map(|rand| {
             let x_index = rand.to_canonical_u64() as usize % n;
 
-            let initial_proof = initial_merkle_trees
+            initial_merkle_trees
                 .iter()
                 .map(|t| {
-                    if t.my_leaves_dev_offset >= 0 && ctx.is_some() {
-                        let ctx = ctx.as_mut().unwrap();
-                        let data = &mut (*ctx).cache_mem_device[t.my_leaves_dev_offset as usize..];
-                        let data = &mut data[x_index * t.my_leaf_len.. (x_index+1) * t.my_leaf_len];
-
-                        let mut values = Vec::<F>::with_capacity(t.my_leaf_len);
-                        unsafe {
-                            values.set_len(data.len());
-                            transmute::<&DeviceSlice<F>, &DeviceSlice<u64>>(data).async_copy_to(
-                                transmute::<&mut Vec<F>, &mut Vec<u64>>(&mut values),
-                                &ctx.inner.stream).unwrap();
-                            ctx.inner.stream.synchronize().unwrap();
-                        }
-                        ctx.inner.stream.synchronize().unwrap();
-                        (values, t.prove(x_index))
+                    if t.my_leaves_dev_offset >= 0 {
+                        let values = backend.lde_leaf(
+                            t.my_leaves_dev_offset as usize,
+                            t.my_leaf_len,
+                            x_index,
+                        )?;
+                        Ok((values, t.prove(x_index)))
                     } else {
-                        (t.get(x_index).to_vec(), t.prove(x_index))
+                        Ok((t.get(x_index).to_vec(), t.prove(x_index)))
                     }
                 })
-                .collect::<Vec<_>>();
-            initial_proof
+                .collect::<ProverResult<Vec<_>>>()
         })
-        .collect_vec();
+        .collect::<ProverResult<Vec<_>>>()?;
 
-    challs.into_par_iter().zip(proofs_vec)
+    Ok(challs.into_par_iter().zip(proofs_vec)
         .map(|(rand, initial_proof)| {
             let x_index = rand.to_canonical_u64() as usize % n;
             fri_prover_query_round::<F, C, D>(initial_proof, trees, x_index, fri_params)
         })
-        .collect()
+        .collect())
 }
 
 fn fri_prover_query_round<
@@ -258,3 +383,67 @@ fn fri_prover_query_round<
         steps: query_steps,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::hash::poseidon::PoseidonPermutation;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn random_job(proof_of_work_bits: u32) -> PowJob<F> {
+        PowJob {
+            duplex_intermediate_state: F::rand_array(),
+            witness_input_pos: 3,
+            min_leading_zeros: proof_of_work_bits,
+        }
+    }
+
+    #[test]
+    fn test_grinding_finds_smallest_witness() -> Result<()> {
+        for _ in 0..4 {
+            let job = random_job(12);
+            let witness = grind_proof_of_work::<F, PoseidonPermutation>(&job, &|| false)?;
+            let expected = (0..)
+                .find(|&candidate| job.accepts::<PoseidonPermutation>(candidate))
+                .unwrap();
+            assert_eq!(witness.to_canonical_u64(), expected);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_grinding_does_not_depend_on_threads() -> Result<()> {
+        let job = random_job(14);
+        let grind_with_threads = |num_threads| {
+            maybe_rayon::rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .unwrap()
+                .install(|| grind_proof_of_work::<F, PoseidonPermutation>(&job, &|| false))
+        };
+        assert_eq!(grind_with_threads(1)?, grind_with_threads(7)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_grinding_is_cancellable() {
+        let job = random_job(64);
+        let rounds = Cell::new(0);
+        let is_cancelled = || {
+            rounds.set(rounds.get() + 1);
+            rounds.get() > 2
+        };
+        let err = grind_proof_of_work::<F, PoseidonPermutation>(&job, &is_cancelled).unwrap_err();
+        assert_eq!(err, ProverError::Cancelled);
+    }
+}
@@ -11,6 +11,7 @@ use crate::iop::generator::{GeneratedValues, SimpleGenerator};
 use crate::iop::target::{BoolTarget, Target};
 use crate::iop::witness::{PartitionWitness, Witness, WitnessWrite};
 use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::util::serialization::{Buffer, IoResult, Read, Write};
 
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     /// Computes `-x`.
@@ -365,7 +366,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 }
 
 #[derive(Debug)]
-struct EqualityGenerator {
+pub(crate) struct EqualityGenerator {
     x: Target,
     y: Target,
     equal: BoolTarget,
@@ -386,6 +387,22 @@ impl<F: RichField> SimpleGenerator<F> for EqualityGenerator {
         out_buffer.set_bool_target(self.equal, x == y);
         out_buffer.set_target(self.inv, inv);
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target(self.x)?;
+        dst.write_target(self.y)?;
+        dst.write_bool_target(self.equal)?;
+        dst.write_target(self.inv)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            x: src.read_target()?,
+            y: src.read_target()?,
+            equal: src.read_bool_target()?,
+            inv: src.read_target()?,
+        })
+    }
 }
 
 /// Represents a base arithmetic operation in the circuit. Used to memoize results.
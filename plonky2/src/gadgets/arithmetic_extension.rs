@@ -13,6 +13,7 @@ use crate::iop::target::Target;
 use crate::iop::witness::{PartitionWitness, Witness, WitnessWrite};
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::util::bits_u64;
+use crate::util::serialization::{Buffer, IoResult, Read, Write};
 
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     pub fn arithmetic_extension(
@@ -494,7 +495,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 }
 
 #[derive(Debug)]
-struct QuotientGeneratorExtension<const D: usize> {
+pub(crate) struct QuotientGeneratorExtension<const D: usize> {
     numerator: ExtensionTarget<D>,
     denominator: ExtensionTarget<D>,
     quotient: ExtensionTarget<D>,
@@ -515,6 +516,20 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
         let quotient = num / dem;
         out_buffer.set_extension_target(self.quotient, quotient)
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_ext(self.numerator)?;
+        dst.write_target_ext(self.denominator)?;
+        dst.write_target_ext(self.quotient)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            numerator: src.read_target_ext()?,
+            denominator: src.read_target_ext()?,
+            quotient: src.read_target_ext()?,
+        })
+    }
 }
 
 /// An iterator over the powers of a certain base element `b`: `b^0, b^1, b^2, ...`.
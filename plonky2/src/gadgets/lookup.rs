@@ -0,0 +1,474 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::field::extension::Extendable;
+use crate::field::polynomial::PolynomialValues;
+use crate::field::types::Field;
+use crate::gates::gate::GateRef;
+use crate::gates::lookup::LookupGate;
+use crate::gates::lookup_table::LookupTableGate;
+use crate::hash::hash_types::RichField;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::util::ceil_div_usize;
+use crate::util::serialization::{Buffer, IoError, IoResult, Read, Remaining, Write};
+
+/// A table of `(input, output)` pairs, sorted by input. Each input appears at most once.
+pub type LookupTable = Arc<Vec<(u16, u16)>>;
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Adds a lookup table to the circuit, returning the index to pass to `add_lookup`. The inputs
+    /// of `table` must be distinct. Adding a table which was already added returns the index of
+    /// the first one.
+    ///
+    /// The table is laid out on `LookupTableGate` rows, `LookupTableGate::num_slots` entries per
+    /// row, when the circuit is built. Since the generator of each `LookupGate` row holds a copy
+    /// of its table, large tables also make large serialized prover data.
+    pub fn add_lookup_table(&mut self, table: &[(u16, u16)]) -> usize {
+        assert!(!table.is_empty(), "A lookup table needs at least one entry");
+        assert!(
+            !self.config.zero_knowledge,
+            "Lookups are not supported in zero-knowledge circuits"
+        );
+        assert!(
+            self.config.max_quotient_degree_factor >= 2,
+            "Lookups need a quotient degree factor of at least 2"
+        );
+
+        let mut table = table.to_vec();
+        table.sort_unstable();
+        assert!(
+            table.windows(2).all(|w| w[0].0 != w[1].0),
+            "The inputs of a lookup table must be distinct"
+        );
+        if let Some(index) = self.lookup_tables.iter().position(|t| **t == table) {
+            return index;
+        }
+        self.lookup_tables.push(Arc::new(table));
+        self.lookup_rows.push(Vec::new());
+        self.lookup_tables.len() - 1
+    }
+
+    /// Looks `input` up in the table `table_index`, returning the matching output. The proof fails
+    /// if `input` is not an input of the table.
+    pub fn add_lookup(&mut self, input: Target, table_index: usize) -> Target {
+        let table = self.lookup_tables[table_index].clone();
+        let gate = LookupGate::new_from_config(&self.config);
+        let (row, slot) = self.find_slot(gate, &[F::from_canonical_usize(table_index)], &[]);
+        if slot == 0 {
+            self.lookup_rows[table_index].push(row);
+            self.add_generators(vec![Box::new(LookupGenerator {
+                row,
+                num_slots: gate.num_slots,
+                table,
+            })]);
+        }
+        self.connect(input, Target::wire(row, LookupGate::wire_ith_input(slot)));
+        Target::wire(row, LookupGate::wire_ith_output(slot))
+    }
+
+    /// Fills the last `LookupGate` of each table with lookups of the first entry of the table, and
+    /// lays out the tables on `LookupTableGate`s. Tables which are never looked up are dropped.
+    pub(crate) fn add_lookup_table_gates(&mut self) {
+        let lookup_gate = LookupGate::new_from_config(&self.config);
+        let gate_ref = GateRef::new(lookup_gate);
+        for table_index in 0..self.lookup_tables.len() {
+            let params = vec![F::from_canonical_usize(table_index)];
+            while self
+                .current_slots
+                .get(&gate_ref)
+                .map_or(false, |slots| slots.current_slot.contains_key(&params))
+            {
+                let first_input = self.lookup_tables[table_index][0].0;
+                let input = self.constant(F::from_canonical_u16(first_input));
+                self.add_lookup(input, table_index);
+            }
+        }
+
+        let table_gate = LookupTableGate::new_from_config(&self.config);
+        for table_index in 0..self.lookup_tables.len() {
+            let table = self.lookup_tables[table_index].clone();
+            let lookup_rows = self.lookup_rows[table_index].clone();
+            if lookup_rows.is_empty() {
+                self.lookup_table_rows.push(Vec::new());
+                continue;
+            }
+            let num_rows = ceil_div_usize(table.len(), table_gate.num_slots);
+            let table_rows = (0..num_rows)
+                .map(|_| self.add_gate(table_gate, vec![]))
+                .collect::<Vec<_>>();
+            self.add_simple_generator(LookupTableGenerator {
+                table,
+                lookup_rows,
+                num_lookup_slots: lookup_gate.num_slots,
+                table_rows: table_rows.clone(),
+                num_table_slots: table_gate.num_slots,
+            });
+            self.lookup_table_rows.push(table_rows);
+        }
+    }
+
+    pub(crate) fn has_lookups(&self) -> bool {
+        self.lookup_rows.iter().any(|rows| !rows.is_empty())
+    }
+
+    /// The preprocessed polynomials of the lookup argument, in the order of
+    /// `CommonCircuitData::lookup_constants_range`.
+    pub(crate) fn lookup_constant_polys(&self) -> Vec<PolynomialValues<F>> {
+        let degree = self.gate_instances.len();
+        let num_table_slots = LookupTableGate::num_slots(&self.config);
+        let mut columns = vec![vec![F::ZERO; degree]; 3 + 2 * num_table_slots];
+        for (table_index, table) in self.lookup_tables.iter().enumerate() {
+            let id = F::from_canonical_usize(table_index);
+            for &row in &self.lookup_rows[table_index] {
+                columns[0][row] = F::ONE;
+                columns[2][row] = id;
+            }
+            for (i, &row) in self.lookup_table_rows[table_index].iter().enumerate() {
+                columns[1][row] = F::ONE;
+                columns[2][row] = id;
+                for slot in 0..num_table_slots {
+                    // The entries past the end of the table repeat its first entry, which is
+                    // given a multiplicity of 0.
+                    let (input, output) = table
+                        .get(i * num_table_slots + slot)
+                        .copied()
+                        .unwrap_or(table[0]);
+                    columns[3 + 2 * slot][row] = F::from_canonical_u16(input);
+                    columns[4 + 2 * slot][row] = F::from_canonical_u16(output);
+                }
+            }
+        }
+        columns.into_iter().map(PolynomialValues::new).collect()
+    }
+}
+
+/// The index in `table` of the entry with input `input`. Otherwise, reports the lookup of row
+/// `row` to `out_buffer`, which fails the generation of the witness.
+fn find_entry<F: Field>(
+    table: &[(u16, u16)],
+    input: F,
+    row: usize,
+    out_buffer: &mut GeneratedValues<F>,
+) -> Option<usize> {
+    let input = input.to_canonical_u64();
+    let entry = u16::try_from(input)
+        .ok()
+        .and_then(|input| table.binary_search_by_key(&input, |&(i, _)| i).ok());
+    if entry.is_none() {
+        out_buffer.fail(format!(
+            "Input {} of a lookup in row {} is not in its lookup table",
+            input, row
+        ));
+    }
+    entry
+}
+
+fn write_table(dst: &mut Vec<u8>, table: &[(u16, u16)]) -> IoResult<()> {
+    dst.write_usize(table.len())?;
+    for &(input, output) in table {
+        dst.write_u32(((input as u32) << 16) | output as u32)?;
+    }
+    Ok(())
+}
+
+fn read_table(src: &mut Buffer) -> IoResult<LookupTable> {
+    let len = src.read_usize()?;
+    if len > src.remaining() / 4 {
+        return Err(IoError);
+    }
+    let table = (0..len)
+        .map(|_| {
+            let entry = src.read_u32()?;
+            Ok(((entry >> 16) as u16, entry as u16))
+        })
+        .collect::<IoResult<Vec<_>>>()?;
+    Ok(Arc::new(table))
+}
+
+/// Sets the output of each lookup of a `LookupGate` row once its input is known. The lookups of a
+/// row are independent, so that the input of a lookup can depend on the output of another.
+pub struct LookupGenerator {
+    row: usize,
+    num_slots: usize,
+    table: LookupTable,
+}
+
+impl fmt::Debug for LookupGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LookupGenerator")
+            .field("row", &self.row)
+            .field("num_slots", &self.num_slots)
+            .field("table_len", &self.table.len())
+            .finish()
+    }
+}
+
+impl<F: RichField> WitnessGenerator<F> for LookupGenerator {
+    fn watch_list(&self) -> Vec<Target> {
+        (0..self.num_slots)
+            .map(|slot| Target::wire(self.row, LookupGate::wire_ith_input(slot)))
+            .collect()
+    }
+
+    fn run(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) -> bool {
+        let mut finished = true;
+        for slot in 0..self.num_slots {
+            let output = Target::wire(self.row, LookupGate::wire_ith_output(slot));
+            if witness.contains(output) {
+                continue;
+            }
+            match witness.try_get_target(Target::wire(self.row, LookupGate::wire_ith_input(slot))) {
+                Some(input) => match find_entry(&self.table, input, self.row, out_buffer) {
+                    Some(entry) => {
+                        let (_, output_value) = self.table[entry];
+                        out_buffer.set_target(output, F::from_canonical_u16(output_value));
+                    }
+                    None => return true,
+                },
+                None => finished = false,
+            }
+        }
+        finished
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        dst.write_usize(self.num_slots)?;
+        write_table(dst, &self.table)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            row: src.read_usize()?,
+            num_slots: src.read_usize()?,
+            table: read_table(src)?,
+        })
+    }
+}
+
+/// Sets the multiplicities of the entries of a table, i.e. the number of lookups of each entry,
+/// once all the lookups of the table are known.
+pub struct LookupTableGenerator {
+    table: LookupTable,
+    /// The rows of the `LookupGate`s looking up the table.
+    lookup_rows: Vec<usize>,
+    num_lookup_slots: usize,
+    /// The rows of the `LookupTableGate`s holding the table.
+    table_rows: Vec<usize>,
+    num_table_slots: usize,
+}
+
+impl LookupTableGenerator {
+    fn inputs(&self) -> impl Iterator<Item = (usize, Target)> + '_ {
+        self.lookup_rows.iter().flat_map(move |&row| {
+            (0..self.num_lookup_slots)
+                .map(move |slot| (row, Target::wire(row, LookupGate::wire_ith_input(slot))))
+        })
+    }
+}
+
+impl fmt::Debug for LookupTableGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LookupTableGenerator")
+            .field("table_len", &self.table.len())
+            .field("lookup_rows", &self.lookup_rows.len())
+            .field("table_rows", &self.table_rows)
+            .finish()
+    }
+}
+
+impl<F: RichField> SimpleGenerator<F> for LookupTableGenerator {
+    fn dependencies(&self) -> Vec<Target> {
+        self.inputs().map(|(_, input)| input).collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let mut multiplicities = vec![0u64; self.table_rows.len() * self.num_table_slots];
+        for (row, input) in self.inputs() {
+            match find_entry(&self.table, witness.get_target(input), row, out_buffer) {
+                Some(entry) => multiplicities[entry] += 1,
+                None => return,
+            }
+        }
+        for (i, &row) in self.table_rows.iter().enumerate() {
+            for slot in 0..self.num_table_slots {
+                out_buffer.set_target(
+                    Target::wire(row, LookupTableGate::wire_ith_multiplicity(slot)),
+                    F::from_canonical_u64(multiplicities[i * self.num_table_slots + slot]),
+                );
+            }
+        }
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        write_table(dst, &self.table)?;
+        dst.write_usize_vec(&self.lookup_rows)?;
+        dst.write_usize(self.num_lookup_slots)?;
+        dst.write_usize_vec(&self.table_rows)?;
+        dst.write_usize(self.num_table_slots)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            table: read_table(src)?,
+            lookup_rows: src.read_usize_vec()?,
+            num_lookup_slots: src.read_usize()?,
+            table_rows: src.read_usize_vec()?,
+            num_table_slots: src.read_usize()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::iop::generator::WitnessGenerationError;
+    use crate::iop::witness::PartialWitness;
+    use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::error::ProverError;
+    use crate::plonk::prover::ProvingStrategy;
+    use crate::plonk::registry::CircuitRegistry;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    /// The XOR of two nibbles, with input `16 * a + b`.
+    fn xor_table() -> Vec<(u16, u16)> {
+        (0..256).map(|i| (i, (i >> 4) ^ (i & 15))).collect()
+    }
+
+    /// A circuit chaining `n` XOR lookups, each input depending on the previous output.
+    fn xor_chain(n: usize) -> (CircuitData<F, C, D>, Target, Target) {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let table = builder.add_lookup_table(&xor_table());
+        let x = builder.add_virtual_target();
+        let sixteen = builder.constant(F::from_canonical_u64(16));
+        let mut acc = x;
+        for i in 0..n {
+            let key = builder.constant(F::from_canonical_usize(i % 16));
+            let input = builder.mul_add(acc, sixteen, key);
+            acc = builder.add_lookup(input, table);
+        }
+        builder.register_public_input(acc);
+        (builder.build::<C>(), x, acc)
+    }
+
+    #[test]
+    fn test_lookups() -> Result<()> {
+        let n = 100;
+        let (data, x, _) = xor_chain(n);
+        assert!(data.common.has_lookups());
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(5));
+        let check = data.check_witness(pw.clone())?;
+        assert!(check.is_satisfied(), "{}", check);
+        let err = data
+            .prove_with_strategy(pw.clone(), ProvingStrategy::CpuFlat)
+            .unwrap_err();
+        assert!(matches!(err, ProverError::Unsupported(_)), "{}", err);
+
+        let proof = data.prove(pw)?;
+        let expected = (0..n).fold(5, |acc, i| acc ^ (i % 16) as u64);
+        assert_eq!(proof.public_inputs, vec![F::from_canonical_u64(expected)]);
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_recursive_lookups() -> Result<()> {
+        let (inner, x, _) = xor_chain(20);
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(12));
+        let inner_proof = inner.prove(pw)?;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let proof_t = builder.add_virtual_proof_with_pis::<C>(&inner.common);
+        let verifier_data_t = builder.constant_verifier_data(&inner.verifier_only);
+        builder.verify_proof::<C>(&proof_t, &verifier_data_t, &inner.common);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_proof_with_pis_target(&proof_t, &inner_proof);
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_lookup_table_deduplication() {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let table = builder.add_lookup_table(&xor_table());
+        let mut reversed = xor_table();
+        reversed.reverse();
+        assert_eq!(builder.add_lookup_table(&reversed), table);
+        assert_ne!(builder.add_lookup_table(&[(0, 1)]), table);
+    }
+
+    #[test]
+    fn test_wrong_output_fails() {
+        let (data, x, acc) = xor_chain(3);
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(5));
+        // The lookup generator does not overwrite an output which is already set, so the proof
+        // is only rejected by the lookup argument.
+        pw.set_target(acc, F::from_canonical_u64(1));
+
+        // The wrong pair is looked up once but is not an entry, and the right one has a
+        // multiplicity of 1 but is not looked up.
+        let check = data.check_witness(pw.clone()).unwrap();
+        assert!(check.gates.is_empty(), "{}", check);
+        assert_eq!(check.lookups.len(), 2, "{}", check);
+        let wrong = check
+            .lookups
+            .iter()
+            .find(|lookup| lookup.output == F::ONE)
+            .unwrap();
+        assert_eq!((wrong.rows.len(), wrong.multiplicity), (1, F::ZERO));
+
+        let err = data.prove(pw).unwrap_err();
+        assert_eq!(
+            err,
+            ProverError::Generator(WitnessGenerationError::Unsatisfied)
+        );
+    }
+
+    #[test]
+    fn test_input_not_in_table_fails() {
+        let (data, x, _) = xor_chain(3);
+        let mut pw = PartialWitness::new();
+        // The first input is `16 * 20`, past the end of the table.
+        pw.set_target(x, F::from_canonical_u64(20));
+        let err = data.prove(pw).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ProverError::Generator(WitnessGenerationError::Failed { .. })
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_serialize_lookup_generators() -> Result<()> {
+        let (data, x, _) = xor_chain(50);
+        let registry = CircuitRegistry::<F, D>::new();
+        let bytes = data.to_bytes(&registry)?;
+        let data = CircuitData::<F, C, D>::from_bytes(bytes, &registry)?;
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(9));
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+}
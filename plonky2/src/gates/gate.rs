@@ -9,6 +9,7 @@ use core::ops::Range;
 
 use hashbrown::HashMap;
 
+use crate::backend::gates::GpuGate;
 use crate::field::batch_util::batch_multiply_inplace;
 use crate::field::extension::{Extendable, FieldExtension};
 use crate::field::types::Field;
@@ -18,14 +19,25 @@ use crate::hash::hash_types::RichField;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::generator::WitnessGenerator;
 use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::error::ProverResult;
+use crate::plonk::registry::AsAny;
 use crate::plonk::vars::{
     EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
 };
+use crate::util::serialization::{Buffer, IoResult};
 
 /// A custom gate.
-pub trait Gate<F: RichField + Extendable<D>, const D: usize>: 'static + Send + Sync {
+pub trait Gate<F: RichField + Extendable<D>, const D: usize>: 'static + Send + Sync + AsAny {
     fn id(&self) -> String;
 
+    /// Writes the parameters of this gate, to be read back by `deserialize`. The type of the gate
+    /// is written by the `CircuitRegistry` it is registered in.
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()>;
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self>
+    where
+        Self: Sized;
+
     fn export_circom_verification_code(&self) -> String;
     fn export_solidity_verification_code(&self) -> String;
 
@@ -203,6 +215,13 @@ pub trait Gate<F: RichField + Extendable<D>, const D: usize>: 'static + Send + S
     fn extra_constant_wires(&self) -> Vec<(usize, usize)> {
         vec![]
     }
+
+    /// The kernel implementing this gate in `plonky2_cuda`, along with the parameters of this
+    /// instance, or `None` if the gate is not accelerated. Fails if the kernel cannot be given the
+    /// parameters of this instance.
+    fn gpu_gate(&self) -> Option<ProverResult<GpuGate>> {
+        None
+    }
 }
 
 /// A wrapper around an `Rc<Gate>` which implements `PartialEq`, `Eq` and `Hash` based on gate IDs.
@@ -0,0 +1,158 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::field::extension::Extendable;
+use crate::gates::gate::Gate;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::WitnessGenerator;
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBaseBatch};
+use crate::util::serialization::{Buffer, IoResult, Read, Write};
+
+/// A gate holding `num_slots` lookups of `(input, output)` pairs in a lookup table. It has no
+/// constraints of its own: the lookup argument checks that each pair of a row is an entry of the
+/// table of that row, as given by the preprocessed polynomials of the argument.
+///
+/// The outputs are set by the `LookupGenerator` added by `CircuitBuilder::add_lookup`.
+#[derive(Copy, Clone, Debug)]
+pub struct LookupGate {
+    pub num_slots: usize,
+}
+
+impl LookupGate {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        Self {
+            num_slots: Self::num_slots(config),
+        }
+    }
+
+    /// The number of lookups of a row. Both the inputs and the outputs are routed.
+    pub(crate) fn num_slots(config: &CircuitConfig) -> usize {
+        config.num_routed_wires / 2
+    }
+
+    pub fn wire_ith_input(i: usize) -> usize {
+        2 * i
+    }
+
+    pub fn wire_ith_output(i: usize) -> usize {
+        2 * i + 1
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for LookupGate {
+    fn id(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_usize(self.num_slots)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            num_slots: src.read_usize()?,
+        })
+    }
+
+    // The gate has no constraints, so these only pass the constraints of the other gates through.
+    fn export_circom_verification_code(&self) -> String {
+        let mut template_str = format!(
+            "template Lookup$NUM_SLOTS() {{
+  signal input constants[NUM_OPENINGS_CONSTANTS()][2];
+  signal input wires[NUM_OPENINGS_WIRES()][2];
+  signal input public_input_hash[4];
+  signal input constraints[NUM_GATE_CONSTRAINTS()][2];
+  signal output out[NUM_GATE_CONSTRAINTS()][2];
+
+  for (var i = 0; i < NUM_GATE_CONSTRAINTS(); i++) {{
+    out[i] <== constraints[i];
+  }}
+}}"
+        );
+        template_str = template_str.replace("$NUM_SLOTS", &self.num_slots.to_string());
+        template_str
+    }
+    fn export_solidity_verification_code(&self) -> String {
+        let mut template_str = format!(
+            "library Lookup$NUM_SLOTSLib {{
+    function set_filter(GatesUtilsLib.EvaluationVars memory ev) internal pure {{
+        $SET_FILTER;
+    }}
+    function eval(GatesUtilsLib.EvaluationVars memory ev, uint64[2][$NUM_GATE_CONSTRAINTS] memory constraints) internal pure {{
+    }}
+}}"
+        );
+        template_str = template_str.replace("$NUM_SLOTS", &self.num_slots.to_string());
+        template_str
+    }
+
+    fn eval_unfiltered(&self, _vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        Vec::new()
+    }
+
+    fn eval_unfiltered_base_batch(&self, _vars: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        Vec::new()
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        _builder: &mut CircuitBuilder<F, D>,
+        _vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        Vec::new()
+    }
+
+    fn generators(&self, _row: usize, _local_constants: &[F]) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        // A row of lookups is generated as a whole, by the generator added by `add_lookup`.
+        Vec::new()
+    }
+
+    fn num_wires(&self) -> usize {
+        2 * self.num_slots
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        0
+    }
+
+    fn num_constraints(&self) -> usize {
+        0
+    }
+
+    fn num_ops(&self) -> usize {
+        self.num_slots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::lookup::LookupGate;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn low_degree() {
+        let gate = LookupGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        test_low_degree::<GoldilocksField, _, 4>(gate)
+    }
+
+    #[test]
+    fn eval_fns() -> anyhow::Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let gate = LookupGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        test_eval_fns::<F, C, _, D>(gate)
+    }
+}
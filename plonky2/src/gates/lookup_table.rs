@@ -0,0 +1,153 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::field::extension::Extendable;
+use crate::gates::gate::Gate;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::WitnessGenerator;
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBaseBatch};
+use crate::util::serialization::{Buffer, IoResult, Read, Write};
+
+/// A gate holding `num_slots` entries of a lookup table, along with the number of times each of
+/// them is looked up. The entries are preprocessed polynomials of the lookup argument, so the
+/// wires only hold the multiplicities. Like `LookupGate`, it has no constraints of its own.
+///
+/// The multiplicities are set by the `LookupTableGenerator` added when the circuit is built.
+#[derive(Copy, Clone, Debug)]
+pub struct LookupTableGate {
+    pub num_slots: usize,
+}
+
+impl LookupTableGate {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        Self {
+            num_slots: Self::num_slots(config),
+        }
+    }
+
+    /// The number of table entries of a row. The fractions of all entries are summed by a single
+    /// helper polynomial of the lookup argument, so there are as many as fit in the quotient
+    /// degree.
+    pub(crate) fn num_slots(config: &CircuitConfig) -> usize {
+        (config.max_quotient_degree_factor - 1).min(config.num_wires)
+    }
+
+    pub fn wire_ith_multiplicity(i: usize) -> usize {
+        i
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for LookupTableGate {
+    fn id(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_usize(self.num_slots)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            num_slots: src.read_usize()?,
+        })
+    }
+
+    // The gate has no constraints, so these only pass the constraints of the other gates through.
+    fn export_circom_verification_code(&self) -> String {
+        let mut template_str = format!(
+            "template LookupTable$NUM_SLOTS() {{
+  signal input constants[NUM_OPENINGS_CONSTANTS()][2];
+  signal input wires[NUM_OPENINGS_WIRES()][2];
+  signal input public_input_hash[4];
+  signal input constraints[NUM_GATE_CONSTRAINTS()][2];
+  signal output out[NUM_GATE_CONSTRAINTS()][2];
+
+  for (var i = 0; i < NUM_GATE_CONSTRAINTS(); i++) {{
+    out[i] <== constraints[i];
+  }}
+}}"
+        );
+        template_str = template_str.replace("$NUM_SLOTS", &self.num_slots.to_string());
+        template_str
+    }
+    fn export_solidity_verification_code(&self) -> String {
+        let mut template_str = format!(
+            "library LookupTable$NUM_SLOTSLib {{
+    function set_filter(GatesUtilsLib.EvaluationVars memory ev) internal pure {{
+        $SET_FILTER;
+    }}
+    function eval(GatesUtilsLib.EvaluationVars memory ev, uint64[2][$NUM_GATE_CONSTRAINTS] memory constraints) internal pure {{
+    }}
+}}"
+        );
+        template_str = template_str.replace("$NUM_SLOTS", &self.num_slots.to_string());
+        template_str
+    }
+
+    fn eval_unfiltered(&self, _vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        Vec::new()
+    }
+
+    fn eval_unfiltered_base_batch(&self, _vars: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        Vec::new()
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        _builder: &mut CircuitBuilder<F, D>,
+        _vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        Vec::new()
+    }
+
+    fn generators(&self, _row: usize, _local_constants: &[F]) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        // The multiplicities of a table depend on all its lookups, and are generated by a single
+        // generator added by `CircuitBuilder::build`.
+        Vec::new()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_slots
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        0
+    }
+
+    fn num_constraints(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::lookup_table::LookupTableGate;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn low_degree() {
+        let gate = LookupTableGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        test_low_degree::<GoldilocksField, _, 4>(gate)
+    }
+
+    #[test]
+    fn eval_fns() -> anyhow::Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let gate = LookupTableGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        test_eval_fns::<F, C, _, D>(gate)
+    }
+}
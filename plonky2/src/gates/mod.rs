@@ -9,11 +9,14 @@ pub mod exponentiation;
 pub mod gate;
 pub mod high_degree_interpolation;
 pub mod interpolation;
+pub mod lookup;
+pub mod lookup_table;
 pub mod low_degree_interpolation;
 pub mod multiplication_extension;
 pub mod noop;
 pub mod packed_util;
 pub mod poseidon;
+pub mod poseidon2;
 pub mod poseidon_mds;
 pub mod public_input;
 pub mod random_access;
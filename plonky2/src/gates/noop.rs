@@ -2,13 +2,16 @@ use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::vec::Vec;
 
+use crate::backend::gates::{GpuGate, GpuGateKind};
 use crate::field::extension::Extendable;
 use crate::gates::gate::Gate;
 use crate::hash::hash_types::RichField;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::generator::WitnessGenerator;
 use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::error::ProverResult;
 use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBaseBatch};
+use crate::util::serialization::{Buffer, IoResult};
 
 /// A gate which does nothing.
 pub struct NoopGate;
@@ -18,6 +21,14 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for NoopGate {
         "NoopGate".into()
     }
 
+    fn serialize(&self, _dst: &mut Vec<u8>) -> IoResult<()> {
+        Ok(())
+    }
+
+    fn deserialize(_src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self)
+    }
+
     fn export_circom_verification_code(&self) -> String {
         todo!()
     }
@@ -60,6 +71,10 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for NoopGate {
     fn num_constraints(&self) -> usize {
         0
     }
+
+    fn gpu_gate(&self) -> Option<ProverResult<GpuGate>> {
+        Some(GpuGate::new(GpuGateKind::Noop, &[]))
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,793 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::marker::PhantomData;
+
+use crate::field::extension::Extendable;
+use crate::field::types::Field;
+use crate::gates::gate::Gate;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::hash::hashing::SPONGE_WIDTH;
+use crate::hash::poseidon2;
+use crate::hash::poseidon2::Poseidon2;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBase};
+use crate::util::serialization::{Buffer, IoResult, Read, Write};
+
+/// Evaluates a full Poseidon2 permutation with 12 state elements.
+///
+/// Like `PoseidonGate`, it has a flag which can be used to swap the first four inputs with the next
+/// four, for ordering sibling digests, and it has the same wire layout.
+#[derive(Debug, Default)]
+pub struct Poseidon2Gate<F: RichField + Extendable<D>, const D: usize>(PhantomData<F>);
+
+impl<F: RichField + Extendable<D>, const D: usize> Poseidon2Gate<F, D> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    /// The wire index for the `i`th input to the permutation.
+    pub fn wire_input(i: usize) -> usize {
+        i
+    }
+
+    /// The wire index for the `i`th output to the permutation.
+    pub fn wire_output(i: usize) -> usize {
+        SPONGE_WIDTH + i
+    }
+
+    /// If this is set to 1, the first four inputs will be swapped with the next four inputs. This
+    /// is useful for ordering hashes in Merkle proofs. Otherwise, this should be set to 0.
+    pub const WIRE_SWAP: usize = 2 * SPONGE_WIDTH;
+
+    const START_DELTA: usize = 2 * SPONGE_WIDTH + 1;
+
+    /// A wire which stores `swap * (input[i + 4] - input[i])`; used to compute the swapped inputs.
+    fn wire_delta(i: usize) -> usize {
+        assert!(i < 4);
+        Self::START_DELTA + i
+    }
+
+    const START_FULL_0: usize = Self::START_DELTA + 4;
+
+    /// A wire which stores the input of the `i`-th S-box of the `round`-th round of the first set
+    /// of full rounds.
+    fn wire_full_sbox_0(round: usize, i: usize) -> usize {
+        debug_assert!(
+            round != 0,
+            "First round S-box inputs are not stored as wires"
+        );
+        debug_assert!(round < poseidon2::HALF_N_FULL_ROUNDS);
+        debug_assert!(i < SPONGE_WIDTH);
+        Self::START_FULL_0 + SPONGE_WIDTH * (round - 1) + i
+    }
+
+    const START_PARTIAL: usize =
+        Self::START_FULL_0 + SPONGE_WIDTH * (poseidon2::HALF_N_FULL_ROUNDS - 1);
+
+    /// A wire which stores the input of the S-box of the `round`-th round of the partial rounds.
+    fn wire_partial_sbox(round: usize) -> usize {
+        debug_assert!(round < poseidon2::N_PARTIAL_ROUNDS);
+        Self::START_PARTIAL + round
+    }
+
+    const START_FULL_1: usize = Self::START_PARTIAL + poseidon2::N_PARTIAL_ROUNDS;
+
+    /// A wire which stores the input of the `i`-th S-box of the `round`-th round of the second set
+    /// of full rounds.
+    fn wire_full_sbox_1(round: usize, i: usize) -> usize {
+        debug_assert!(round < poseidon2::HALF_N_FULL_ROUNDS);
+        debug_assert!(i < SPONGE_WIDTH);
+        Self::START_FULL_1 + SPONGE_WIDTH * round + i
+    }
+
+    /// End of wire indices, exclusive.
+    fn end() -> usize {
+        Self::START_FULL_1 + SPONGE_WIDTH * poseidon2::HALF_N_FULL_ROUNDS
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for Poseidon2Gate<F, D> {
+    fn id(&self) -> String {
+        format!("{self:?}<WIDTH={SPONGE_WIDTH}>")
+    }
+
+    fn serialize(&self, _dst: &mut Vec<u8>) -> IoResult<()> {
+        Ok(())
+    }
+
+    fn deserialize(_src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self::new())
+    }
+
+    fn export_circom_verification_code(&self) -> String {
+        let mut template_str = format!(
+            "template Poseidon2_12() {{
+  signal input constants[NUM_OPENINGS_CONSTANTS()][2];
+  signal input wires[NUM_OPENINGS_WIRES()][2];
+  signal input public_input_hash[4];
+  signal input constraints[NUM_GATE_CONSTRAINTS()][2];
+  signal output out[NUM_GATE_CONSTRAINTS()][2];
+
+  signal filter[2];
+  $SET_FILTER;
+
+  var index = 0;
+  out[index] <== ConstraintPush()(constraints[index], filter, GlExtMul()(wires[$WIRE_SWAP], GlExtSub()(wires[$WIRE_SWAP], GlExt(1, 0)())));
+  index++;
+
+  for (var i = 0; i < 4; i++) {{
+    out[index] <== ConstraintPush()(constraints[index], filter, GlExtSub()(GlExtMul()(wires[$WIRE_SWAP], GlExtSub()(wires[i + 4], wires[i])), wires[$START_DELTA + i]));
+    index++;
+  }}
+
+  signal state[12][$HALF_N_FULL_ROUNDS * 8 + 1 + $N_PARTIAL_ROUNDS * 2][2];
+  var state_round = 0;
+  for (var i = 0; i < 4; i++) {{
+    state[i][state_round] <== GlExtAdd()(wires[i], wires[$START_DELTA + i]);
+    state[i + 4][state_round] <== GlExtSub()(wires[i + 4], wires[$START_DELTA + i]);
+  }}
+  for (var i = 8; i < 12; i++) {{
+    state[i][state_round] <== wires[i];
+  }}
+  state_round++;
+
+  // The external linear layer, applied to the input and after each full round.
+  signal external_acc[$HALF_N_FULL_ROUNDS * 2 + 1][12][13][2];
+  var external_ctr = 0;
+  for (var i = 0; i < 12; i++) {{
+    external_acc[external_ctr][i][0][0] <== 0;
+    external_acc[external_ctr][i][0][1] <== 0;
+    for (var j = 0; j < 12; j++) {{
+      external_acc[external_ctr][i][j + 1] <== GlExtAdd()(external_acc[external_ctr][i][j], GlExtMul()(state[j][state_round - 1], GlExt(POSEIDON2_EXTERNAL_MATRIX(i, j), 0)()));
+    }}
+    state[i][state_round] <== external_acc[external_ctr][i][12];
+  }}
+  state_round++;
+  external_ctr++;
+
+  // First set of full rounds.
+  for (var r = 0; r < $HALF_N_FULL_ROUNDS; r++) {{
+    for (var i = 0; i < 12; i++) {{
+      state[i][state_round] <== GlExtAdd()(state[i][state_round - 1], GlExt(POSEIDON2_EXTERNAL_ROUND_CONSTANT(r, i), 0)());
+    }}
+    state_round++;
+    if (r != 0) {{
+      for (var i = 0; i < 12; i++) {{
+        state[i][state_round] <== wires[$START_FULL_0 + 12 * (r - 1) + i];
+        out[index] <== ConstraintPush()(constraints[index], filter, GlExtSub()(state[i][state_round - 1], state[i][state_round]));
+        index++;
+      }}
+      state_round++;
+    }}
+    for (var i = 0; i < 12; i++) {{
+      state[i][state_round] <== GlExtExpN(3)(state[i][state_round - 1], 7);
+    }}
+    state_round++;
+    for (var i = 0; i < 12; i++) {{
+      external_acc[external_ctr][i][0][0] <== 0;
+      external_acc[external_ctr][i][0][1] <== 0;
+      for (var j = 0; j < 12; j++) {{
+        external_acc[external_ctr][i][j + 1] <== GlExtAdd()(external_acc[external_ctr][i][j], GlExtMul()(state[j][state_round - 1], GlExt(POSEIDON2_EXTERNAL_MATRIX(i, j), 0)()));
+      }}
+      state[i][state_round] <== external_acc[external_ctr][i][12];
+    }}
+    state_round++;
+    external_ctr++;
+  }}
+
+  // Partial rounds.
+  signal internal_sum[$N_PARTIAL_ROUNDS][13][2];
+  for (var r = 0; r < $N_PARTIAL_ROUNDS; r++) {{
+    out[index] <== ConstraintPush()(constraints[index], filter, GlExtSub()(GlExtAdd()(state[0][state_round - 1], GlExt(POSEIDON2_INTERNAL_ROUND_CONSTANT(r), 0)()), wires[$START_PARTIAL + r]));
+    index++;
+    state[0][state_round] <== GlExtExpN(3)(wires[$START_PARTIAL + r], 7);
+    for (var i = 1; i < 12; i++) {{
+      state[i][state_round] <== state[i][state_round - 1];
+    }}
+    state_round++;
+    internal_sum[r][0][0] <== 0;
+    internal_sum[r][0][1] <== 0;
+    for (var i = 0; i < 12; i++) {{
+      internal_sum[r][i + 1] <== GlExtAdd()(internal_sum[r][i], state[i][state_round - 1]);
+    }}
+    for (var i = 0; i < 12; i++) {{
+      state[i][state_round] <== GlExtAdd()(GlExtMul()(state[i][state_round - 1], GlExt(POSEIDON2_INTERNAL_MATRIX_DIAG_M_1(i), 0)()), internal_sum[r][12]);
+    }}
+    state_round++;
+  }}
+
+  // Second set of full rounds.
+  for (var r = 0; r < $HALF_N_FULL_ROUNDS; r++) {{
+    for (var i = 0; i < 12; i++) {{
+      state[i][state_round] <== GlExtAdd()(state[i][state_round - 1], GlExt(POSEIDON2_EXTERNAL_ROUND_CONSTANT($HALF_N_FULL_ROUNDS + r, i), 0)());
+    }}
+    state_round++;
+    for (var i = 0; i < 12; i++) {{
+      state[i][state_round] <== wires[$START_FULL_1 + 12 * r + i];
+      out[index] <== ConstraintPush()(constraints[index], filter, GlExtSub()(state[i][state_round - 1], state[i][state_round]));
+      index++;
+    }}
+    state_round++;
+    for (var i = 0; i < 12; i++) {{
+      state[i][state_round] <== GlExtExpN(3)(state[i][state_round - 1], 7);
+    }}
+    state_round++;
+    for (var i = 0; i < 12; i++) {{
+      external_acc[external_ctr][i][0][0] <== 0;
+      external_acc[external_ctr][i][0][1] <== 0;
+      for (var j = 0; j < 12; j++) {{
+        external_acc[external_ctr][i][j + 1] <== GlExtAdd()(external_acc[external_ctr][i][j], GlExtMul()(state[j][state_round - 1], GlExt(POSEIDON2_EXTERNAL_MATRIX(i, j), 0)()));
+      }}
+      state[i][state_round] <== external_acc[external_ctr][i][12];
+    }}
+    state_round++;
+    external_ctr++;
+  }}
+
+  for (var i = 0; i < 12; i++) {{
+    out[index] <== ConstraintPush()(constraints[index], filter, GlExtSub()(state[i][state_round - 1], wires[12 + i]));
+    index++;
+  }}
+
+  for (var i = index; i < NUM_GATE_CONSTRAINTS(); i++) {{
+    out[i] <== constraints[i];
+  }}
+}}
+function POSEIDON2_EXTERNAL_MATRIX(i, j) {{
+  var m4[4][4] = [[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+  return (i \\ 4 == j \\ 4 ? 2 : 1) * m4[i % 4][j % 4];
+}}
+function POSEIDON2_EXTERNAL_ROUND_CONSTANT(i, j) {{
+  var value[$N_FULL_ROUNDS_TOTAL][12];
+  $SET_EXTERNAL_ROUND_CONSTANTS;
+  return value[i][j];
+}}
+function POSEIDON2_INTERNAL_ROUND_CONSTANT(i) {{
+  var value[$N_PARTIAL_ROUNDS];
+  $SET_INTERNAL_ROUND_CONSTANTS;
+  return value[i];
+}}
+function POSEIDON2_INTERNAL_MATRIX_DIAG_M_1(i) {{
+  var value[12];
+  $SET_INTERNAL_MATRIX_DIAG_M_1;
+  return value[i];
+}}"
+        );
+        template_str = template_str.replace("$WIRE_SWAP", &Self::WIRE_SWAP.to_string());
+        template_str = template_str.replace("$START_DELTA", &Self::START_DELTA.to_string());
+        template_str = template_str.replace("$START_FULL_0", &Self::START_FULL_0.to_string());
+        template_str = template_str.replace("$START_PARTIAL", &Self::START_PARTIAL.to_string());
+        template_str = template_str.replace("$START_FULL_1", &Self::START_FULL_1.to_string());
+        template_str = template_str.replace(
+            "$HALF_N_FULL_ROUNDS",
+            &poseidon2::HALF_N_FULL_ROUNDS.to_string(),
+        );
+        template_str = template_str.replace(
+            "$N_FULL_ROUNDS_TOTAL",
+            &poseidon2::N_FULL_ROUNDS_TOTAL.to_string(),
+        );
+        template_str = template_str.replace(
+            "$N_PARTIAL_ROUNDS",
+            &poseidon2::N_PARTIAL_ROUNDS.to_string(),
+        );
+
+        let mut external_str = String::new();
+        for (r, round_constants) in <F as Poseidon2>::EXTERNAL_ROUND_CONSTANTS
+            .iter()
+            .enumerate()
+        {
+            for (i, c) in round_constants.iter().enumerate() {
+                external_str += &format!("  value[{r}][{i}] = {c};\n");
+            }
+        }
+        template_str = template_str.replace("  $SET_EXTERNAL_ROUND_CONSTANTS;\n", &external_str);
+
+        let mut internal_str = String::new();
+        for (r, c) in <F as Poseidon2>::INTERNAL_ROUND_CONSTANTS
+            .iter()
+            .enumerate()
+        {
+            internal_str += &format!("  value[{r}] = {c};\n");
+        }
+        template_str = template_str.replace("  $SET_INTERNAL_ROUND_CONSTANTS;\n", &internal_str);
+
+        let mut diag_str = String::new();
+        for (i, c) in <F as Poseidon2>::INTERNAL_MATRIX_DIAG_M_1
+            .iter()
+            .enumerate()
+        {
+            diag_str += &format!("  value[{i}] = {c};\n");
+        }
+        template_str = template_str.replace("  $SET_INTERNAL_MATRIX_DIAG_M_1;\n", &diag_str);
+
+        template_str
+    }
+    fn export_solidity_verification_code(&self) -> String {
+        // The Solidity verifier has no Poseidon2 permutation, so a proof using this gate is
+        // rejected instead of being checked without its constraints.
+        String::from(
+            "library Poseidon2_12Lib {
+    function set_filter(GatesUtilsLib.EvaluationVars memory ev) internal pure {
+        $SET_FILTER;
+    }
+    function eval(GatesUtilsLib.EvaluationVars memory ev, uint64[2][$NUM_GATE_CONSTRAINTS] memory constraints) internal pure {
+        revert(\"Poseidon2Gate is not supported by the Solidity verifier\");
+    }
+}",
+        )
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        // Assert that `swap` is binary.
+        let swap = vars.local_wires[Self::WIRE_SWAP];
+        constraints.push(swap * (swap - F::Extension::ONE));
+
+        // Assert that each delta wire is set properly: `delta_i = swap * (rhs - lhs)`.
+        for i in 0..4 {
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            constraints.push(swap * (input_rhs - input_lhs) - delta_i);
+        }
+
+        // Compute the possibly-swapped input layer.
+        let mut state = [F::Extension::ZERO; SPONGE_WIDTH];
+        for i in 0..4 {
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let input_lhs = Self::wire_input(i);
+            let input_rhs = Self::wire_input(i + 4);
+            state[i] = vars.local_wires[input_lhs] + delta_i;
+            state[i + 4] = vars.local_wires[input_rhs] - delta_i;
+        }
+        for i in 8..SPONGE_WIDTH {
+            state[i] = vars.local_wires[Self::wire_input(i)];
+        }
+
+        <F as Poseidon2>::external_linear_layer_field(&mut state);
+
+        // First set of full rounds.
+        for r in 0..poseidon2::HALF_N_FULL_ROUNDS {
+            <F as Poseidon2>::external_constant_layer_field(&mut state, r);
+            if r != 0 {
+                for i in 0..SPONGE_WIDTH {
+                    let sbox_in = vars.local_wires[Self::wire_full_sbox_0(r, i)];
+                    constraints.push(state[i] - sbox_in);
+                    state[i] = sbox_in;
+                }
+            }
+            <F as Poseidon2>::sbox_layer_field(&mut state);
+            <F as Poseidon2>::external_linear_layer_field(&mut state);
+        }
+
+        // Partial rounds.
+        for r in 0..poseidon2::N_PARTIAL_ROUNDS {
+            state[0] +=
+                F::Extension::from_canonical_u64(<F as Poseidon2>::INTERNAL_ROUND_CONSTANTS[r]);
+            let sbox_in = vars.local_wires[Self::wire_partial_sbox(r)];
+            constraints.push(state[0] - sbox_in);
+            state[0] = <F as Poseidon2>::sbox_monomial(sbox_in);
+            <F as Poseidon2>::internal_linear_layer_field(&mut state);
+        }
+
+        // Second set of full rounds.
+        for r in 0..poseidon2::HALF_N_FULL_ROUNDS {
+            <F as Poseidon2>::external_constant_layer_field(
+                &mut state,
+                poseidon2::HALF_N_FULL_ROUNDS + r,
+            );
+            for i in 0..SPONGE_WIDTH {
+                let sbox_in = vars.local_wires[Self::wire_full_sbox_1(r, i)];
+                constraints.push(state[i] - sbox_in);
+                state[i] = sbox_in;
+            }
+            <F as Poseidon2>::sbox_layer_field(&mut state);
+            <F as Poseidon2>::external_linear_layer_field(&mut state);
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            constraints.push(state[i] - vars.local_wires[Self::wire_output(i)]);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        vars: EvaluationVarsBase<F>,
+        mut yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        // Assert that `swap` is binary.
+        let swap = vars.local_wires[Self::WIRE_SWAP];
+        yield_constr.one(swap * swap.sub_one());
+
+        // Assert that each delta wire is set properly: `delta_i = swap * (rhs - lhs)`.
+        for i in 0..4 {
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            yield_constr.one(swap * (input_rhs - input_lhs) - delta_i);
+        }
+
+        // Compute the possibly-swapped input layer.
+        let mut state = [F::ZERO; SPONGE_WIDTH];
+        for i in 0..4 {
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let input_lhs = Self::wire_input(i);
+            let input_rhs = Self::wire_input(i + 4);
+            state[i] = vars.local_wires[input_lhs] + delta_i;
+            state[i + 4] = vars.local_wires[input_rhs] - delta_i;
+        }
+        for i in 8..SPONGE_WIDTH {
+            state[i] = vars.local_wires[Self::wire_input(i)];
+        }
+
+        <F as Poseidon2>::external_linear_layer(&mut state);
+
+        // First set of full rounds.
+        for r in 0..poseidon2::HALF_N_FULL_ROUNDS {
+            <F as Poseidon2>::external_constant_layer_field(&mut state, r);
+            if r != 0 {
+                for i in 0..SPONGE_WIDTH {
+                    let sbox_in = vars.local_wires[Self::wire_full_sbox_0(r, i)];
+                    yield_constr.one(state[i] - sbox_in);
+                    state[i] = sbox_in;
+                }
+            }
+            <F as Poseidon2>::sbox_layer_field(&mut state);
+            <F as Poseidon2>::external_linear_layer(&mut state);
+        }
+
+        // Partial rounds.
+        for r in 0..poseidon2::N_PARTIAL_ROUNDS {
+            state[0] += F::from_canonical_u64(<F as Poseidon2>::INTERNAL_ROUND_CONSTANTS[r]);
+            let sbox_in = vars.local_wires[Self::wire_partial_sbox(r)];
+            yield_constr.one(state[0] - sbox_in);
+            state[0] = <F as Poseidon2>::sbox_monomial(sbox_in);
+            <F as Poseidon2>::internal_linear_layer(&mut state);
+        }
+
+        // Second set of full rounds.
+        for r in 0..poseidon2::HALF_N_FULL_ROUNDS {
+            <F as Poseidon2>::external_constant_layer_field(
+                &mut state,
+                poseidon2::HALF_N_FULL_ROUNDS + r,
+            );
+            for i in 0..SPONGE_WIDTH {
+                let sbox_in = vars.local_wires[Self::wire_full_sbox_1(r, i)];
+                yield_constr.one(state[i] - sbox_in);
+                state[i] = sbox_in;
+            }
+            <F as Poseidon2>::sbox_layer_field(&mut state);
+            <F as Poseidon2>::external_linear_layer(&mut state);
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            yield_constr.one(state[i] - vars.local_wires[Self::wire_output(i)]);
+        }
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        // Assert that `swap` is binary.
+        let swap = vars.local_wires[Self::WIRE_SWAP];
+        constraints.push(builder.mul_sub_extension(swap, swap, swap));
+
+        // Assert that each delta wire is set properly: `delta_i = swap * (rhs - lhs)`.
+        for i in 0..4 {
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let diff = builder.sub_extension(input_rhs, input_lhs);
+            constraints.push(builder.mul_sub_extension(swap, diff, delta_i));
+        }
+
+        // Compute the possibly-swapped input layer.
+        let mut state = [builder.zero_extension(); SPONGE_WIDTH];
+        for i in 0..4 {
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            state[i] = builder.add_extension(input_lhs, delta_i);
+            state[i + 4] = builder.sub_extension(input_rhs, delta_i);
+        }
+        for i in 8..SPONGE_WIDTH {
+            state[i] = vars.local_wires[Self::wire_input(i)];
+        }
+
+        <F as Poseidon2>::external_linear_layer_circuit(builder, &mut state);
+
+        // First set of full rounds.
+        for r in 0..poseidon2::HALF_N_FULL_ROUNDS {
+            <F as Poseidon2>::external_constant_layer_circuit(builder, &mut state, r);
+            if r != 0 {
+                for i in 0..SPONGE_WIDTH {
+                    let sbox_in = vars.local_wires[Self::wire_full_sbox_0(r, i)];
+                    constraints.push(builder.sub_extension(state[i], sbox_in));
+                    state[i] = sbox_in;
+                }
+            }
+            <F as Poseidon2>::sbox_layer_circuit(builder, &mut state);
+            <F as Poseidon2>::external_linear_layer_circuit(builder, &mut state);
+        }
+
+        // Partial rounds.
+        for r in 0..poseidon2::N_PARTIAL_ROUNDS {
+            let c = F::from_canonical_u64(<F as Poseidon2>::INTERNAL_ROUND_CONSTANTS[r]);
+            state[0] = builder.add_const_extension(state[0], c);
+            let sbox_in = vars.local_wires[Self::wire_partial_sbox(r)];
+            constraints.push(builder.sub_extension(state[0], sbox_in));
+            state[0] = <F as Poseidon2>::sbox_monomial_circuit(builder, sbox_in);
+            <F as Poseidon2>::internal_linear_layer_circuit(builder, &mut state);
+        }
+
+        // Second set of full rounds.
+        for r in 0..poseidon2::HALF_N_FULL_ROUNDS {
+            <F as Poseidon2>::external_constant_layer_circuit(
+                builder,
+                &mut state,
+                poseidon2::HALF_N_FULL_ROUNDS + r,
+            );
+            for i in 0..SPONGE_WIDTH {
+                let sbox_in = vars.local_wires[Self::wire_full_sbox_1(r, i)];
+                constraints.push(builder.sub_extension(state[i], sbox_in));
+                state[i] = sbox_in;
+            }
+            <F as Poseidon2>::sbox_layer_circuit(builder, &mut state);
+            <F as Poseidon2>::external_linear_layer_circuit(builder, &mut state);
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            constraints
+                .push(builder.sub_extension(state[i], vars.local_wires[Self::wire_output(i)]));
+        }
+
+        constraints
+    }
+
+    fn generators(&self, row: usize, _local_constants: &[F]) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        let gen = Poseidon2Generator::<F, D> {
+            row,
+            _phantom: PhantomData,
+        };
+        vec![Box::new(gen.adapter())]
+    }
+
+    fn num_wires(&self) -> usize {
+        Self::end()
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        7
+    }
+
+    fn num_constraints(&self) -> usize {
+        SPONGE_WIDTH * (poseidon2::N_FULL_ROUNDS_TOTAL - 1)
+            + poseidon2::N_PARTIAL_ROUNDS
+            + SPONGE_WIDTH
+            + 1
+            + 4
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Poseidon2Generator<F: RichField + Extendable<D>, const D: usize> {
+    row: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F> for Poseidon2Generator<F, D> {
+    fn dependencies(&self) -> Vec<Target> {
+        (0..SPONGE_WIDTH)
+            .map(|i| Poseidon2Gate::<F, D>::wire_input(i))
+            .chain(Some(Poseidon2Gate::<F, D>::WIRE_SWAP))
+            .map(|column| Target::wire(self.row, column))
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |column| Wire {
+            row: self.row,
+            column,
+        };
+
+        let mut state = (0..SPONGE_WIDTH)
+            .map(|i| witness.get_wire(local_wire(Poseidon2Gate::<F, D>::wire_input(i))))
+            .collect::<Vec<_>>();
+
+        let swap_value = witness.get_wire(local_wire(Poseidon2Gate::<F, D>::WIRE_SWAP));
+        debug_assert!(swap_value == F::ZERO || swap_value == F::ONE);
+
+        for i in 0..4 {
+            let delta_i = swap_value * (state[i + 4] - state[i]);
+            out_buffer.set_wire(local_wire(Poseidon2Gate::<F, D>::wire_delta(i)), delta_i);
+        }
+
+        if swap_value == F::ONE {
+            for i in 0..4 {
+                state.swap(i, 4 + i);
+            }
+        }
+
+        let mut state: [F; SPONGE_WIDTH] = state.try_into().unwrap();
+
+        <F as Poseidon2>::external_linear_layer(&mut state);
+
+        for r in 0..poseidon2::HALF_N_FULL_ROUNDS {
+            <F as Poseidon2>::external_constant_layer_field(&mut state, r);
+            if r != 0 {
+                for i in 0..SPONGE_WIDTH {
+                    out_buffer.set_wire(
+                        local_wire(Poseidon2Gate::<F, D>::wire_full_sbox_0(r, i)),
+                        state[i],
+                    );
+                }
+            }
+            <F as Poseidon2>::sbox_layer_field(&mut state);
+            <F as Poseidon2>::external_linear_layer(&mut state);
+        }
+
+        for r in 0..poseidon2::N_PARTIAL_ROUNDS {
+            state[0] += F::from_canonical_u64(<F as Poseidon2>::INTERNAL_ROUND_CONSTANTS[r]);
+            out_buffer.set_wire(
+                local_wire(Poseidon2Gate::<F, D>::wire_partial_sbox(r)),
+                state[0],
+            );
+            state[0] = <F as Poseidon2>::sbox_monomial(state[0]);
+            <F as Poseidon2>::internal_linear_layer(&mut state);
+        }
+
+        for r in 0..poseidon2::HALF_N_FULL_ROUNDS {
+            <F as Poseidon2>::external_constant_layer_field(
+                &mut state,
+                poseidon2::HALF_N_FULL_ROUNDS + r,
+            );
+            for i in 0..SPONGE_WIDTH {
+                out_buffer.set_wire(
+                    local_wire(Poseidon2Gate::<F, D>::wire_full_sbox_1(r, i)),
+                    state[i],
+                );
+            }
+            <F as Poseidon2>::sbox_layer_field(&mut state);
+            <F as Poseidon2>::external_linear_layer(&mut state);
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            out_buffer.set_wire(local_wire(Poseidon2Gate::<F, D>::wire_output(i)), state[i]);
+        }
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_usize(self.row)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            row: src.read_usize()?,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::field::types::Field;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::poseidon2::Poseidon2Gate;
+    use crate::hash::hashing::SPONGE_WIDTH;
+    use crate::hash::poseidon2::Poseidon2;
+    use crate::iop::generator::generate_partial_witness;
+    use crate::iop::wire::Wire;
+    use crate::iop::witness::{PartialWitness, Witness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, Poseidon2GoldilocksConfig};
+
+    #[test]
+    fn wire_indices() {
+        type F = GoldilocksField;
+        type Gate = Poseidon2Gate<F, 4>;
+
+        assert_eq!(Gate::wire_input(0), 0);
+        assert_eq!(Gate::wire_output(11), 23);
+        assert_eq!(Gate::WIRE_SWAP, 24);
+        assert_eq!(Gate::wire_delta(3), 28);
+        assert_eq!(Gate::wire_full_sbox_0(1, 0), 29);
+        assert_eq!(Gate::wire_partial_sbox(0), 65);
+        assert_eq!(Gate::wire_full_sbox_1(3, 11), 134);
+        assert_eq!(Gate::end(), 135);
+    }
+
+    #[test]
+    fn generated_output() {
+        const D: usize = 2;
+        type C = Poseidon2GoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig {
+            num_wires: 143,
+            ..CircuitConfig::standard_recursion_config()
+        };
+        let mut builder = CircuitBuilder::new(config);
+        type Gate = Poseidon2Gate<F, D>;
+        let gate = Gate::new();
+        let row = builder.add_gate(gate, vec![]);
+        let circuit = builder.build_prover::<C>();
+
+        let permutation_inputs = (0..SPONGE_WIDTH)
+            .map(F::from_canonical_usize)
+            .collect::<Vec<_>>();
+
+        // Swap the first two chunks of the inputs, as when hashing a right sibling.
+        let mut inputs = PartialWitness::new();
+        inputs.set_wire(
+            Wire {
+                row,
+                column: Gate::WIRE_SWAP,
+            },
+            F::ONE,
+        );
+        for i in 0..SPONGE_WIDTH {
+            inputs.set_wire(
+                Wire {
+                    row,
+                    column: Gate::wire_input(i),
+                },
+                permutation_inputs[i],
+            );
+        }
+
+        let witness =
+            generate_partial_witness(inputs, &circuit.prover_only, &circuit.common).unwrap();
+
+        let mut swapped_inputs: [F; SPONGE_WIDTH] = permutation_inputs.try_into().unwrap();
+        for i in 0..4 {
+            swapped_inputs.swap(i, 4 + i);
+        }
+        let expected_outputs = F::poseidon2(swapped_inputs);
+        for i in 0..SPONGE_WIDTH {
+            let out = witness.get_wire(Wire {
+                row: 0,
+                column: Gate::wire_output(i),
+            });
+            assert_eq!(out, expected_outputs[i]);
+        }
+    }
+
+    #[test]
+    fn low_degree() {
+        type F = GoldilocksField;
+        let gate = Poseidon2Gate::<F, 4>::new();
+        test_low_degree(gate)
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = Poseidon2GoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let gate = Poseidon2Gate::<F, 2>::new();
+        test_eval_fns::<F, C, _, D>(gate)
+    }
+}
@@ -0,0 +1,49 @@
+use core::arch::aarch64::*;
+
+use crate::field::goldilocks_field::GoldilocksField;
+use crate::field::types::Field;
+
+const WIDTH: usize = 12;
+
+// The external linear layer has small coefficients, so we compute it without any modular
+// reduction. Each element of the state is split into its low and high 32-bit halves, held in the
+// two lanes of a vector. The entries of the external matrix sum to at most 64 along a row, so the
+// lanes stay below 2^38 and the two halves are recombined and reduced once at the end.
+
+#[inline(always)]
+unsafe fn split(x: GoldilocksField) -> uint64x2_t {
+    let x = x.0;
+    vld1q_u64([x & 0xffffffff, x >> 32].as_ptr())
+}
+
+#[inline(always)]
+unsafe fn combine(x: uint64x2_t) -> GoldilocksField {
+    let lo = vgetq_lane_u64::<0>(x) as u128;
+    let hi = vgetq_lane_u64::<1>(x) as u128;
+    GoldilocksField::from_noncanonical_u128(lo + (hi << 32))
+}
+
+/// Multiplies a chunk of four elements by the 4x4 matrix. See `Poseidon2::m4_field`.
+#[inline(always)]
+unsafe fn m4(x: [uint64x2_t; 4]) -> [uint64x2_t; 4] {
+    let [x0, x1, x2, x3] = x;
+    let t0 = vaddq_u64(x0, x1);
+    let t1 = vaddq_u64(x2, x3);
+    let t2 = vaddq_u64(vshlq_n_u64::<1>(x1), t1);
+    let t3 = vaddq_u64(vshlq_n_u64::<1>(x3), t0);
+    let t4 = vaddq_u64(vshlq_n_u64::<2>(t1), t3);
+    let t5 = vaddq_u64(vshlq_n_u64::<2>(t0), t2);
+    [vaddq_u64(t3, t5), t5, vaddq_u64(t2, t4), t4]
+}
+
+#[inline(always)]
+pub unsafe fn external_linear_layer(state: &mut [GoldilocksField; WIDTH]) {
+    let chunks: [[uint64x2_t; 4]; 3] =
+        core::array::from_fn(|c| m4(core::array::from_fn(|i| split(state[4 * c + i]))));
+    for i in 0..4 {
+        let sum = vaddq_u64(vaddq_u64(chunks[0][i], chunks[1][i]), chunks[2][i]);
+        for c in 0..3 {
+            state[4 * c + i] = combine(vaddq_u64(chunks[c][i], sum));
+        }
+    }
+}
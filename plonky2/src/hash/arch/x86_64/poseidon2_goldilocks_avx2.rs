@@ -0,0 +1,275 @@
+use core::arch::x86_64::*;
+use core::mem::transmute;
+
+use crate::field::goldilocks_field::GoldilocksField;
+use crate::field::types::{Field, Field64, PrimeField64};
+use crate::hash::poseidon2::{Poseidon2, HALF_N_FULL_ROUNDS, N_FULL_ROUNDS_TOTAL};
+
+// The state is kept in four vectors, the `j`-th of them holding `[x_j, x_{4 + j}, x_{8 + j}, 0]`.
+// With this layout, the 4x4 matrix of the external layer is applied to the three chunks of the
+// state at once with vertical operations, and sums over the chunks are sums over the lanes of a
+// vector.
+// The last lane of each vector always holds zero.
+
+// * Constant definitions *
+
+const WIDTH: usize = 12;
+
+type Columns = [[u64; 4]; 4];
+
+const fn to_columns(x: [u64; WIDTH]) -> Columns {
+    let mut res = [[0; 4]; 4];
+    let mut i = 0;
+    while i < WIDTH {
+        res[i % 4][i / 4] = x[i];
+        i += 1;
+    }
+    res
+}
+
+const fn make_external_round_constants() -> [Columns; N_FULL_ROUNDS_TOTAL] {
+    let mut res = [[[0; 4]; 4]; N_FULL_ROUNDS_TOTAL];
+    let mut r = 0;
+    while r < N_FULL_ROUNDS_TOTAL {
+        res[r] = to_columns(<GoldilocksField as Poseidon2>::EXTERNAL_ROUND_CONSTANTS[r]);
+        r += 1;
+    }
+    res
+}
+const EXTERNAL_ROUND_CONSTANTS: [Columns; N_FULL_ROUNDS_TOTAL] = make_external_round_constants();
+
+const INTERNAL_MATRIX_DIAG_M_1: Columns =
+    to_columns(<GoldilocksField as Poseidon2>::INTERNAL_MATRIX_DIAG_M_1);
+
+// * Goldilocks arithmetic *
+//
+// These are the routines of the AVX2 packed Goldilocks field, which is private to the field crate.
+// Values are in `0..2^64`, not necessarily canonical. See the comments of
+// `avx2_goldilocks_field.rs` for the `_s` suffix and the shifting trick.
+
+const SIGN_BIT: __m256i = unsafe { transmute([i64::MIN; 4]) };
+const SHIFTED_FIELD_ORDER: __m256i =
+    unsafe { transmute([GoldilocksField::ORDER ^ (i64::MIN as u64); 4]) };
+const EPSILON: __m256i = unsafe { transmute([GoldilocksField::ORDER.wrapping_neg(); 4]) };
+
+#[inline(always)]
+unsafe fn shift(x: __m256i) -> __m256i {
+    _mm256_xor_si256(x, SIGN_BIT)
+}
+
+#[inline(always)]
+unsafe fn canonicalize_s(x_s: __m256i) -> __m256i {
+    // If x >= FIELD_ORDER then corresponding mask bits are all 0; otherwise all 1.
+    let mask = _mm256_cmpgt_epi64(SHIFTED_FIELD_ORDER, x_s);
+    // wrapback_amt is -FIELD_ORDER if mask is 0; otherwise 0.
+    let wrapback_amt = _mm256_andnot_si256(mask, EPSILON);
+    _mm256_add_epi64(x_s, wrapback_amt)
+}
+
+#[inline(always)]
+unsafe fn add_no_double_overflow_64_64s_s(x: __m256i, y_s: __m256i) -> __m256i {
+    let res_wrapped_s = _mm256_add_epi64(x, y_s);
+    let mask = _mm256_cmpgt_epi64(y_s, res_wrapped_s); // -1 if overflowed else 0.
+    let wrapback_amt = _mm256_srli_epi64::<32>(mask); // -FIELD_ORDER if overflowed else 0.
+    _mm256_add_epi64(res_wrapped_s, wrapback_amt)
+}
+
+#[inline(always)]
+unsafe fn add(x: __m256i, y: __m256i) -> __m256i {
+    let y_s = shift(y);
+    let res_s = add_no_double_overflow_64_64s_s(x, canonicalize_s(y_s));
+    shift(res_s)
+}
+
+#[inline(always)]
+unsafe fn double(x: __m256i) -> __m256i {
+    add(x, x)
+}
+
+/// Full 64-bit by 64-bit multiplication, returning the high and low words.
+#[inline(always)]
+unsafe fn mul64_64(x: __m256i, y: __m256i) -> (__m256i, __m256i) {
+    let x_hi = _mm256_castps_si256(_mm256_movehdup_ps(_mm256_castsi256_ps(x)));
+    let y_hi = _mm256_castps_si256(_mm256_movehdup_ps(_mm256_castsi256_ps(y)));
+
+    let mul_ll = _mm256_mul_epu32(x, y);
+    let mul_lh = _mm256_mul_epu32(x, y_hi);
+    let mul_hl = _mm256_mul_epu32(x_hi, y);
+    let mul_hh = _mm256_mul_epu32(x_hi, y_hi);
+
+    let mul_ll_hi = _mm256_srli_epi64::<32>(mul_ll);
+    let t0 = _mm256_add_epi64(mul_hl, mul_ll_hi);
+    let t0_lo = _mm256_and_si256(t0, EPSILON);
+    let t0_hi = _mm256_srli_epi64::<32>(t0);
+    let t1 = _mm256_add_epi64(mul_lh, t0_lo);
+    let t2 = _mm256_add_epi64(mul_hh, t0_hi);
+    let t1_hi = _mm256_srli_epi64::<32>(t1);
+    let res_hi = _mm256_add_epi64(t2, t1_hi);
+
+    let t1_lo = _mm256_castps_si256(_mm256_moveldup_ps(_mm256_castsi256_ps(t1)));
+    let res_lo = _mm256_blend_epi32::<0xaa>(mul_ll, t1_lo);
+
+    (res_hi, res_lo)
+}
+
+/// Full 64-bit squaring, returning the high and low words.
+#[inline(always)]
+unsafe fn square64(x: __m256i) -> (__m256i, __m256i) {
+    let x_hi = _mm256_castps_si256(_mm256_movehdup_ps(_mm256_castsi256_ps(x)));
+
+    let mul_ll = _mm256_mul_epu32(x, x);
+    let mul_lh = _mm256_mul_epu32(x, x_hi);
+    let mul_hh = _mm256_mul_epu32(x_hi, x_hi);
+
+    let mul_ll_hi = _mm256_srli_epi64::<33>(mul_ll);
+    let t0 = _mm256_add_epi64(mul_lh, mul_ll_hi);
+    let t0_hi = _mm256_srli_epi64::<31>(t0);
+    let res_hi = _mm256_add_epi64(mul_hh, t0_hi);
+
+    let mul_lh_lo = _mm256_slli_epi64::<33>(mul_lh);
+    let res_lo = _mm256_add_epi64(mul_ll, mul_lh_lo);
+
+    (res_hi, res_lo)
+}
+
+/// Goldilocks addition of a number `y <= 0xffffffff00000000` to a shifted `x_s`.
+#[inline(always)]
+unsafe fn add_small_64s_64_s(x_s: __m256i, y: __m256i) -> __m256i {
+    let res_wrapped_s = _mm256_add_epi64(x_s, y);
+    let mask = _mm256_cmpgt_epi32(x_s, res_wrapped_s); // -1 if overflowed else 0.
+    let wrapback_amt = _mm256_srli_epi64::<32>(mask); // -FIELD_ORDER if overflowed else 0.
+    _mm256_add_epi64(res_wrapped_s, wrapback_amt)
+}
+
+/// Goldilocks subtraction of a number `y <= 0xffffffff00000000` from a shifted `x_s`.
+#[inline(always)]
+unsafe fn sub_small_64s_64_s(x_s: __m256i, y: __m256i) -> __m256i {
+    let res_wrapped_s = _mm256_sub_epi64(x_s, y);
+    let mask = _mm256_cmpgt_epi32(res_wrapped_s, x_s); // -1 if underflowed else 0.
+    let wrapback_amt = _mm256_srli_epi64::<32>(mask); // -FIELD_ORDER if underflowed else 0.
+    _mm256_sub_epi64(res_wrapped_s, wrapback_amt)
+}
+
+#[inline(always)]
+unsafe fn reduce128(x: (__m256i, __m256i)) -> __m256i {
+    let (hi0, lo0) = x;
+    let lo0_s = shift(lo0);
+    let hi_hi0 = _mm256_srli_epi64::<32>(hi0);
+    let lo1_s = sub_small_64s_64_s(lo0_s, hi_hi0);
+    let t1 = _mm256_mul_epu32(hi0, EPSILON);
+    let lo2_s = add_small_64s_64_s(lo1_s, t1);
+    shift(lo2_s)
+}
+
+#[inline(always)]
+unsafe fn mul(x: __m256i, y: __m256i) -> __m256i {
+    reduce128(mul64_64(x, y))
+}
+
+#[inline(always)]
+unsafe fn square(x: __m256i) -> __m256i {
+    reduce128(square64(x))
+}
+
+/// Sums the first three lanes of `x`, and broadcasts the result to them. The last lane of `x`
+/// must be zero, and stays so.
+#[inline(always)]
+unsafe fn sum_lanes(x: __m256i) -> __m256i {
+    // Lanes [1, 2, 0, 3] and [2, 0, 1, 3] of `x`.
+    let x1 = _mm256_permute4x64_epi64::<0b11_00_10_01>(x);
+    let x2 = _mm256_permute4x64_epi64::<0b11_01_00_10>(x);
+    add(add(x, x1), x2)
+}
+
+// * Permutation *
+
+#[inline(always)]
+unsafe fn load_columns(c: &Columns) -> [__m256i; 4] {
+    c.map(|column| _mm256_loadu_si256(column.as_ptr().cast::<__m256i>()))
+}
+
+#[inline(always)]
+unsafe fn constant_layer(state: &mut [__m256i; 4], round_constants: &Columns) {
+    let round_constants = load_columns(round_constants);
+    for j in 0..4 {
+        state[j] = add(state[j], round_constants[j]);
+    }
+}
+
+#[inline(always)]
+unsafe fn sbox_monomial(x: __m256i) -> __m256i {
+    // x |--> x^7
+    let x2 = square(x);
+    let x4 = square(x2);
+    let x3 = mul(x, x2);
+    mul(x3, x4)
+}
+
+#[inline(always)]
+unsafe fn sbox_layer(state: &mut [__m256i; 4]) {
+    for j in 0..4 {
+        state[j] = sbox_monomial(state[j]);
+    }
+}
+
+#[inline(always)]
+unsafe fn external_linear_layer(state: &mut [__m256i; 4]) {
+    // The 4x4 matrix, applied to the three chunks at once. See `Poseidon2::m4_field`.
+    let [x0, x1, x2, x3] = *state;
+    let t0 = add(x0, x1);
+    let t1 = add(x2, x3);
+    let t2 = add(double(x1), t1);
+    let t3 = add(double(x3), t0);
+    let t4 = add(double(double(t1)), t3);
+    let t5 = add(double(double(t0)), t2);
+    let t6 = add(t3, t5);
+    let t7 = add(t2, t4);
+
+    // Add the sum of the chunks to each of them.
+    *state = [t6, t5, t7, t4].map(|x| add(x, sum_lanes(x)));
+}
+
+#[inline(always)]
+unsafe fn internal_linear_layer(state: &mut [__m256i; 4], diag_m_1: &[__m256i; 4]) {
+    let partial_sums = add(add(state[0], state[1]), add(state[2], state[3]));
+    let sum = sum_lanes(partial_sums);
+    for j in 0..4 {
+        state[j] = add(mul(state[j], diag_m_1[j]), sum);
+    }
+}
+
+#[inline(always)]
+unsafe fn full_rounds(state: &mut [__m256i; 4], round_constants: &[Columns]) {
+    for round_constants in round_constants {
+        constant_layer(state, round_constants);
+        sbox_layer(state);
+        external_linear_layer(state);
+    }
+}
+
+#[inline(always)]
+unsafe fn partial_rounds(state: &mut [__m256i; 4]) {
+    let diag_m_1 = load_columns(&INTERNAL_MATRIX_DIAG_M_1);
+    for &round_constant in &<GoldilocksField as Poseidon2>::INTERNAL_ROUND_CONSTANTS {
+        // The S-box of the first element is cheaper in a scalar register.
+        let x = GoldilocksField::from_noncanonical_u64(_mm256_extract_epi64::<0>(state[0]) as u64);
+        let x = <GoldilocksField as Poseidon2>::sbox_monomial(
+            x + GoldilocksField::from_canonical_u64(round_constant),
+        );
+        state[0] = _mm256_insert_epi64::<0>(state[0], x.to_noncanonical_u64() as i64);
+        internal_linear_layer(state, &diag_m_1);
+    }
+}
+
+#[inline]
+pub unsafe fn poseidon2(input: &[GoldilocksField; WIDTH]) -> [GoldilocksField; WIDTH] {
+    let mut state = load_columns(&to_columns(input.map(|x| x.0)));
+
+    external_linear_layer(&mut state);
+    full_rounds(&mut state, &EXTERNAL_ROUND_CONSTANTS[..HALF_N_FULL_ROUNDS]);
+    partial_rounds(&mut state);
+    full_rounds(&mut state, &EXTERNAL_ROUND_CONSTANTS[HALF_N_FULL_ROUNDS..]);
+
+    let columns: Columns = transmute(state);
+    core::array::from_fn(|i| GoldilocksField::from_noncanonical_u64(columns[i % 4][i / 4]))
+}
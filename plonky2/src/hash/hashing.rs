@@ -74,6 +74,14 @@ pub fn compress<F: RichField, P: PlonkyPermutation<F>>(x: HashOut<F>, y: HashOut
 /// Permutation that can be used in the sponge construction for an algebraic hash.
 pub trait PlonkyPermutation<F: RichField> {
     fn permute(input: [F; SPONGE_WIDTH]) -> [F; SPONGE_WIDTH];
+
+    /// Permutes each of `states` in place. Permutations with a SIMD implementation across states
+    /// override this to permute several states per call.
+    fn permute_many(states: &mut [[F; SPONGE_WIDTH]]) {
+        for state in states.iter_mut() {
+            *state = Self::permute(*state);
+        }
+    }
 }
 
 /// Hash a message without any padding step. Note that this can enable length-extension attacks.
@@ -5,8 +5,6 @@ use std::sync::Arc;
 use itertools::Itertools;
 
 use maybe_rayon::*;
-use plonky2_cuda::DataSlice;
-use rustacuda::memory::DeviceSlice;
 use serde::{Deserialize, Serialize};
 
 use crate::hash::hash_types::RichField;
@@ -380,6 +378,15 @@ impl<F: RichField, H: Hasher<F>> MerkleTree<F, H> {
         }
     }
 
+    /// Number of leaves, in either representation.
+    pub fn num_leaves(&self) -> usize {
+        if self.my_leaves.is_empty() {
+            self.leaves.len()
+        } else {
+            self.my_leaves_len / self.my_leaf_len
+        }
+    }
+
     pub fn get(&self, i: usize) -> &[F] {
         if self.my_leaves.is_empty() {
             &self.leaves[i]
@@ -6,4 +6,6 @@ pub mod merkle_proofs;
 pub mod merkle_tree;
 pub mod path_compression;
 pub mod poseidon;
+pub mod poseidon2;
+pub mod poseidon2_goldilocks;
 pub mod poseidon_goldilocks;
@@ -7,6 +7,9 @@ use alloc::vec::Vec;
 use unroll::unroll_for_loops;
 
 use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::ops::Square;
+use crate::field::packable::Packable;
+use crate::field::packed::PackedField;
 use crate::field::types::{Field, PrimeField64};
 use crate::gates::gate::Gate;
 use crate::gates::poseidon::PoseidonGate;
@@ -615,6 +618,109 @@ pub trait Poseidon: PrimeField64 {
         state
     }
 
+    /// Same as `sbox_monomial` for packed fields.
+    #[inline(always)]
+    fn sbox_monomial_packed<P: PackedField<Scalar = Self>>(x: P) -> P {
+        // x |--> x^7
+        let x2 = x.square();
+        let x4 = x2.square();
+        let x3 = x * x2;
+        x3 * x4
+    }
+
+    /// Same as `mds_layer` for packed fields.
+    fn mds_layer_packed<P: PackedField<Scalar = Self>>(state: &[P; WIDTH]) -> [P; WIDTH] {
+        let mut result = [P::ZEROS; WIDTH];
+        for r in 0..WIDTH {
+            for i in 0..WIDTH {
+                result[r] +=
+                    state[(i + r) % WIDTH] * Self::from_canonical_u64(Self::MDS_MATRIX_CIRC[i]);
+            }
+            result[r] += state[r] * Self::from_canonical_u64(Self::MDS_MATRIX_DIAG[r]);
+        }
+        result
+    }
+
+    /// Same as `mds_partial_layer_fast` for packed fields.
+    fn mds_partial_layer_fast_packed<P: PackedField<Scalar = Self>>(
+        state: &[P; WIDTH],
+        r: usize,
+    ) -> [P; WIDTH] {
+        let mds0to0 = Self::MDS_MATRIX_CIRC[0] + Self::MDS_MATRIX_DIAG[0];
+        let mut d = state[0] * Self::from_canonical_u64(mds0to0);
+        for i in 1..WIDTH {
+            d += state[i] * Self::from_canonical_u64(Self::FAST_PARTIAL_ROUND_W_HATS[r][i - 1]);
+        }
+
+        // result = [d] concat [state[0] * v + state[shift up by 1]]
+        let mut result = [P::ZEROS; WIDTH];
+        result[0] = d;
+        for i in 1..WIDTH {
+            let t = Self::from_canonical_u64(Self::FAST_PARTIAL_ROUND_VS[r][i - 1]);
+            result[i] = state[0] * t + state[i];
+        }
+        result
+    }
+
+    /// Same as `full_rounds` for packed fields.
+    fn full_rounds_packed<P: PackedField<Scalar = Self>>(
+        state: &mut [P; WIDTH],
+        round_ctr: &mut usize,
+    ) {
+        for _ in 0..HALF_N_FULL_ROUNDS {
+            for i in 0..WIDTH {
+                state[i] += Self::from_canonical_u64(ALL_ROUND_CONSTANTS[i + WIDTH * *round_ctr]);
+                state[i] = Self::sbox_monomial_packed(state[i]);
+            }
+            *state = Self::mds_layer_packed(state);
+            *round_ctr += 1;
+        }
+    }
+
+    /// Same as `partial_rounds` for packed fields.
+    fn partial_rounds_packed<P: PackedField<Scalar = Self>>(
+        state: &mut [P; WIDTH],
+        round_ctr: &mut usize,
+    ) {
+        for i in 0..WIDTH {
+            state[i] += Self::from_canonical_u64(Self::FAST_PARTIAL_FIRST_ROUND_CONSTANT[i]);
+        }
+
+        // Same as `mds_partial_layer_init`.
+        let mut result = [P::ZEROS; WIDTH];
+        result[0] = state[0];
+        for r in 1..WIDTH {
+            for c in 1..WIDTH {
+                let t =
+                    Self::from_canonical_u64(Self::FAST_PARTIAL_ROUND_INITIAL_MATRIX[r - 1][c - 1]);
+                result[c] += state[r] * t;
+            }
+        }
+        *state = result;
+
+        for i in 0..N_PARTIAL_ROUNDS {
+            state[0] = Self::sbox_monomial_packed(state[0]);
+            state[0] += Self::from_canonical_u64(Self::FAST_PARTIAL_ROUND_CONSTANTS[i]);
+            *state = Self::mds_partial_layer_fast_packed(state, i);
+        }
+        *round_ctr += N_PARTIAL_ROUNDS;
+    }
+
+    /// Same as `poseidon` for `P::WIDTH` independent states, one in each lane of `P`, so that
+    /// several permutations share the SIMD instructions of the target.
+    #[inline]
+    fn poseidon_packed<P: PackedField<Scalar = Self>>(input: [P; WIDTH]) -> [P; WIDTH] {
+        let mut state = input;
+        let mut round_ctr = 0;
+
+        Self::full_rounds_packed(&mut state, &mut round_ctr);
+        Self::partial_rounds_packed(&mut state, &mut round_ctr);
+        Self::full_rounds_packed(&mut state, &mut round_ctr);
+        debug_assert_eq!(round_ctr, N_ROUNDS);
+
+        state
+    }
+
     // For testing only, to ensure that various tricks are correct.
     #[inline]
     fn partial_rounds_naive(state: &mut [Self; WIDTH], round_ctr: &mut usize) {
@@ -645,6 +751,35 @@ impl<F: RichField> PlonkyPermutation<F> for PoseidonPermutation {
     fn permute(input: [F; SPONGE_WIDTH]) -> [F; SPONGE_WIDTH] {
         F::poseidon(input)
     }
+
+    fn permute_many(states: &mut [[F; SPONGE_WIDTH]]) {
+        let width = <F::Packing as PackedField>::WIDTH;
+        if width == 1 {
+            for state in states.iter_mut() {
+                *state = F::poseidon(*state);
+            }
+            return;
+        }
+
+        let mut chunks = states.chunks_exact_mut(width);
+        for chunk in &mut chunks {
+            let mut packed = [F::Packing::ZEROS; SPONGE_WIDTH];
+            for (i, p) in packed.iter_mut().enumerate() {
+                for (lane, state) in p.as_slice_mut().iter_mut().zip(chunk.iter()) {
+                    *lane = state[i];
+                }
+            }
+            let packed = F::poseidon_packed(packed);
+            for (i, p) in packed.iter().enumerate() {
+                for (lane, state) in p.as_slice().iter().zip(chunk.iter_mut()) {
+                    state[i] = *lane;
+                }
+            }
+        }
+        for state in chunks.into_remainder() {
+            *state = F::poseidon(*state);
+        }
+    }
 }
 
 /// Poseidon hash function.
@@ -711,9 +846,12 @@ impl<F: RichField> AlgebraicHasher<F> for PoseidonHash {
 
 #[cfg(test)]
 pub(crate) mod test_helpers {
-    use crate::field::types::Field;
-    use crate::hash::hashing::SPONGE_WIDTH;
-    use crate::hash::poseidon::Poseidon;
+    use alloc::vec::Vec;
+
+    use crate::field::types::{Field, Sample};
+    use crate::hash::hash_types::RichField;
+    use crate::hash::hashing::{PlonkyPermutation, SPONGE_WIDTH};
+    use crate::hash::poseidon::{Poseidon, PoseidonPermutation};
 
     pub(crate) fn check_test_vectors<F: Field>(
         test_vectors: Vec<([u64; SPONGE_WIDTH], [u64; SPONGE_WIDTH])>,
@@ -747,4 +885,12 @@ pub(crate) mod test_helpers {
             assert_eq!(output[i], output_naive[i]);
         }
     }
+
+    pub(crate) fn check_permute_many<F: RichField>() {
+        // An odd number of states, so that some are left over after the packed ones.
+        let mut states = (0..19).map(|_| F::rand_array()).collect::<Vec<_>>();
+        let expected = states.iter().map(|&s| F::poseidon(s)).collect::<Vec<_>>();
+        PoseidonPermutation::permute_many(&mut states);
+        assert_eq!(states, expected);
+    }
 }
@@ -0,0 +1,412 @@
+//! Implementation of the Poseidon2 hash function, as described in
+//! <https://eprint.iacr.org/2023/323.pdf>
+//!
+//! Poseidon2 keeps the round structure of Poseidon, but replaces its dense MDS matrix with two
+//! cheaper linear layers: the external layer of the full rounds, built from a fixed 4x4 matrix,
+//! and the internal layer of the partial rounds, whose matrix is the sum of the all-ones matrix
+//! and of a diagonal matrix.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::types::{Field, PrimeField64};
+use crate::gates::poseidon2::Poseidon2Gate;
+use crate::hash::hash_types::{HashOut, HashOutTarget, RichField};
+use crate::hash::hashing::{compress, hash_n_to_hash_no_pad, PlonkyPermutation, SPONGE_WIDTH};
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::target::{BoolTarget, Target};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::config::{AlgebraicHasher, Hasher};
+
+// The numbers of rounds are those of the reference implementation for the Goldilocks field with
+// width 12 and s-box x^7.
+//
+// NB: Changing any of these values will require regenerating the round constants and changes the
+// wire layout of `Poseidon2Gate`.
+pub const HALF_N_FULL_ROUNDS: usize = 4;
+pub(crate) const N_FULL_ROUNDS_TOTAL: usize = 2 * HALF_N_FULL_ROUNDS;
+pub const N_PARTIAL_ROUNDS: usize = 22;
+pub const N_ROUNDS: usize = N_FULL_ROUNDS_TOTAL + N_PARTIAL_ROUNDS;
+
+const WIDTH: usize = SPONGE_WIDTH;
+
+pub trait Poseidon2: PrimeField64 {
+    /// The round constants of the full rounds, the first `HALF_N_FULL_ROUNDS` of them being used
+    /// before the partial rounds.
+    const EXTERNAL_ROUND_CONSTANTS: [[u64; WIDTH]; N_FULL_ROUNDS_TOTAL];
+
+    /// The round constants of the partial rounds, which are only added to the first element of the
+    /// state.
+    const INTERNAL_ROUND_CONSTANTS: [u64; N_PARTIAL_ROUNDS];
+
+    // The internal matrix we use is J + D, where J is the matrix whose entries are all one, and D
+    // is the diagonal matrix whose diagonal is given by `INTERNAL_MATRIX_DIAG_M_1`.
+    const INTERNAL_MATRIX_DIAG_M_1: [u64; WIDTH];
+
+    /// Multiplies a chunk of the state by the 4x4 matrix
+    /// ```text
+    /// [ 5 7 1 3 ]
+    /// [ 4 6 1 1 ]
+    /// [ 1 3 5 7 ]
+    /// [ 1 1 4 6 ]
+    /// ```
+    /// with 8 additions and 4 doublings.
+    #[inline(always)]
+    fn m4_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(x: &mut [F]) {
+        debug_assert_eq!(x.len(), 4);
+        let t0 = x[0] + x[1];
+        let t1 = x[2] + x[3];
+        let t2 = x[1].double() + t1;
+        let t3 = x[3].double() + t0;
+        let t4 = t1.double().double() + t3;
+        let t5 = t0.double().double() + t2;
+        let t6 = t3 + t5;
+        let t7 = t2 + t4;
+        x.copy_from_slice(&[t6, t5, t7, t4]);
+    }
+
+    /// Recursive version of `m4_field`.
+    fn m4_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        x: &mut [ExtensionTarget<D>],
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        debug_assert_eq!(x.len(), 4);
+        let t0 = builder.add_extension(x[0], x[1]);
+        let t1 = builder.add_extension(x[2], x[3]);
+        let t2 = builder.mul_const_add_extension(Self::TWO, x[1], t1);
+        let t3 = builder.mul_const_add_extension(Self::TWO, x[3], t0);
+        let t4 = builder.mul_const_add_extension(Self::from_canonical_u8(4), t1, t3);
+        let t5 = builder.mul_const_add_extension(Self::from_canonical_u8(4), t0, t2);
+        let t6 = builder.add_extension(t3, t5);
+        let t7 = builder.add_extension(t2, t4);
+        x.copy_from_slice(&[t6, t5, t7, t4]);
+    }
+
+    /// The linear layer of the full rounds, which is also applied to the input of the permutation.
+    /// Its matrix is the block matrix whose diagonal blocks are `2 M4`, and whose other blocks are
+    /// `M4`.
+    #[inline]
+    fn external_linear_layer(state: &mut [Self; WIDTH]) {
+        Self::external_linear_layer_field(state);
+    }
+
+    /// Same as `external_linear_layer` for field extensions of `Self`.
+    #[inline]
+    fn external_linear_layer_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; WIDTH],
+    ) {
+        for chunk in state.chunks_exact_mut(4) {
+            Self::m4_field(chunk);
+        }
+
+        let mut sums = [F::ZERO; 4];
+        for i in 0..WIDTH {
+            sums[i % 4] += state[i];
+        }
+        for i in 0..WIDTH {
+            state[i] += sums[i % 4];
+        }
+    }
+
+    /// Recursive version of `external_linear_layer`.
+    fn external_linear_layer_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; WIDTH],
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        for chunk in state.chunks_exact_mut(4) {
+            Self::m4_circuit(builder, chunk);
+        }
+
+        let sums: [ExtensionTarget<D>; 4] = core::array::from_fn(|j| {
+            let terms = (j..WIDTH).step_by(4).map(|i| state[i]).collect::<Vec<_>>();
+            builder.add_many_extension(terms)
+        });
+        for i in 0..WIDTH {
+            state[i] = builder.add_extension(state[i], sums[i % 4]);
+        }
+    }
+
+    /// The linear layer of the partial rounds.
+    #[inline]
+    fn internal_linear_layer(state: &mut [Self; WIDTH]) {
+        Self::internal_linear_layer_field(state);
+    }
+
+    /// Same as `internal_linear_layer` for field extensions of `Self`.
+    #[inline]
+    fn internal_linear_layer_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; WIDTH],
+    ) {
+        let sum = state.iter().copied().sum::<F>();
+        for i in 0..WIDTH {
+            let diag = F::from_canonical_u64(Self::INTERNAL_MATRIX_DIAG_M_1[i]);
+            state[i] = state[i] * diag + sum;
+        }
+    }
+
+    /// Recursive version of `internal_linear_layer`.
+    fn internal_linear_layer_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; WIDTH],
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        let sum = builder.add_many_extension(state.iter());
+        for i in 0..WIDTH {
+            let diag = Self::from_canonical_u64(Self::INTERNAL_MATRIX_DIAG_M_1[i]);
+            state[i] = builder.mul_const_add_extension(diag, state[i], sum);
+        }
+    }
+
+    /// Adds the round constants of the `round`-th full round.
+    #[inline]
+    fn external_constant_layer_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; WIDTH],
+        round: usize,
+    ) {
+        for i in 0..WIDTH {
+            state[i] += F::from_canonical_u64(Self::EXTERNAL_ROUND_CONSTANTS[round][i]);
+        }
+    }
+
+    /// Recursive version of `external_constant_layer_field`.
+    fn external_constant_layer_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; WIDTH],
+        round: usize,
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        for i in 0..WIDTH {
+            let c = Self::from_canonical_u64(Self::EXTERNAL_ROUND_CONSTANTS[round][i]);
+            state[i] = builder.add_const_extension(state[i], c);
+        }
+    }
+
+    #[inline(always)]
+    fn sbox_monomial<F: FieldExtension<D, BaseField = Self>, const D: usize>(x: F) -> F {
+        // x |--> x^7
+        let x2 = x.square();
+        let x4 = x2.square();
+        let x3 = x * x2;
+        x3 * x4
+    }
+
+    /// Recursive version of `sbox_monomial`.
+    fn sbox_monomial_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        x: ExtensionTarget<D>,
+    ) -> ExtensionTarget<D>
+    where
+        Self: RichField + Extendable<D>,
+    {
+        // x |--> x^7
+        builder.exp_u64_extension(x, 7)
+    }
+
+    /// Same as `sbox_monomial`, applied to the whole state.
+    #[inline]
+    fn sbox_layer_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; WIDTH],
+    ) {
+        for i in 0..WIDTH {
+            state[i] = Self::sbox_monomial(state[i]);
+        }
+    }
+
+    /// Recursive version of `sbox_layer_field`.
+    fn sbox_layer_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; WIDTH],
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        for i in 0..WIDTH {
+            state[i] = <Self as Poseidon2>::sbox_monomial_circuit(builder, state[i]);
+        }
+    }
+
+    #[inline]
+    fn full_rounds(state: &mut [Self; WIDTH], round_ctr: &mut usize) {
+        for _ in 0..HALF_N_FULL_ROUNDS {
+            Self::external_constant_layer_field(state, *round_ctr);
+            Self::sbox_layer_field(state);
+            Self::external_linear_layer(state);
+            *round_ctr += 1;
+        }
+    }
+
+    #[inline]
+    fn partial_rounds(state: &mut [Self; WIDTH]) {
+        for r in 0..N_PARTIAL_ROUNDS {
+            state[0] += Self::from_canonical_u64(Self::INTERNAL_ROUND_CONSTANTS[r]);
+            state[0] = Self::sbox_monomial(state[0]);
+            Self::internal_linear_layer(state);
+        }
+    }
+
+    #[inline]
+    fn poseidon2(input: [Self; WIDTH]) -> [Self; WIDTH] {
+        let mut state = input;
+        // The counter of the full rounds, which selects their round constants.
+        let mut round_ctr = 0;
+
+        Self::external_linear_layer(&mut state);
+        Self::full_rounds(&mut state, &mut round_ctr);
+        Self::partial_rounds(&mut state);
+        Self::full_rounds(&mut state, &mut round_ctr);
+        debug_assert_eq!(round_ctr, N_FULL_ROUNDS_TOTAL);
+
+        state
+    }
+}
+
+pub struct Poseidon2Permutation;
+impl<F: RichField> PlonkyPermutation<F> for Poseidon2Permutation {
+    fn permute(input: [F; SPONGE_WIDTH]) -> [F; SPONGE_WIDTH] {
+        F::poseidon2(input)
+    }
+}
+
+/// Poseidon2 hash function.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Poseidon2Hash;
+impl<F: RichField> Hasher<F> for Poseidon2Hash {
+    const HASH_SIZE: usize = 4 * 8;
+    type Hash = HashOut<F>;
+    type Permutation = Poseidon2Permutation;
+
+    fn hash_no_pad(input: &[F]) -> Self::Hash {
+        hash_n_to_hash_no_pad::<F, Self::Permutation>(input)
+    }
+
+    fn hash_public_inputs(input: &[F]) -> Self::Hash {
+        Poseidon2Hash::hash_no_pad(input)
+    }
+
+    fn two_to_one(left: Self::Hash, right: Self::Hash) -> Self::Hash {
+        compress::<F, Self::Permutation>(left, right)
+    }
+}
+
+impl<F: RichField> AlgebraicHasher<F> for Poseidon2Hash {
+    fn permute_swapped<const D: usize>(
+        inputs: [Target; SPONGE_WIDTH],
+        swap: BoolTarget,
+        builder: &mut CircuitBuilder<F, D>,
+    ) -> [Target; SPONGE_WIDTH]
+    where
+        F: RichField + Extendable<D>,
+    {
+        let gate_type = Poseidon2Gate::<F, D>::new();
+        let gate = builder.add_gate(gate_type, vec![]);
+
+        let swap_wire = Poseidon2Gate::<F, D>::WIRE_SWAP;
+        let swap_wire = Target::wire(gate, swap_wire);
+        builder.connect(swap.target, swap_wire);
+
+        // Route input wires.
+        for i in 0..SPONGE_WIDTH {
+            let in_wire = Poseidon2Gate::<F, D>::wire_input(i);
+            let in_wire = Target::wire(gate, in_wire);
+            builder.connect(inputs[i], in_wire);
+        }
+
+        // Collect output wires.
+        (0..SPONGE_WIDTH)
+            .map(|i| Target::wire(gate, Poseidon2Gate::<F, D>::wire_output(i)))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap()
+    }
+
+    fn public_inputs_hash<const D: usize>(
+        inputs: Vec<Target>,
+        builder: &mut CircuitBuilder<F, D>,
+    ) -> HashOutTarget
+    where
+        F: RichField + Extendable<D>,
+    {
+        HashOutTarget::from_vec(builder.hash_n_to_m_no_pad::<Poseidon2Hash>(inputs, 4))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    use crate::field::types::Field;
+    use crate::hash::hashing::SPONGE_WIDTH;
+    use crate::hash::poseidon2::{Poseidon2, HALF_N_FULL_ROUNDS, N_PARTIAL_ROUNDS};
+
+    pub(crate) fn check_test_vectors<F: Field>(
+        test_vectors: Vec<([u64; SPONGE_WIDTH], [u64; SPONGE_WIDTH])>,
+    ) where
+        F: Poseidon2,
+    {
+        for (input_, expected_output_) in test_vectors.into_iter() {
+            let input = input_.map(F::from_canonical_u64);
+            let output = F::poseidon2(input);
+            for i in 0..SPONGE_WIDTH {
+                let ex_output = F::from_canonical_u64(expected_output_[i]);
+                assert_eq!(output[i], ex_output);
+            }
+        }
+    }
+
+    /// The permutation computed with dense matrices, to check the linear layers and any
+    /// architecture-specific implementation.
+    fn poseidon2_naive<F: Poseidon2>(input: [F; SPONGE_WIDTH]) -> [F; SPONGE_WIDTH] {
+        const M4: [[u64; 4]; 4] = [[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+        let external_matrix: [[F; SPONGE_WIDTH]; SPONGE_WIDTH] = core::array::from_fn(|i| {
+            core::array::from_fn(|j| {
+                let block_factor = if i / 4 == j / 4 { 2 } else { 1 };
+                F::from_canonical_u64(block_factor * M4[i % 4][j % 4])
+            })
+        });
+        let internal_matrix: [[F; SPONGE_WIDTH]; SPONGE_WIDTH] = core::array::from_fn(|i| {
+            core::array::from_fn(|j| {
+                if i == j {
+                    F::ONE + F::from_canonical_u64(F::INTERNAL_MATRIX_DIAG_M_1[i])
+                } else {
+                    F::ONE
+                }
+            })
+        });
+        let mat_vec = |m: &[[F; SPONGE_WIDTH]; SPONGE_WIDTH], v: [F; SPONGE_WIDTH]| {
+            m.map(|row| row.iter().zip(v).map(|(&a, b)| a * b).sum::<F>())
+        };
+        let sbox = |x: F| x.exp_u64(7);
+
+        let mut state = mat_vec(&external_matrix, input);
+        for r in 0..HALF_N_FULL_ROUNDS {
+            let rc = F::EXTERNAL_ROUND_CONSTANTS[r];
+            let state_rc = core::array::from_fn(|i| sbox(state[i] + F::from_canonical_u64(rc[i])));
+            state = mat_vec(&external_matrix, state_rc);
+        }
+        for r in 0..N_PARTIAL_ROUNDS {
+            state[0] = sbox(state[0] + F::from_canonical_u64(F::INTERNAL_ROUND_CONSTANTS[r]));
+            state = mat_vec(&internal_matrix, state);
+        }
+        for r in HALF_N_FULL_ROUNDS..2 * HALF_N_FULL_ROUNDS {
+            let rc = F::EXTERNAL_ROUND_CONSTANTS[r];
+            let state_rc = core::array::from_fn(|i| sbox(state[i] + F::from_canonical_u64(rc[i])));
+            state = mat_vec(&external_matrix, state_rc);
+        }
+        state
+    }
+
+    pub(crate) fn check_consistency<F: Field>()
+    where
+        F: Poseidon2,
+    {
+        let input = core::array::from_fn(|i| F::from_canonical_u64(i as u64));
+        assert_eq!(F::poseidon2(input), poseidon2_naive(input));
+
+        let input = [F::NEG_ONE; SPONGE_WIDTH];
+        assert_eq!(F::poseidon2(input), poseidon2_naive(input));
+    }
+}
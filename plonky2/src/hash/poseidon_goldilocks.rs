@@ -211,45 +211,45 @@ impl Poseidon for GoldilocksField {
          0xdcedab70f40718ba, 0xe796d293a47a64cb, 0x80772dc2645b280b, ],
     ];
 
-    // #[cfg(all(target_arch="x86_64", target_feature="avx2", target_feature="bmi2"))]
-    // #[inline]
-    // fn poseidon(input: [Self; 12]) -> [Self; 12] {
-    //     unsafe {
-    //         crate::hash::arch::x86_64::poseidon_goldilocks_avx2_bmi2::poseidon(&input)
-    //     }
-    // }
+    #[cfg(all(target_arch="x86_64", target_feature="avx2", target_feature="bmi2"))]
+    #[inline]
+    fn poseidon(input: [Self; 12]) -> [Self; 12] {
+        unsafe {
+            crate::hash::arch::x86_64::poseidon_goldilocks_avx2_bmi2::poseidon(&input)
+        }
+    }
 
-    // #[cfg(all(target_arch="x86_64", target_feature="avx2", target_feature="bmi2"))]
-    // #[inline(always)]
-    // fn constant_layer(state: &mut [Self; 12], round_ctr: usize) {
-    //     unsafe {
-    //         crate::hash::arch::x86_64::poseidon_goldilocks_avx2_bmi2::constant_layer(state, round_ctr);
-    //     }
-    // }
+    #[cfg(all(target_arch="x86_64", target_feature="avx2", target_feature="bmi2"))]
+    #[inline(always)]
+    fn constant_layer(state: &mut [Self; 12], round_ctr: usize) {
+        unsafe {
+            crate::hash::arch::x86_64::poseidon_goldilocks_avx2_bmi2::constant_layer(state, round_ctr);
+        }
+    }
 
-    // #[cfg(all(target_arch="x86_64", target_feature="avx2", target_feature="bmi2"))]
-    // #[inline(always)]
-    // fn sbox_layer(state: &mut [Self; 12]) {
-    //     unsafe {
-    //         crate::hash::arch::x86_64::poseidon_goldilocks_avx2_bmi2::sbox_layer(state);
-    //     }
-    // }
+    #[cfg(all(target_arch="x86_64", target_feature="avx2", target_feature="bmi2"))]
+    #[inline(always)]
+    fn sbox_layer(state: &mut [Self; 12]) {
+        unsafe {
+            crate::hash::arch::x86_64::poseidon_goldilocks_avx2_bmi2::sbox_layer(state);
+        }
+    }
 
-    // #[cfg(all(target_arch="x86_64", target_feature="avx2", target_feature="bmi2"))]
-    // #[inline(always)]
-    // fn mds_layer(state: &[Self; 12]) -> [Self; 12] {
-    //     unsafe {
-    //         crate::hash::arch::x86_64::poseidon_goldilocks_avx2_bmi2::mds_layer(state)
-    //     }
-    // }
+    #[cfg(all(target_arch="x86_64", target_feature="avx2", target_feature="bmi2"))]
+    #[inline(always)]
+    fn mds_layer(state: &[Self; 12]) -> [Self; 12] {
+        unsafe {
+            crate::hash::arch::x86_64::poseidon_goldilocks_avx2_bmi2::mds_layer(state)
+        }
+    }
 
-    // #[cfg(all(target_arch="aarch64", target_feature="neon"))]
-    // #[inline]
-    // fn poseidon(input: [Self; 12]) -> [Self; 12] {
-    //     unsafe {
-    //         crate::hash::arch::aarch64::poseidon_goldilocks_neon::poseidon(input)
-    //     }
-    // }
+    #[cfg(all(target_arch="aarch64", target_feature="neon"))]
+    #[inline]
+    fn poseidon(input: [Self; 12]) -> [Self; 12] {
+        unsafe {
+            crate::hash::arch::aarch64::poseidon_goldilocks_neon::poseidon(input)
+        }
+    }
 
     #[cfg(all(target_arch="aarch64", target_feature="neon"))]
     #[inline(always)]
@@ -272,7 +272,9 @@ impl Poseidon for GoldilocksField {
 mod tests {
     use crate::field::goldilocks_field::GoldilocksField as F;
     use crate::field::types::{Field, PrimeField64};
-    use crate::hash::poseidon::test_helpers::{check_consistency, check_test_vectors};
+    use crate::hash::poseidon::test_helpers::{
+        check_consistency, check_permute_many, check_test_vectors,
+    };
 
     #[test]
     fn test_vectors() {
@@ -316,4 +318,9 @@ mod tests {
     fn consistency() {
         check_consistency::<F>();
     }
+
+    #[test]
+    fn permute_many() {
+        check_permute_many::<F>();
+    }
 }
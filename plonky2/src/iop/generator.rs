@@ -1,8 +1,13 @@
+use alloc::format;
+use alloc::string::{String, ToString};
 use alloc::vec;
 use alloc::vec::Vec;
+use core::fmt;
 use core::fmt::Debug;
 use core::marker::PhantomData;
 
+use maybe_rayon::*;
+
 use crate::field::extension::Extendable;
 use crate::field::types::Field;
 use crate::hash::hash_types::RichField;
@@ -12,9 +17,13 @@ use crate::iop::wire::Wire;
 use crate::iop::witness::{PartialWitness, PartitionWitness, Witness, WitnessWrite};
 use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
 use crate::plonk::config::GenericConfig;
+use crate::plonk::error::{ProverError, ProverResult};
+use crate::plonk::registry::AsAny;
+use crate::util::serialization::{Buffer, IoResult, Read, Write};
 
 /// Given a `PartitionWitness` that has only inputs set, populates the rest of the witness using the
-/// given set of generators.
+/// given set of generators. Fails with a `WitnessGenerationError` naming the generators which could
+/// not finish, or the two writers which set a partition to different values.
 pub(crate) fn generate_partial_witness<
     'a,
     F: RichField + Extendable<D>,
@@ -24,10 +33,90 @@ pub(crate) fn generate_partial_witness<
     inputs: PartialWitness<F>,
     prover_data: &'a ProverOnlyCircuitData<F, C, D>,
     common_data: &'a CommonCircuitData<F, D>,
-) -> PartitionWitness<'a, F> {
+) -> ProverResult<PartitionWitness<'a, F>> {
+    generate_partial_witness_counted(inputs, prover_data, common_data).map(|(witness, _)| witness)
+}
+
+/// Like `generate_partial_witness`, also returning the number of times a generator was run.
+pub(crate) fn generate_partial_witness_counted<
+    'a,
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    inputs: PartialWitness<F>,
+    prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+    common_data: &'a CommonCircuitData<F, D>,
+) -> ProverResult<(PartitionWitness<'a, F>, usize)> {
+    let (witness, generator_is_expired, generator_runs) =
+        match run_generators(&inputs, prover_data, common_data, None) {
+            Ok(result) => result,
+            Err(Interruption::Conflict {
+                target,
+                rep_index,
+                old_value,
+                new_value,
+                writer,
+            }) => {
+                // The generators are deterministic, so running them again until the partition is
+                // first set tells us who set the old value.
+                let first_writer =
+                    match run_generators(&inputs, prover_data, common_data, Some(rep_index)) {
+                        Err(Interruption::FirstWrite(first_writer)) => {
+                            Some(first_writer.describe(prover_data))
+                        }
+                        _ => None,
+                    };
+                return Err(ProverError::Generator(WitnessGenerationError::Conflict {
+                    target,
+                    old_value: old_value.to_string(),
+                    new_value: new_value.to_string(),
+                    first_writer,
+                    second_writer: writer.describe(prover_data),
+                }));
+            }
+            Err(Interruption::Failed { generator, message }) => {
+                return Err(ProverError::Generator(WitnessGenerationError::Failed {
+                    generator: GeneratorInfo::new(generator, prover_data),
+                    message,
+                }));
+            }
+            Err(Interruption::FirstWrite(_)) => unreachable!("Not replaying a partition"),
+        };
+
+    let unfinished: Vec<_> = generator_is_expired
+        .iter()
+        .enumerate()
+        .filter(|&(_, &expired)| !expired)
+        .map(|(index, _)| UnfinishedGenerator {
+            generator: GeneratorInfo::new(index, prover_data),
+            unset_watches: prover_data.generators[index]
+                .watch_list()
+                .into_iter()
+                .filter(|&t| witness.try_get_target(t).is_none())
+                .collect(),
+        })
+        .collect();
+    if !unfinished.is_empty() {
+        return Err(ProverError::Generator(WitnessGenerationError::Unfinished(
+            unfinished,
+        )));
+    }
+
+    Ok((witness, generator_runs))
+}
+
+/// Runs the generators until they stop making progress, returning the witness, whether each
+/// generator finished, and the number of generator runs. If `replayed_rep` is set, conflicting
+/// writes are ignored and the run stops at the first write to that partition.
+fn run_generators<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    inputs: &PartialWitness<F>,
+    prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+    common_data: &'a CommonCircuitData<F, D>,
+    replayed_rep: Option<usize>,
+) -> Result<(PartitionWitness<'a, F>, Vec<bool>, usize), Interruption<F>> {
     let config = &common_data.config;
     let generators = &prover_data.generators;
-    let generator_indices_by_watches = &prover_data.generator_indices_by_watches;
 
     let mut witness = PartitionWitness::new(
         config.num_wires,
@@ -35,18 +124,46 @@ pub(crate) fn generate_partial_witness<
         &prover_data.representative_map,
     );
 
-    for (t, v) in inputs.target_values.into_iter() {
-        witness.set_target(t, v);
+    for (&t, &v) in inputs.target_values.iter() {
+        set_target(&mut witness, t, v, Writer::Input, replayed_rep)?;
     }
 
+    // We track a list of "expired" generators which have already returned false.
+    let mut generator_is_expired = vec![false; generators.len()];
+
+    let generator_runs = match prover_data.witness_scheduler {
+        WitnessScheduler::Sequential => run_sequentially(
+            &mut witness,
+            prover_data,
+            &mut generator_is_expired,
+            replayed_rep,
+        )?,
+        WitnessScheduler::Parallel => run_in_parallel(
+            &mut witness,
+            prover_data,
+            &mut generator_is_expired,
+            replayed_rep,
+        )?,
+    };
+
+    Ok((witness, generator_is_expired, generator_runs))
+}
+
+/// Runs each queued generator in turn, merging its values into the witness before running the
+/// next one. Returns the number of generator runs.
+fn run_sequentially<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    witness: &mut PartitionWitness<F>,
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    generator_is_expired: &mut [bool],
+    replayed_rep: Option<usize>,
+) -> Result<usize, Interruption<F>> {
+    let generators = &prover_data.generators;
+    let mut generator_runs = 0;
+
     // Build a list of "pending" generators which are queued to be run. Initially, all generators
     // are queued.
     let mut pending_generator_indices: Vec<_> = (0..generators.len()).collect();
 
-    // We also track a list of "expired" generators which have already returned false.
-    let mut generator_is_expired = vec![false; generators.len()];
-    let mut remaining_generators = generators.len();
-
     let mut buffer = GeneratedValues::empty();
 
     // Keep running generators until we fail to make progress.
@@ -58,28 +175,29 @@ pub(crate) fn generate_partial_witness<
                 continue;
             }
 
-            let finished = generators[generator_idx].run(&witness, &mut buffer);
+            let finished = generators[generator_idx].run(witness, &mut buffer);
+            generator_runs += 1;
+            if let Some(message) = buffer.failure.take() {
+                return Err(Interruption::Failed {
+                    generator: generator_idx,
+                    message,
+                });
+            }
             if finished {
                 generator_is_expired[generator_idx] = true;
-                remaining_generators -= 1;
             }
 
-            // Merge any generated values into our witness, and get a list of newly-populated
-            // targets' representatives.
-            let new_target_reps = buffer
-                .target_values
-                .drain(..)
-                .flat_map(|(t, v)| witness.set_target_returning_rep(t, v));
-
-            // Enqueue unfinished generators that were watching one of the newly populated targets.
-            for watch in new_target_reps {
-                let opt_watchers = generator_indices_by_watches.get(&watch);
-                if let Some(watchers) = opt_watchers {
-                    for &watching_generator_idx in watchers {
-                        if !generator_is_expired[watching_generator_idx] {
-                            next_pending_generator_indices.push(watching_generator_idx);
-                        }
-                    }
+            // Merge any generated values into our witness, and enqueue unfinished generators that
+            // were watching one of the newly populated targets.
+            for (t, v) in buffer.target_values.drain(..) {
+                let writer = Writer::Generator(generator_idx);
+                if let Some(rep) = set_target(witness, t, v, writer, replayed_rep)? {
+                    enqueue_watchers(
+                        prover_data,
+                        rep,
+                        generator_is_expired,
+                        &mut next_pending_generator_indices,
+                    );
                 }
             }
         }
@@ -87,17 +205,310 @@ pub(crate) fn generate_partial_witness<
         pending_generator_indices = next_pending_generator_indices;
     }
 
-    assert_eq!(
-        remaining_generators, 0,
-        "{} generators weren't run",
-        remaining_generators,
-    );
+    Ok(generator_runs)
+}
+
+/// Runs the queued generators in rounds. The generators of a round run in parallel on the witness
+/// left by the previous round, and their values are then merged in the order of the generators,
+/// so that the witness, and any conflict, does not depend on the scheduling of the threads. Returns
+/// the number of generator runs.
+fn run_in_parallel<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    witness: &mut PartitionWitness<F>,
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    generator_is_expired: &mut [bool],
+    replayed_rep: Option<usize>,
+) -> Result<usize, Interruption<F>> {
+    let generators = &prover_data.generators;
+    let mut pending_generator_indices: Vec<_> = (0..generators.len()).collect();
+    let mut generator_runs = 0;
+
+    while !pending_generator_indices.is_empty() {
+        generator_runs += pending_generator_indices.len();
+        let round_witness = &*witness;
+        let outputs: Vec<_> = pending_generator_indices
+            .par_iter()
+            .map(|&generator_idx| {
+                let mut buffer = GeneratedValues::empty();
+                let finished = generators[generator_idx].run(round_witness, &mut buffer);
+                (finished, buffer)
+            })
+            .collect();
+
+        let mut next_pending_generator_indices = Vec::new();
+        for (&generator_idx, (finished, buffer)) in pending_generator_indices.iter().zip(outputs) {
+            if let Some(message) = buffer.failure {
+                return Err(Interruption::Failed {
+                    generator: generator_idx,
+                    message,
+                });
+            }
+            if finished {
+                generator_is_expired[generator_idx] = true;
+            }
+            for (t, v) in buffer.target_values {
+                let writer = Writer::Generator(generator_idx);
+                if let Some(rep) = set_target(witness, t, v, writer, replayed_rep)? {
+                    enqueue_watchers(
+                        prover_data,
+                        rep,
+                        generator_is_expired,
+                        &mut next_pending_generator_indices,
+                    );
+                }
+            }
+        }
+
+        // A generator may watch several targets set in this round, and may have finished after
+        // being enqueued.
+        next_pending_generator_indices.sort_unstable();
+        next_pending_generator_indices.dedup();
+        next_pending_generator_indices
+            .retain(|&generator_idx| !generator_is_expired[generator_idx]);
+        pending_generator_indices = next_pending_generator_indices;
+    }
+
+    Ok(generator_runs)
+}
+
+/// Enqueues the unfinished generators watching the partition with representative `rep`.
+fn enqueue_watchers<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    rep: usize,
+    generator_is_expired: &[bool],
+    pending_generator_indices: &mut Vec<usize>,
+) {
+    if let Some(watchers) = prover_data.generator_indices_by_watches.get(&rep) {
+        for &watching_generator_idx in watchers {
+            if !generator_is_expired[watching_generator_idx] {
+                pending_generator_indices.push(watching_generator_idx);
+            }
+        }
+    }
+}
+
+/// How the generators are scheduled while generating a witness. Both schedulers give the same
+/// witness, as long as each generator only depends on the values of the targets it reads.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WitnessScheduler {
+    /// Runs one generator at a time, each seeing the values set by the previous ones.
+    Sequential,
+    /// Runs the generators whose inputs are ready in parallel, in deterministic rounds.
+    Parallel,
+}
+
+impl Default for WitnessScheduler {
+    fn default() -> Self {
+        Self::Sequential
+    }
+}
+
+/// Sets a target on behalf of `writer`, returning its representative index if the partition was
+/// not set before.
+fn set_target<F: Field>(
+    witness: &mut PartitionWitness<F>,
+    target: Target,
+    value: F,
+    writer: Writer,
+    replayed_rep: Option<usize>,
+) -> Result<Option<usize>, Interruption<F>> {
+    match witness.try_set_target_returning_rep(target, value) {
+        Ok(Some(rep_index)) if replayed_rep == Some(rep_index) => {
+            Err(Interruption::FirstWrite(writer))
+        }
+        Ok(opt_rep_index) => Ok(opt_rep_index),
+        Err(_) if replayed_rep.is_some() => Ok(None),
+        Err(old_value) => Err(Interruption::Conflict {
+            target,
+            rep_index: witness.representative_map[witness.target_index(target)],
+            old_value,
+            new_value: value,
+            writer,
+        }),
+    }
+}
+
+/// Why `run_generators` stopped before the generators stopped making progress.
+enum Interruption<F> {
+    /// A target was set to a value different from the one its partition holds.
+    Conflict {
+        target: Target,
+        rep_index: usize,
+        old_value: F,
+        new_value: F,
+        writer: Writer,
+    },
+    /// A generator could not compute its values from its inputs.
+    Failed { generator: usize, message: String },
+    /// The replayed partition was set.
+    FirstWrite(Writer),
+}
+
+/// Who set a target, as stored while running the generators.
+#[derive(Copy, Clone)]
+enum Writer {
+    Input,
+    Generator(usize),
+}
+
+impl Writer {
+    fn describe<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+    ) -> WitnessWriter {
+        match self {
+            Self::Input => WitnessWriter::Input,
+            Self::Generator(index) => {
+                WitnessWriter::Generator(GeneratorInfo::new(index, prover_data))
+            }
+        }
+    }
+}
+
+/// Why the witness could not be generated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum WitnessGenerationError {
+    /// Some generators never finished, usually because a target they watch was never set.
+    Unfinished(Vec<UnfinishedGenerator>),
+    /// Two targets of the same partition were set to different values.
+    Conflict {
+        target: Target,
+        old_value: String,
+        new_value: String,
+        /// The writer of `old_value`, or `None` if running the generators again did not set the
+        /// partition, which can only happen if some generator is not deterministic.
+        first_writer: Option<WitnessWriter>,
+        /// The writer of `new_value`, which set `target`.
+        second_writer: WitnessWriter,
+    },
+    /// The witness was generated but does not satisfy the constraints, so the vanishing
+    /// polynomial is not divisible by `Z_H`. `check_witness` tells which gates fail.
+    Unsatisfied,
+    /// A generator could not compute its values from its inputs, e.g. the input of a lookup is
+    /// not in its table.
+    Failed {
+        generator: GeneratorInfo,
+        message: String,
+    },
+}
+
+/// A generator which had not finished when the generators stopped making progress.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnfinishedGenerator {
+    pub generator: GeneratorInfo,
+    /// The targets of the generator's watch list which were never set.
+    pub unset_watches: Vec<Target>,
+}
+
+/// Who set a target of the witness.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum WitnessWriter {
+    /// The `PartialWitness` given to the prover.
+    Input,
+    Generator(GeneratorInfo),
+}
 
-    witness
+/// A generator of a circuit, described for debugging.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GeneratorInfo {
+    /// The index of the generator in `ProverOnlyCircuitData::generators`.
+    pub index: usize,
+    /// The `Debug` representation of the generator.
+    pub name: String,
+    /// The stack of `with_context` scopes in which the generator was added.
+    pub context: String,
+}
+
+impl GeneratorInfo {
+    fn new<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        index: usize,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+    ) -> Self {
+        let context = prover_data
+            .generator_contexts
+            .get(index)
+            .and_then(|&i| prover_data.contexts.get(i))
+            .cloned()
+            .unwrap_or_default();
+        Self {
+            index,
+            name: format!("{:?}", prover_data.generators[index]),
+            context,
+        }
+    }
+}
+
+/// The number of unfinished generators listed by the `Display` implementation.
+const MAX_DISPLAYED_GENERATORS: usize = 16;
+
+impl fmt::Display for WitnessGenerationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unfinished(generators) => {
+                write!(f, "{} generators weren't run", generators.len())?;
+                for unfinished in generators.iter().take(MAX_DISPLAYED_GENERATORS) {
+                    write!(
+                        f,
+                        "\n  {}, waiting on {:?}",
+                        unfinished.generator, unfinished.unset_watches
+                    )?;
+                }
+                if generators.len() > MAX_DISPLAYED_GENERATORS {
+                    write!(
+                        f,
+                        "\n  and {} more",
+                        generators.len() - MAX_DISPLAYED_GENERATORS
+                    )?;
+                }
+                Ok(())
+            }
+            Self::Conflict {
+                target,
+                old_value,
+                new_value,
+                first_writer,
+                second_writer,
+            } => {
+                write!(
+                    f,
+                    "Partition containing {:?} was set twice with different values: {} by ",
+                    target, old_value
+                )?;
+                match first_writer {
+                    Some(writer) => write!(f, "{}", writer)?,
+                    None => write!(f, "an unknown writer")?,
+                }
+                write!(f, " != {} by {}", new_value, second_writer)
+            }
+            Self::Unsatisfied => write!(
+                f,
+                "Quotient has failed, the vanishing polynomial is not divisible by Z_H"
+            ),
+            Self::Failed { generator, message } => write!(f, "{} failed: {}", generator, message),
+        }
+    }
+}
+
+impl fmt::Display for WitnessWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Input => write!(f, "the partial witness"),
+            Self::Generator(generator) => write!(f, "{}", generator),
+        }
+    }
+}
+
+impl fmt::Display for GeneratorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "generator {} in {}: {}",
+            self.index, self.context, self.name
+        )
+    }
 }
 
 /// A generator participates in the generation of the witness.
-pub trait WitnessGenerator<F: Field>: 'static + Send + Sync + Debug {
+pub trait WitnessGenerator<F: Field>: 'static + Send + Sync + Debug + AsAny {
     /// Targets to be "watched" by this generator. Whenever a target in the watch list is populated,
     /// the generator will be queued to run.
     fn watch_list(&self) -> Vec<Target>;
@@ -106,17 +517,30 @@ pub trait WitnessGenerator<F: Field>: 'static + Send + Sync + Debug {
     /// flag is true, the generator will never be run again, otherwise it will be queued for another
     /// run next time a target in its watch list is populated.
     fn run(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) -> bool;
+
+    /// Writes this generator, to be read back by `deserialize`. The type of the generator is
+    /// written by the `CircuitRegistry` it is registered in.
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()>;
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self>
+    where
+        Self: Sized;
 }
 
 /// Values generated by a generator invocation.
 #[derive(Debug)]
 pub struct GeneratedValues<F: Field> {
     pub(crate) target_values: Vec<(Target, F)>,
+    /// Set by `fail`, which stops the generation of the witness.
+    pub(crate) failure: Option<String>,
 }
 
 impl<F: Field> From<Vec<(Target, F)>> for GeneratedValues<F> {
     fn from(target_values: Vec<(Target, F)>) -> Self {
-        Self { target_values }
+        Self {
+            target_values,
+            failure: None,
+        }
     }
 }
 
@@ -135,6 +559,12 @@ impl<F: Field> GeneratedValues<F> {
         Vec::new().into()
     }
 
+    /// Reports that the generator cannot compute its values from its inputs. The generation of
+    /// the witness then fails with `WitnessGenerationError::Failed`.
+    pub fn fail(&mut self, message: String) {
+        self.failure = Some(message);
+    }
+
     pub fn singleton_wire(wire: Wire, value: F) -> Self {
         Self::singleton_target(Target::Wire(wire), value)
     }
@@ -162,6 +592,13 @@ pub trait SimpleGenerator<F: Field>: 'static + Send + Sync + Debug {
 
     fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>);
 
+    /// Writes this generator, to be read back by `deserialize`.
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()>;
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self>
+    where
+        Self: Sized;
+
     fn adapter(self) -> SimpleGeneratorAdapter<F, Self>
     where
         Self: Sized,
@@ -192,6 +629,14 @@ impl<F: Field, SG: SimpleGenerator<F>> WitnessGenerator<F> for SimpleGeneratorAd
             false
         }
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        self.inner.serialize(dst)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(SG::deserialize(src)?.adapter())
+    }
 }
 
 /// A generator which copies one wire to another.
@@ -210,6 +655,18 @@ impl<F: Field> SimpleGenerator<F> for CopyGenerator {
         let value = witness.get_target(self.src);
         out_buffer.set_target(self.dst, value);
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target(self.src)?;
+        dst.write_target(self.dst)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            src: src.read_target()?,
+            dst: src.read_target()?,
+        })
+    }
 }
 
 /// A generator for including a random value
@@ -228,6 +685,16 @@ impl<F: Field> SimpleGenerator<F> for RandomValueGenerator {
         let random_value = F::from_canonical_u64(0x8838327483783);
         out_buffer.set_target(self.target, random_value);
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target(self.target)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            target: src.read_target()?,
+        })
+    }
 }
 
 /// A generator for testing if a value equals zero
@@ -253,6 +720,18 @@ impl<F: Field> SimpleGenerator<F> for NonzeroTestGenerator {
 
         out_buffer.set_target(self.dummy, dummy_value);
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target(self.to_test)?;
+        dst.write_target(self.dummy)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            to_test: src.read_target()?,
+            dummy: src.read_target()?,
+        })
+    }
 }
 
 /// Generator used to fill an extra constant.
@@ -270,7 +749,7 @@ impl<F: Field> ConstantGenerator<F> {
     }
 }
 
-impl<F: Field> SimpleGenerator<F> for ConstantGenerator<F> {
+impl<F: RichField> SimpleGenerator<F> for ConstantGenerator<F> {
     fn dependencies(&self) -> Vec<Target> {
         vec![]
     }
@@ -278,4 +757,164 @@ impl<F: Field> SimpleGenerator<F> for ConstantGenerator<F> {
     fn run_once(&self, _witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
         out_buffer.set_target(Target::wire(self.row, self.wire_index), self.constant);
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        dst.write_usize(self.constant_index)?;
+        dst.write_usize(self.wire_index)?;
+        dst.write_field(self.constant)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            row: src.read_usize()?,
+            constant_index: src.read_usize()?,
+            wire_index: src.read_usize()?,
+            constant: src.read_field()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::hash::poseidon::PoseidonHash;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::with_context;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_unfinished_generators_are_named() {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = with_context!(builder, "square x", builder.square(x));
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let err = generate_partial_witness(PartialWitness::new(), &data.prover_only, &data.common)
+            .err()
+            .unwrap();
+        let unfinished = match err {
+            ProverError::Generator(WitnessGenerationError::Unfinished(unfinished)) => unfinished,
+            _ => panic!("Unexpected error: {}", err),
+        };
+        let stuck = unfinished
+            .iter()
+            .find(|u| u.generator.context == "root > square x")
+            .expect("The generator squaring x should be unfinished");
+        assert!(stuck.generator.name.contains("ArithmeticBaseGenerator"));
+        assert!(!stuck.unset_watches.is_empty());
+    }
+
+    #[test]
+    fn test_conflict_names_both_writers() {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let [x, y, z] = [(); 3].map(|_| builder.add_virtual_target());
+        with_context!(builder, "copy x", builder.generate_copy(x, y));
+        with_context!(builder, "copy z", builder.generate_copy(z, y));
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::ONE);
+        pw.set_target(z, F::TWO);
+        let err = generate_partial_witness(pw, &data.prover_only, &data.common)
+            .err()
+            .unwrap();
+        let (target, first, second) = match err {
+            ProverError::Generator(WitnessGenerationError::Conflict {
+                target,
+                first_writer: Some(WitnessWriter::Generator(first)),
+                second_writer: WitnessWriter::Generator(second),
+                ..
+            }) => (target, first, second),
+            _ => panic!("Unexpected error: {}", err),
+        };
+        assert_eq!(target, y);
+        assert_eq!(first.context, "root > copy x");
+        assert_eq!(second.context, "root > copy z");
+    }
+
+    #[test]
+    fn test_parallel_scheduler_matches_sequential() -> anyhow::Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let inputs = builder.add_virtual_targets(8);
+        let mut state = inputs.clone();
+        for i in 0..4 {
+            let hash = builder.hash_n_to_hash_no_pad::<PoseidonHash>(state.clone());
+            let product = builder.mul_many(&state[i..i + 3]);
+            let bits = builder.split_le(hash.elements[0], 64);
+            let sum = builder.add_many(bits.iter().map(|b| b.target));
+            state = hash.elements.to_vec();
+            state.extend([product, sum, inputs[i], inputs[i + 4]]);
+        }
+        builder.register_public_inputs(&state);
+        let mut data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        for (&t, x) in inputs.iter().zip(F::rand_vec(inputs.len())) {
+            pw.set_target(t, x);
+        }
+        let sequential = generate_partial_witness(pw.clone(), &data.prover_only, &data.common)?;
+        let sequential_values = sequential.values;
+        let sequential_proof = data.prove(pw.clone())?;
+
+        data.prover_only.witness_scheduler = WitnessScheduler::Parallel;
+        let parallel = generate_partial_witness(pw.clone(), &data.prover_only, &data.common)?;
+        assert_eq!(parallel.values, sequential_values);
+        assert_eq!(data.prove(pw)?, sequential_proof);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_scheduler_reports_same_conflict() {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let [x, y, z] = [(); 3].map(|_| builder.add_virtual_target());
+        builder.generate_copy(x, y);
+        builder.generate_copy(z, y);
+        let mut data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::ONE);
+        pw.set_target(z, F::TWO);
+        let sequential = generate_partial_witness(pw.clone(), &data.prover_only, &data.common)
+            .err()
+            .unwrap();
+        data.prover_only.witness_scheduler = WitnessScheduler::Parallel;
+        let parallel = generate_partial_witness(pw, &data.prover_only, &data.common)
+            .err()
+            .unwrap();
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn test_conflicting_input() {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        builder.generate_copy(x, y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::ONE);
+        pw.set_target(y, F::TWO);
+        let err = generate_partial_witness(pw, &data.prover_only, &data.common)
+            .err()
+            .unwrap();
+        let (first_writer, second_writer) = match err {
+            ProverError::Generator(WitnessGenerationError::Conflict {
+                first_writer,
+                second_writer,
+                ..
+            }) => (first_writer, second_writer),
+            _ => panic!("Unexpected error: {}", err),
+        };
+        assert_eq!(first_writer, Some(WitnessWriter::Input));
+        assert!(matches!(second_writer, WitnessWriter::Generator(_)));
+    }
 }
@@ -315,18 +315,32 @@ impl<'a, F: Field> PartitionWitness<'a, F> {
     /// Set a `Target`. On success, returns the representative index of the newly-set target. If the
     /// target was already set, returns `None`.
     pub(crate) fn set_target_returning_rep(&mut self, target: Target, value: F) -> Option<usize> {
+        self.try_set_target_returning_rep(target, value)
+            .unwrap_or_else(|old_value| {
+                panic!(
+                    "Partition containing {:?} was set twice with different values: {} != {}",
+                    target, old_value, value
+                )
+            })
+    }
+
+    /// Like `set_target_returning_rep`, but returns the value already held by the partition if it
+    /// differs from `value`.
+    pub(crate) fn try_set_target_returning_rep(
+        &mut self,
+        target: Target,
+        value: F,
+    ) -> Result<Option<usize>, F> {
         let rep_index = self.representative_map[self.target_index(target)];
         let rep_value = &mut self.values[rep_index];
         if let Some(old_value) = *rep_value {
-            assert_eq!(
-                value, old_value,
-                "Partition containing {:?} was set twice with different values: {} != {}",
-                target, old_value, value
-            );
-            None
+            if value != old_value {
+                return Err(old_value);
+            }
+            Ok(None)
         } else {
             *rep_value = Some(value);
-            Some(rep_index)
+            Ok(Some(rep_index))
         }
     }
 
@@ -0,0 +1,304 @@
+//! Proving the same circuit for many witnesses. Generating the witness of a proof only needs the
+//! CPU, so [`BatchProver`] runs the generators of the next proofs on a dedicated thread and rayon
+//! pool while the current proof is being committed to in the global pool.
+//!
+//! The commitments themselves are computed one proof at a time, on the calling thread: a backend
+//! has a single working buffer, which holds every commitment of the proof being opened.
+
+use alloc::vec::Vec;
+use std::sync::mpsc::sync_channel;
+use std::thread;
+
+use crate::backend::CpuBackend;
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::RichField;
+use crate::iop::witness::PartialWitness;
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::GenericConfig;
+use crate::plonk::error::{ProverError, ProverResult};
+use crate::plonk::proof::ProofWithPublicInputs;
+use crate::plonk::prover::{commit_and_open, generate_witness, ProvingStrategy};
+use crate::plonk::report::ProvingReport;
+use crate::util::timing::TimingTree;
+
+/// Proves a circuit for a sequence of witnesses, overlapping the witness generation of the next
+/// proofs with the commitments of the current one. Proofs are returned in the order of their
+/// witnesses.
+pub struct BatchProver<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+{
+    prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+    common_data: &'a CommonCircuitData<F, D>,
+    max_in_flight: usize,
+    witness_threads: usize,
+}
+
+impl<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    BatchProver<'a, F, C, D>
+{
+    pub fn new(
+        prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+        common_data: &'a CommonCircuitData<F, D>,
+    ) -> Self {
+        Self {
+            prover_data,
+            common_data,
+            max_in_flight: 2,
+            witness_threads: default_witness_threads(),
+        }
+    }
+
+    /// Maximum number of proofs whose witness is generated, or being generated, while their
+    /// commitments are not done. This bounds the memory held by pending witnesses. With 1, proofs
+    /// are computed one after the other. Defaults to 2.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        assert!(max_in_flight > 0, "at least one proof must be in flight");
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    /// Number of threads of the dedicated rayon pool running the generators. Defaults to a quarter
+    /// of the available parallelism, and at least one thread, leaving the global pool to the
+    /// commitments.
+    pub fn witness_threads(mut self, witness_threads: usize) -> Self {
+        assert!(witness_threads > 0, "the witness pool needs at least one thread");
+        self.witness_threads = witness_threads;
+        self
+    }
+
+    /// Proves every witness of `inputs` and returns the proofs in order.
+    pub fn prove_all<I>(
+        &self,
+        inputs: I,
+        strategy: ProvingStrategy<F, C, D>,
+    ) -> ProverResult<Vec<ProofWithPublicInputs<F, C, D>>>
+    where
+        I: IntoIterator<Item = PartialWitness<F>>,
+        I::IntoIter: Send,
+    {
+        let mut proofs = Vec::new();
+        self.prove_each(inputs, strategy, |_, proof| {
+            proofs.push(proof);
+            Ok(())
+        })?;
+        Ok(proofs)
+    }
+
+    /// Proves every witness of `inputs`, passing each proof and its index to `sink` in order, as
+    /// soon as it is done. Stops at the first error, of the prover or of `sink`.
+    ///
+    /// With `ProvingStrategy::CpuFlat`, a single `CpuBackend` is allocated for the whole batch.
+    pub fn prove_each<I, S>(
+        &self,
+        inputs: I,
+        mut strategy: ProvingStrategy<F, C, D>,
+        mut sink: S,
+    ) -> ProverResult<()>
+    where
+        I: IntoIterator<Item = PartialWitness<F>>,
+        I::IntoIter: Send,
+        S: FnMut(usize, ProofWithPublicInputs<F, C, D>) -> ProverResult<()>,
+    {
+        let (prover_data, common_data) = (self.prover_data, self.common_data);
+        let flattened = strategy.is_flattened();
+        let mut cpu_backend = match strategy {
+            ProvingStrategy::CpuFlat => Some(CpuBackend::for_circuit(prover_data, common_data)?),
+            _ => None,
+        };
+        let mut prove_next = |index, witness| {
+            let strategy = match &mut cpu_backend {
+                Some(backend) => ProvingStrategy::Accelerated(backend),
+                None => strategy.reborrow(),
+            };
+            let (proof, _) = commit_and_open(
+                prover_data,
+                common_data,
+                witness,
+                &mut TimingTree::default(),
+                strategy,
+                false,
+                &mut ProvingReport::default(),
+            )?;
+            sink(index, proof)
+        };
+
+        let pool = WitnessPool::new(self.witness_threads)?;
+        let generate = |inputs| {
+            generate_witness(
+                prover_data,
+                common_data,
+                inputs,
+                &mut TimingTree::default(),
+                flattened,
+            )
+        };
+
+        if self.max_in_flight == 1 {
+            for (index, inputs) in inputs.into_iter().enumerate() {
+                let witness = pool.install(|| generate(inputs))?;
+                prove_next(index, witness)?;
+            }
+            return Ok(());
+        }
+
+        // One witness is being committed to and one is being generated; the others wait in the
+        // channel.
+        let (sender, receiver) = sync_channel(self.max_in_flight - 2);
+        let inputs = inputs.into_iter();
+        let (pool, generate) = (&pool, &generate);
+        thread::scope(|scope| {
+            // Moving the sender into the generating thread closes the channel once it is done.
+            scope.spawn(move || {
+                pool.install(|| {
+                    for inputs in inputs {
+                        let witness = generate(inputs);
+                        let failed = witness.is_err();
+                        // The receiver is gone if a proof failed.
+                        if sender.send(witness).is_err() || failed {
+                            break;
+                        }
+                    }
+                })
+            });
+            // Dropping the receiver on an error, before the scope joins the generating thread,
+            // stops it at its next witness.
+            for (index, witness) in receiver.into_iter().enumerate() {
+                prove_next(index, witness?)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+fn default_witness_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| (n.get() / 4).max(1))
+}
+
+/// The rayon pool running the generators. Without the `parallel` feature, they run on the calling
+/// thread.
+struct WitnessPool {
+    #[cfg(feature = "parallel")]
+    pool: maybe_rayon::rayon::ThreadPool,
+}
+
+impl WitnessPool {
+    #[allow(unused_variables)]
+    fn new(num_threads: usize) -> ProverResult<Self> {
+        Ok(Self {
+            #[cfg(feature = "parallel")]
+            pool: maybe_rayon::rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .thread_name(|i| alloc::format!("witness-{}", i))
+                .build()
+                .map_err(|e| ProverError::Allocation {
+                    bytes: 0,
+                    message: alloc::format!("failed to build the witness generation pool: {}", e),
+                })?,
+        })
+    }
+
+    #[cfg(feature = "parallel")]
+    fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        self.pool.install(op)
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        op()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::target::Target;
+    use crate::iop::witness::WitnessWrite;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn cube_circuit() -> (CircuitData<F, C, D>, Target) {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let x2 = builder.square(x);
+        let x3 = builder.mul(x2, x);
+        builder.register_public_input(x3);
+        (builder.build::<C>(), x)
+    }
+
+    fn witnesses(x: Target, n: u64) -> Vec<PartialWitness<F>> {
+        (0..n)
+            .map(|i| {
+                let mut pw = PartialWitness::new();
+                pw.set_target(x, F::from_canonical_u64(i + 2));
+                pw
+            })
+            .collect()
+    }
+
+    fn check_batch(max_in_flight: usize, strategy: ProvingStrategy<F, C, D>) -> Result<()> {
+        let (data, x) = cube_circuit();
+        let proofs = data
+            .batch_prover()
+            .max_in_flight(max_in_flight)
+            .witness_threads(2)
+            .prove_all(witnesses(x, 5), strategy)?;
+
+        assert_eq!(proofs.len(), 5);
+        for (i, proof) in proofs.into_iter().enumerate() {
+            let x = F::from_canonical_u64(i as u64 + 2);
+            assert_eq!(proof.public_inputs, vec![x.cube()]);
+            data.verify(proof)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_in_order() -> Result<()> {
+        check_batch(3, ProvingStrategy::Cpu)
+    }
+
+    #[test]
+    fn test_batch_flat() -> Result<()> {
+        check_batch(2, ProvingStrategy::CpuFlat)
+    }
+
+    #[test]
+    fn test_batch_sequential() -> Result<()> {
+        check_batch(1, ProvingStrategy::Cpu)
+    }
+
+    #[test]
+    fn test_batch_matches_single_proofs() -> Result<()> {
+        let (data, x) = cube_circuit();
+        let proofs = BatchProver::new(&data.prover_only, &data.common)
+            .prove_all(witnesses(x, 3), ProvingStrategy::CpuFlat)?;
+        for (pw, proof) in witnesses(x, 3).into_iter().zip(proofs) {
+            assert_eq!(data.prove(pw)?, proof);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_stops_at_failed_witness() {
+        let (data, x) = cube_circuit();
+        let mut inputs = witnesses(x, 4);
+        inputs[2] = PartialWitness::new();
+
+        let mut proved = Vec::new();
+        let err = BatchProver::new(&data.prover_only, &data.common)
+            .prove_each(inputs, ProvingStrategy::Cpu, |index, _| {
+                proved.push(index);
+                Ok(())
+            })
+            .unwrap_err();
+        assert!(matches!(err, ProverError::Generator(_)), "{}", err);
+        assert_eq!(proved, vec![0, 1]);
+    }
+}
@@ -1,19 +1,15 @@
 use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::cmp::max;
-use std::fs::File;
-use std::io::{Read, Write};
-use std::process::exit;
-use std::sync::Arc;
 #[cfg(feature = "std")]
 use std::time::Instant;
 
 use hashbrown::{HashMap, HashSet};
 use itertools::Itertools;
-use log::{debug, info, Level};
-use maybe_rayon::MaybeParChunks;
+use log::{debug, info, warn, Level};
 
 use crate::field::cosets::get_unique_coset_shifts;
 use crate::field::extension::{Extendable, FieldExtension};
@@ -24,20 +20,24 @@ use crate::fri::oracle::PolynomialBatch;
 use crate::fri::{FriConfig, FriParams};
 use crate::gadgets::arithmetic::BaseArithmeticOperation;
 use crate::gadgets::arithmetic_extension::ExtensionArithmeticOperation;
+use crate::gadgets::lookup::LookupTable;
 use crate::gadgets::polynomial::PolynomialCoeffsExtTarget;
 use crate::gates::arithmetic_base::ArithmeticGate;
 use crate::gates::arithmetic_extension::ArithmeticExtensionGate;
 use crate::gates::constant::ConstantGate;
 use crate::gates::gate::{CurrentSlot, Gate, GateInstance, GateRef};
+use crate::gates::lookup::LookupGate;
+use crate::gates::lookup_table::LookupTableGate;
 use crate::gates::noop::NoopGate;
 use crate::gates::public_input::PublicInputGate;
 use crate::gates::selectors::selector_polynomials;
 use crate::hash::hash_types::{HashOut, HashOutTarget, MerkleCapTarget, RichField};
 use crate::hash::merkle_proofs::MerkleProofTarget;
-use crate::hash::merkle_tree::{MerkleCap, MerkleTree};
+use crate::hash::merkle_tree::MerkleCap;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::generator::{
     ConstantGenerator, CopyGenerator, RandomValueGenerator, SimpleGenerator, WitnessGenerator,
+    WitnessScheduler,
 };
 use crate::iop::target::{BoolTarget, Target};
 use crate::iop::wire::Wire;
@@ -49,14 +49,12 @@ use crate::plonk::config::{AlgebraicHasher, GenericConfig, GenericHashOut, Hashe
 use crate::plonk::copy_constraint::CopyConstraint;
 use crate::plonk::permutation_argument::Forest;
 use crate::plonk::plonk_common::PlonkOracle;
+use crate::plonk::preprocessed::{PreprocessedCache, PreprocessedKey};
 use crate::timed;
 use crate::util::context_tree::ContextTree;
 use crate::util::partial_products::num_partial_products;
 use crate::util::timing::TimingTree;
 use crate::util::{log2_ceil, log2_strict, transpose, transpose_poly_values};
-use maybe_rayon::ParallelIterator;
-use plonky2_field::polynomial::PolynomialCoeffs;
-use crate::hash::poseidon::PoseidonHash;
 
 pub struct CircuitBuilder<F: RichField + Extendable<D>, const D: usize> {
     pub config: CircuitConfig,
@@ -86,6 +84,22 @@ pub struct CircuitBuilder<F: RichField + Extendable<D>, const D: usize> {
     /// Generators used to generate the witness.
     generators: Vec<Box<dyn WitnessGenerator<F>>>,
 
+    /// The distinct stacks of open scopes in which gates or generators were added.
+    contexts: Vec<String>,
+
+    /// The index in `contexts` of each stack of open scopes.
+    context_indices: HashMap<String, usize>,
+
+    /// The index in `contexts` of the currently open scopes, if it was computed since the last
+    /// `push_context` or `pop_context`.
+    current_context: Option<usize>,
+
+    /// For each generator, the index in `contexts` of the scopes it was added in.
+    generator_contexts: Vec<usize>,
+
+    /// For each gate instance, the index in `contexts` of the scopes it was added in.
+    gate_contexts: Vec<usize>,
+
     constants_to_targets: HashMap<F, Target>,
     targets_to_constants: HashMap<Target, F>,
 
@@ -96,11 +110,21 @@ pub struct CircuitBuilder<F: RichField + Extendable<D>, const D: usize> {
     pub(crate) arithmetic_results: HashMap<ExtensionArithmeticOperation<F, D>, ExtensionTarget<D>>,
 
     /// Map between gate type and the current gate of this type with available slots.
-    current_slots: HashMap<GateRef<F, D>, CurrentSlot<F, D>>,
+    pub(crate) current_slots: HashMap<GateRef<F, D>, CurrentSlot<F, D>>,
 
     /// List of constant generators used to fill the constant wires.
     constant_generators: Vec<ConstantGenerator<F>>,
 
+    /// The tables added by `add_lookup_table`.
+    pub(crate) lookup_tables: Vec<LookupTable>,
+
+    /// For each lookup table, the rows of the `LookupGate`s looking it up.
+    pub(crate) lookup_rows: Vec<Vec<usize>>,
+
+    /// For each lookup table, the rows of the `LookupTableGate`s holding it. Set when the circuit
+    /// is built.
+    pub(crate) lookup_table_rows: Vec<Vec<usize>>,
+
     /// Optional common data. When it is `Some(goal_data)`, the `build` function panics if the resulting
     /// common data doesn't equal `goal_data`.
     /// This is used in cyclic recursion.
@@ -123,12 +147,20 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             copy_constraints: Vec::new(),
             context_log: ContextTree::new(),
             generators: Vec::new(),
+            contexts: Vec::new(),
+            context_indices: HashMap::new(),
+            current_context: None,
+            generator_contexts: Vec::new(),
+            gate_contexts: Vec::new(),
             constants_to_targets: HashMap::new(),
             targets_to_constants: HashMap::new(),
             base_arithmetic_results: HashMap::new(),
             arithmetic_results: HashMap::new(),
             current_slots: HashMap::new(),
             constant_generators: Vec::new(),
+            lookup_tables: Vec::new(),
+            lookup_rows: Vec::new(),
+            lookup_table_rows: Vec::new(),
             goal_common_data: None,
             verifier_data_public_input: None,
         };
@@ -309,6 +341,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             gate_ref,
             constants,
         });
+        let context = self.current_context();
+        self.gate_contexts.push(context);
 
         row
     }
@@ -371,10 +405,15 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     }
 
     pub fn add_generators(&mut self, generators: Vec<Box<dyn WitnessGenerator<F>>>) {
+        let context = self.current_context();
+        self.generator_contexts
+            .extend(core::iter::repeat(context).take(generators.len()));
         self.generators.extend(generators);
     }
 
     pub fn add_simple_generator<G: SimpleGenerator<F>>(&mut self, generator: G) {
+        let context = self.current_context();
+        self.generator_contexts.push(context);
         self.generators.push(Box::new(generator.adapter()));
     }
 
@@ -484,10 +523,32 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 
     pub fn push_context(&mut self, level: log::Level, ctx: &str) {
         self.context_log.push(ctx, level, self.num_gates());
+        self.current_context = None;
     }
 
     pub fn pop_context(&mut self) {
         self.context_log.pop(self.num_gates());
+        self.current_context = None;
+    }
+
+    /// The index in `contexts` of the currently open scopes, which is recorded for each gate and
+    /// generator to point witness generation failures back to where they were added.
+    fn current_context(&mut self) -> usize {
+        if let Some(index) = self.current_context {
+            return index;
+        }
+        let stack = self.context_log.open_stack();
+        let index = match self.context_indices.get(&stack) {
+            Some(&index) => index,
+            None => {
+                let index = self.contexts.len();
+                self.contexts.push(stack.clone());
+                self.context_indices.insert(stack, index);
+                index
+            }
+        };
+        self.current_context = Some(index);
+        index
     }
 
     /// Find an available slot, of the form `(row, op)` for gate `G` using parameters `params`
@@ -762,13 +823,33 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     }
 
     /// Builds a "full circuit", with both prover and verifier data.
-    pub fn build<C: GenericConfig<D, F = F>>(mut self) -> CircuitData<F, C, D> {
+    pub fn build<C: GenericConfig<D, F = F>>(self) -> CircuitData<F, C, D> {
+        self.build_inner(None)
+    }
+
+    /// Like `build`, but reads the commitment to the constants and sigmas polynomials and the FFT
+    /// root tables from the file of `cache` for the `PreprocessedKey` of the circuit, if there is
+    /// one. Otherwise, they are computed and written to the cache.
+    pub fn build_with_cache<C: GenericConfig<D, F = F>>(
+        self,
+        cache: &PreprocessedCache,
+    ) -> CircuitData<F, C, D> {
+        self.build_inner(Some(cache))
+    }
+
+    fn build_inner<C: GenericConfig<D, F = F>>(
+        mut self,
+        cache: Option<&PreprocessedCache>,
+    ) -> CircuitData<F, C, D> {
         let mut timing = TimingTree::new("preprocess", Level::Trace);
         #[cfg(feature = "std")]
         let start = Instant::now();
         let rate_bits = self.config.fri_config.rate_bits;
         let cap_height = self.config.fri_config.cap_height;
 
+        // Fill the last lookup gates and lay out the lookup tables, which may add constants.
+        self.add_lookup_table_gates();
+
         // Hash the public inputs, and route them to a `PublicInputGate` which will enforce that
         // those hash wires match the claimed public inputs.
         let num_public_inputs = self.public_inputs.len();
@@ -834,6 +915,12 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let (mut constant_vecs, selectors_info) =
             selector_polynomials(&gates, &self.gate_instances, quotient_degree_factor + 1);
         constant_vecs.extend(self.constant_polys());
+        // The preprocessed polynomials of the lookup argument come last, see
+        // `CommonCircuitData::lookup_constants_range`.
+        let has_lookups = self.has_lookups();
+        if has_lookups {
+            constant_vecs.extend(self.lookup_constant_polys());
+        }
         let num_constants = constant_vecs.len();
 
         let subgroup = F::two_adic_subgroup(degree_bits);
@@ -845,344 +932,78 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             self.sigma_vecs(&k_is, &subgroup, &mut timing)
         );
 
-        // Precompute FFT roots.
-        let max_fft_points = 1 << (degree_bits + max(rate_bits, log2_ceil(quotient_degree_factor)));
-        let fft_root_table_max = fft_root_table(max_fft_points);
-        let fft_root_table_deg    = fft_root_table(1 << degree_bits).concat();
-
-        let constants_sigmas_vecs = [constant_vecs, sigma_vecs.clone()].concat();
-        let constants_sigmas_commitment = timed!(
-            timing,
-            "compute constants_sigmas_commitment",
-            PolynomialBatch::from_values(
-                constants_sigmas_vecs,
-                rate_bits,
-                PlonkOracle::CONSTANTS_SIGMAS.blinding,
-                cap_height,
-                &mut timing,
-                Some(&fft_root_table_max),
-            ));
-
-        // Map between gates where not all generators are used and the gate's number of used generators.
-        let incomplete_gates = self
-            .current_slots
-            .values()
-            .flat_map(|current_slot| current_slot.current_slot.values().copied())
-            .collect::<HashMap<_, _>>();
-
-        // Add gate generators.
-        self.add_generators(
-            self.gate_instances
-                .iter()
-                .enumerate()
-                .flat_map(|(index, gate)| {
-                    let mut gens = gate.gate_ref.0.generators(index, &gate.constants);
-                    // Remove unused generators, if any.
-                    if let Some(&op) = incomplete_gates.get(&index) {
-                        gens.drain(op..);
-                    }
-                    gens
-                })
-                .collect(),
-        );
-
-        // Index generator indices by their watched targets.
-        let mut generator_indices_by_watches = BTreeMap::new();
-        for (i, generator) in self.generators.iter().enumerate() {
-            for watch in generator.watch_list() {
-                let watch_index = forest.target_index(watch);
-                let watch_rep_index = forest.parents[watch_index];
-                generator_indices_by_watches
-                    .entry(watch_rep_index)
-                    .or_insert_with(Vec::new)
-                    .push(i);
-            }
-        }
-        for indices in generator_indices_by_watches.values_mut() {
-            indices.dedup();
-            indices.shrink_to_fit();
-        }
-
-        let num_gate_constraints = gates
-            .iter()
-            .map(|gate| gate.0.num_constraints())
-            .max()
-            .expect("No gates?");
-
-        let num_partial_products =
-            num_partial_products(self.config.num_routed_wires, quotient_degree_factor);
-
-        let constants_sigmas_cap = constants_sigmas_commitment.merkle_tree.cap.clone();
-        let domain_separator = self.domain_separator.unwrap_or_default();
-        let domain_separator_digest = C::Hasher::hash_pad(&domain_separator);
-        // TODO: This should also include an encoding of gate constraints.
-        let circuit_digest_parts = [
-            constants_sigmas_cap.flatten(),
-            domain_separator_digest.to_vec(),
-            vec![
-                F::from_canonical_usize(degree_bits),
-                /* Add other circuit data here */
-            ],
-        ];
-        let circuit_digest = C::Hasher::hash_no_pad(&circuit_digest_parts.concat());
-
-        let common = CommonCircuitData {
-            config: self.config,
-            fri_params,
-            gates,
-            selectors_info,
-            quotient_degree_factor,
-            num_gate_constraints,
-            num_constants,
-            num_public_inputs,
-            k_is,
-            num_partial_products,
-        };
-        if let Some(goal_data) = self.goal_common_data {
-            assert_eq!(goal_data, common, "The expected circuit data passed to cyclic recursion method did not match the actual circuit");
-        }
-
-        let prover_only = ProverOnlyCircuitData {
-            generators: self.generators,
-            generator_indices_by_watches,
-            constants_sigmas_commitment,
-            sigmas: transpose_poly_values(sigma_vecs),
-            subgroup,
-            public_inputs: self.public_inputs,
-            representative_map: forest.parents,
-            fft_root_table: Some(fft_root_table_max),
-            fft_root_table_deg,
-            circuit_digest,
-        };
-
-        let verifier_only = VerifierOnlyCircuitData {
-            constants_sigmas_cap,
-            circuit_digest,
-        };
-
-        timing.print();
-        #[cfg(feature = "std")]
-        debug!("Building circuit took {}s", start.elapsed().as_secs_f32());
-        CircuitData {
-            prover_only,
-            verifier_only,
-            common,
-        }
-    }
-
-    pub fn my_build<C: GenericConfig<D, F = F>>(mut self) -> CircuitData<F, C, D> {
-        let mut timing = TimingTree::new("preprocess", Level::Trace);
-        #[cfg(feature = "std")]
-            let start = Instant::now();
-        let rate_bits = self.config.fri_config.rate_bits;
-        let cap_height = self.config.fri_config.cap_height;
-
-        // Hash the public inputs, and route them to a `PublicInputGate` which will enforce that
-        // those hash wires match the claimed public inputs.
-        let num_public_inputs = self.public_inputs.len();
-        let public_inputs_hash =
-            self.public_inputs_hash::<C::InnerHasher>(self.public_inputs.clone());
-        let pi_gate = self.add_gate(PublicInputGate, vec![]);
-        for (&hash_part, wire) in public_inputs_hash
-            .elements
-            .iter()
-            .zip(PublicInputGate::wires_public_inputs_hash())
-        {
-            self.connect(hash_part, Target::wire(pi_gate, wire))
-        }
-        self.randomize_unused_pi_wires(pi_gate);
-
-        // Make sure we have enough constant generators. If not, add a `ConstantGate`.
-        while self.constants_to_targets.len() > self.constant_generators.len() {
-            self.add_gate(
-                ConstantGate {
-                    num_consts: self.config.num_constants,
-                },
-                vec![],
+        let sigmas = transpose_poly_values(sigma_vecs.clone());
+        let cache = cache.map(|cache| {
+            let key = timed!(
+                timing,
+                "hash preprocessed data key",
+                PreprocessedKey::new::<F, C, D>(
+                    &self.config,
+                    degree_bits,
+                    &constant_vecs,
+                    &sigma_vecs
+                )
             );
-        }
-
-        // For each constant-target pair used in the circuit, use a constant generator to fill this target.
-        for ((c, t), mut const_gen) in self
-            .constants_to_targets
-            .clone()
-            .into_iter()
-            // We need to enumerate constants_to_targets in some deterministic order to ensure that
-            // building a circuit is deterministic.
-            .sorted_by_key(|(c, _t)| c.to_canonical_u64())
-            .zip(self.constant_generators.clone())
-        {
-            // Set the constant in the constant polynomial.
-            self.gate_instances[const_gen.row].constants[const_gen.constant_index] = c;
-            // Generate a copy between the target and the routable wire.
-            self.connect(Target::wire(const_gen.row, const_gen.wire_index), t);
-            // Set the constant in the generator (it's initially set with a dummy value).
-            const_gen.set_constant(c);
-            self.add_simple_generator(const_gen);
-        }
-
-        info!(
-            "Degree before blinding & padding: {}",
-            self.gate_instances.len()
-        );
-        self.blind_and_pad();
-        let degree = self.gate_instances.len();
-        info!("Degree after blinding & padding: {}", degree);
-        let degree_bits = log2_strict(degree);
-        let fri_params = self.fri_params(degree_bits);
-        assert!(
-            fri_params.total_arities() <= degree_bits + rate_bits - cap_height,
-            "FRI total reduction arity is too large.",
-        );
-
-        let quotient_degree_factor = self.config.max_quotient_degree_factor;
-        let mut gates = self.gates.iter().cloned().collect::<Vec<_>>();
-        // Gates need to be sorted by their degrees (and ID to make the ordering deterministic) to compute the selector polynomials.
-        gates.sort_unstable_by_key(|g| (g.0.degree(), g.0.id()));
-        let (mut constant_vecs, selectors_info) =
-            selector_polynomials(&gates, &self.gate_instances, quotient_degree_factor + 1);
-        // constant_vecs.extend(self.constant_polys());
-        // let num_constants = constant_vecs.len();
-        // println!("num_constants: {}", num_constants);
-
-        let num_constants = 8;
-
-        let subgroup = F::two_adic_subgroup(degree_bits);
-
-        let k_is = get_unique_coset_shifts(degree, self.config.num_routed_wires);
-        // let (sigma_vecs, forest) = timed!(
-        //     timing,
-        //     "generate sigma polynomials",
-        //     self.sigma_vecs(&k_is, &subgroup, &mut timing)
-        // );
-
-        // Precompute FFT roots.
-        let max_fft_points = 1 << (degree_bits + max(rate_bits, log2_ceil(quotient_degree_factor)));
-        let fft_root_table_max = fft_root_table(max_fft_points);
-        let fft_root_table_deg    = fft_root_table(1 << degree_bits).concat();
-
-        // let constants_sigmas_vecs = [constant_vecs, sigma_vecs.clone()].concat();
-        // let constants_sigmas_commitment = timed!(
-        //     timing,
-        //     "compute constants_sigmas_commitment",
-        //     PolynomialBatch::from_values(
-        //         constants_sigmas_vecs,
-        //         rate_bits,
-        //         PlonkOracle::CONSTANTS_SIGMAS.blinding,
-        //         cap_height,
-        //         &mut timing,
-        //         Some(&fft_root_table_max),
-        //     ));
-
-        // if false
-        // {
-        //     unsafe {
-        //         let sigma_vec_vallen = sigma_vecs[0].values.len();
-        //         let coeffs_vallen = constants_sigmas_commitment.polynomials[0].coeffs.len();
-        //         let leaves_vallen = constants_sigmas_commitment.merkle_tree.leaves[0].len();
-        //         let digests_len = constants_sigmas_commitment.merkle_tree.digests.len();
-        //         let caps_len = constants_sigmas_commitment.merkle_tree.cap.0.len();
-        //
-        //         println!("sigma_vec_vallen: {}, coeffs_vallen: {}, leaves_vallen: {}, digests_len: {}, caps_len: {}",
-        //                  sigma_vec_vallen, coeffs_vallen, leaves_vallen, digests_len, caps_len);
-        //
-        //         println!("num_wires: {}, num_routed_wires: {}, degree: {}", forest.num_wires, forest.num_routed_wires, forest.degree);
-        //         let mut file = File::create("forest.bin").unwrap();
-        //         file.write_all(std::slice::from_raw_parts(forest.parents.as_ptr() as *const u8, forest.parents.len()*8));
-        //
-        //         let mut file = File::create("sigma_vecs.bin").unwrap();
-        //         let v = sigma_vecs.iter().flat_map(|v| v.values.clone()).collect::<Vec<_>>();
-        //         file.write_all(std::slice::from_raw_parts(v.as_ptr() as *const u8, v.len()*8));
-        //
-        //         exit(0);
-        //
-        //         let mut file = File::create("constants_sigmas_commitment.polynomials.bin").unwrap();
-        //         for value in constants_sigmas_commitment.polynomials.iter().flat_map(|v| v.coeffs.clone()) {
-        //             file.write_all(std::mem::transmute::<&F, &[u8; 8]>(&value)).unwrap();
-        //         }
-        //         let mut file = File::create("constants_sigmas_commitment.leaves.bin").unwrap();
-        //         for value in constants_sigmas_commitment.merkle_tree.leaves.concat() {
-        //             file.write_all(std::mem::transmute::<&F, &[u8; 8]>(&value)).unwrap();
-        //         }
-        //         let mut file = File::create("constants_sigmas_commitment.digests.bin").unwrap();
-        //         for value in constants_sigmas_commitment.merkle_tree.digests {
-        //             file.write_all(std::mem::transmute::<&_, &[u8; 32]>(&value)).unwrap();
-        //         }
-        //         let mut file = File::create("constants_sigmas_commitment.caps.bin").unwrap();
-        //         for value in constants_sigmas_commitment.merkle_tree.cap.0 {
-        //             file.write_all(std::mem::transmute::<&_, &[u8; 32]>(&value)).unwrap();
-        //         }
-        //         exit(0);
-        //     }
-        // }
-
-        let (sigma_vecs, constants_sigmas_commitment, forest) = unsafe  {
-            let sigma_vec_vallen = 262144;
-            let coeffs_vallen = 262144;
-            let leaves_vallen = 88;
-            let digests_len = 4194272;
-            let caps_len = 16;
-
-            // let sigma_vecs: Vec<PolynomialValues<F>> =
-            let sigmas = std::fs::read("sigma_vecs.bin").unwrap();
-            let sigmas_slice = std::slice::from_raw_parts(sigmas.as_ptr() as *const F, sigmas.len()/8);
-            let sigma_vecs = sigmas_slice.par_chunks_exact(sigma_vec_vallen)
-                .map(|chunk| PolynomialValues{ values: chunk.to_vec() })
-                .collect::<Vec<_>>();
-
-            let polynomials = std::fs::read("constants_sigmas_commitment.polynomials.bin").unwrap();
-            let polynomials_slice = std::slice::from_raw_parts(polynomials.as_ptr() as *const F, polynomials.len()/8);
-            let polynomials_vec = polynomials_slice.par_chunks_exact(coeffs_vallen)
-                .map(|chunk| PolynomialCoeffs{ coeffs: chunk.to_vec()})
-                .collect::<Vec<_>>();
-
-            let leaves = std::fs::read("constants_sigmas_commitment.leaves.bin").unwrap();
-            let leaves_slice = std::slice::from_raw_parts(leaves.as_ptr() as *const F, leaves.len()/8);
-            let leaves_vec = leaves_slice.par_chunks_exact(leaves_vallen)
-                .map(|chunk| { chunk.to_vec() })
-                .collect::<Vec<_>>();
-
-            let digests = std::fs::read("constants_sigmas_commitment.digests.bin").unwrap();
-            let digests_slice = std::slice::from_raw_parts(digests.as_ptr() as *const <<C as GenericConfig<D>>::Hasher as Hasher<F>>::Hash, digests.len()/32);
-            let digests_vec = digests_slice.to_vec();
-            // let digests_vec = std::mem::transmute::<Vec<_>, Vec<GenericHashOut<F>>>(digests_vec);
-            assert!(digests_vec.len() == digests_len);
-
-            let caps = std::fs::read("constants_sigmas_commitment.caps.bin").unwrap();
-            let caps_slice = std::slice::from_raw_parts(caps.as_ptr() as *const <<C as GenericConfig<D>>::Hasher as Hasher<F>>::Hash, caps.len()/32);
-            let caps_vec = caps_slice.to_vec();
-            assert!(caps_vec.len() == caps_len);
-
-            let forest = std::fs::read("forest.bin").unwrap();
-            let forest_slice = std::slice::from_raw_parts(forest.as_ptr() as *const usize, forest.len()/8);
-            let forest_vec = forest_slice.to_vec();
-            let config = &self.config;
-            let mut forest = Forest::new(
-                config.num_wires,
-                config.num_routed_wires,
-                degree,
-                self.virtual_target_index,
+            (cache, key)
+        });
+        let cached = cache.and_then(|(cache, key)| {
+            let cached = timed!(
+                timing,
+                "load preprocessed data",
+                cache.load::<F, C, D>(&self.config, degree_bits, &key)
             );
-            forest.parents = forest_vec;
-
-            let constants_sigmas_commitment = PolynomialBatch {
-                polynomials: polynomials_vec,
-                merkle_tree: MerkleTree {
-                    leaves: leaves_vec,
-                    digests: digests_vec,
-                    cap: MerkleCap(caps_vec),
-                    my_leaf_len: 0,
-                    my_leaves: Arc::new(vec![]),
-                    my_leaves_len: 0,
-                    my_leaves_dev_offset: -1,
-                    my_digests: Arc::new(vec![]),
-                },
-                degree_log: degree_bits,
-                rate_bits,
-                blinding: PlonkOracle::CONSTANTS_SIGMAS.blinding,
-                my_polynomials: vec![],
-            };
-            (sigma_vecs, constants_sigmas_commitment, forest)
+            match cached {
+                Ok(cached)
+                    if cached.sigmas == sigmas
+                        && cached.constants_sigmas_commitment.polynomials.len()
+                            == num_constants + sigma_vecs.len() =>
+                {
+                    Some(cached)
+                }
+                Ok(_) => {
+                    warn!("Ignoring preprocessed data of another circuit");
+                    None
+                }
+                Err(e) => {
+                    info!("No usable preprocessed data: {}", e);
+                    None
+                }
+            }
+        });
+        let loaded = cached.is_some();
+
+        let (constants_sigmas_commitment, fft_root_table_max, fft_root_table_deg) = match cached {
+            Some(cached) => (
+                cached.constants_sigmas_commitment,
+                cached.fft_root_table,
+                cached.fft_root_table_deg,
+            ),
+            None => {
+                // Precompute FFT roots.
+                let max_fft_points =
+                    1 << (degree_bits + max(rate_bits, log2_ceil(quotient_degree_factor)));
+                let fft_root_table_max = fft_root_table(max_fft_points);
+                let fft_root_table_deg = fft_root_table(1 << degree_bits).concat();
+
+                let constants_sigmas_vecs = [constant_vecs, sigma_vecs].concat();
+                let constants_sigmas_commitment = timed!(
+                    timing,
+                    "compute constants_sigmas_commitment",
+                    PolynomialBatch::from_values(
+                        constants_sigmas_vecs,
+                        rate_bits,
+                        PlonkOracle::CONSTANTS_SIGMAS.blinding,
+                        cap_height,
+                        &mut timing,
+                        Some(&fft_root_table_max),
+                    )
+                );
+                (
+                    constants_sigmas_commitment,
+                    Some(fft_root_table_max),
+                    fft_root_table_deg,
+                )
+            }
         };
 
         // Map between gates where not all generators are used and the gate's number of used generators.
@@ -1192,21 +1013,17 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             .flat_map(|current_slot| current_slot.current_slot.values().copied())
             .collect::<HashMap<_, _>>();
 
-        // Add gate generators.
-        self.add_generators(
-            self.gate_instances
-                .iter()
-                .enumerate()
-                .flat_map(|(index, gate)| {
-                    let mut gens = gate.gate_ref.0.generators(index, &gate.constants);
-                    // Remove unused generators, if any.
-                    if let Some(&op) = incomplete_gates.get(&index) {
-                        gens.drain(op..);
-                    }
-                    gens
-                })
-                .collect(),
-        );
+        // Add gate generators, each in the scopes its gate was added in.
+        for (index, gate) in self.gate_instances.iter().enumerate() {
+            let mut gens = gate.gate_ref.0.generators(index, &gate.constants);
+            // Remove unused generators, if any.
+            if let Some(&op) = incomplete_gates.get(&index) {
+                gens.drain(op..);
+            }
+            self.generator_contexts
+                .extend(core::iter::repeat(self.gate_contexts[index]).take(gens.len()));
+            self.generators.extend(gens);
+        }
 
         // Index generator indices by their watched targets.
         let mut generator_indices_by_watches = BTreeMap::new();
@@ -1233,6 +1050,14 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 
         let num_partial_products =
             num_partial_products(self.config.num_routed_wires, quotient_degree_factor);
+        let (num_lookup_slots, num_lookup_table_slots) = if has_lookups {
+            (
+                LookupGate::num_slots(&self.config),
+                LookupTableGate::num_slots(&self.config),
+            )
+        } else {
+            (0, 0)
+        };
 
         let constants_sigmas_cap = constants_sigmas_commitment.merkle_tree.cap.clone();
         let domain_separator = self.domain_separator.unwrap_or_default();
@@ -1247,6 +1072,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             ],
         ];
         let circuit_digest = C::Hasher::hash_no_pad(&circuit_digest_parts.concat());
+
         let common = CommonCircuitData {
             config: self.config,
             fri_params,
@@ -1258,6 +1084,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             num_public_inputs,
             k_is,
             num_partial_products,
+            num_lookup_slots,
+            num_lookup_table_slots,
         };
         if let Some(goal_data) = self.goal_common_data {
             assert_eq!(goal_data, common, "The expected circuit data passed to cyclic recursion method did not match the actual circuit");
@@ -1266,15 +1094,25 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let prover_only = ProverOnlyCircuitData {
             generators: self.generators,
             generator_indices_by_watches,
+            contexts: self.contexts,
+            generator_contexts: self.generator_contexts,
+            gate_contexts: self.gate_contexts,
+            witness_scheduler: WitnessScheduler::default(),
             constants_sigmas_commitment,
-            sigmas: transpose_poly_values(sigma_vecs),
+            sigmas,
             subgroup,
             public_inputs: self.public_inputs,
             representative_map: forest.parents,
-            fft_root_table: Some(fft_root_table_max),
+            fft_root_table: fft_root_table_max,
             fft_root_table_deg,
             circuit_digest,
         };
+        if let (Some((cache, key)), false) = (cache, loaded) {
+            match cache.store(&key, &prover_only, &common) {
+                Ok(path) => info!("Preprocessed data written to {}", path.display()),
+                Err(e) => warn!("Failed to write preprocessed data: {}", e),
+            }
+        }
 
         let verifier_only = VerifierOnlyCircuitData {
             constants_sigmas_cap,
@@ -1,10 +1,12 @@
 use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::ops::{Range, RangeFrom};
 
-use anyhow::Result;
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
 
 use crate::field::extension::Extendable;
 use crate::field::fft::FftRootTable;
@@ -21,18 +23,26 @@ use crate::gates::selectors::SelectorsInfo;
 use crate::hash::hash_types::{HashOutTarget, MerkleCapTarget, RichField};
 use crate::hash::merkle_tree::MerkleCap;
 use crate::iop::ext_target::ExtensionTarget;
-use crate::iop::generator::WitnessGenerator;
+use crate::iop::generator::{WitnessGenerator, WitnessScheduler};
 use crate::iop::target::Target;
 use crate::iop::witness::PartialWitness;
+use crate::plonk::batch::BatchProver;
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::constraint_check::{check_witness, check_witness_snapshot, WitnessCheck};
+use crate::plonk::error::ProverResult;
 use crate::plonk::plonk_common::PlonkOracle;
 use crate::plonk::proof::{CompressedProofWithPublicInputs, ProofWithPublicInputs};
-use crate::plonk::prover::prove;
+use crate::plonk::prover::{prove, prove_from_witness, prove_with_report, ProvingStrategy};
+use crate::plonk::registry::CircuitRegistry;
+use crate::plonk::report::ProvingReport;
 use crate::plonk::verifier::verify;
+use crate::plonk::witness_snapshot::WitnessSnapshot;
+use crate::util::ceil_div_usize;
+use crate::util::serialization::{Buffer, Read, Remaining, Write};
 use crate::util::timing::TimingTree;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct CircuitConfig {
     pub num_wires: usize,
     pub num_routed_wires: usize,
@@ -116,14 +126,78 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
     CircuitData<F, C, D>
 {
     pub fn prove(&self, inputs: PartialWitness<F>) -> Result<ProofWithPublicInputs<F, C, D>> {
+        Ok(self.prove_with_strategy(inputs, ProvingStrategy::Cpu)?)
+    }
+
+    /// Proves with the given strategy. On a `ProverError` for which `can_retry_on_cpu` holds,
+    /// proving again with `ProvingStrategy::Cpu` may succeed.
+    pub fn prove_with_strategy(
+        &self,
+        inputs: PartialWitness<F>,
+        strategy: ProvingStrategy<F, C, D>,
+    ) -> ProverResult<ProofWithPublicInputs<F, C, D>> {
         prove(
             &self.prover_only,
             &self.common,
             inputs,
             &mut TimingTree::default(),
+            strategy,
+        )
+    }
+
+    /// Proves with the given strategy, and reports the time and memory taken by each stage.
+    pub fn prove_with_report(
+        &self,
+        inputs: PartialWitness<F>,
+        strategy: ProvingStrategy<F, C, D>,
+    ) -> ProverResult<(ProofWithPublicInputs<F, C, D>, ProvingReport)> {
+        prove_with_report(&self.prover_only, &self.common, inputs, strategy)
+    }
+
+    /// A prover for many witnesses of this circuit.
+    pub fn batch_prover(&self) -> BatchProver<F, C, D> {
+        BatchProver::new(&self.prover_only, &self.common)
+    }
+
+    /// Generates the witness for `inputs` and reports the gate constraints, copy constraints and
+    /// lookups it does not satisfy, instead of proving.
+    pub fn check_witness(&self, inputs: PartialWitness<F>) -> ProverResult<WitnessCheck<F>> {
+        check_witness(inputs, &self.prover_only, &self.common)
+    }
+
+    /// Runs the generators on `inputs`, and returns the witness to be proved later with
+    /// `prove_from_witness`.
+    pub fn generate_witness_snapshot(
+        &self,
+        inputs: PartialWitness<F>,
+    ) -> ProverResult<WitnessSnapshot<F, C, D>> {
+        WitnessSnapshot::generate(inputs, &self.prover_only, &self.common)
+    }
+
+    /// Proves a witness generated by `generate_witness_snapshot`, without running the generators.
+    pub fn prove_from_witness(
+        &self,
+        snapshot: WitnessSnapshot<F, C, D>,
+        strategy: ProvingStrategy<F, C, D>,
+    ) -> ProverResult<ProofWithPublicInputs<F, C, D>> {
+        prove_from_witness(
+            &self.prover_only,
+            &self.common,
+            snapshot,
+            &mut TimingTree::default(),
+            strategy,
         )
     }
 
+    /// Reports the constraints the witness of `snapshot` does not satisfy, as `check_witness`
+    /// does, without running the generators.
+    pub fn check_witness_snapshot(
+        &self,
+        snapshot: &WitnessSnapshot<F, C, D>,
+    ) -> ProverResult<WitnessCheck<F>> {
+        check_witness_snapshot(snapshot, &self.prover_only, &self.common)
+    }
+
     pub fn verify(&self, proof_with_pis: ProofWithPublicInputs<F, C, D>) -> Result<()> {
         verify(proof_with_pis, &self.verifier_only, &self.common)
     }
@@ -172,6 +246,39 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             common,
         }
     }
+
+    /// Serializes this circuit. Every gate and generator of the circuit must be registered in
+    /// `registry`.
+    pub fn to_bytes(&self, registry: &CircuitRegistry<F, D>) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        registry.write_common_data(&mut buffer, &self.common)?;
+        buffer
+            .write_verifier_only_circuit_data(&self.verifier_only)
+            .map_err(anyhow::Error::msg)?;
+        registry.write_prover_data(&mut buffer, &self.prover_only)?;
+        Ok(buffer)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, registry: &CircuitRegistry<F, D>) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let common = registry.read_common_data(&mut buffer)?;
+        let verifier_only = buffer
+            .read_verifier_only_circuit_data(common.config.fri_config.cap_height)
+            .map_err(anyhow::Error::msg)?;
+        let prover_only = registry.read_prover_data(&mut buffer, &common)?;
+        ensure!(buffer.is_empty(), "Trailing bytes after the circuit data");
+        ensure!(
+            prover_only.circuit_digest == verifier_only.circuit_digest
+                && prover_only.constants_sigmas_commitment.merkle_tree.cap
+                    == verifier_only.constants_sigmas_cap,
+            "The prover and verifier data are of different circuits"
+        );
+        Ok(Self {
+            prover_only,
+            verifier_only,
+            common,
+        })
+    }
 }
 
 /// Circuit data required by the prover. This may be thought of as a proving key, although it
@@ -194,13 +301,97 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
     ProverCircuitData<F, C, D>
 {
     pub fn prove(&self, inputs: PartialWitness<F>) -> Result<ProofWithPublicInputs<F, C, D>> {
+        Ok(self.prove_with_strategy(inputs, ProvingStrategy::Cpu)?)
+    }
+
+    /// Proves with the given strategy. On a `ProverError` for which `can_retry_on_cpu` holds,
+    /// proving again with `ProvingStrategy::Cpu` may succeed.
+    pub fn prove_with_strategy(
+        &self,
+        inputs: PartialWitness<F>,
+        strategy: ProvingStrategy<F, C, D>,
+    ) -> ProverResult<ProofWithPublicInputs<F, C, D>> {
         prove(
             &self.prover_only,
             &self.common,
             inputs,
             &mut TimingTree::default(),
+            strategy,
         )
     }
+
+    /// Proves with the given strategy, and reports the time and memory taken by each stage.
+    pub fn prove_with_report(
+        &self,
+        inputs: PartialWitness<F>,
+        strategy: ProvingStrategy<F, C, D>,
+    ) -> ProverResult<(ProofWithPublicInputs<F, C, D>, ProvingReport)> {
+        prove_with_report(&self.prover_only, &self.common, inputs, strategy)
+    }
+
+    /// A prover for many witnesses of this circuit.
+    pub fn batch_prover(&self) -> BatchProver<F, C, D> {
+        BatchProver::new(&self.prover_only, &self.common)
+    }
+
+    /// Generates the witness for `inputs` and reports the gate constraints, copy constraints and
+    /// lookups it does not satisfy, instead of proving.
+    pub fn check_witness(&self, inputs: PartialWitness<F>) -> ProverResult<WitnessCheck<F>> {
+        check_witness(inputs, &self.prover_only, &self.common)
+    }
+
+    /// Runs the generators on `inputs`, and returns the witness to be proved later with
+    /// `prove_from_witness`.
+    pub fn generate_witness_snapshot(
+        &self,
+        inputs: PartialWitness<F>,
+    ) -> ProverResult<WitnessSnapshot<F, C, D>> {
+        WitnessSnapshot::generate(inputs, &self.prover_only, &self.common)
+    }
+
+    /// Proves a witness generated by `generate_witness_snapshot`, without running the generators.
+    pub fn prove_from_witness(
+        &self,
+        snapshot: WitnessSnapshot<F, C, D>,
+        strategy: ProvingStrategy<F, C, D>,
+    ) -> ProverResult<ProofWithPublicInputs<F, C, D>> {
+        prove_from_witness(
+            &self.prover_only,
+            &self.common,
+            snapshot,
+            &mut TimingTree::default(),
+            strategy,
+        )
+    }
+
+    /// Reports the constraints the witness of `snapshot` does not satisfy, as `check_witness`
+    /// does, without running the generators.
+    pub fn check_witness_snapshot(
+        &self,
+        snapshot: &WitnessSnapshot<F, C, D>,
+    ) -> ProverResult<WitnessCheck<F>> {
+        check_witness_snapshot(snapshot, &self.prover_only, &self.common)
+    }
+
+    /// Serializes this prover data. Every gate and generator of the circuit must be registered in
+    /// `registry`.
+    pub fn to_bytes(&self, registry: &CircuitRegistry<F, D>) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        registry.write_common_data(&mut buffer, &self.common)?;
+        registry.write_prover_data(&mut buffer, &self.prover_only)?;
+        Ok(buffer)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, registry: &CircuitRegistry<F, D>) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let common = registry.read_common_data(&mut buffer)?;
+        let prover_only = registry.read_prover_data(&mut buffer, &common)?;
+        ensure!(buffer.is_empty(), "Trailing bytes after the prover data");
+        Ok(Self {
+            prover_only,
+            common,
+        })
+    }
 }
 
 /// Circuit data required by the prover.
@@ -239,6 +430,16 @@ pub struct ProverOnlyCircuitData<
     /// Generator indices (within the `Vec` above), indexed by the representative of each target
     /// they watch.
     pub generator_indices_by_watches: BTreeMap<usize, Vec<usize>>,
+    /// The distinct stacks of `with_context` scopes in which gates and generators were added, as
+    /// written by `ContextTree::open_stack`.
+    pub contexts: Vec<String>,
+    /// For each generator, the index in `contexts` of the scopes it was added in.
+    pub generator_contexts: Vec<usize>,
+    /// For each row, the index in `contexts` of the scopes its gate was added in.
+    pub gate_contexts: Vec<usize>,
+    /// How the generators are scheduled when generating a witness. It is not part of the circuit,
+    /// so it is not serialized and may be changed before proving.
+    pub witness_scheduler: WitnessScheduler,
     /// Commitments to the constants polynomials and sigma polynomials.
     pub constants_sigmas_commitment: PolynomialBatch<F, C, D>,
     /// The transpose of the list of sigma polynomials.
@@ -297,6 +498,12 @@ pub struct CommonCircuitData<F: RichField + Extendable<D>, const D: usize> {
 
     /// The number of partial products needed to compute the `Z` polynomials.
     pub num_partial_products: usize,
+
+    /// The number of lookups of a `LookupGate`, or 0 if the circuit has no lookups.
+    pub num_lookup_slots: usize,
+
+    /// The number of table entries of a `LookupTableGate`, or 0 if the circuit has no lookups.
+    pub num_lookup_table_slots: usize,
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
@@ -304,6 +511,20 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
         self.fri_params.degree_bits
     }
 
+    /// Serializes this common data. Every gate of the circuit must be registered in `registry`.
+    pub fn to_bytes(&self, registry: &CircuitRegistry<F, D>) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        registry.write_common_data(&mut buffer, self)?;
+        Ok(buffer)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, registry: &CircuitRegistry<F, D>) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let common = registry.read_common_data(&mut buffer)?;
+        ensure!(buffer.is_empty(), "Trailing bytes after the common data");
+        Ok(common)
+    }
+
     pub fn degree(&self) -> usize {
         1 << self.degree_bits()
     }
@@ -338,14 +559,58 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
         self.num_constants..self.num_constants + self.config.num_routed_wires
     }
 
-    /// Range of the `z`s polynomials in the `zs_partial_products_commitment`.
+    /// Range of the `z`s polynomials in the `zs_partial_products_commitment`. The running sums of
+    /// the lookup argument, if any, follow the `z`s, so that they are also opened at `g * zeta`.
     pub fn zs_range(&self) -> Range<usize> {
-        0..self.config.num_challenges
+        let num_sums = if self.has_lookups() { 2 } else { 1 };
+        0..num_sums * self.config.num_challenges
     }
 
-    /// Range of the partial products polynomials in the `zs_partial_products_commitment`.
+    /// Range of the partial products polynomials in the `zs_partial_products_commitment`. They are
+    /// followed by the helper polynomials of the lookup argument, if any.
     pub fn partial_products_range(&self) -> RangeFrom<usize> {
-        self.config.num_challenges..
+        self.zs_range().end..
+    }
+
+    pub fn has_lookups(&self) -> bool {
+        self.num_lookup_slots > 0
+    }
+
+    /// The number of lookups, or table entries, whose fractions are summed by one helper
+    /// polynomial of the lookup argument. The helper constraints then have degree
+    /// `quotient_degree_factor + 1`.
+    pub(crate) fn lookup_chunk_size(&self) -> usize {
+        self.quotient_degree_factor - 1
+    }
+
+    /// The number of helper polynomials of the lookup argument per challenge.
+    pub(crate) fn num_lookup_helpers(&self) -> usize {
+        if self.has_lookups() {
+            ceil_div_usize(
+                self.num_lookup_slots.max(self.num_lookup_table_slots),
+                self.lookup_chunk_size(),
+            )
+        } else {
+            0
+        }
+    }
+
+    /// Range of the preprocessed polynomials of the lookup argument in the constants: the lookup
+    /// and table row selectors, the table index, and the input and output of each table entry of
+    /// a `LookupTableGate`. It is empty if the circuit has no lookups.
+    pub fn lookup_constants_range(&self) -> Range<usize> {
+        let num_lookup_constants = if self.has_lookups() {
+            3 + 2 * self.num_lookup_table_slots
+        } else {
+            0
+        };
+        self.num_constants - num_lookup_constants..self.num_constants
+    }
+
+    /// The number of partial products and lookup helper polynomials, i.e. of values in
+    /// `partial_products_range`.
+    pub(crate) fn num_partial_products_polys(&self) -> usize {
+        self.config.num_challenges * (self.num_partial_products + self.num_lookup_helpers())
     }
 
     pub(crate) fn get_fri_instance(&self, zeta: F::Extension) -> FriInstanceInfo<F, D> {
@@ -441,7 +706,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
     }
 
     pub(crate) fn num_zs_partial_products_polys(&self) -> usize {
-        self.config.num_challenges * (1 + self.num_partial_products)
+        self.zs_range().end + self.num_partial_products_polys()
     }
 
     fn fri_zs_polys(&self) -> Vec<FriPolynomialInfo> {
@@ -0,0 +1,433 @@
+//! Checking a witness against the constraints of a circuit, without proving.
+//!
+//! A witness which does not satisfy the constraints still yields a proof; it is only noticed when
+//! that proof fails to verify. [`check_witness`] instead runs the generators, evaluates the gate
+//! constraints of every row on the resulting `MatrixWitness`, checks the copy constraints encoded
+//! by the sigma polynomials, and checks the lookups against the multiplicities of their tables.
+//! Each unsatisfied gate is reported with the stack of `with_context` scopes in which it was added.
+//!
+//! The generators write one value per partition of copied wires, so a witness they generate
+//! satisfies the copy constraints unless a generator writes a wire directly. A witness loaded from
+//! a [`WitnessSnapshot`] has no such guarantee, and [`check_witness_snapshot`] checks it without
+//! running the generators.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use hashbrown::HashMap;
+
+use crate::field::extension::Extendable;
+use crate::field::types::{Field, PrimeField64};
+use crate::gates::lookup::LookupGate;
+use crate::gates::lookup_table::LookupTableGate;
+use crate::gates::selectors::SelectorsInfo;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::generator::generate_partial_witness;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartialWitness, Witness};
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::error::{ProverError, ProverResult};
+use crate::plonk::vars::EvaluationVarsBaseBatch;
+use crate::plonk::witness_snapshot::WitnessSnapshot;
+
+/// A row whose gate constraints do not all vanish.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnsatisfiedGate<F: Field> {
+    pub row: usize,
+    pub gate_id: String,
+    /// The indices of the constraints which do not vanish, along with their values.
+    pub constraints: Vec<(usize, F)>,
+    /// The values of the wires of the row.
+    pub wires: Vec<F>,
+    /// The stack of `with_context` scopes in which the gate was added.
+    pub context: String,
+}
+
+/// A routed wire whose value differs from that of the next wire of its partition.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnsatisfiedCopy<F: Field> {
+    pub wire: Wire,
+    pub value: F,
+    /// The wire `wire` is mapped to by the sigma polynomials.
+    pub sigma_wire: Wire,
+    pub sigma_value: F,
+}
+
+/// An `(input, output)` pair of a lookup table which is looked up a different number of times than
+/// its multiplicity in the table. A pair which is not an entry of the table has a multiplicity of
+/// 0.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnsatisfiedLookup<F: Field> {
+    /// The index of the table, as returned by `CircuitBuilder::add_lookup_table`.
+    pub table: usize,
+    pub input: F,
+    pub output: F,
+    /// The rows of the `LookupGate`s looking the pair up, once per lookup.
+    pub rows: Vec<usize>,
+    pub multiplicity: F,
+}
+
+/// The constraints a witness does not satisfy.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WitnessCheck<F: Field> {
+    /// The rows with unsatisfied gate constraints, in increasing order.
+    pub gates: Vec<UnsatisfiedGate<F>>,
+    /// The routed wires whose value is not copied to the next wire of their partition, in
+    /// increasing order of row and then column.
+    pub copies: Vec<UnsatisfiedCopy<F>>,
+    /// The pairs of the lookup tables whose lookups do not match their multiplicities.
+    pub lookups: Vec<UnsatisfiedLookup<F>>,
+}
+
+impl<F: Field> WitnessCheck<F> {
+    pub fn is_satisfied(&self) -> bool {
+        self.gates.is_empty() && self.copies.is_empty() && self.lookups.is_empty()
+    }
+
+    pub fn first_unsatisfied_gate(&self) -> Option<&UnsatisfiedGate<F>> {
+        self.gates.first()
+    }
+}
+
+impl<F: Field> fmt::Display for WitnessCheck<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_satisfied() {
+            return write!(f, "All constraints are satisfied");
+        }
+        for gate in &self.gates {
+            writeln!(
+                f,
+                "Row {} ({}) in {}: constraints {:?} do not vanish, wires are {:?}",
+                gate.row, gate.gate_id, gate.context, gate.constraints, gate.wires
+            )?;
+        }
+        for copy in &self.copies {
+            writeln!(
+                f,
+                "Wire {:?} = {} is copied to wire {:?} = {}",
+                copy.wire, copy.value, copy.sigma_wire, copy.sigma_value
+            )?;
+        }
+        for lookup in &self.lookups {
+            writeln!(
+                f,
+                "Pair ({}, {}) of table {} is looked up in rows {:?}, its multiplicity is {}",
+                lookup.input, lookup.output, lookup.table, lookup.rows, lookup.multiplicity
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Generates the witness for `inputs` and checks it against every gate constraint, copy
+/// constraint and lookup of the circuit. Fails if the witness cannot be generated, or if the
+/// selectors of a row match no gate.
+pub fn check_witness<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    inputs: PartialWitness<F>,
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> ProverResult<WitnessCheck<F>> {
+    let partition_witness = generate_partial_witness(inputs, prover_data, common_data)?;
+    let public_inputs = partition_witness.get_targets(&prover_data.public_inputs);
+    let wire_values = partition_witness.full_witness().wire_values;
+    check_wire_values(&wire_values, &public_inputs, prover_data, common_data)
+}
+
+/// Checks the witness of a snapshot, as [`check_witness`] does, without running the generators.
+/// Fails if the snapshot is of another circuit.
+pub fn check_witness_snapshot<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    snapshot: &WitnessSnapshot<F, C, D>,
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> ProverResult<WitnessCheck<F>> {
+    snapshot.ensure_matches(prover_data, common_data)?;
+    let wire_values = snapshot
+        .wire_values
+        .chunks(snapshot.degree)
+        .map(|column| column.to_vec())
+        .collect::<Vec<_>>();
+    check_wire_values(
+        &wire_values,
+        &snapshot.public_inputs,
+        prover_data,
+        common_data,
+    )
+}
+
+/// Checks the values of the wires, one vector per column, against the constraints of the circuit.
+fn check_wire_values<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    wire_values: &[Vec<F>],
+    public_inputs: &[F],
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> ProverResult<WitnessCheck<F>> {
+    let public_inputs_hash = C::InnerHasher::hash_public_inputs(public_inputs);
+
+    let degree = common_data.degree();
+    let num_selectors = common_data.selectors_info.num_selectors();
+    let constant_values = prover_data.constants_sigmas_commitment.polynomials
+        [common_data.constants_range()]
+    .iter()
+    .map(|poly| poly.clone().fft().values)
+    .collect::<Vec<_>>();
+
+    let mut gates = Vec::new();
+    for row in 0..degree {
+        let constants = constant_values.iter().map(|c| c[row]).collect::<Vec<_>>();
+        let gate_index = row_gate(&constants, &common_data.selectors_info).ok_or_else(|| {
+            ProverError::Shape(format!("The selectors of row {} match no gate", row))
+        })?;
+        let gate = &common_data.gates[gate_index].0;
+        let wires = wire_values.iter().map(|w| w[row]).collect::<Vec<_>>();
+
+        let vars = EvaluationVarsBaseBatch::new(
+            1,
+            &constants[num_selectors..],
+            &wires,
+            &public_inputs_hash,
+        );
+        let mut values = vec![F::ZERO; gate.num_constraints()];
+        gate.eval_unfiltered_base_one(
+            vars.view(0),
+            StridedConstraintConsumer::new(&mut values, 1, 0),
+        );
+        let constraints = values
+            .into_iter()
+            .enumerate()
+            .filter(|(_, value)| value.is_nonzero())
+            .collect::<Vec<_>>();
+        if !constraints.is_empty() {
+            gates.push(UnsatisfiedGate {
+                row,
+                gate_id: gate.id(),
+                constraints,
+                wires,
+                context: prover_data.contexts[prover_data.gate_contexts[row]].clone(),
+            });
+        }
+    }
+
+    let copies = check_copies(wire_values, prover_data, common_data);
+    let lookups = if common_data.has_lookups() {
+        check_lookups(
+            &constant_values[common_data.lookup_constants_range()],
+            wire_values,
+            common_data,
+        )
+    } else {
+        Vec::new()
+    };
+
+    Ok(WitnessCheck {
+        gates,
+        copies,
+        lookups,
+    })
+}
+
+/// Compares the value of each routed wire with that of the wire the sigma polynomials map it to.
+fn check_copies<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    wire_values: &[Vec<F>],
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> Vec<UnsatisfiedCopy<F>> {
+    // Sigma maps each routed wire `(column, row)` to `k_is[column] * g^row` of the next wire of its
+    // partition, so we invert that encoding to find the wire.
+    let degree = common_data.degree();
+    let num_routed_wires = common_data.config.num_routed_wires;
+    let wires_by_sigma = (0..num_routed_wires)
+        .flat_map(|column| (0..degree).map(move |row| Wire { row, column }))
+        .map(|wire| {
+            let sigma = common_data.k_is[wire.column] * prover_data.subgroup[wire.row];
+            (sigma, wire)
+        })
+        .collect::<HashMap<_, _>>();
+    let mut copies = Vec::new();
+    for row in 0..degree {
+        for column in 0..num_routed_wires {
+            let wire = Wire { row, column };
+            let sigma_wire = wires_by_sigma[&prover_data.sigmas[row][column]];
+            let value = wire_values[column][row];
+            let sigma_value = wire_values[sigma_wire.column][sigma_wire.row];
+            if value != sigma_value {
+                copies.push(UnsatisfiedCopy {
+                    wire,
+                    value,
+                    sigma_wire,
+                    sigma_value,
+                });
+            }
+        }
+    }
+    copies
+}
+
+/// Compares the number of lookups of each pair of each table with its multiplicity, given the
+/// values of the preprocessed polynomials of the lookup argument, in the order of
+/// `CommonCircuitData::lookup_constants_range`.
+fn check_lookups<F: RichField + Extendable<D>, const D: usize>(
+    lookup_constants: &[Vec<F>],
+    wire_values: &[Vec<F>],
+    common_data: &CommonCircuitData<F, D>,
+) -> Vec<UnsatisfiedLookup<F>> {
+    let is_lookup_row = &lookup_constants[0];
+    let is_table_row = &lookup_constants[1];
+    let table_ids = &lookup_constants[2];
+    // The rows looking each pair up, and the multiplicity of the pair, keyed by the canonical
+    // values of the table index, input and output.
+    let mut pairs = BTreeMap::<(u64, u64, u64), (Vec<usize>, F)>::new();
+    for row in 0..common_data.degree() {
+        let table = table_ids[row].to_canonical_u64();
+        if is_lookup_row[row].is_one() {
+            for slot in 0..common_data.num_lookup_slots {
+                let input = wire_values[LookupGate::wire_ith_input(slot)][row];
+                let output = wire_values[LookupGate::wire_ith_output(slot)][row];
+                let key = (table, input.to_canonical_u64(), output.to_canonical_u64());
+                pairs
+                    .entry(key)
+                    .or_insert((Vec::new(), F::ZERO))
+                    .0
+                    .push(row);
+            }
+        }
+        if is_table_row[row].is_one() {
+            for slot in 0..common_data.num_lookup_table_slots {
+                let input = lookup_constants[3 + 2 * slot][row];
+                let output = lookup_constants[4 + 2 * slot][row];
+                let multiplicity = wire_values[LookupTableGate::wire_ith_multiplicity(slot)][row];
+                let key = (table, input.to_canonical_u64(), output.to_canonical_u64());
+                pairs.entry(key).or_insert((Vec::new(), F::ZERO)).1 += multiplicity;
+            }
+        }
+    }
+
+    pairs
+        .into_iter()
+        .filter(|(_, (rows, multiplicity))| F::from_canonical_usize(rows.len()) != *multiplicity)
+        .map(
+            |((table, input, output), (rows, multiplicity))| UnsatisfiedLookup {
+                table: table as usize,
+                input: F::from_canonical_u64(input),
+                output: F::from_canonical_u64(output),
+                rows,
+                multiplicity,
+            },
+        )
+        .collect()
+}
+
+/// The index in `CommonCircuitData::gates` of the gate of a row, given the constants of the row.
+/// The selector of a gate's group holds the index of the gate on the rows of that gate.
+fn row_gate<F: Field>(constants: &[F], selectors_info: &SelectorsInfo) -> Option<usize> {
+    selectors_info
+        .selector_indices
+        .iter()
+        .enumerate()
+        .position(|(gate_index, &selector_index)| {
+            constants[selector_index] == F::from_canonical_usize(gate_index)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::gates::gate::Gate;
+    use crate::gates::public_input::PublicInputGate;
+    use crate::iop::target::Target;
+    use crate::iop::witness::WitnessWrite;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::with_context;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_satisfied_witness() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.exp_u64(x, 7);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3));
+        let check = data.check_witness(pw)?;
+        assert!(check.is_satisfied(), "{}", check);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reports_unsatisfied_row() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        // No generator sets the wires of this gate to the public inputs hash, so its constraints
+        // do not hold.
+        let row = with_context!(builder, "bad hash", {
+            let row = builder.add_gate(PublicInputGate, vec![]);
+            builder.connect(x, Target::wire(row, 0));
+            row
+        });
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3));
+        let check = data.check_witness(pw)?;
+        assert!(check.copies.is_empty(), "{}", check);
+        assert_eq!(check.gates.len(), 1, "{}", check);
+        let gate = check.first_unsatisfied_gate().unwrap();
+        assert_eq!(gate.row, row);
+        assert_eq!(gate.context, "root > bad hash");
+        assert_eq!(gate.gate_id, Gate::<F, D>::id(&PublicInputGate));
+        assert_eq!(gate.constraints[0].0, 0);
+        assert_eq!(gate.wires[0], F::from_canonical_u64(3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reports_unsatisfied_copy() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.mul(x, x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3));
+        let mut snapshot = data.generate_witness_snapshot(pw)?;
+        let check = data.check_witness_snapshot(&snapshot)?;
+        assert!(check.is_satisfied(), "{}", check);
+
+        // Change the value of a routed wire which is copied to another wire.
+        let degree = data.common.degree();
+        let wire = (0..data.common.config.num_routed_wires)
+            .flat_map(|column| (0..degree).map(move |row| Wire { row, column }))
+            .find(|wire| {
+                data.prover_only.sigmas[wire.row][wire.column]
+                    != data.common.k_is[wire.column] * data.prover_only.subgroup[wire.row]
+            })
+            .unwrap();
+        snapshot.wire_values[wire.column * degree + wire.row] += F::ONE;
+
+        let check = data.check_witness_snapshot(&snapshot)?;
+        assert!(!check.is_satisfied(), "{}", check);
+        let copy = check.copies.iter().find(|copy| copy.wire == wire).unwrap();
+        assert_ne!(copy.sigma_wire, wire);
+        assert_eq!(copy.sigma_value + F::ONE, copy.value);
+        Ok(())
+    }
+}
@@ -0,0 +1,386 @@
+//! Differential testing of the two layouts of the prover.
+//!
+//! `MatrixWitness` and `MerkleTree` have a classic representation (`wire_values`, `leaves` and
+//! `digests`) used by `ProvingStrategy::Cpu`, and a flattened one (`my_wire_values`, `my_leaves`
+//! and `my_digests`) used by `ProvingStrategy::CpuFlat` and accelerated backends. Both must give
+//! the same proof. [`compare_layouts`] proves a circuit with each of them and reports the first
+//! [`ProofStage`] at which they disagree.
+//!
+//! The transcript only depends on the witness and the commitments, so it is deterministic as long
+//! as the circuit is not zero-knowledge; otherwise the salts of the commitments are random.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use anyhow::{bail, ensure, Result};
+
+use crate::field::extension::Extendable;
+use crate::field::polynomial::PolynomialCoeffs;
+use crate::fri::oracle::PolynomialBatch;
+use crate::fri::proof::FriProof;
+use crate::hash::hash_types::RichField;
+use crate::hash::merkle_tree::MerkleCap;
+use crate::iop::witness::PartialWitness;
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::error::ProverResult;
+use crate::plonk::proof::ProofWithPublicInputs;
+use crate::plonk::prover::{prove_traced, ProvingStrategy};
+use crate::util::timing::TimingTree;
+
+/// The values of a proof, in the order they are computed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ProofStage {
+    PublicInputs,
+    WireValues,
+    WiresCommitment,
+    /// The betas and gammas of the permutation argument.
+    PermutationChallenges,
+    ZsPartialProductsCommitment,
+    Alphas,
+    QuotientPolysCommitment,
+    Zeta,
+    Openings,
+    FriCommitPhase,
+    FriQueryRounds,
+    FriFinalPoly,
+    FriPowWitness,
+    /// Anything else in `ProofWithPublicInputs`.
+    Proof,
+}
+
+impl ProofStage {
+    const COMMITMENTS: [Self; 3] = [
+        Self::WiresCommitment,
+        Self::ZsPartialProductsCommitment,
+        Self::QuotientPolysCommitment,
+    ];
+}
+
+/// The first difference between two proofs of the same circuit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Mismatch {
+    pub stage: ProofStage,
+    pub detail: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "proofs first differ at {:?}: {}",
+            self.stage, self.detail
+        )
+    }
+}
+
+/// A commitment in a layout-independent form.
+#[derive(Clone, Debug)]
+pub struct CommitmentTrace<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+{
+    pub polynomials: Vec<PolynomialCoeffs<F>>,
+    /// The leaves in bit-reversed order, salt included.
+    pub leaves: Vec<Vec<F>>,
+    /// The siblings of the Merkle proof of each leaf.
+    pub siblings: Vec<Vec<<C::Hasher as Hasher<F>>::Hash>>,
+    pub cap: MerkleCap<F, C::Hasher>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    CommitmentTrace<F, C, D>
+{
+    pub fn new(batch: &PolynomialBatch<F, C, D>) -> Self {
+        let tree = &batch.merkle_tree;
+        let num_leaves = tree.num_leaves();
+        Self {
+            polynomials: batch.polynomials.clone(),
+            leaves: (0..num_leaves).map(|i| tree.get(i).to_vec()).collect(),
+            siblings: (0..num_leaves).map(|i| tree.prove(i).siblings).collect(),
+            cap: tree.cap.clone(),
+        }
+    }
+
+    fn first_difference(&self, other: &Self) -> Option<String> {
+        first_difference("polynomial", &self.polynomials, &other.polynomials)
+            .or_else(|| first_difference("leaf", &self.leaves, &other.leaves))
+            .or_else(|| first_difference("Merkle proof", &self.siblings, &other.siblings))
+            .or_else(|| first_difference("cap element", &self.cap.0, &other.cap.0))
+    }
+}
+
+/// The intermediate values of a proof, as returned by `prove_traced`.
+#[derive(Clone, Debug)]
+pub struct ProofTrace<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    /// The values of each wire.
+    pub wire_values: Vec<Vec<F>>,
+    /// The wires, Z's and partial products, and quotient polynomials commitments.
+    pub commitments: Vec<CommitmentTrace<F, C, D>>,
+    pub betas: Vec<F>,
+    pub gammas: Vec<F>,
+    pub alphas: Vec<F>,
+    pub zeta: F::Extension,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> ProofTrace<F, C, D> {
+    pub(crate) fn new(
+        wire_values: Vec<Vec<F>>,
+        commitments: [&PolynomialBatch<F, C, D>; 3],
+        betas: &[F],
+        gammas: &[F],
+        alphas: &[F],
+        zeta: F::Extension,
+    ) -> Self {
+        Self {
+            wire_values,
+            commitments: commitments.into_iter().map(CommitmentTrace::new).collect(),
+            betas: betas.to_vec(),
+            gammas: gammas.to_vec(),
+            alphas: alphas.to_vec(),
+            zeta,
+        }
+    }
+}
+
+/// Compares two proofs of the same circuit along with their traces, stage by stage.
+pub fn first_mismatch<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    (proof_a, trace_a): (&ProofWithPublicInputs<F, C, D>, &ProofTrace<F, C, D>),
+    (proof_b, trace_b): (&ProofWithPublicInputs<F, C, D>, &ProofTrace<F, C, D>),
+) -> Option<Mismatch> {
+    let mismatch = |stage, detail| Some(Mismatch { stage, detail });
+
+    if let Some(detail) = first_difference(
+        "public input",
+        &proof_a.public_inputs,
+        &proof_b.public_inputs,
+    ) {
+        return mismatch(ProofStage::PublicInputs, detail);
+    }
+    if let Some(detail) = first_difference("wire", &trace_a.wire_values, &trace_b.wire_values) {
+        return mismatch(ProofStage::WireValues, detail);
+    }
+
+    let commitment = |i: usize| {
+        let stage = ProofStage::COMMITMENTS[i];
+        match (trace_a.commitments.get(i), trace_b.commitments.get(i)) {
+            (Some(a), Some(b)) => a
+                .first_difference(b)
+                .map(|detail| Mismatch { stage, detail }),
+            _ => Some(Mismatch {
+                stage,
+                detail: String::from("missing commitment"),
+            }),
+        }
+    };
+
+    if let Some(m) = commitment(0) {
+        return Some(m);
+    }
+    if let Some(detail) = first_difference("beta", &trace_a.betas, &trace_b.betas)
+        .or_else(|| first_difference("gamma", &trace_a.gammas, &trace_b.gammas))
+    {
+        return mismatch(ProofStage::PermutationChallenges, detail);
+    }
+    if let Some(m) = commitment(1) {
+        return Some(m);
+    }
+    if let Some(detail) = first_difference("alpha", &trace_a.alphas, &trace_b.alphas) {
+        return mismatch(ProofStage::Alphas, detail);
+    }
+    if let Some(m) = commitment(2) {
+        return Some(m);
+    }
+    if trace_a.zeta != trace_b.zeta {
+        return mismatch(
+            ProofStage::Zeta,
+            format!("{} != {}", trace_a.zeta, trace_b.zeta),
+        );
+    }
+
+    let (a, b) = (&proof_a.proof, &proof_b.proof);
+    if a.openings != b.openings {
+        return mismatch(ProofStage::Openings, String::from("opening sets differ"));
+    }
+    if let Some(m) = fri_first_mismatch(&a.opening_proof, &b.opening_proof) {
+        return Some(m);
+    }
+    if proof_a != proof_b {
+        return mismatch(ProofStage::Proof, String::from("proofs differ"));
+    }
+    None
+}
+
+fn fri_first_mismatch<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize>(
+    a: &FriProof<F, H, D>,
+    b: &FriProof<F, H, D>,
+) -> Option<Mismatch> {
+    let detail = if let Some(detail) = first_difference(
+        "commit phase cap",
+        &a.commit_phase_merkle_caps,
+        &b.commit_phase_merkle_caps,
+    ) {
+        (ProofStage::FriCommitPhase, detail)
+    } else if let Some(detail) =
+        first_difference("query round", &a.query_round_proofs, &b.query_round_proofs)
+    {
+        (ProofStage::FriQueryRounds, detail)
+    } else if a.final_poly != b.final_poly {
+        (
+            ProofStage::FriFinalPoly,
+            String::from("final polynomials differ"),
+        )
+    } else if a.pow_witness != b.pow_witness {
+        (
+            ProofStage::FriPowWitness,
+            format!("{} != {}", a.pow_witness, b.pow_witness),
+        )
+    } else {
+        return None;
+    };
+    Some(Mismatch {
+        stage: detail.0,
+        detail: detail.1,
+    })
+}
+
+/// Describes the first difference between `a` and `b`, if any.
+fn first_difference<T: PartialEq>(what: &str, a: &[T], b: &[T]) -> Option<String> {
+    if a.len() != b.len() {
+        return Some(format!("{} {}s vs {}", a.len(), what, b.len()));
+    }
+    a.iter()
+        .zip(b)
+        .position(|(x, y)| x != y)
+        .map(|i| format!("{} {} differs", what, i))
+}
+
+/// Builds the circuit described by `circuit`, proves it with the classic and the flattened
+/// layouts, and returns the first stage at which the two proofs differ. Both proofs must verify.
+pub fn compare_layouts<F, C, const D: usize>(
+    config: CircuitConfig,
+    circuit: impl FnOnce(&mut CircuitBuilder<F, D>, &mut PartialWitness<F>),
+) -> Result<Option<Mismatch>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    ensure!(
+        !config.zero_knowledge,
+        "Zero-knowledge proofs use random salts and cannot be compared"
+    );
+    let mut builder = CircuitBuilder::<F, D>::new(config);
+    let mut pw = PartialWitness::new();
+    circuit(&mut builder, &mut pw);
+    let data = builder.build::<C>();
+
+    let classic = prove_with(&data, pw.clone(), ProvingStrategy::Cpu)?;
+    let flat = prove_with(&data, pw, ProvingStrategy::CpuFlat)?;
+    let mismatch = first_mismatch((&classic.0, &classic.1), (&flat.0, &flat.1));
+    data.verify(classic.0)?;
+    data.verify(flat.0)?;
+    Ok(mismatch)
+}
+
+/// Like [`compare_layouts`], but fails on a mismatch.
+pub fn check_layouts_agree<F, C, const D: usize>(
+    config: CircuitConfig,
+    circuit: impl FnOnce(&mut CircuitBuilder<F, D>, &mut PartialWitness<F>),
+) -> Result<()>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    if let Some(mismatch) = compare_layouts::<F, C, D>(config, circuit)? {
+        bail!("{}", mismatch);
+    }
+    Ok(())
+}
+
+fn prove_with<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    data: &CircuitData<F, C, D>,
+    pw: PartialWitness<F>,
+    strategy: ProvingStrategy<F, C, D>,
+) -> ProverResult<(ProofWithPublicInputs<F, C, D>, ProofTrace<F, C, D>)> {
+    prove_traced(
+        &data.prover_only,
+        &data.common,
+        pw,
+        &mut TimingTree::default(),
+        strategy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::{Field, Sample};
+    use crate::hash::poseidon::PoseidonHash;
+    use crate::iop::witness::WitnessWrite;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn mul_add(builder: &mut CircuitBuilder<F, D>, pw: &mut PartialWitness<F>) {
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        let z = builder.mul_add(x, y, x);
+        builder.register_public_input(z);
+        pw.set_target(x, F::rand());
+        pw.set_target(y, F::rand());
+    }
+
+    #[test]
+    fn test_layouts_agree_on_mul_add() -> Result<()> {
+        check_layouts_agree::<F, C, D>(CircuitConfig::standard_recursion_config(), mul_add)
+    }
+
+    #[test]
+    fn test_layouts_agree_on_hashes_and_range_checks() -> Result<()> {
+        check_layouts_agree::<F, C, D>(CircuitConfig::standard_recursion_config(), |builder, pw| {
+            let x = builder.add_virtual_target();
+            let y = builder.constant(F::from_canonical_u64(7));
+            let z = builder.mul_add(x, y, x);
+            builder.range_check(z, 40);
+            let h = builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![x, z]);
+            builder.register_public_inputs(&h.elements);
+            pw.set_target(x, F::from_canonical_u64(1 << 30));
+        })
+    }
+
+    #[test]
+    fn test_first_mismatch_reports_earliest_stage() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let mut pw = PartialWitness::new();
+        mul_add(&mut builder, &mut pw);
+        let data = builder.build::<C>();
+        let (proof, trace) = prove_with(&data, pw, ProvingStrategy::Cpu)?;
+        assert_eq!(first_mismatch((&proof, &trace), (&proof, &trace)), None);
+
+        let mut other = trace.clone();
+        other.commitments[1].leaves[5][0] += F::ONE;
+        other.alphas[0] += F::ONE;
+        let mismatch = first_mismatch((&proof, &trace), (&proof, &other)).unwrap();
+        assert_eq!(mismatch.stage, ProofStage::ZsPartialProductsCommitment);
+        assert_eq!(mismatch.detail, "leaf 5 differs");
+
+        let mut other_proof = proof.clone();
+        other_proof.proof.opening_proof.pow_witness += F::ONE;
+        let mismatch = first_mismatch((&proof, &trace), (&other_proof, &trace)).unwrap();
+        assert_eq!(mismatch.stage, ProofStage::FriPowWitness);
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_knowledge_is_rejected() {
+        assert!(
+            compare_layouts::<F, C, D>(CircuitConfig::standard_recursion_zk_config(), mul_add)
+                .is_err()
+        );
+    }
+}
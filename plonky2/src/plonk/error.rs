@@ -0,0 +1,133 @@
+//! Errors of the prover, detailed enough for a caller to decide whether proving again on the CPU
+//! can succeed.
+
+use alloc::string::String;
+use core::fmt;
+
+use crate::iop::generator::WitnessGenerationError;
+
+/// A failure of `prove`, of a `ProverBackend` or of the witness generation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProverError {
+    /// The device could not be initialized, or a context, stream or copy operation failed.
+    Device(String),
+    /// A device or pinned host allocation of `bytes` bytes failed, or would not fit in the memory
+    /// budget.
+    Allocation { bytes: usize, message: String },
+    /// A kernel returned an error.
+    Kernel { name: &'static str, message: String },
+    /// A buffer, range or job does not have the shape expected by the backend.
+    Shape(String),
+    /// The circuit uses a feature the backend does not implement.
+    Unsupported(String),
+    /// The witness could not be generated, e.g. a generator never ran or two generators set the
+    /// same target to different values.
+    Generator(WitnessGenerationError),
+    /// A Fiat-Shamir challenge was degenerate, such as an opening point in the subgroup.
+    Transcript(String),
+    /// The caller cancelled the proof, e.g. during the proof-of-work search.
+    Cancelled,
+}
+
+pub type ProverResult<T> = Result<T, ProverError>;
+
+impl ProverError {
+    /// Whether the classic CPU prover may succeed where this error occurred. Witness and transcript
+    /// failures do not depend on how the commitments are computed, and a cancelled proof should
+    /// not be started again.
+    pub fn can_retry_on_cpu(&self) -> bool {
+        !matches!(
+            self,
+            Self::Generator(_) | Self::Transcript(_) | Self::Cancelled
+        )
+    }
+}
+
+impl fmt::Display for ProverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Device(message) => write!(f, "Device error: {}", message),
+            Self::Allocation { bytes, message } => {
+                write!(f, "Failed to allocate {} bytes: {}", bytes, message)
+            }
+            Self::Kernel { name, message } => write!(f, "{} failed: {}", name, message),
+            Self::Shape(message) => write!(f, "Shape mismatch: {}", message),
+            Self::Unsupported(message) => write!(f, "Unsupported circuit: {}", message),
+            Self::Generator(error) => write!(f, "Witness generation failed: {}", error),
+            Self::Transcript(message) => write!(f, "Degenerate challenge: {}", message),
+            Self::Cancelled => write!(f, "Proof cancelled"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ProverError {}
+
+/// Returns a `ProverError::Shape` with the given message if the condition does not hold.
+macro_rules! ensure_shape {
+    ($cond:expr $(,)?) => {
+        $crate::plonk::error::ensure_shape!($cond, "Condition failed: `{}`", stringify!($cond))
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            return Err($crate::plonk::error::ProverError::Shape(alloc::format!($($arg)+)));
+        }
+    };
+}
+
+pub(crate) use ensure_shape;
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::backend::CpuBackend;
+    use crate::field::types::{Field, Sample};
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::prover::ProvingStrategy;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_small_buffer_falls_back_to_cpu() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::rand());
+
+        let mut backend = CpuBackend::<F>::new(16, 0);
+        let err = data
+            .prove_with_strategy(pw.clone(), ProvingStrategy::Accelerated(&mut backend))
+            .unwrap_err();
+        assert!(matches!(err, ProverError::Shape(_)), "{}", err);
+        assert!(err.can_retry_on_cpu());
+
+        let proof = data.prove_with_strategy(pw, ProvingStrategy::Cpu)?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_missing_input_is_a_generator_error() {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let err = data
+            .prove_with_strategy(PartialWitness::new(), ProvingStrategy::Cpu)
+            .unwrap_err();
+        assert!(matches!(err, ProverError::Generator(_)), "{}", err);
+        assert!(!err.can_retry_on_cpu());
+    }
+}
@@ -0,0 +1,261 @@
+//! A log-derivative lookup argument. Each lookup `(input, output)` of a `LookupGate` row, and each
+//! entry `(input, output)` of a `LookupTableGate` row, is compressed to
+//! `id + delta * input + delta^2 * output`, where `id` is the index of the table of the row. The
+//! lookups are then all entries of their tables if and only if
+//!
+//! `sum_lookups 1 / (alpha - lookup) = sum_entries multiplicity / (alpha - entry)`
+//!
+//! for a random `alpha`. The prover commits to a running sum `Phi` of the difference, with
+//! `Phi(g x) = Phi(x) + sum_k u_k(x)`, where each helper `u_k` sums the fractions of a chunk of
+//! `quotient_degree_factor - 1` lookups or entries of a row, so that the constraints checking the
+//! helpers have the degree of the quotient. As `Phi` is checked on all of `H`, including from the
+//! last row to the first, the sum of all the fractions is 0.
+//!
+//! The argument reuses the challenges of the permutation argument, with `alpha = gamma` and
+//! `delta = beta`. The running sums follow the `Z`s in the `zs_partial_products_commitment`, and
+//! the helpers follow the partial products.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use maybe_rayon::*;
+
+use crate::field::extension::Extendable;
+use crate::field::polynomial::PolynomialValues;
+use crate::field::types::Field;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::witness::MatrixWitness;
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::GenericConfig;
+use crate::util::transpose;
+
+/// The lookups, or table entries, of the `k`th chunk of a row of `len` of them.
+fn chunk_range(k: usize, chunk_size: usize, len: usize) -> Range<usize> {
+    (k * chunk_size).min(len)..((k + 1) * chunk_size).min(len)
+}
+
+/// The numerator and denominator of `sum_i weight_i / denominator_i`.
+fn sum_fractions<T: Field>(fractions: impl Iterator<Item = (T, T)>) -> (T, T) {
+    fractions.fold((T::ZERO, T::ONE), |(n, d), (weight, denominator)| {
+        (n * denominator + weight * d, d * denominator)
+    })
+}
+
+/// The `alpha - lookup` of each lookup of a row, and the `alpha - entry` of each table entry of a
+/// row.
+fn lookup_denominators<F: RichField + Extendable<D>, const D: usize, T: Field>(
+    common_data: &CommonCircuitData<F, D>,
+    lookup_constants: &[T],
+    wires: &[T],
+    alpha: T,
+    delta: T,
+) -> (Vec<T>, Vec<T>) {
+    let id = lookup_constants[2];
+    let delta_sq = delta.square();
+    let combo = |input: T, output: T| alpha - (id + delta * input + delta_sq * output);
+    let lookups = (0..common_data.num_lookup_slots)
+        .map(|s| combo(wires[2 * s], wires[2 * s + 1]))
+        .collect();
+    let entries = (0..common_data.num_lookup_table_slots)
+        .map(|t| combo(lookup_constants[3 + 2 * t], lookup_constants[4 + 2 * t]))
+        .collect();
+    (lookups, entries)
+}
+
+/// The vanishing terms of the lookup argument for one challenge: the step of the running sum,
+/// then one term per helper.
+pub(crate) fn check_lookups<F: RichField + Extendable<D>, const D: usize, T: Field>(
+    common_data: &CommonCircuitData<F, D>,
+    lookup_constants: &[T],
+    wires: &[T],
+    sum: T,
+    next_sum: T,
+    helpers: &[T],
+    alpha: T,
+    delta: T,
+) -> Vec<T> {
+    let chunk_size = common_data.lookup_chunk_size();
+    let (is_lookup, is_table) = (lookup_constants[0], lookup_constants[1]);
+    let (lookups, entries) =
+        lookup_denominators(common_data, lookup_constants, wires, alpha, delta);
+
+    let mut terms = Vec::with_capacity(1 + helpers.len());
+    terms.push(helpers.iter().fold(next_sum - sum, |acc, &u| acc - u));
+    for (k, &u) in helpers.iter().enumerate() {
+        let (lookups_n, lookups_d) = sum_fractions(
+            lookups[chunk_range(k, chunk_size, lookups.len())]
+                .iter()
+                .map(|&d| (T::ONE, d)),
+        );
+        let range = chunk_range(k, chunk_size, entries.len());
+        let (entries_n, entries_d) = sum_fractions(
+            entries[range.clone()]
+                .iter()
+                .zip(&wires[range])
+                .map(|(&d, &multiplicity)| (multiplicity, d)),
+        );
+        terms.push(
+            is_lookup * (u * lookups_d - lookups_n)
+                + is_table * (u * entries_d + entries_n)
+                + (T::ONE - is_lookup - is_table) * u,
+        );
+    }
+    terms
+}
+
+/// Circuit version of `check_lookups`.
+pub(crate) fn check_lookups_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    common_data: &CommonCircuitData<F, D>,
+    lookup_constants: &[ExtensionTarget<D>],
+    wires: &[ExtensionTarget<D>],
+    sum: ExtensionTarget<D>,
+    next_sum: ExtensionTarget<D>,
+    helpers: &[ExtensionTarget<D>],
+    alpha: ExtensionTarget<D>,
+    delta: ExtensionTarget<D>,
+) -> Vec<ExtensionTarget<D>> {
+    let chunk_size = common_data.lookup_chunk_size();
+    let (is_lookup, is_table, id) = (
+        lookup_constants[0],
+        lookup_constants[1],
+        lookup_constants[2],
+    );
+    let delta_sq = builder.square_extension(delta);
+    let combo = |builder: &mut CircuitBuilder<F, D>,
+                 input: ExtensionTarget<D>,
+                 output: ExtensionTarget<D>| {
+        let combo = builder.mul_add_extension(delta, input, id);
+        let combo = builder.mul_add_extension(delta_sq, output, combo);
+        builder.sub_extension(alpha, combo)
+    };
+    let lookups = (0..common_data.num_lookup_slots)
+        .map(|s| combo(builder, wires[2 * s], wires[2 * s + 1]))
+        .collect::<Vec<_>>();
+    let entries = (0..common_data.num_lookup_table_slots)
+        .map(|t| {
+            combo(
+                builder,
+                lookup_constants[3 + 2 * t],
+                lookup_constants[4 + 2 * t],
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut terms = Vec::with_capacity(1 + helpers.len());
+    let mut step = builder.sub_extension(next_sum, sum);
+    for &u in helpers {
+        step = builder.sub_extension(step, u);
+    }
+    terms.push(step);
+    for (k, &u) in helpers.iter().enumerate() {
+        let (mut lookups_n, mut lookups_d) = (builder.zero_extension(), builder.one_extension());
+        for &d in &lookups[chunk_range(k, chunk_size, lookups.len())] {
+            lookups_n = builder.mul_add_extension(lookups_n, d, lookups_d);
+            lookups_d = builder.mul_extension(lookups_d, d);
+        }
+        let (mut entries_n, mut entries_d) = (builder.zero_extension(), builder.one_extension());
+        let range = chunk_range(k, chunk_size, entries.len());
+        for (&d, &multiplicity) in entries[range.clone()].iter().zip(&wires[range]) {
+            let weighted = builder.mul_extension(multiplicity, entries_d);
+            entries_n = builder.mul_add_extension(entries_n, d, weighted);
+            entries_d = builder.mul_extension(entries_d, d);
+        }
+
+        // `u + is_lookup * (lookup_term - u) + is_table * (table_term - u)`.
+        let lookup_term = builder.mul_sub_extension(u, lookups_d, lookups_n);
+        let table_term = builder.mul_add_extension(u, entries_d, entries_n);
+        let lookup_diff = builder.sub_extension(lookup_term, u);
+        let table_diff = builder.sub_extension(table_term, u);
+        let term = builder.mul_add_extension(is_lookup, lookup_diff, u);
+        terms.push(builder.mul_add_extension(is_table, table_diff, term));
+    }
+    terms
+}
+
+/// Computes the running sums of the lookup argument, one per challenge, and their helpers, ordered
+/// by challenge.
+pub(crate) fn lookup_polys<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    witness: &MatrixWitness<F>,
+    betas: &[F],
+    gammas: &[F],
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> (Vec<PolynomialValues<F>>, Vec<PolynomialValues<F>>) {
+    let degree = common_data.degree();
+    let chunk_size = common_data.lookup_chunk_size();
+    let num_helpers = common_data.num_lookup_helpers();
+    let num_wires = common_data
+        .num_lookup_table_slots
+        .max(2 * common_data.num_lookup_slots);
+    let lookup_constants = transpose(
+        &prover_data.constants_sigmas_commitment.polynomials[common_data.lookup_constants_range()]
+            .iter()
+            .map(|poly| poly.clone().fft().values)
+            .collect::<Vec<_>>(),
+    );
+
+    let mut sums = Vec::with_capacity(common_data.config.num_challenges);
+    let mut helpers = Vec::with_capacity(common_data.config.num_challenges * num_helpers);
+    for (&delta, &alpha) in betas.iter().zip(gammas) {
+        let helper_rows = (0..degree)
+            .into_par_iter()
+            .map(|row| {
+                let constants = &lookup_constants[row];
+                let (is_lookup, is_table) = (constants[0], constants[1]);
+                if is_lookup.is_zero() && is_table.is_zero() {
+                    return vec![F::ZERO; num_helpers];
+                }
+                let wires = (0..num_wires)
+                    .map(|j| witness.get_wire(row, j))
+                    .collect::<Vec<_>>();
+                let (lookups, entries) =
+                    lookup_denominators(common_data, constants, &wires, alpha, delta);
+                if is_lookup.is_one() {
+                    let invs = F::batch_multiplicative_inverse(&lookups);
+                    (0..num_helpers)
+                        .map(|k| {
+                            invs[chunk_range(k, chunk_size, invs.len())]
+                                .iter()
+                                .copied()
+                                .sum()
+                        })
+                        .collect()
+                } else {
+                    let invs = F::batch_multiplicative_inverse(&entries);
+                    (0..num_helpers)
+                        .map(|k| {
+                            let range = chunk_range(k, chunk_size, invs.len());
+                            -invs[range.clone()]
+                                .iter()
+                                .zip(&wires[range])
+                                .map(|(&inv, &multiplicity)| inv * multiplicity)
+                                .sum::<F>()
+                        })
+                        .collect()
+                }
+            })
+            .collect::<Vec<Vec<F>>>();
+
+        let mut sum = Vec::with_capacity(degree);
+        let mut acc = F::ZERO;
+        for row in &helper_rows {
+            sum.push(acc);
+            acc += row.iter().copied().sum();
+        }
+        sums.push(PolynomialValues::new(sum));
+        helpers.extend(
+            transpose(&helper_rows)
+                .into_iter()
+                .map(PolynomialValues::new),
+        );
+    }
+    (sums, helpers)
+}
@@ -1,13 +1,23 @@
+pub mod batch;
 pub mod circuit_builder;
 pub mod circuit_data;
 pub mod config;
+pub mod constraint_check;
 pub(crate) mod copy_constraint;
+pub mod differential;
+pub mod error;
 mod get_challenges;
+pub(crate) mod lookup;
 pub(crate) mod permutation_argument;
 pub mod plonk_common;
+pub mod preprocessed;
 pub mod proof;
 pub mod prover;
+pub mod registry;
+pub mod report;
 mod validate_shape;
 pub(crate) mod vanishing_poly;
 pub mod vars;
 pub mod verifier;
+pub mod verifier_key;
+pub mod witness_snapshot;
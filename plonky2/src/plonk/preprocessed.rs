@@ -0,0 +1,592 @@
+//! On-disk cache of the preprocessed data of a circuit which is the slowest to build: the
+//! commitment to the constants and sigmas polynomials, with its LDE leaves and Merkle digests,
+//! the sigmas and the FFT root tables.
+//!
+//! A cache file is named after a [`PreprocessedKey`], the hash of everything the preprocessed data
+//! is computed from, so that it is found before the data is built. It starts with a format
+//! version, the key and the `CircuitConfig` it was built with, and ends with a digest of all the
+//! bytes before it. A file is only loaded if the version, key and config match and the digest is
+//! that of its content, which covers the coefficients, the leaves and the Merkle digests.
+//!
+//! The leaves are streamed from the file into the vectors owned by the `MerkleTree`, so a file is
+//! never held in memory in addition to the data it holds. They are not memory-mapped: the tree
+//! owns its leaves as an `Arc<Vec<F>>`, which a mapping cannot back without a copy. The device
+//! leaves of `CudaInvContext` are uploaded from the restored tree, so they are not rebuilt either.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::type_name;
+use core::cmp::min;
+use core::convert::Infallible;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write as _};
+use std::path::PathBuf;
+
+use anyhow::{ensure, Result};
+use keccak_hash::keccak;
+
+use crate::field::extension::Extendable;
+use crate::field::fft::FftRootTable;
+use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
+use crate::fri::oracle::PolynomialBatch;
+use crate::hash::hash_types::RichField;
+use crate::hash::merkle_tree::{MerkleCap, MerkleTree};
+use crate::plonk::circuit_data::{CircuitConfig, CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::GenericConfig;
+use crate::plonk::plonk_common::PlonkOracle;
+use crate::util::serialization::{IoError, IoResult, Read, Write};
+
+/// Version of the cache file format, bumped whenever the layout of a file changes.
+pub const PREPROCESSED_FORMAT_VERSION: u32 = 2;
+
+const MAGIC: &[u8; 8] = b"PLKYPREP";
+
+/// The data restored from a cache file, to be moved into a `ProverOnlyCircuitData`.
+pub struct PreprocessedCircuitData<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    pub constants_sigmas_commitment: PolynomialBatch<F, C, D>,
+    pub sigmas: Vec<Vec<F>>,
+    pub fft_root_table: Option<FftRootTable<F>>,
+    pub fft_root_table_deg: Vec<F>,
+}
+
+/// The key of the preprocessed data of a circuit: the Keccak-256 hash of the name of the
+/// `GenericConfig`, the `CircuitConfig`, the degree, and the values of the constants polynomials,
+/// which include the selectors of the gates, and of the sigmas polynomials of the permutation.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PreprocessedKey(pub [u8; 32]);
+
+impl PreprocessedKey {
+    pub fn new<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        config: &CircuitConfig,
+        degree_bits: usize,
+        constant_vecs: &[PolynomialValues<F>],
+        sigma_vecs: &[PolynomialValues<F>],
+    ) -> Self {
+        let mut hasher = PayloadHasher::new();
+        let name = type_name::<C>();
+        let write = |hasher: &mut PayloadHasher| -> IoResult<()> {
+            hasher.write_usize(name.len())?;
+            hasher.write_all(name.as_bytes())?;
+            hasher.write_circuit_config(config)?;
+            hasher.write_usize(degree_bits)?;
+            for polys in [constant_vecs, sigma_vecs] {
+                hasher.write_usize(polys.len())?;
+                for poly in polys {
+                    hasher.write_field_vec(&poly.values)?;
+                }
+            }
+            Ok(())
+        };
+        write(&mut hasher).expect("Hashing cannot fail.");
+        Self(hasher.finalize())
+    }
+}
+
+/// A directory of cache files, one per `PreprocessedKey`.
+#[derive(Clone, Debug)]
+pub struct PreprocessedCache {
+    dir: PathBuf,
+}
+
+impl PreprocessedCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The file holding the preprocessed data with the given key.
+    pub fn path(&self, key: &PreprocessedKey) -> PathBuf {
+        let name = key
+            .0
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        self.dir.join(format!("{}.bin", name))
+    }
+
+    /// Writes the preprocessed data of a circuit to the cache file of `key`, and returns the path
+    /// of the file.
+    pub fn store<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+        key: &PreprocessedKey,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(key);
+        // Written to a temporary file first, so that an interrupted write does not leave a
+        // truncated cache file behind.
+        let tmp_path = path.with_extension("tmp");
+        let mut writer = Hashed::new(FileWriter(BufWriter::new(File::create(&tmp_path)?)));
+        write_preprocessed(&mut writer, key, prover_data, common_data)
+            .map_err(anyhow::Error::msg)?;
+        let digest = writer.hasher.finalize();
+        let mut writer = writer.inner;
+        writer.write_all(&digest).map_err(anyhow::Error::msg)?;
+        writer.0.flush()?;
+        drop(writer);
+        fs::rename(&tmp_path, &path)?;
+        Ok(path)
+    }
+
+    /// Reads the preprocessed data with the given key, of a circuit built with `config` and of
+    /// degree `2^degree_bits`. Fails if there is no cache file for this key, if its version, key,
+    /// config or shape does not match, or if its content does not match its digest.
+    pub fn load<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+        config: &CircuitConfig,
+        degree_bits: usize,
+        key: &PreprocessedKey,
+    ) -> Result<PreprocessedCircuitData<F, C, D>> {
+        let path = self.path(key);
+        let mut reader = Hashed::new(FileReader(BufReader::new(File::open(&path)?)));
+
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic).map_err(anyhow::Error::msg)?;
+        ensure!(
+            &magic == MAGIC,
+            "{} is not a preprocessed data file",
+            path.display()
+        );
+        let version = reader.read_u32().map_err(anyhow::Error::msg)?;
+        ensure!(
+            version == PREPROCESSED_FORMAT_VERSION,
+            "Preprocessed data file version {} is not supported, expected version {}",
+            version,
+            PREPROCESSED_FORMAT_VERSION
+        );
+        let mut file_key = [0; 32];
+        reader
+            .read_exact(&mut file_key)
+            .map_err(anyhow::Error::msg)?;
+        ensure!(
+            PreprocessedKey(file_key) == *key,
+            "Preprocessed data file of another circuit"
+        );
+        let file_config = reader.read_circuit_config().map_err(anyhow::Error::msg)?;
+        ensure!(
+            &file_config == config,
+            "Preprocessed data file built with another config: {:?}",
+            file_config
+        );
+
+        let data =
+            read_preprocessed_data(&mut reader, config, degree_bits).map_err(anyhow::Error::msg)?;
+        let digest = reader.hasher.finalize();
+        let mut reader = reader.inner;
+        let mut file_digest = [0; 32];
+        reader
+            .read_exact(&mut file_digest)
+            .map_err(anyhow::Error::msg)?;
+        ensure!(
+            file_digest == digest,
+            "Preprocessed data file does not match its digest"
+        );
+        ensure!(
+            reader.0.fill_buf()?.is_empty(),
+            "Trailing bytes in preprocessed data file"
+        );
+        Ok(data)
+    }
+}
+
+fn write_preprocessed<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    writer: &mut Hashed<FileWriter>,
+    key: &PreprocessedKey,
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> IoResult<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_u32(PREPROCESSED_FORMAT_VERSION)?;
+    writer.write_all(&key.0)?;
+    writer.write_circuit_config(&common_data.config)?;
+    write_preprocessed_data(writer, prover_data)
+}
+
+/// Writes the constants/sigmas commitment, the sigmas and the FFT root tables of a circuit, as
+/// read back by `read_preprocessed_data`.
+pub(crate) fn write_preprocessed_data<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+    W: Write,
+>(
+    writer: &mut W,
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+) -> IoResult<()> {
+    let commitment = &prover_data.constants_sigmas_commitment;
+    writer.write_usize(commitment.polynomials.len())?;
+    for poly in &commitment.polynomials {
+        writer.write_field_vec(&poly.coeffs)?;
+    }
+    let tree = &commitment.merkle_tree;
+    if tree.my_leaves.is_empty() {
+        writer.write_usize(tree.leaves[0].len())?;
+        for leaf in &tree.leaves {
+            writer.write_field_vec(leaf)?;
+        }
+        for &digest in tree.digests.iter().chain(&tree.cap.0) {
+            writer.write_hash::<F, C::Hasher>(digest)?;
+        }
+    } else {
+        writer.write_usize(tree.my_leaf_len)?;
+        writer.write_field_vec(&tree.my_leaves)?;
+        for &digest in tree.my_digests.iter() {
+            writer.write_hash::<F, C::Hasher>(digest)?;
+        }
+    }
+
+    writer.write_usize(prover_data.sigmas.len())?;
+    for row in &prover_data.sigmas {
+        writer.write_field_vec(row)?;
+    }
+    writer.write_bool(prover_data.fft_root_table.is_some())?;
+    if let Some(fft_root_table) = &prover_data.fft_root_table {
+        writer.write_usize(fft_root_table.len())?;
+        for layer in fft_root_table {
+            writer.write_usize(layer.len())?;
+            writer.write_field_vec(layer)?;
+        }
+    }
+    writer.write_usize(prover_data.fft_root_table_deg.len())?;
+    writer.write_field_vec(&prover_data.fft_root_table_deg)
+}
+
+/// Reads the data written by `write_preprocessed_data`, for a circuit built with `config` and of
+/// degree `2^degree_bits`. The Merkle tree of the commitment is restored in its flat form.
+pub(crate) fn read_preprocessed_data<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+    R: Read,
+>(
+    reader: &mut R,
+    config: &CircuitConfig,
+    degree_bits: usize,
+) -> IoResult<PreprocessedCircuitData<F, C, D>> {
+    let degree = 1 << degree_bits;
+    let rate_bits = config.fri_config.rate_bits;
+    let cap_height = config.fri_config.cap_height;
+    let num_leaves = degree << rate_bits;
+    let blinding = PlonkOracle::CONSTANTS_SIGMAS.blinding;
+
+    let num_polys = reader.read_usize()?;
+    let polynomials = (0..num_polys)
+        .map(|_| reader.read_field_vec(degree).map(PolynomialCoeffs::new))
+        .collect::<Result<Vec<_>, _>>()?;
+    let leaf_len = reader.read_usize()?;
+    if leaf_len != num_polys {
+        return Err(IoError);
+    }
+    let leaves = reader.read_field_vec(num_leaves * leaf_len)?;
+    let num_digests = 2 * (num_leaves - (1 << cap_height)) + (1 << cap_height);
+    let digests = (0..num_digests)
+        .map(|_| reader.read_hash::<F, C::Hasher>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let merkle_tree = MerkleTree {
+        leaves: vec![],
+        digests: vec![],
+        cap: MerkleCap(digests[num_digests - (1 << cap_height)..].to_vec()),
+        my_leaf_len: leaf_len,
+        my_leaves: Arc::new(leaves),
+        my_leaves_len: num_leaves * leaf_len,
+        my_leaves_dev_offset: -1,
+        my_digests: Arc::new(digests),
+    };
+
+    let num_rows = reader.read_usize()?;
+    let sigmas = (0..num_rows)
+        .map(|_| reader.read_field_vec(config.num_routed_wires))
+        .collect::<Result<Vec<_>, _>>()?;
+    let fft_root_table = if reader.read_bool()? {
+        let num_layers = reader.read_usize()?;
+        Some(
+            (0..num_layers)
+                .map(|_| {
+                    let len = reader.read_usize()?;
+                    reader.read_field_vec(len)
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    } else {
+        None
+    };
+    let len = reader.read_usize()?;
+    let fft_root_table_deg = reader.read_field_vec(len)?;
+
+    Ok(PreprocessedCircuitData {
+        constants_sigmas_commitment: PolynomialBatch {
+            polynomials,
+            merkle_tree,
+            degree_log: degree_bits,
+            rate_bits,
+            blinding,
+            my_polynomials: vec![],
+        },
+        sigmas,
+        fft_root_table,
+        fft_root_table_deg,
+    })
+}
+
+/// Number of bytes hashed at once by a `PayloadHasher`.
+const HASH_CHUNK_LEN: usize = 1 << 20;
+
+/// Keccak-256 of a stream of bytes. The stream is hashed in chunks of `HASH_CHUNK_LEN` bytes, each
+/// along with the hash of the chunks before it, so that it is never held in memory.
+struct PayloadHasher {
+    /// The hash of the previous chunks, followed by the bytes of the current chunk.
+    buffer: Vec<u8>,
+}
+
+impl PayloadHasher {
+    fn new() -> Self {
+        let mut buffer = Vec::with_capacity(32 + HASH_CHUNK_LEN);
+        buffer.extend([0; 32]);
+        Self { buffer }
+    }
+
+    fn update(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let len = min(bytes.len(), 32 + HASH_CHUNK_LEN - self.buffer.len());
+            self.buffer.extend_from_slice(&bytes[..len]);
+            bytes = &bytes[len..];
+            if self.buffer.len() == 32 + HASH_CHUNK_LEN {
+                self.compress();
+            }
+        }
+    }
+
+    fn compress(&mut self) {
+        let hash = keccak(&self.buffer).0;
+        self.buffer.clear();
+        self.buffer.extend(hash);
+    }
+
+    fn finalize(mut self) -> [u8; 32] {
+        self.compress();
+        self.buffer[..].try_into().unwrap()
+    }
+}
+
+impl Write for PayloadHasher {
+    type Error = Infallible;
+
+    fn write_all(&mut self, bytes: &[u8]) -> IoResult<()> {
+        self.update(bytes);
+        Ok(())
+    }
+}
+
+/// A reader or writer which hashes the bytes it reads or writes.
+struct Hashed<T> {
+    inner: T,
+    hasher: PayloadHasher,
+}
+
+impl<T> Hashed<T> {
+    fn new(inner: T) -> Self {
+        Self {
+            inner,
+            hasher: PayloadHasher::new(),
+        }
+    }
+}
+
+impl<R: Read> Read for Hashed<R> {
+    fn read_exact(&mut self, bytes: &mut [u8]) -> IoResult<()> {
+        self.inner.read_exact(bytes)?;
+        self.hasher.update(bytes);
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Hashed<W> {
+    type Error = W::Error;
+
+    fn write_all(&mut self, bytes: &[u8]) -> IoResult<()> {
+        self.inner.write_all(bytes)?;
+        self.hasher.update(bytes);
+        Ok(())
+    }
+}
+
+pub(crate) struct FileReader(pub(crate) BufReader<File>);
+
+impl Read for FileReader {
+    fn read_exact(&mut self, bytes: &mut [u8]) -> IoResult<()> {
+        io::Read::read_exact(&mut self.0, bytes).map_err(|_| IoError)
+    }
+}
+
+pub(crate) struct FileWriter(pub(crate) BufWriter<File>);
+
+impl Write for FileWriter {
+    type Error = IoError;
+
+    fn write_all(&mut self, bytes: &[u8]) -> IoResult<()> {
+        io::Write::write_all(&mut self.0, bytes).map_err(|_| IoError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::path::Path;
+
+    use super::*;
+    use crate::field::types::{Field, Sample};
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitData;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn cache_dir(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("plonky2-{}-{}", name, std::process::id()))
+    }
+
+    fn build(config: CircuitConfig, cache: &PreprocessedCache) -> CircuitData<F, C, D> {
+        build_scaled(3, config, cache)
+    }
+
+    /// A circuit multiplying its public input by `factor`.
+    fn build_scaled(
+        factor: u64,
+        config: CircuitConfig,
+        cache: &PreprocessedCache,
+    ) -> CircuitData<F, C, D> {
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_public_input();
+        let y = builder.mul_const(F::from_canonical_u64(factor), x);
+        builder.register_public_input(y);
+        builder.build_with_cache::<C>(cache)
+    }
+
+    /// The paths of the cache files in `dir`.
+    fn cache_files(dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>>>()?;
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// The key a cache file is stored under, from its header.
+    fn file_key(path: &Path) -> Result<PreprocessedKey> {
+        let bytes = fs::read(path)?;
+        let offset = MAGIC.len() + 4;
+        Ok(PreprocessedKey(bytes[offset..offset + 32].try_into()?))
+    }
+
+    #[test]
+    fn test_build_from_cache() -> Result<()> {
+        let dir = cache_dir("build-from-cache");
+        let cache = PreprocessedCache::new(&dir);
+        let config = CircuitConfig::standard_recursion_config();
+
+        let data = build(config.clone(), &cache);
+        let files = cache_files(&dir)?;
+        assert_eq!(files.len(), 1);
+        assert_eq!(cache.path(&file_key(&files[0])?), files[0]);
+
+        let cached = build(config, &cache);
+        let (tree, cached_tree) = (
+            &data.prover_only.constants_sigmas_commitment.merkle_tree,
+            &cached.prover_only.constants_sigmas_commitment.merkle_tree,
+        );
+        assert!(!cached_tree.my_leaves.is_empty(), "the cache was not used");
+        assert_eq!(*cached_tree.my_leaves, tree.leaves.concat());
+        assert_eq!(cached_tree.cap, tree.cap);
+        assert_eq!(cached.verifier_only, data.verifier_only);
+        assert_eq!(cached.prover_only.sigmas, data.prover_only.sigmas);
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(cached.prover_only.public_inputs[0], F::rand());
+        let proof = cached.prove(pw)?;
+        data.verify(proof)?;
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_mismatched_file() -> Result<()> {
+        let dir = cache_dir("reject-mismatched-file");
+        let cache = PreprocessedCache::new(&dir);
+        let config = CircuitConfig::standard_recursion_config();
+        let data = build(config.clone(), &cache);
+        let path = cache_files(&dir)?.remove(0);
+        let key = file_key(&path)?;
+        let degree_bits = data.common.degree_bits();
+
+        let mut other_config = config.clone();
+        other_config.fri_config.num_query_rounds += 1;
+        assert!(cache
+            .load::<F, C, D>(&other_config, degree_bits, &key)
+            .is_err());
+
+        // A file of another circuit, under the name of this one.
+        let other_key = PreprocessedKey([0xab; 32]);
+        fs::copy(&path, cache.path(&other_key))?;
+        assert!(cache
+            .load::<F, C, D>(&config, degree_bits, &other_key)
+            .is_err());
+
+        // A file whose content was altered after it was written.
+        let bytes = fs::read(&path)?;
+        let mut altered = bytes.clone();
+        altered[bytes.len() / 2] ^= 1;
+        fs::write(&path, altered)?;
+        let err = cache
+            .load::<F, C, D>(&config, degree_bits, &key)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("digest"), "{}", err);
+
+        // A file of another version.
+        let mut altered = bytes;
+        altered[MAGIC.len()] += 1;
+        fs::write(&path, altered)?;
+        assert!(cache.load::<F, C, D>(&config, degree_bits, &key).is_err());
+
+        // The circuit is still built, without the cache.
+        let rebuilt = build(config, &cache);
+        assert_eq!(rebuilt.verifier_only, data.verifier_only);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_depends_on_constants() -> Result<()> {
+        let dir = cache_dir("key-depends-on-constants");
+        let cache = PreprocessedCache::new(&dir);
+        let config = CircuitConfig::standard_recursion_config();
+        let data = build_scaled(3, config.clone(), &cache);
+
+        // Same gates and wiring, only the constants differ.
+        let other = build_scaled(5, config, &cache);
+        let other_tree = &other.prover_only.constants_sigmas_commitment.merkle_tree;
+        assert!(other_tree.my_leaves.is_empty(), "the cache was used");
+        assert_ne!(other.verifier_only, data.verifier_only);
+        assert_eq!(cache_files(&dir)?.len(), 2);
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(other.prover_only.public_inputs[0], F::rand());
+        let proof = other.prove(pw)?;
+        other.verify(proof)?;
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -1,68 +1,334 @@
+use alloc::string::String;
 use alloc::vec::Vec;
 use alloc::{format, vec};
 use core::mem::swap;
-use std::ffi::c_void;
-use std::fs::File;
-use std::io::Write;
-use std::mem::transmute;
-use std::process::exit;
-use std::thread::sleep;
-use std::time;
-
-use anyhow::{ensure, Result};
+
 use maybe_rayon::*;
 
+use crate::backend::{CpuBackend, FlatTreeParams, ProverBackend, QuotientPolysJob};
 use crate::field::extension::Extendable;
 use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
 use crate::field::types::Field;
 use crate::field::zero_poly_coset::ZeroPolyOnCoset;
-use crate::fri::oracle::{CudaInnerContext, PolynomialBatch};
+use crate::fri::oracle::PolynomialBatch;
 use crate::hash::hash_types::RichField;
 use crate::iop::challenger::Challenger;
-use crate::iop::generator::generate_partial_witness;
+use crate::iop::generator::{generate_partial_witness_counted, WitnessGenerationError};
 use crate::iop::witness::{MatrixWitness, PartialWitness, Witness};
 use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
 use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::differential::ProofTrace;
+use crate::plonk::error::{ensure_shape, ProverError, ProverResult};
+use crate::plonk::lookup::lookup_polys;
 use crate::plonk::plonk_common::PlonkOracle;
 use crate::plonk::proof::{OpeningSet, Proof, ProofWithPublicInputs};
+use crate::plonk::report::ProvingReport;
 use crate::plonk::vanishing_poly::eval_vanishing_poly_base_batch;
 use crate::plonk::vars::EvaluationVarsBaseBatch;
+use crate::plonk::witness_snapshot::WitnessSnapshot;
 use crate::timed;
 use crate::util::partial_products::{partial_products_and_z_gx, quotient_chunk_products};
 use crate::util::timing::TimingTree;
 use crate::util::{ceil_div_usize, log2_ceil, transpose};
-use plonky2_cuda;
-use plonky2_cuda::DataSlice;
-use plonky2_util::log2_strict;
-use rustacuda::memory::DeviceSlice;
-use rustacuda::prelude::CopyDestination;
-use rustacuda::memory::AsyncCopyDestination;
+
+/// How [`prove`] computes the commitments of a proof. All strategies produce the same proof, which
+/// is checked by the same `verify`.
+pub enum ProvingStrategy<
+    'a,
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    /// Commits with `PolynomialBatch` on the host.
+    Cpu,
+    /// Commits through the flattened column-major layout of the accelerated path, on a
+    /// `CpuBackend` allocated for the proof.
+    CpuFlat,
+    /// Commits through the flattened layout of a backend built for the circuit, such as a
+    /// `CudaInvContext`.
+    Accelerated(&'a mut dyn ProverBackend<F, C, D>),
+}
+
+impl<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    ProvingStrategy<'a, F, C, D>
+{
+    /// A strategy borrowing the backend of this one, so that it can be used for several proofs.
+    pub fn reborrow(&mut self) -> ProvingStrategy<'_, F, C, D> {
+        match self {
+            Self::Cpu => ProvingStrategy::Cpu,
+            Self::CpuFlat => ProvingStrategy::CpuFlat,
+            Self::Accelerated(backend) => ProvingStrategy::Accelerated(&mut **backend),
+        }
+    }
+
+    /// Whether the commitments are computed through the flattened layout.
+    pub fn is_flattened(&self) -> bool {
+        !matches!(self, Self::Cpu)
+    }
+}
+
+impl<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> Default
+    for ProvingStrategy<'a, F, C, D>
+{
+    fn default() -> Self {
+        Self::Cpu
+    }
+}
 
 pub fn prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: usize>(
     prover_data: &ProverOnlyCircuitData<F, C, D>,
     common_data: &CommonCircuitData<F, D>,
     inputs: PartialWitness<F>,
     timing: &mut TimingTree,
-) -> Result<ProofWithPublicInputs<F, C, D>> {
-    let config = &common_data.config;
-    let num_challenges = config.num_challenges;
-    let quotient_degree = common_data.quotient_degree();
-    let degree = common_data.degree();
+    strategy: ProvingStrategy<F, C, D>,
+) -> ProverResult<ProofWithPublicInputs<F, C, D>> {
+    let mut report = ProvingReport::default();
+    run_strategy(
+        prover_data,
+        common_data,
+        inputs,
+        timing,
+        strategy,
+        false,
+        &mut report,
+    )
+    .map(|(proof, _)| proof)
+}
+
+/// Like [`prove`], but times the stages of the proof on its own `TimingTree` and returns a
+/// [`ProvingReport`] of them alongside the proof.
+pub fn prove_with_report<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: PartialWitness<F>,
+    strategy: ProvingStrategy<F, C, D>,
+) -> ProverResult<(ProofWithPublicInputs<F, C, D>, ProvingReport)> {
+    let mut timing = TimingTree::new("prove", log::Level::Debug);
+    let mut report = ProvingReport::default();
+    let (proof, _) = run_strategy(
+        prover_data,
+        common_data,
+        inputs,
+        &mut timing,
+        strategy,
+        false,
+        &mut report,
+    )?;
+    timing.pop();
+    report.record_stages(&timing);
+    Ok((proof, report))
+}
 
-    let partition_witness = timed!(
+/// Like [`prove`], but also returns the intermediate values of the proof, to locate where two
+/// strategies diverge.
+pub fn prove_traced<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: PartialWitness<F>,
+    timing: &mut TimingTree,
+    strategy: ProvingStrategy<F, C, D>,
+) -> ProverResult<(ProofWithPublicInputs<F, C, D>, ProofTrace<F, C, D>)> {
+    let mut report = ProvingReport::default();
+    let (proof, trace) = run_strategy(
+        prover_data,
+        common_data,
+        inputs,
         timing,
-        &format!("run {} generators", prover_data.generators.len()),
-        generate_partial_witness(inputs, prover_data, common_data)
-    );
+        strategy,
+        true,
+        &mut report,
+    )?;
+    Ok((proof, trace.expect("the trace was requested")))
+}
 
-    let public_inputs = partition_witness.get_targets(&prover_data.public_inputs);
+/// Like [`prove`], but commits to a witness generated beforehand, possibly on another machine,
+/// instead of running the generators.
+pub fn prove_from_witness<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    mut snapshot: WitnessSnapshot<F, C, D>,
+    timing: &mut TimingTree,
+    strategy: ProvingStrategy<F, C, D>,
+) -> ProverResult<ProofWithPublicInputs<F, C, D>> {
+    snapshot.ensure_matches(prover_data, common_data)?;
+    let public_inputs = core::mem::take(&mut snapshot.public_inputs);
     let public_inputs_hash = C::InnerHasher::hash_public_inputs(&public_inputs);
+    let witness = GeneratedWitness {
+        public_inputs,
+        public_inputs_hash,
+        witness: snapshot.matrix_witness(strategy.is_flattened()),
+        generator_runs: 0,
+    };
+    let mut report = ProvingReport::default();
+    commit_and_open(
+        prover_data,
+        common_data,
+        witness,
+        timing,
+        strategy,
+        false,
+        &mut report,
+    )
+    .map(|(proof, _)| proof)
+}
 
-    let witness = timed!(
+fn run_strategy<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: PartialWitness<F>,
+    timing: &mut TimingTree,
+    strategy: ProvingStrategy<F, C, D>,
+    record_trace: bool,
+    report: &mut ProvingReport,
+) -> ProverResult<(ProofWithPublicInputs<F, C, D>, Option<ProofTrace<F, C, D>>)> {
+    let witness = generate_witness(
+        prover_data,
+        common_data,
+        inputs,
         timing,
-        "compute full witness",
-        partition_witness.full_witness()
-    );
+        strategy.is_flattened(),
+    )?;
+    report.generator_runs = witness.generator_runs;
+    commit_and_open(
+        prover_data,
+        common_data,
+        witness,
+        timing,
+        strategy,
+        record_trace,
+        report,
+    )
+}
+
+/// The witness of a proof, which is all that the commitments need from the inputs.
+pub(crate) struct GeneratedWitness<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    public_inputs: Vec<F>,
+    public_inputs_hash: <C::InnerHasher as Hasher<F>>::Hash,
+    witness: MatrixWitness<F>,
+    generator_runs: usize,
+}
+
+/// Runs the generators and collects the wire values, column-major. The flattened path expects
+/// them in a single vector.
+pub(crate) fn generate_witness<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: PartialWitness<F>,
+    timing: &mut TimingTree,
+    flattened: bool,
+) -> ProverResult<GeneratedWitness<F, C, D>> {
+    let (partition_witness, generator_runs) = timed!(
+        timing,
+        &format!("run {} generators", prover_data.generators.len()),
+        generate_partial_witness_counted(inputs, prover_data, common_data)
+    )?;
+
+    let (public_inputs_hash, public_inputs) = timed!(timing, "get public_inputs_hash", {
+        let public_inputs = partition_witness.get_targets(&prover_data.public_inputs);
+        let public_inputs_hash = C::InnerHasher::hash_public_inputs(&public_inputs);
+        (public_inputs_hash, public_inputs)
+    });
+
+    let witness = timed!(timing, "compute full witness", {
+        if flattened {
+            partition_witness.my_full_witness()
+        } else {
+            partition_witness.full_witness()
+        }
+    });
+
+    Ok(GeneratedWitness {
+        public_inputs,
+        public_inputs_hash,
+        witness,
+        generator_runs,
+    })
+}
+
+/// Commits to a witness generated by [`generate_witness`] and opens the commitments, recording the
+/// memory they take in `report`.
+pub(crate) fn commit_and_open<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    witness: GeneratedWitness<F, C, D>,
+    timing: &mut TimingTree,
+    strategy: ProvingStrategy<F, C, D>,
+    record_trace: bool,
+    report: &mut ProvingReport,
+) -> ProverResult<(ProofWithPublicInputs<F, C, D>, Option<ProofTrace<F, C, D>>)> {
+    report.record_witness(&witness.witness);
+    match strategy {
+        ProvingStrategy::Cpu => prove_with_batches(
+            prover_data,
+            common_data,
+            witness,
+            timing,
+            record_trace,
+            report,
+        ),
+        ProvingStrategy::CpuFlat => {
+            let mut backend = CpuBackend::for_circuit(prover_data, common_data)?;
+            prove_with_backend(
+                prover_data,
+                common_data,
+                witness,
+                timing,
+                &mut backend,
+                record_trace,
+                report,
+            )
+        }
+        ProvingStrategy::Accelerated(backend) => prove_with_backend(
+            prover_data,
+            common_data,
+            witness,
+            timing,
+            backend,
+            record_trace,
+            report,
+        ),
+    }
+}
+
+fn prove_with_batches<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    witness: GeneratedWitness<F, C, D>,
+    timing: &mut TimingTree,
+    record_trace: bool,
+    report: &mut ProvingReport,
+) -> ProverResult<(ProofWithPublicInputs<F, C, D>, Option<ProofTrace<F, C, D>>)> {
+    let config = &common_data.config;
+    let num_challenges = config.num_challenges;
+    let quotient_degree = common_data.quotient_degree();
+    let degree = common_data.degree();
+
+    let GeneratedWitness {
+        public_inputs,
+        public_inputs_hash,
+        witness,
+        ..
+    } = witness;
 
     let wires_values: Vec<PolynomialValues<F>> = timed!(
         timing,
@@ -77,18 +343,16 @@ pub fn prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: us
     let wires_commitment = timed!(
         timing,
         "compute wires commitment",
-        // PolynomialBatch::from_values_with_gpu(
         PolynomialBatch::from_values(
             wires_values,
             config.fri_config.rate_bits,
             config.zero_knowledge && PlonkOracle::WIRES.blinding,
             config.fri_config.cap_height,
             timing,
-            // &prover_data.fft_root_table_deg,
             prover_data.fft_root_table.as_ref(),
         )
     );
-    // sleep(time::Duration::from_secs(10000));
+    report.record_commitment("wires", &wires_commitment);
     let mut challenger = Challenger::<F, C::Hasher>::new();
 
     // Observe the instance.
@@ -99,7 +363,7 @@ pub fn prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: us
     let betas = challenger.get_n_challenges(num_challenges);
     let gammas = challenger.get_n_challenges(num_challenges);
 
-    assert!(
+    ensure_shape!(
         common_data.quotient_degree_factor < common_data.config.num_routed_wires,
         "When the number of routed wires is smaller that the degree, we should change the logic to avoid computing partial products."
     );
@@ -113,8 +377,25 @@ pub fn prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: us
     let plonk_z_vecs = partial_products_and_zs
         .iter_mut()
         .map(|partial_products_and_z| partial_products_and_z.pop().unwrap())
-        .collect();
-    let zs_partial_products = [plonk_z_vecs, partial_products_and_zs.concat()].concat();
+        .collect::<Vec<_>>();
+    // The running sums of the lookup argument follow the `Z`s, and their helpers follow the
+    // partial products.
+    let (lookup_sums, lookup_helpers) = if common_data.has_lookups() {
+        timed!(
+            timing,
+            "compute lookup polys",
+            lookup_polys(&witness, &betas, &gammas, prover_data, common_data)
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    let zs_partial_products = [
+        plonk_z_vecs,
+        lookup_sums,
+        partial_products_and_zs.concat(),
+        lookup_helpers,
+    ]
+    .concat();
 
     let partial_products_and_zs_commitment = timed!(
         timing,
@@ -129,6 +410,7 @@ pub fn prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: us
         )
     );
 
+    report.record_commitment("zs_partial_products", &partial_products_and_zs_commitment);
     challenger.observe_cap(&partial_products_and_zs_commitment.merkle_tree.cap);
 
     let alphas = challenger.get_n_challenges(num_challenges);
@@ -155,14 +437,15 @@ pub fn prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: us
         "split up quotient polys",
         quotient_polys
             .into_par_iter()
-            .flat_map(|mut quotient_poly| {
-                quotient_poly.trim_to_len(quotient_degree).expect(
-                    "Quotient has failed, the vanishing polynomial is not divisible by Z_H",
-                );
+            .map(|mut quotient_poly| {
+                quotient_poly.trim_to_len(quotient_degree).map_err(|_| {
+                    ProverError::Generator(WitnessGenerationError::Unsatisfied)
+                })?;
                 // Split quotient into degree-n chunks.
-                quotient_poly.chunks(degree)
+                Ok(quotient_poly.chunks(degree))
             })
-            .collect()
+            .collect::<ProverResult<Vec<_>>>()?
+            .concat()
     );
 
     let quotient_polys_commitment = timed!(
@@ -178,6 +461,7 @@ pub fn prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: us
         )
     );
 
+    report.record_commitment("quotient_polys", &quotient_polys_commitment);
     challenger.observe_cap(&quotient_polys_commitment.merkle_tree.cap);
 
     let zeta = challenger.get_extension_challenge::<D>();
@@ -185,10 +469,11 @@ pub fn prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: us
     // `g * zeta`, are not in our subgroup `H`. It suffices to check `zeta` only, since
     // `(g * zeta)^n = zeta^n`, where `n` is the order of `g`.
     let g = F::Extension::primitive_root_of_unity(common_data.degree_bits());
-    ensure!(
-        zeta.exp_power_of_2(common_data.degree_bits()) != F::Extension::ONE,
-        "Opening point is in the subgroup."
-    );
+    if zeta.exp_power_of_2(common_data.degree_bits()) == F::Extension::ONE {
+        return Err(ProverError::Transcript(String::from(
+            "Opening point is in the subgroup.",
+        )));
+    }
 
     let openings = timed!(
         timing,
@@ -205,6 +490,21 @@ pub fn prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: us
     );
     challenger.observe_openings(&openings.to_fri_openings());
 
+    let trace = record_trace.then(|| {
+        ProofTrace::new(
+            witness.wire_values.clone(),
+            [
+                &wires_commitment,
+                &partial_products_and_zs_commitment,
+                &quotient_polys_commitment,
+            ],
+            &betas,
+            &gammas,
+            &alphas,
+            zeta,
+        )
+    });
+
     let opening_proof = timed!(
         timing,
         "compute opening proofs",
@@ -219,9 +519,9 @@ pub fn prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: us
             &mut challenger,
             &common_data.fri_params,
             timing,
-            &mut None,
+            None,
         )
-    );
+    )?;
 
     let proof = Proof {
         wires_cap: wires_commitment.merkle_tree.cap,
@@ -230,75 +530,66 @@ pub fn prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: us
         openings,
         opening_proof,
     };
-    Ok(ProofWithPublicInputs {
-        proof,
-        public_inputs,
-    })
+    Ok((
+        ProofWithPublicInputs {
+            proof,
+            public_inputs,
+        },
+        trace,
+    ))
 }
 
-pub fn my_prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: usize>(
+/// Proves through the flattened path of `backend`. The wires commitment is laid out at the start
+/// of its working buffer, the Z's and partial products commitment at `second_stage_offset`, and
+/// the quotient polynomials right after the leaves of the latter.
+fn prove_with_backend<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D: usize>(
     prover_data: &ProverOnlyCircuitData<F, C, D>,
     common_data: &CommonCircuitData<F, D>,
-    inputs: PartialWitness<F>,
+    witness: GeneratedWitness<F, C, D>,
     timing: &mut TimingTree,
-    ctx: &mut crate::fri::oracle::CudaInvContext<F, C, D>,
-) -> Result<ProofWithPublicInputs<F, C, D>> {
+    backend: &mut dyn ProverBackend<F, C, D>,
+    record_trace: bool,
+    report: &mut ProvingReport,
+) -> ProverResult<(ProofWithPublicInputs<F, C, D>, Option<ProofTrace<F, C, D>>)> {
     let config = &common_data.config;
     let num_challenges = config.num_challenges;
     let quotient_degree = common_data.quotient_degree();
     let degree = common_data.degree();
+    let rate_bits = config.fri_config.rate_bits;
+    report.backend_bytes = backend.buffer_len() * core::mem::size_of::<F>();
+    if common_data.has_lookups() {
+        return Err(ProverError::Unsupported(String::from(
+            "The flattened prover does not support the lookup argument",
+        )));
+    }
 
-    let partition_witness = timed!(
-        timing,
-        &format!("run {} generators", prover_data.generators.len()),
-        generate_partial_witness(inputs, prover_data, common_data)
-    );
-
-    let (public_inputs_hash, public_inputs) = timed!(
-        timing,
-        "get public_inputs_hash",
-        {
-            let public_inputs = partition_witness.get_targets(&prover_data.public_inputs);
-            let public_inputs_hash = C::InnerHasher::hash_public_inputs(&public_inputs);
-            (public_inputs_hash, public_inputs)
-        });
-
-    let mut witness = timed!(
-        timing,
-        "compute full witness",
-        partition_witness.my_full_witness()
-    );
+    let GeneratedWitness {
+        public_inputs,
+        public_inputs_hash,
+        witness,
+        ..
+    } = witness;
 
     let wires_values = &witness.my_wire_values;
-    // let wires_values: Vec<PolynomialValues<F>> = timed!(
-    //     timing,
-    //     "compute wire polynomials",
-    //     witness
-    //         .wire_values
-    //         .par_iter()
-    //         .map(|column| PolynomialValues::new(column.clone()))
-    //         .collect()
-    // );
-    assert!(wires_values.len() % degree == 0);
+    ensure_shape!(wires_values.len() == config.num_wires * degree);
 
+    let wires_params = FlatTreeParams::wires(common_data).with_chunk_polys(backend.chunk_polys());
     let wires_commitment = timed!(
         timing,
         "compute wires commitment",
         PolynomialBatch::from_values_with_gpu(
-        // PolynomialBatch::from_values(
             wires_values,
-            common_data.config.num_wires,
+            wires_params.poly_num,
             degree,
-            config.fri_config.rate_bits,
+            rate_bits,
             config.zero_knowledge && PlonkOracle::WIRES.blinding,
             config.fri_config.cap_height,
+            0,
             timing,
-            prover_data.fft_root_table.as_ref(),
-            &prover_data.fft_root_table_deg,
-            ctx,
-        )
+            backend,
+        )?
     );
-    // sleep(time::Duration::from_secs(10000));
+    report.record_commitment("wires", &wires_commitment);
     let mut challenger = Challenger::<F, C::Hasher>::new();
 
     let (betas, gammas) = timed!(
@@ -315,7 +606,7 @@ pub fn my_prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D:
             (betas, gammas)
         });
 
-    assert!(
+    ensure_shape!(
         common_data.quotient_degree_factor < common_data.config.num_routed_wires,
         "When the number of routed wires is smaller that the degree, we should change the logic to avoid computing partial products."
     );
@@ -325,312 +616,87 @@ pub fn my_prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D:
         all_wires_permutation_partial_products(&witness, &betas, &gammas, prover_data, common_data)
     );
 
-    // let zs_partial_products = timed!(
-    //     timing,
-    //     "get zs_partial_products",
-    //     {
-    //         // Z is expected at the front of our batch; see `zs_range` and `partial_products_range`.
-    //         let plonk_z_vecs = partial_products_and_zs
-    //             .iter_mut()
-    //             .map(|partial_products_and_z| partial_products_and_z.pop().unwrap())
-    //             .collect();
-    //         let zs_partial_products =
-    //             [plonk_z_vecs, partial_products_and_zs.concat()].concat().into_iter().flat_map(|v| v.values).collect::<Vec<_>>();
-    //         zs_partial_products
-    //     });
-    // let zs_partial_products = &zs_partial_products;
-    //
-    // unsafe {
-    //     let v = zs_partial_products;
-    //     let mut file = File::create("zs_partial_products-new.bin").unwrap();
-    //     file.write_all(std::slice::from_raw_parts(v.as_ptr() as *const u8, v.len()*8));
-    // }
-    // exit(0);
-
     // Z is expected at the front of our batch; see `zs_range` and `partial_products_range`.
     let plonk_z_vecs = partial_products_and_zs
         .iter_mut()
         .map(|partial_products_and_z| partial_products_and_z.pop().unwrap())
         .collect();
     let zs_partial_products = [plonk_z_vecs, partial_products_and_zs.concat()].concat();
-    println!("zs_partial_products len:{}, itemLen:{}", zs_partial_products.len(), zs_partial_products[0].values.len());
-
-
-    // unsafe {
-    //     let v = zs_partial_products.iter().flat_map(|p|p.values.to_vec()).collect::<Vec<_>>();
-    //     let mut file = File::create("zs_partial_products-old.bin").unwrap();
-    //     file.write_all(std::slice::from_raw_parts(v.as_ptr() as *const u8, v.len()*8));
-    // }
+    let zs_partial_products = &zs_partial_products
+        .into_iter()
+        .flat_map(|p| p.values)
+        .collect::<Vec<_>>();
 
-    let zs_partial_products = &zs_partial_products.iter().flat_map(|p|p.values.to_vec()).collect::<Vec<_>>();
+    let zs_params = FlatTreeParams::zs_partial_products(common_data)
+        .with_chunk_polys(backend.chunk_polys());
+    let zs_partial_products_offset = backend.second_stage_offset();
     let partial_products_and_zs_commitment = timed!(
         timing,
         "commit to partial products and Z's",
-        // PolynomialBatch::from_values(
         PolynomialBatch::from_values_with_gpu(
             zs_partial_products,
-            zs_partial_products.len()/degree,
+            zs_params.poly_num,
             degree,
-            config.fri_config.rate_bits,
+            rate_bits,
             config.zero_knowledge && PlonkOracle::ZS_PARTIAL_PRODUCTS.blinding,
             config.fri_config.cap_height,
+            zs_partial_products_offset,
             timing,
-            prover_data.fft_root_table.as_ref(),
-            &prover_data.fft_root_table_deg,
-            ctx,
-        )
+            backend,
+        )?
     );
 
-
-    // let partial_products_and_zs_commitment = timed!(
-    //     timing,
-    //     "commit to partial products and Z's",
-    //     PolynomialBatch::from_values(
-    //     // PolynomialBatch::from_values_with_gpu(
-    //         zs_partial_products,
-    //         // zs_partial_products.len()/degree,
-    //         // degree,
-    //         config.fri_config.rate_bits,
-    //         config.zero_knowledge && PlonkOracle::ZS_PARTIAL_PRODUCTS.blinding,
-    //         config.fri_config.cap_height,
-    //         timing,
-    //         prover_data.fft_root_table.as_ref(),
-    //         // &prover_data.fft_root_table_deg,
-    //         // ctx,
-    //     )
-    // );
+    report.record_commitment("zs_partial_products", &partial_products_and_zs_commitment);
 
     let alphas = timed!(
         timing,
         "observe_cap for alphas",
         {
             challenger.observe_cap(&partial_products_and_zs_commitment.merkle_tree.cap);
-
-            let alphas = challenger.get_n_challenges(num_challenges);
-            alphas
+            challenger.get_n_challenges(num_challenges)
         });
 
-    // let quotient_polys = timed!(
-    //     timing,
-    //     "compute quotient polys",
-    //     compute_quotient_polys(
-    //         common_data,
-    //         prover_data,
-    //         &public_inputs_hash,
-    //         &wires_commitment,
-    //         &partial_products_and_zs_commitment,
-    //         &betas,
-    //         &gammas,
-    //         &alphas,
-    //         timing,
-    //     )
-    // );
-
+    // The quotient polynomials are computed in coefficient form and split into `1 << rate_bits`
+    // degree-`n` chunks each, which are committed to in place.
+    ensure_shape!(
+        quotient_degree == degree << rate_bits,
+        "the flattened path requires a quotient degree factor of 1 << rate_bits"
+    );
+    let quotient_polys_offset = zs_partial_products_offset + zs_params.ext_values_len();
     timed!(
         timing,
         "compute quotient polys",
-        {
-            let poly_num = common_data.config.num_wires;
-            let values_num_per_poly = degree;
-            let lg_n = log2_strict(values_num_per_poly );
-            let values_flatten_len = poly_num*values_num_per_poly;
-
-            let rate_bits = config.fri_config.rate_bits;
-            let blinding = config.zero_knowledge && PlonkOracle::WIRES.blinding;
-            let salt_size = if blinding { 4 } else { 0 };
-
-            let ext_values_flatten_len = (values_flatten_len+salt_size*values_num_per_poly) * (1<<rate_bits);
-            let pad_extvalues_len = ext_values_flatten_len;
-            let values_num_per_extpoly = values_num_per_poly*(1<<rate_bits);
-
-            let (ext_values_device, remained) = ctx.cache_mem_device.split_at_mut(ctx.second_stage_offset);
-            // let (_, ext_values_device) = front_msm.split_at(values_flatten_len);
-            let root_table_device2 = &mut ctx.root_table_device2;
-            let shift_inv_powers_device = &mut ctx.shift_inv_powers_device;
-
-
-            let (partial_products_and_zs_commitment_leaves_device, alphas_device, betas_device, gammas_device,
-                d_outs, d_quotient_polys) = timed!(
-                timing,
-                "copy params",
-                {
-                    let mut useCnt = 0;
-                    // let partial_products_and_zs_commitment_leaves = if partial_products_and_zs_commitment.merkle_tree.my_leaves.is_empty() {
-                    //     partial_products_and_zs_commitment.merkle_tree.leaves.concat()
-                    // } else {
-                    //     partial_products_and_zs_commitment.merkle_tree.my_leaves.to_vec()
-                    // };
-                    // // unsafe
-                    // // {
-                    // //     let mut file = File::create("partial_products_and_zs_commitment_leaves.bin").unwrap();
-                    // //     file.write_all(std::slice::from_raw_parts(partial_products_and_zs_commitment_leaves.as_ptr() as *const u8, partial_products_and_zs_commitment_leaves.len()*8));
-                    // // }
-                    //
-                    // useCnt = partial_products_and_zs_commitment_leaves.len();
-
-                    // let (_, remained) = remained.split_at_mut(ctx.values_flatten2.len());
-
-                    useCnt = zs_partial_products.len() << rate_bits;
-                    let (data, remained) = remained.split_at_mut(useCnt);
-
-                    let partial_products_and_zs_commitment_leaves_device =
-                        DataSlice{ptr: data.as_ptr() as *const c_void, len: useCnt as i32 };
-                    // unsafe {
-                    //     transmute::<&mut DeviceSlice<F>, &mut DeviceSlice<u64>>(data).async_copy_from(
-                    //         transmute::<&Vec<F>, &Vec<u64>>(&partial_products_and_zs_commitment_leaves),
-                    //         &ctx.inner.stream
-                    //     ).unwrap();
-                    // }
-
-                    useCnt = values_num_per_extpoly*2;
-                    let (d_quotient_polys, remained) = remained.split_at_mut(useCnt);
-
-                    useCnt = values_num_per_extpoly*2;
-                    let (d_outs, remained) = remained.split_at_mut(useCnt);
-
-                    useCnt = num_challenges;
-                    let (d_alphas, remained) = remained.split_at_mut(useCnt);
-                    unsafe {
-                        transmute::<&mut DeviceSlice<F>, &mut DeviceSlice<u64>>(d_alphas).async_copy_from(
-                            transmute::<&Vec<F>, &Vec<u64>>(&alphas),
-                            &ctx.inner.stream
-                        ).unwrap();
-                    }
-                    let alphas_device = DataSlice{ptr: d_alphas.as_ptr() as *const c_void, len: alphas.len() as i32 };
-
-                    let (d_betas, remained) = remained.split_at_mut(useCnt);
-                    unsafe {
-                        transmute::<&mut DeviceSlice<F>, &mut DeviceSlice<u64>>(d_betas).async_copy_from(
-                            transmute::<&Vec<F>, &Vec<u64>>(&betas),
-                            &ctx.inner.stream
-                        ).unwrap();
-                    }
-                    let betas_device = DataSlice{ptr: d_betas.as_ptr() as *const c_void, len: betas.len() as i32 };
-
-                    let (d_gammas, remained) = remained.split_at_mut(useCnt);
-                    unsafe {
-                        transmute::<&mut DeviceSlice<F>, &mut DeviceSlice<u64>>(d_gammas).async_copy_from(
-                            transmute::<&Vec<F>, &Vec<u64>>(&gammas),
-                            &ctx.inner.stream
-                        ).unwrap();
-                    }
-                    let gammas_device = DataSlice{ptr: d_gammas.as_ptr() as *const c_void, len: gammas.len() as i32 };
-
-                    ctx.inner.stream.synchronize().unwrap();
-
-                    (partial_products_and_zs_commitment_leaves_device, alphas_device, betas_device, gammas_device, d_outs, d_quotient_polys)
-                }
-            );
-
-            let points_device = DataSlice{ptr: ctx.points_device.as_ptr() as *const c_void, len: ctx.points_device.len() as i32 };
-            let z_h_on_coset_evals_device = DataSlice{ptr: ctx.z_h_on_coset_evals_device.as_ptr() as *const c_void, len: ctx.z_h_on_coset_evals_device.len() as i32 };
-            let z_h_on_coset_inverses_device = DataSlice{ptr: ctx.z_h_on_coset_inverses_device.as_ptr() as *const c_void, len: ctx.z_h_on_coset_inverses_device.len() as i32 };
-            let k_is_device = DataSlice{ptr: ctx.k_is_device.as_ptr() as *const c_void, len: ctx.k_is_device.len() as i32 };
-
-            let constants_sigmas_commitment_leaves_device = DataSlice{
-                ptr: ctx.constants_sigmas_commitment_leaves_device.as_ptr() as *const c_void,
-                len: ctx.constants_sigmas_commitment_leaves_device.len() as i32,
-            };
-            let ctx_ptr :*mut CudaInnerContext = &mut ctx.inner;
-            timed!(
-                timing,
-                "compute quotient polys with GPU",
-                unsafe {
-                    plonky2_cuda::compute_quotient_polys(
-                        ext_values_device.as_ptr() as *const u64,
-
-                        poly_num as i32,
-                        values_num_per_poly as i32,
-                        lg_n as i32,
-                        root_table_device2.as_ptr() as *const u64,
-                        shift_inv_powers_device.as_ptr() as *const u64,
-                        rate_bits as i32,
-                        salt_size as i32,
-
-                        &partial_products_and_zs_commitment_leaves_device,
-                        &constants_sigmas_commitment_leaves_device,
-
-                        d_outs.as_mut_ptr() as *mut c_void,
-                        d_quotient_polys.as_mut_ptr() as *mut c_void,
-
-                        &points_device,
-                        &z_h_on_coset_evals_device,
-                        &z_h_on_coset_inverses_device,
-                        &k_is_device,
-
-                        &alphas_device,
-                        &betas_device,
-                        &gammas_device,
-
-                        ctx_ptr as *mut core::ffi::c_void,
-                    )
-                }
-            );
-            // let mut quotient_polys_flatten :Vec<F> = vec![F::ZERO; values_num_per_extpoly*2];
-            // timed!(
-            //         timing,
-            //         "copy result",
-            //         {
-            //             unsafe {
-            //                 transmute::<&DeviceSlice<F>, &DeviceSlice<u64>>(d_quotient_polys).async_copy_to(
-            //                 transmute::<&mut Vec<F>, &mut Vec<u64>>(&mut quotient_polys_flatten),
-            //                 &ctx.inner.stream).unwrap();
-            //                 ctx.inner.stream.synchronize().unwrap();
-            //             }
-            //         }
-            //     );
-            //
-            // (quotient_polys_flatten.chunks(values_num_per_extpoly).map(|c|PolynomialCoeffs{coeffs: c.to_vec()}).collect::<Vec<_>>(), d_quotient_polys)
-        });
+        backend.compute_quotient_polys(&QuotientPolysJob {
+            common_data,
+            public_inputs_hash: &public_inputs_hash,
+            wires_offset: 0,
+            wires: wires_params,
+            zs_partial_products_offset,
+            zs_partial_products: zs_params,
+            quotient_polys_offset,
+            alphas: &alphas,
+            betas: &betas,
+            gammas: &gammas,
+        })
+    )?;
 
-    // // Compute the quotient polynomials, aka `t` in the Plonk paper.
-    // let all_quotient_poly_chunks :Vec<PolynomialCoeffs<F>> = timed!(
-    //     timing,
-    //     "split up quotient polys",
-    //     quotient_polys
-    //         .into_par_iter()
-    //         .flat_map(|mut quotient_poly| {
-    //             quotient_poly.trim_to_len(quotient_degree).expect(
-    //                 "Quotient has failed, the vanishing polynomial is not divisible by Z_H",
-    //             );
-    //             // Split quotient into degree-n chunks.
-    //             quotient_poly.chunks(degree)
-    //         })
-    //         .collect()
-    // );
-    // println!("all_quotient_poly_chunks len:{}, itemLen:{}", all_quotient_poly_chunks.len(), all_quotient_poly_chunks[0].coeffs.len());
-
-    assert!(quotient_degree == (degree << config.fri_config.rate_bits));
-    // let quotient_polys_commitment = timed!(
-    //     timing,
-    //     "commit to quotient polys",
-    //     PolynomialBatch::from_coeffs(
-    //         all_quotient_poly_chunks,
-    //         config.fri_config.rate_bits,
-    //         config.zero_knowledge && PlonkOracle::QUOTIENT.blinding,
-    //         config.fri_config.cap_height,
-    //         timing,
-    //         prover_data.fft_root_table.as_ref(),
-    //     )
-    // );
-
-    println!("offset: {}, values: {}, zs product: {}",
-             ctx.second_stage_offset, ctx.values_flatten2.len(), zs_partial_products.len()<<config.fri_config.rate_bits);
     let quotient_polys_commitment = timed!(
         timing,
         "commit to quotient polys",
         PolynomialBatch::from_coeffs_with_gpu(
-            ctx.second_stage_offset+(zs_partial_products.len()<<config.fri_config.rate_bits),
+            quotient_polys_offset,
             degree,
-            num_challenges*(1 << config.fri_config.rate_bits),
-            config.fri_config.rate_bits,
+            num_challenges << rate_bits,
+            rate_bits,
             config.zero_knowledge && PlonkOracle::QUOTIENT.blinding,
             config.fri_config.cap_height,
             timing,
-            ctx,
-        )
+            backend,
+        )?
     );
 
+    report.record_commitment("quotient_polys", &quotient_polys_commitment);
+
     let (zeta, g) = timed!(
         timing,
         "get zeta and g",
@@ -642,11 +708,12 @@ pub fn my_prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D:
             // `g * zeta`, are not in our subgroup `H`. It suffices to check `zeta` only, since
             // `(g * zeta)^n = zeta^n`, where `n` is the order of `g`.
             let g = F::Extension::primitive_root_of_unity(common_data.degree_bits());
-            ensure!(
-                zeta.exp_power_of_2(common_data.degree_bits()) != F::Extension::ONE,
-                "Opening point is in the subgroup."
-            );
-                    (zeta, g)
+            if zeta.exp_power_of_2(common_data.degree_bits()) == F::Extension::ONE {
+                return Err(ProverError::Transcript(String::from(
+                    "Opening point is in the subgroup.",
+                )));
+            }
+            (zeta, g)
         });
     let openings = timed!(
         timing,
@@ -665,8 +732,23 @@ pub fn my_prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D:
     timed!(
         timing,
         "observe_openings",
-            challenger.observe_openings(&openings.to_fri_openings())
-        );
+        challenger.observe_openings(&openings.to_fri_openings())
+    );
+
+    let trace = record_trace.then(|| {
+        ProofTrace::new(
+            wires_values.chunks(degree).map(|w| w.to_vec()).collect(),
+            [
+                &wires_commitment,
+                &partial_products_and_zs_commitment,
+                &quotient_polys_commitment,
+            ],
+            &betas,
+            &gammas,
+            &alphas,
+            zeta,
+        )
+    });
 
     let opening_proof = timed!(
         timing,
@@ -682,9 +764,9 @@ pub fn my_prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D:
             &mut challenger,
             &common_data.fri_params,
             timing,
-            &mut Some(ctx),
+            Some(backend),
         )
-    );
+    )?;
 
     let proof = Proof {
         wires_cap: wires_commitment.merkle_tree.cap,
@@ -693,12 +775,15 @@ pub fn my_prove<F: RichField + Extendable<D>, C: GenericConfig<D, F=F>, const D:
         openings,
         opening_proof,
     };
-    Ok(ProofWithPublicInputs {
-        proof,
-        public_inputs,
-    })
+    Ok((
+        ProofWithPublicInputs {
+            proof,
+            public_inputs,
+        },
+        trace,
+    ))
 }
-/// Compute the partial products used in the `Z` polynomials.
+
 fn all_wires_permutation_partial_products<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F=F>,
@@ -785,7 +870,7 @@ fn wires_permutation_partial_products_and_zs<
         .collect()
 }
 
-const BATCH_SIZE: usize = 32;
+pub(crate) const BATCH_SIZE: usize = 32;
 
 fn compute_quotient_polys<
     'a,
@@ -822,64 +907,13 @@ fn compute_quotient_polys<
     let lde_size = points.len();
 
     let z_h_on_coset = ZeroPolyOnCoset::new(common_data.degree_bits(), quotient_degree_bits);
-    println!("z_h_on_coset, n: {}, rate: {}", z_h_on_coset.n, z_h_on_coset.rate);
-    println!("step: {}, next_step: {}, lde_size: {}", step, next_step, lde_size);
-    println!("public_inputs_hash: {:?}", public_inputs_hash);
-    unsafe {
-    //     let mut file = File::create("zs_partial_products_commitment.polynomials.bin").unwrap();
-    //     for value in zs_partial_products_commitment.polynomials.iter().flat_map(|v| v.coeffs.clone()) {
-    //         file.write_all(std::mem::transmute::<&F, &[u8; 8]>(&value)).unwrap();
-    //     }
-    //     let mut file = File::create("zs_partial_products_commitment.leaves.bin").unwrap();
-    //     for value in zs_partial_products_commitment.merkle_tree.leaves.concat() {
-    //         file.write_all(std::mem::transmute::<&F, &[u8; 8]>(&value)).unwrap();
-    //     }
-    //     let mut file = File::create("zs_partial_products_commitment.digests.bin").unwrap();
-    //     for value in zs_partial_products_commitment.merkle_tree.digests.iter() {
-    //         file.write_all(std::mem::transmute::<&_, &[u8; 32]>(value)).unwrap();
-    //     }
-    //     let mut file = File::create("zs_partial_products_commitment.caps.bin").unwrap();
-    //     for value in zs_partial_products_commitment.merkle_tree.cap.0.iter() {
-    //         file.write_all(std::mem::transmute::<&_, &[u8; 32]>(value)).unwrap();
-    //     }
-    //
-    //     let mut file = File::create("alphas.bin").unwrap();
-    //     for value in alphas {
-    //         file.write_all(std::mem::transmute::<&F, &[u8; 8]>(value)).unwrap();
-    //     }
-    //
-    //     let mut file = File::create("betas.bin").unwrap();
-    //     for value in betas {
-    //         file.write_all(std::mem::transmute::<&F, &[u8; 8]>(value)).unwrap();
-    //     }
-    //     let mut file = File::create("gammas.bin").unwrap();
-    //     for value in gammas {
-    //         file.write_all(std::mem::transmute::<&F, &[u8; 8]>(value)).unwrap();
-    //     }
-    //     let mut file = File::create("k_is.bin").unwrap();
-    //     for value in common_data.k_is.iter() {
-    //         file.write_all(std::mem::transmute::<&F, &[u8; 8]>(value)).unwrap();
-    //     }
-    //
-    //     let mut file = File::create("points.bin").unwrap();
-    //     for value in points.iter() {
-    //         file.write_all(std::mem::transmute::<&F, &[u8; 8]>(value)).unwrap();
-    //     }
-    //
-    //     let mut file = File::create("z_h_on_coset.evals.bin").unwrap();
-    //     println!("z_h_on_coset.evals len: {}", z_h_on_coset.evals.len());
-    //     for value in z_h_on_coset.evals.iter() {
-    //         file.write_all(std::mem::transmute::<&F, &[u8; 8]>(value)).unwrap();
-    //     }
-    //     let mut file = File::create("z_h_on_coset.inverses.bin").unwrap();
-    //     for value in z_h_on_coset.inverses.iter() {
-    //         file.write_all(std::mem::transmute::<&F, &[u8; 8]>(value)).unwrap();
-    //     }
-    }
+    log::trace!("z_h_on_coset, n: {}, rate: {}", z_h_on_coset.n, z_h_on_coset.rate);
+    log::trace!("step: {}, next_step: {}, lde_size: {}", step, next_step, lde_size);
+    log::trace!("public_inputs_hash: {:?}", public_inputs_hash);
 
-    println!("alphas: {:?}", alphas);
-    println!("betas: {:?}", betas);
-    println!("gammas: {:?}", gammas);
+    log::trace!("alphas: {:?}", alphas);
+    log::trace!("betas: {:?}", betas);
+    log::trace!("gammas: {:?}", gammas);
 
     let points_batches = points.par_chunks(BATCH_SIZE);
     let num_batches = ceil_div_usize(points.len(), BATCH_SIZE);
@@ -925,13 +959,23 @@ fn compute_quotient_polys<
                     &local_zs_partial_products[common_data.partial_products_range()];
 
                 if i == 1048576 {
-                    println!("i: {}, len: {}, lcs: {:?}", i, local_constants_sigmas.len(), local_constants_sigmas);
-                    println!("i: {}, len: {}, lw: {:?}", i, local_wires.len(), local_wires);
-                    println!("i: {}, len: {}, lzpp: {:?}", i, local_zs_partial_products.len(), local_zs_partial_products);
-                    println!("i: {}, len: {}, nzs: {:?}", i, next_zs.len(), next_zs);
+                    log::trace!(
+                        "i: {}, len: {}, lcs: {:?}",
+                        i,
+                        local_constants_sigmas.len(),
+                        local_constants_sigmas
+                    );
+                    log::trace!("i: {}, len: {}, lw: {:?}", i, local_wires.len(), local_wires);
+                    log::trace!(
+                        "i: {}, len: {}, lzpp: {:?}",
+                        i,
+                        local_zs_partial_products.len(),
+                        local_zs_partial_products
+                    );
+                    log::trace!("i: {}, len: {}, nzs: {:?}", i, next_zs.len(), next_zs);
                 }
                 debug_assert_eq!(local_wires.len(), common_data.config.num_wires);
-                debug_assert_eq!(local_zs.len(), num_challenges);
+                debug_assert_eq!(local_zs.len(), common_data.zs_range().len());
 
                 local_constants_batch_refs.push(local_constants);
                 local_wires_batch_refs.push(local_wires);
@@ -990,7 +1034,7 @@ fn compute_quotient_polys<
                     .for_each(|v| *v *= denominator_inv);
 
                 if i == 1048576 {
-                    println!("i: {}, res: {:?}", i, quotient_values);
+                    log::trace!("i: {}, res: {:?}", i, quotient_values);
                 }
             }
             quotient_values_batch
@@ -998,14 +1042,11 @@ fn compute_quotient_polys<
         .collect()
     );
 
-    println!("quotient_values len:{}, itemLen:{}", quotient_values.len(), quotient_values[0].len());
-    // unsafe
-    // {
-    //     let mut file = File::create("quotient_values.bin").unwrap();
-    //     let v = quotient_values.concat();
-    //     file.write_all(std::slice::from_raw_parts(v.as_ptr() as *const u8, v.len()*8));
-    // }
-
+    log::trace!(
+        "quotient_values len:{}, itemLen:{}",
+        quotient_values.len(),
+        quotient_values[0].len()
+    );
     let values = timed!(
         timing,
         "transpose",
@@ -1020,15 +1061,5 @@ fn compute_quotient_polys<
             .collect()
     );
 
-    // unsafe
-    // {
-    //     let mut file = File::create("quotient_values2.bin").unwrap();
-    //     let v = res.iter().flat_map(|f|f.coeffs.clone()).collect::<Vec<_>>();
-    //     file.write_all(std::slice::from_raw_parts(v.as_ptr() as *const u8, v.len()*8));
-    // }
-
-    // let n_inv = F::inverse_2exp(21);
-    // println!("n_inv with 21: {:?}", n_inv);
-    // println!("v1: {:?}, v2: {:?}", res[0].coeffs[1048576], res[1].coeffs[1048576]);
     res
 }
@@ -0,0 +1,595 @@
+//! Serialization of circuits. Gates are `Arc<dyn Gate>`s and witness generators are
+//! `Box<dyn WitnessGenerator>`s, so a [`CircuitRegistry`] maps each of their types to the id it is
+//! written under, and each id to the function reading the type back. The parameters of a gate or
+//! generator are written by its own `serialize` method.
+//!
+//! [`CircuitRegistry::new`] registers the gates and generators of this crate. Crates defining
+//! other gates or generators export a function registering theirs.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::{type_name, Any, TypeId};
+
+use anyhow::{anyhow, ensure, Result};
+use hashbrown::HashMap;
+
+use crate::field::extension::Extendable;
+use crate::field::types::Field;
+use crate::fri::FriParams;
+use crate::gadgets::arithmetic::EqualityGenerator;
+use crate::gadgets::arithmetic_extension::QuotientGeneratorExtension;
+use crate::gadgets::lookup::{LookupGenerator, LookupTableGenerator};
+use crate::gadgets::range_check::LowHighGenerator;
+use crate::gadgets::split_base::BaseSumGenerator;
+use crate::gadgets::split_join::{SplitGenerator, WireSplitGenerator};
+use crate::gates::arithmetic_base::{ArithmeticBaseGenerator, ArithmeticGate};
+use crate::gates::arithmetic_extension::{ArithmeticExtensionGate, ArithmeticExtensionGenerator};
+use crate::gates::base_sum::{BaseSplitGenerator, BaseSumGate};
+use crate::gates::constant::ConstantGate;
+use crate::gates::exponentiation::{ExponentiationGate, ExponentiationGenerator};
+use crate::gates::gate::{Gate, GateRef};
+use crate::gates::high_degree_interpolation::HighDegreeInterpolationGate;
+use crate::gates::lookup::LookupGate;
+use crate::gates::lookup_table::LookupTableGate;
+use crate::gates::low_degree_interpolation::LowDegreeInterpolationGate;
+use crate::gates::multiplication_extension::{MulExtensionGate, MulExtensionGenerator};
+use crate::gates::noop::NoopGate;
+use crate::gates::poseidon::{PoseidonGate, PoseidonGenerator};
+use crate::gates::poseidon2::{Poseidon2Gate, Poseidon2Generator};
+use crate::gates::poseidon_mds::{PoseidonMdsGate, PoseidonMdsGenerator};
+use crate::gates::public_input::PublicInputGate;
+use crate::gates::random_access::{RandomAccessGate, RandomAccessGenerator};
+use crate::gates::reducing::ReducingGate;
+use crate::gates::reducing_extension::ReducingExtensionGate;
+use crate::gates::{
+    high_degree_interpolation, low_degree_interpolation, reducing, reducing_extension,
+};
+use crate::hash::hash_types::RichField;
+use crate::iop::generator::{
+    ConstantGenerator, CopyGenerator, NonzeroTestGenerator, RandomValueGenerator, SimpleGenerator,
+    SimpleGeneratorAdapter, WitnessGenerator, WitnessScheduler,
+};
+use crate::plonk::circuit_data::{CircuitConfig, CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::GenericConfig;
+use crate::plonk::preprocessed::{read_preprocessed_data, write_preprocessed_data};
+use crate::recursion::dummy_circuit::DummyProofGenerator;
+use crate::util::serialization::{Buffer, IoError, IoResult, Read, Remaining, Write};
+
+/// Gives access to the concrete type behind a gate or generator trait object.
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+
+    fn type_name(&self) -> &'static str;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        type_name::<T>()
+    }
+}
+
+type GateReader<F, const D: usize> = fn(&mut Buffer) -> IoResult<GateRef<F, D>>;
+type GeneratorReader<F> = fn(&mut Buffer) -> IoResult<Box<dyn WitnessGenerator<F>>>;
+
+/// The ids of the registered types, and the reader of each id.
+struct Table<R> {
+    ids: HashMap<TypeId, &'static str>,
+    readers: HashMap<&'static str, R>,
+}
+
+impl<R> Table<R> {
+    fn new() -> Self {
+        Self {
+            ids: HashMap::new(),
+            readers: HashMap::new(),
+        }
+    }
+
+    /// Registering a type again under the same id does nothing, so that crates registering the
+    /// types of their dependencies can be combined.
+    fn register(&mut self, type_id: TypeId, type_name: &str, id: &'static str, reader: R) {
+        if let Some(&registered) = self.ids.get(&type_id) {
+            assert_eq!(
+                registered, id,
+                "{} is already registered under another id",
+                type_name
+            );
+            return;
+        }
+        assert!(
+            !self.readers.contains_key(id),
+            "id {} is already registered for another type than {}",
+            id,
+            type_name
+        );
+        self.ids.insert(type_id, id);
+        self.readers.insert(id, reader);
+    }
+}
+
+/// The gate and witness generator types which can be serialized.
+pub struct CircuitRegistry<F: RichField + Extendable<D>, const D: usize> {
+    gates: Table<GateReader<F, D>>,
+    generators: Table<GeneratorReader<F>>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Default for CircuitRegistry<F, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitRegistry<F, D> {
+    /// A registry of the gates and generators of this crate.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry
+            .register_gate::<ArithmeticGate>("ArithmeticGate")
+            .register_gate::<ArithmeticExtensionGate<D>>("ArithmeticExtensionGate")
+            .register_gate::<BaseSumGate<2>>("BaseSumGate<2>")
+            .register_gate::<ConstantGate>("ConstantGate")
+            .register_gate::<ExponentiationGate<F, D>>("ExponentiationGate")
+            .register_gate::<HighDegreeInterpolationGate<F, D>>("HighDegreeInterpolationGate")
+            .register_gate::<LookupGate>("LookupGate")
+            .register_gate::<LookupTableGate>("LookupTableGate")
+            .register_gate::<LowDegreeInterpolationGate<F, D>>("LowDegreeInterpolationGate")
+            .register_gate::<MulExtensionGate<D>>("MulExtensionGate")
+            .register_gate::<NoopGate>("NoopGate")
+            .register_gate::<PoseidonGate<F, D>>("PoseidonGate")
+            .register_gate::<Poseidon2Gate<F, D>>("Poseidon2Gate")
+            .register_gate::<PoseidonMdsGate<F, D>>("PoseidonMdsGate")
+            .register_gate::<PublicInputGate>("PublicInputGate")
+            .register_gate::<RandomAccessGate<F, D>>("RandomAccessGate")
+            .register_gate::<ReducingGate<D>>("ReducingGate")
+            .register_gate::<ReducingExtensionGate<D>>("ReducingExtensionGate");
+        registry
+            .register_simple_generator::<ArithmeticBaseGenerator<F, D>>("ArithmeticBaseGenerator")
+            .register_simple_generator::<ArithmeticExtensionGenerator<F, D>>(
+                "ArithmeticExtensionGenerator",
+            )
+            .register_simple_generator::<BaseSplitGenerator<2>>("BaseSplitGenerator<2>")
+            .register_simple_generator::<BaseSumGenerator<2>>("BaseSumGenerator<2>")
+            .register_simple_generator::<ConstantGenerator<F>>("ConstantGenerator")
+            .register_simple_generator::<CopyGenerator>("CopyGenerator")
+            .register_simple_generator::<DummyProofGenerator<F>>("DummyProofGenerator")
+            .register_simple_generator::<EqualityGenerator>("EqualityGenerator")
+            .register_simple_generator::<ExponentiationGenerator<F, D>>("ExponentiationGenerator")
+            .register_simple_generator::<high_degree_interpolation::InterpolationGenerator<F, D>>(
+                "HighDegreeInterpolationGenerator",
+            )
+            .register_simple_generator::<low_degree_interpolation::InterpolationGenerator<F, D>>(
+                "LowDegreeInterpolationGenerator",
+            )
+            .register_generator::<LookupGenerator>("LookupGenerator")
+            .register_simple_generator::<LookupTableGenerator>("LookupTableGenerator")
+            .register_simple_generator::<LowHighGenerator>("LowHighGenerator")
+            .register_simple_generator::<MulExtensionGenerator<F, D>>("MulExtensionGenerator")
+            .register_simple_generator::<NonzeroTestGenerator>("NonzeroTestGenerator")
+            .register_simple_generator::<PoseidonGenerator<F, D>>("PoseidonGenerator")
+            .register_simple_generator::<Poseidon2Generator<F, D>>("Poseidon2Generator")
+            .register_simple_generator::<PoseidonMdsGenerator<D>>("PoseidonMdsGenerator")
+            .register_simple_generator::<QuotientGeneratorExtension<D>>(
+                "QuotientGeneratorExtension",
+            )
+            .register_simple_generator::<RandomAccessGenerator<F, D>>("RandomAccessGenerator")
+            .register_simple_generator::<RandomValueGenerator>("RandomValueGenerator")
+            .register_simple_generator::<reducing::ReducingGenerator<D>>("ReducingGenerator")
+            .register_simple_generator::<reducing_extension::ReducingGenerator<D>>(
+                "ReducingExtensionGenerator",
+            )
+            .register_simple_generator::<SplitGenerator>("SplitGenerator")
+            .register_simple_generator::<WireSplitGenerator>("WireSplitGenerator");
+        registry
+    }
+
+    /// A registry without any gate or generator.
+    pub fn empty() -> Self {
+        Self {
+            gates: Table::new(),
+            generators: Table::new(),
+        }
+    }
+
+    /// Registers the gate type `G` under `id`. Panics if `G` is registered under another id, or
+    /// `id` is used by another type.
+    pub fn register_gate<G: Gate<F, D>>(&mut self, id: &'static str) -> &mut Self {
+        self.gates
+            .register(TypeId::of::<G>(), type_name::<G>(), id, |src| {
+                Ok(GateRef::new(G::deserialize(src)?))
+            });
+        self
+    }
+
+    /// Registers the generator type `G` under `id`, like `register_gate`.
+    pub fn register_generator<G: WitnessGenerator<F>>(&mut self, id: &'static str) -> &mut Self {
+        self.generators
+            .register(TypeId::of::<G>(), type_name::<G>(), id, |src| {
+                Ok(Box::new(G::deserialize(src)?))
+            });
+        self
+    }
+
+    /// Registers the generator of the simple generator type `SG`, as added by
+    /// `CircuitBuilder::add_simple_generator`.
+    pub fn register_simple_generator<SG: SimpleGenerator<F>>(
+        &mut self,
+        id: &'static str,
+    ) -> &mut Self {
+        self.register_generator::<SimpleGeneratorAdapter<F, SG>>(id)
+    }
+
+    /// The id `gate` is registered under.
+    pub fn gate_id(&self, gate: &GateRef<F, D>) -> Result<&'static str> {
+        let gate = &*gate.0;
+        self.gates
+            .ids
+            .get(&gate.as_any().type_id())
+            .copied()
+            .ok_or_else(|| anyhow!("Gate {} is not registered", gate.type_name()))
+    }
+
+    pub fn write_gate(&self, dst: &mut Vec<u8>, gate: &GateRef<F, D>) -> Result<()> {
+        write_string(dst, self.gate_id(gate)?)?;
+        gate.0
+            .serialize(dst)
+            .map_err(|_| anyhow!("Failed to write gate {}", gate.0.id()))
+    }
+
+    pub fn read_gate(&self, src: &mut Buffer) -> Result<GateRef<F, D>> {
+        let id = read_string(src)?;
+        self.read_gate_params(&id, src)
+    }
+
+    /// Reads the parameters of a gate registered under `id`, as written by its `serialize` method.
+    pub fn read_gate_params(&self, id: &str, src: &mut Buffer) -> Result<GateRef<F, D>> {
+        let reader = self
+            .gates
+            .readers
+            .get(id)
+            .ok_or_else(|| anyhow!("Unknown gate id {}", id))?;
+        reader(src).map_err(|_| anyhow!("Failed to read gate {}", id))
+    }
+
+    pub fn write_generator(
+        &self,
+        dst: &mut Vec<u8>,
+        generator: &dyn WitnessGenerator<F>,
+    ) -> Result<()> {
+        let id = self
+            .generators
+            .ids
+            .get(&generator.as_any().type_id())
+            .ok_or_else(|| anyhow!("Generator {} is not registered", generator.type_name()))?;
+        write_string(dst, id)?;
+        generator
+            .serialize(dst)
+            .map_err(|_| anyhow!("Failed to write generator {}", id))
+    }
+
+    pub fn read_generator(&self, src: &mut Buffer) -> Result<Box<dyn WitnessGenerator<F>>> {
+        let id = read_string(src)?;
+        let reader = self
+            .generators
+            .readers
+            .get(id.as_str())
+            .ok_or_else(|| anyhow!("Unknown generator id {}", id))?;
+        reader(src).map_err(|_| anyhow!("Failed to read generator {}", id))
+    }
+
+    pub fn write_common_data(
+        &self,
+        dst: &mut Vec<u8>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<()> {
+        dst.write_circuit_config(&common_data.config)
+            .and_then(|_| dst.write_fri_params(&common_data.fri_params))
+            .and_then(|_| dst.write_usize(common_data.gates.len()))
+            .map_err(anyhow::Error::msg)?;
+        for gate in &common_data.gates {
+            self.write_gate(dst, gate)?;
+        }
+        write_common_fields(dst, common_data).map_err(anyhow::Error::msg)
+    }
+
+    pub fn read_common_data(&self, src: &mut Buffer) -> Result<CommonCircuitData<F, D>> {
+        let config = src.read_circuit_config().map_err(anyhow::Error::msg)?;
+        let fri_params = src.read_fri_params().map_err(anyhow::Error::msg)?;
+        let num_gates = src.read_usize().map_err(anyhow::Error::msg)?;
+        let gates = (0..num_gates)
+            .map(|_| self.read_gate(src))
+            .collect::<Result<Vec<_>>>()?;
+        read_common_fields(src, config, fri_params, gates).map_err(anyhow::Error::msg)
+    }
+
+    pub fn write_prover_data<C: GenericConfig<D, F = F>>(
+        &self,
+        dst: &mut Vec<u8>,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+    ) -> Result<()> {
+        dst.write_usize(prover_data.generators.len())
+            .map_err(anyhow::Error::msg)?;
+        for generator in &prover_data.generators {
+            self.write_generator(dst, &**generator)?;
+        }
+        dst.write_usize(prover_data.contexts.len())
+            .map_err(anyhow::Error::msg)?;
+        for context in &prover_data.contexts {
+            write_string(dst, context)?;
+        }
+        dst.write_usize_vec(&prover_data.generator_contexts)
+            .and_then(|_| dst.write_usize_vec(&prover_data.gate_contexts))
+            .map_err(anyhow::Error::msg)?;
+        write_prover_fields(dst, prover_data).map_err(anyhow::Error::msg)
+    }
+
+    pub fn read_prover_data<C: GenericConfig<D, F = F>>(
+        &self,
+        src: &mut Buffer,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<ProverOnlyCircuitData<F, C, D>> {
+        let num_generators = src.read_usize().map_err(anyhow::Error::msg)?;
+        let generators = (0..num_generators)
+            .map(|_| self.read_generator(src))
+            .collect::<Result<Vec<_>>>()?;
+        let num_contexts = src.read_usize().map_err(anyhow::Error::msg)?;
+        let contexts = (0..num_contexts)
+            .map(|_| read_string(src))
+            .collect::<Result<Vec<_>>>()?;
+        let generator_contexts = src.read_usize_vec().map_err(anyhow::Error::msg)?;
+        let gate_contexts = src.read_usize_vec().map_err(anyhow::Error::msg)?;
+        ensure!(
+            generator_contexts.len() == num_generators
+                && gate_contexts.len() == common_data.degree()
+                && generator_contexts
+                    .iter()
+                    .chain(&gate_contexts)
+                    .all(|&i| i < num_contexts),
+            "Contexts out of range"
+        );
+        let mut prover_data =
+            read_prover_fields(src, common_data, generators).map_err(anyhow::Error::msg)?;
+        prover_data.contexts = contexts;
+        prover_data.generator_contexts = generator_contexts;
+        prover_data.gate_contexts = gate_contexts;
+        ensure!(
+            prover_data
+                .generator_indices_by_watches
+                .values()
+                .flatten()
+                .all(|&i| i < num_generators),
+            "Generator index out of range"
+        );
+        Ok(prover_data)
+    }
+}
+
+fn write_string(dst: &mut Vec<u8>, string: &str) -> Result<()> {
+    dst.write_usize(string.len())
+        .and_then(|_| dst.write_all(string.as_bytes()))
+        .map_err(anyhow::Error::msg)
+}
+
+fn read_string(src: &mut Buffer) -> Result<String> {
+    let len = src.read_usize().map_err(anyhow::Error::msg)?;
+    ensure!(len <= src.remaining(), "Truncated string");
+    let mut bytes = vec![0; len];
+    src.read_exact(&mut bytes).map_err(anyhow::Error::msg)?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+fn write_common_fields<F: RichField + Extendable<D>, const D: usize>(
+    dst: &mut Vec<u8>,
+    common_data: &CommonCircuitData<F, D>,
+) -> IoResult<()> {
+    dst.write_selectors_info(&common_data.selectors_info)?;
+    dst.write_usize(common_data.quotient_degree_factor)?;
+    dst.write_usize(common_data.num_gate_constraints)?;
+    dst.write_usize(common_data.num_constants)?;
+    dst.write_usize(common_data.num_public_inputs)?;
+    dst.write_usize(common_data.k_is.len())?;
+    dst.write_field_vec(&common_data.k_is)?;
+    dst.write_usize(common_data.num_partial_products)?;
+    dst.write_usize(common_data.num_lookup_slots)?;
+    dst.write_usize(common_data.num_lookup_table_slots)
+}
+
+fn read_common_fields<F: RichField + Extendable<D>, const D: usize>(
+    src: &mut Buffer,
+    config: CircuitConfig,
+    fri_params: FriParams,
+    gates: Vec<GateRef<F, D>>,
+) -> IoResult<CommonCircuitData<F, D>> {
+    let selectors_info = src.read_selectors_info()?;
+    let quotient_degree_factor = src.read_usize()?;
+    let num_gate_constraints = src.read_usize()?;
+    let num_constants = src.read_usize()?;
+    let num_public_inputs = src.read_usize()?;
+    let num_k_is = src.read_usize()?;
+    let k_is = src.read_field_vec(num_k_is)?;
+    let num_partial_products = src.read_usize()?;
+    let num_lookup_slots = src.read_usize()?;
+    let num_lookup_table_slots = src.read_usize()?;
+    Ok(CommonCircuitData {
+        config,
+        fri_params,
+        gates,
+        selectors_info,
+        quotient_degree_factor,
+        num_gate_constraints,
+        num_constants,
+        num_public_inputs,
+        k_is,
+        num_partial_products,
+        num_lookup_slots,
+        num_lookup_table_slots,
+    })
+}
+
+fn write_prover_fields<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    dst: &mut Vec<u8>,
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+) -> IoResult<()> {
+    dst.write_usize(prover_data.generator_indices_by_watches.len())?;
+    for (&watch, indices) in &prover_data.generator_indices_by_watches {
+        dst.write_usize(watch)?;
+        dst.write_usize_vec(indices)?;
+    }
+    write_preprocessed_data(dst, prover_data)?;
+    dst.write_target_vec(&prover_data.public_inputs)?;
+    dst.write_usize_vec(&prover_data.representative_map)?;
+    dst.write_hash::<F, C::Hasher>(prover_data.circuit_digest)?;
+    dst.write_u8(match prover_data.witness_scheduler {
+        WitnessScheduler::Sequential => 0,
+        WitnessScheduler::Parallel => 1,
+    })
+}
+
+fn read_prover_fields<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    src: &mut Buffer,
+    common_data: &CommonCircuitData<F, D>,
+    generators: Vec<Box<dyn WitnessGenerator<F>>>,
+) -> IoResult<ProverOnlyCircuitData<F, C, D>> {
+    let num_watches = src.read_usize()?;
+    let generator_indices_by_watches = (0..num_watches)
+        .map(|_| Ok((src.read_usize()?, src.read_usize_vec()?)))
+        .collect::<IoResult<BTreeMap<_, _>>>()?;
+    let preprocessed =
+        read_preprocessed_data::<F, C, D, _>(src, &common_data.config, common_data.degree_bits())?;
+    let public_inputs = src.read_target_vec()?;
+    let representative_map = src.read_usize_vec()?;
+    let circuit_digest = src.read_hash::<F, C::Hasher>()?;
+    let witness_scheduler = match src.read_u8()? {
+        0 => WitnessScheduler::Sequential,
+        1 => WitnessScheduler::Parallel,
+        _ => return Err(IoError),
+    };
+    if public_inputs.len() != common_data.num_public_inputs {
+        return Err(IoError);
+    }
+    Ok(ProverOnlyCircuitData {
+        generators,
+        generator_indices_by_watches,
+        contexts: Vec::new(),
+        generator_contexts: Vec::new(),
+        gate_contexts: Vec::new(),
+        witness_scheduler,
+        constants_sigmas_commitment: preprocessed.constants_sigmas_commitment,
+        sigmas: preprocessed.sigmas,
+        subgroup: F::two_adic_subgroup(common_data.degree_bits()),
+        public_inputs,
+        representative_map,
+        fft_root_table: preprocessed.fft_root_table,
+        fft_root_table_deg: preprocessed.fft_root_table_deg,
+        circuit_digest,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::{CircuitData, VerifierCircuitTarget};
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    /// A circuit conditionally verifying a proof of a small inner circuit, which covers most of
+    /// the builtin gates and generators, along with its witness.
+    fn recursive_circuit() -> Result<(CircuitData<F, C, D>, PartialWitness<F>)> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let mut pw = PartialWitness::new();
+        let t = builder.add_virtual_target();
+        pw.set_target(t, F::rand());
+        builder.register_public_input(t);
+        let t3 = builder.exp_u64(t, 3);
+        builder.register_public_input(t3);
+        for _ in 0..64 {
+            builder.add_gate(NoopGate, vec![]);
+        }
+        let inner = builder.build::<C>();
+        let inner_proof = inner.prove(pw)?;
+
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut pw = PartialWitness::new();
+        let pt = builder.add_virtual_proof_with_pis::<C>(&inner.common);
+        pw.set_proof_with_pis_target(&pt, &inner_proof);
+        let inner_data = VerifierCircuitTarget {
+            constants_sigmas_cap: builder
+                .add_virtual_cap(inner.common.config.fri_config.cap_height),
+            circuit_digest: builder.add_virtual_hash(),
+        };
+        pw.set_verifier_data_target(&inner_data, &inner.verifier_only);
+        let condition = builder.add_virtual_bool_target_safe();
+        pw.set_bool_target(condition, true);
+        builder.conditionally_verify_proof_or_dummy::<C>(
+            condition,
+            &pt,
+            &inner_data,
+            &inner.common,
+        )?;
+        let x = builder.add_virtual_target();
+        pw.set_target(x, F::rand());
+        let bits = builder.split_le(x, 64);
+        let y = builder.le_sum(bits.into_iter());
+        let y_inv = builder.inverse(y);
+        builder.register_public_input(y_inv);
+
+        Ok((builder.build::<C>(), pw))
+    }
+
+    #[test]
+    fn test_circuit_data_round_trip() -> Result<()> {
+        let (data, pw) = recursive_circuit()?;
+        let registry = CircuitRegistry::new();
+        let bytes = data.to_bytes(&registry)?;
+        let deserialized = CircuitData::<F, C, D>::from_bytes(bytes.clone(), &registry)?;
+
+        assert_eq!(deserialized.common, data.common);
+        assert_eq!(deserialized.verifier_only, data.verifier_only);
+        assert_eq!(deserialized.to_bytes(&registry)?, bytes);
+
+        let proof = data.prove(pw.clone())?;
+        assert_eq!(deserialized.prove(pw)?, proof);
+        deserialized.verify(proof)
+    }
+
+    #[test]
+    fn test_witness_scheduler_round_trip() -> Result<()> {
+        let (mut data, _) = recursive_circuit()?;
+        data.prover_only.witness_scheduler = WitnessScheduler::Parallel;
+        let registry = CircuitRegistry::new();
+        let bytes = data.to_bytes(&registry)?;
+        let deserialized = CircuitData::<F, C, D>::from_bytes(bytes, &registry)?;
+        assert_eq!(
+            deserialized.prover_only.witness_scheduler,
+            WitnessScheduler::Parallel
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unregistered_gate() -> Result<()> {
+        let (data, _) = recursive_circuit()?;
+        assert!(data.to_bytes(&CircuitRegistry::empty()).is_err());
+
+        let mut registry = CircuitRegistry::empty();
+        registry.register_gate::<NoopGate>("NoopGate");
+        let bytes = data.common.to_bytes(&CircuitRegistry::new())?;
+        assert!(CommonCircuitData::from_bytes(bytes, &registry).is_err());
+        Ok(())
+    }
+}
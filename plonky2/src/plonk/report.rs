@@ -0,0 +1,274 @@
+//! Profiles of proofs. [`prove_with_report`](crate::plonk::prover::prove_with_report) returns a
+//! [`ProvingReport`] alongside the proof, with the wall time of each stage of the prover, the
+//! memory held by the commitments on the host and on the backend, the shape of each committed
+//! batch of polynomials, and the number of generator runs.
+//!
+//! A report can be exported as JSON, to compare the profiles of two releases, or in the Chrome
+//! trace-event format, to be opened in `chrome://tracing` or Perfetto.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use serde::{Deserialize, Serialize};
+
+use crate::field::extension::Extendable;
+use crate::fri::oracle::PolynomialBatch;
+use crate::hash::hash_types::RichField;
+use crate::iop::witness::MatrixWitness;
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::util::timing::TimingTree;
+
+/// The profile of a proof.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProvingReport {
+    /// The timed scopes of the prover, in the order they were entered.
+    pub stages: Vec<StageReport>,
+    /// The bytes of the witness and of the commitments held on the host.
+    pub host_bytes: usize,
+    /// The bytes of the working buffer of the backend, or 0 for `ProvingStrategy::Cpu`.
+    pub backend_bytes: usize,
+    /// The batches of polynomials committed to, in the order they were committed to.
+    pub commitments: Vec<CommitmentReport>,
+    /// The number of times a generator was run, or 0 if the witness was not generated by the
+    /// prover.
+    pub generator_runs: usize,
+}
+
+/// The wall time of a scope of the prover. Scopes are nested: a scope of depth `d + 1` belongs to
+/// the last scope of depth `d` before it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StageReport {
+    pub name: String,
+    pub depth: usize,
+    /// Seconds from the start of the proof to the start of this scope.
+    pub start_secs: f64,
+    pub duration_secs: f64,
+}
+
+/// A committed batch of polynomials.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CommitmentReport {
+    pub name: String,
+    pub num_polys: usize,
+    /// The number of coefficients of each polynomial.
+    pub degree: usize,
+    /// The number of points of the LDE of each polynomial.
+    pub lde_size: usize,
+    /// The bytes of the polynomials, leaves and digests of the commitment held on the host.
+    pub host_bytes: usize,
+}
+
+impl ProvingReport {
+    /// Records the stages timed by `timing`.
+    pub(crate) fn record_stages(&mut self, timing: &TimingTree) {
+        self.stages = timing
+            .scopes()
+            .into_iter()
+            .map(|scope| StageReport {
+                name: scope.name,
+                depth: scope.depth,
+                start_secs: scope.start.as_secs_f64(),
+                duration_secs: scope.duration.as_secs_f64(),
+            })
+            .collect();
+    }
+
+    pub(crate) fn record_witness<F: RichField>(&mut self, witness: &MatrixWitness<F>) {
+        let len =
+            witness.wire_values.iter().map(Vec::len).sum::<usize>() + witness.my_wire_values.len();
+        self.host_bytes += len * size_of::<F>();
+    }
+
+    pub(crate) fn record_commitment<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        name: &str,
+        batch: &PolynomialBatch<F, C, D>,
+    ) {
+        let tree = &batch.merkle_tree;
+        let elements = batch
+            .polynomials
+            .iter()
+            .chain(&batch.my_polynomials)
+            .map(|poly| poly.len())
+            .sum::<usize>()
+            + tree.leaves.iter().map(Vec::len).sum::<usize>()
+            + tree.my_leaves.len();
+        let digests = tree.digests.len() + tree.my_digests.len() + tree.cap.0.len();
+        let host_bytes =
+            elements * size_of::<F>() + digests * size_of::<<C::Hasher as Hasher<F>>::Hash>();
+        let degree = 1 << batch.degree_log;
+        self.host_bytes += host_bytes;
+        self.commitments.push(CommitmentReport {
+            name: name.to_string(),
+            num_polys: batch.polynomials.len(),
+            degree,
+            lde_size: degree << batch.rate_bits,
+            host_bytes,
+        });
+    }
+
+    /// The wall time of the proof, i.e. of its outermost stage.
+    pub fn total_secs(&self) -> f64 {
+        self.stages
+            .iter()
+            .filter(|stage| stage.depth == 0)
+            .map(|stage| stage.duration_secs)
+            .sum()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("A report is always serializable")
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// This report in the Chrome trace-event format. Each stage is a complete event, and the memory
+    /// of the proof is a counter at the start of the trace.
+    pub fn to_chrome_trace(&self) -> String {
+        let mut events = self
+            .stages
+            .iter()
+            .map(|stage| TraceEvent {
+                name: stage.name.clone(),
+                cat: "prove",
+                ph: "X",
+                ts: stage.start_secs * 1e6,
+                dur: Some(stage.duration_secs * 1e6),
+                pid: 0,
+                tid: 0,
+                args: None,
+            })
+            .collect::<Vec<_>>();
+        events.push(TraceEvent {
+            name: "memory".to_string(),
+            cat: "prove",
+            ph: "C",
+            ts: 0.0,
+            dur: None,
+            pid: 0,
+            tid: 0,
+            args: Some(MemoryArgs {
+                host_bytes: self.host_bytes,
+                backend_bytes: self.backend_bytes,
+            }),
+        });
+        serde_json::to_string(&ChromeTrace {
+            trace_events: events,
+            display_time_unit: "ms",
+        })
+        .expect("A trace is always serializable")
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChromeTrace {
+    trace_events: Vec<TraceEvent>,
+    display_time_unit: &'static str,
+}
+
+#[derive(Serialize)]
+struct TraceEvent {
+    name: String,
+    cat: &'static str,
+    ph: &'static str,
+    /// Microseconds from the start of the trace.
+    ts: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dur: Option<f64>,
+    pid: u32,
+    tid: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    args: Option<MemoryArgs>,
+}
+
+#[derive(Serialize)]
+struct MemoryArgs {
+    host_bytes: usize,
+    backend_bytes: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::plonk::prover::ProvingStrategy;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_proving_report() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.exp_u64(x, 5);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        for strategy in [ProvingStrategy::Cpu, ProvingStrategy::CpuFlat] {
+            let flattened = strategy.is_flattened();
+            let mut pw = PartialWitness::new();
+            pw.set_target(x, F::rand());
+            let (proof, report) = data.prove_with_report(pw, strategy)?;
+            data.verify(proof)?;
+
+            let names = report
+                .commitments
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["wires", "zs_partial_products", "quotient_polys"]);
+            let wires = &report.commitments[0];
+            assert_eq!(wires.num_polys, data.common.config.num_wires);
+            assert_eq!(wires.degree, data.common.degree());
+            assert_eq!(wires.lde_size, data.common.lde_size());
+            let commitments_bytes = report
+                .commitments
+                .iter()
+                .map(|c| c.host_bytes)
+                .sum::<usize>();
+            assert!(report.host_bytes > commitments_bytes);
+            assert_eq!(report.backend_bytes > 0, flattened);
+            assert!(report.generator_runs >= data.prover_only.generators.len());
+
+            #[cfg(feature = "timing")]
+            {
+                assert_eq!(report.stages[0].name, "prove");
+                assert_eq!(report.stages[0].depth, 0);
+                assert!(report
+                    .stages
+                    .iter()
+                    .any(|s| s.name == "compute wires commitment"));
+                assert_eq!(report.total_secs(), report.stages[0].duration_secs);
+            }
+
+            let decoded = ProvingReport::from_json(&report.to_json())?;
+            assert_eq!(decoded.commitments, report.commitments);
+            assert_eq!(decoded.stages.len(), report.stages.len());
+            assert_eq!(decoded.host_bytes, report.host_bytes);
+            assert_eq!(decoded.generator_runs, report.generator_runs);
+            let trace: serde_json::Value = serde_json::from_str(&report.to_chrome_trace())?;
+            let events = trace["traceEvents"].as_array().unwrap();
+            assert_eq!(events.len(), report.stages.len() + 1);
+            assert_eq!(
+                events.last().unwrap()["args"]["host_bytes"],
+                report.host_bytes
+            );
+        }
+        Ok(())
+    }
+}
@@ -60,9 +60,9 @@ where
     ensure!(constants.len() == common_data.num_constants);
     ensure!(plonk_sigmas.len() == config.num_routed_wires);
     ensure!(wires.len() == config.num_wires);
-    ensure!(plonk_zs.len() == config.num_challenges);
-    ensure!(plonk_zs_next.len() == config.num_challenges);
-    ensure!(partial_products.len() == config.num_challenges * common_data.num_partial_products);
+    ensure!(plonk_zs.len() == common_data.zs_range().len());
+    ensure!(plonk_zs_next.len() == common_data.zs_range().len());
+    ensure!(partial_products.len() == common_data.num_partial_products_polys());
     ensure!(quotient_polys.len() == common_data.num_quotient_polys());
     Ok(())
 }
@@ -11,6 +11,7 @@ use crate::iop::target::Target;
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::circuit_data::CommonCircuitData;
 use crate::plonk::config::GenericConfig;
+use crate::plonk::lookup::{check_lookups, check_lookups_circuit};
 use crate::plonk::plonk_common;
 use crate::plonk::plonk_common::eval_l_0_circuit;
 use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBaseBatch};
@@ -47,10 +48,16 @@ pub(crate) fn eval_vanishing_poly<
     let mut vanishing_z_1_terms = Vec::new();
     // The terms checking the partial products.
     let mut vanishing_partial_products_terms = Vec::new();
+    // The terms of the lookup argument.
+    let mut vanishing_lookup_terms = Vec::new();
+
+    let num_challenges = common_data.config.num_challenges;
+    let num_helpers = common_data.num_lookup_helpers();
+    let lookup_constants = &vars.local_constants[common_data.lookup_constants_range()];
 
     let l_0_x = plonk_common::eval_l_0(common_data.degree(), x);
 
-    for i in 0..common_data.config.num_challenges {
+    for i in 0..num_challenges {
         let z_x = local_zs[i];
         let z_gx = next_zs[i];
         vanishing_z_1_terms.push(l_0_x * (z_x - F::Extension::ONE));
@@ -83,11 +90,26 @@ pub(crate) fn eval_vanishing_poly<
             max_degree,
         );
         vanishing_partial_products_terms.extend(partial_product_checks);
+
+        if common_data.has_lookups() {
+            let helpers_start = num_challenges * num_prods + i * num_helpers;
+            vanishing_lookup_terms.extend(check_lookups(
+                common_data,
+                lookup_constants,
+                vars.local_wires,
+                local_zs[num_challenges + i],
+                next_zs[num_challenges + i],
+                &partial_products[helpers_start..helpers_start + num_helpers],
+                gammas[i].into(),
+                betas[i].into(),
+            ));
+        }
     }
 
     let vanishing_terms = [
         vanishing_z_1_terms,
         vanishing_partial_products_terms,
+        vanishing_lookup_terms,
         constraint_terms,
     ]
     .concat();
@@ -142,6 +164,13 @@ pub(crate) fn eval_vanishing_poly_base_batch<
     let mut vanishing_z_1_terms = Vec::with_capacity(num_challenges);
     // The terms checking the partial products.
     let mut vanishing_partial_products_terms = Vec::new();
+    // The terms of the lookup argument.
+    let mut vanishing_lookup_terms = Vec::new();
+
+    let num_helpers = common_data.num_lookup_helpers();
+    let num_lookup_wires = common_data
+        .num_lookup_table_slots
+        .max(2 * common_data.num_lookup_slots);
 
     let mut res_batch: Vec<Vec<F>> = Vec::with_capacity(n);
     for k in 0..n {
@@ -199,6 +228,27 @@ pub(crate) fn eval_vanishing_poly_base_batch<
             );
             vanishing_partial_products_terms.extend(partial_product_checks);
 
+            if common_data.has_lookups() {
+                let lookup_constants = common_data
+                    .lookup_constants_range()
+                    .map(|j| vars.local_constants[j])
+                    .collect::<Vec<_>>();
+                let lookup_wires = (0..num_lookup_wires)
+                    .map(|j| vars.local_wires[j])
+                    .collect::<Vec<_>>();
+                let helpers_start = num_challenges * num_prods + i * num_helpers;
+                vanishing_lookup_terms.extend(check_lookups(
+                    common_data,
+                    &lookup_constants,
+                    &lookup_wires,
+                    local_zs[num_challenges + i],
+                    next_zs[num_challenges + i],
+                    &partial_products[helpers_start..helpers_start + num_helpers],
+                    gammas[i],
+                    betas[i],
+                ));
+            }
+
             // if (index == 1048576 ) {
             //     println!("i: {}, numerator_values: {:?}", index, numerator_values);
             //     println!("i: {}, denominator_values: {:?}", index, denominator_values);
@@ -215,12 +265,14 @@ pub(crate) fn eval_vanishing_poly_base_batch<
         let vanishing_terms = vanishing_z_1_terms
             .iter()
             .chain(vanishing_partial_products_terms.iter())
+            .chain(vanishing_lookup_terms.iter())
             .chain(constraint_terms);
         let res = plonk_common::reduce_with_powers_multi(vanishing_terms, alphas);
         res_batch.push(res);
 
         vanishing_z_1_terms.clear();
         vanishing_partial_products_terms.clear();
+        vanishing_lookup_terms.clear();
     }
     res_batch
 }
@@ -371,6 +423,12 @@ pub(crate) fn eval_vanishing_poly_circuit<
     let mut vanishing_z_1_terms = Vec::new();
     // The terms checking the partial products.
     let mut vanishing_partial_products_terms = Vec::new();
+    // The terms of the lookup argument.
+    let mut vanishing_lookup_terms = Vec::new();
+
+    let num_challenges = common_data.config.num_challenges;
+    let num_helpers = common_data.num_lookup_helpers();
+    let lookup_constants = &vars.local_constants[common_data.lookup_constants_range()];
 
     let l_0_x = eval_l_0_circuit(builder, common_data.degree(), x, x_pow_deg);
 
@@ -381,7 +439,7 @@ pub(crate) fn eval_vanishing_poly_circuit<
         s_ids.push(builder.scalar_mul_ext(k, x));
     }
 
-    for i in 0..common_data.config.num_challenges {
+    for i in 0..num_challenges {
         let z_x = local_zs[i];
         let z_gx = next_zs[i];
 
@@ -419,11 +477,34 @@ pub(crate) fn eval_vanishing_poly_circuit<
             max_degree,
         );
         vanishing_partial_products_terms.extend(partial_product_checks);
+
+        if common_data.has_lookups() {
+            let helpers_start = num_challenges * num_prods + i * num_helpers;
+            let alpha = builder.convert_to_ext(gammas[i]);
+            let delta = builder.convert_to_ext(betas[i]);
+            let lookup_checks = with_context!(
+                builder,
+                "check lookups",
+                check_lookups_circuit(
+                    builder,
+                    common_data,
+                    lookup_constants,
+                    vars.local_wires,
+                    local_zs[num_challenges + i],
+                    next_zs[num_challenges + i],
+                    &partial_products[helpers_start..helpers_start + num_helpers],
+                    alpha,
+                    delta,
+                )
+            );
+            vanishing_lookup_terms.extend(lookup_checks);
+        }
     }
 
     let vanishing_terms = [
         vanishing_z_1_terms,
         vanishing_partial_products_terms,
+        vanishing_lookup_terms,
         constraint_terms,
     ]
     .concat();
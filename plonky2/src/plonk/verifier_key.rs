@@ -0,0 +1,272 @@
+//! A standalone encoding of verifier keys. A verifier key is the [`VerifierOnlyCircuitData`] of a
+//! circuit, along with the [`CommonCircuitData`] the verifier needs, so that a service can check
+//! proofs with [`verify_from_bytes`] without building the circuit.
+//!
+//! The binary encoding starts with a magic number and [`VERIFIER_KEY_FORMAT_VERSION`]. The JSON
+//! encoding has the same fields, with each gate given by its registry id and the bytes written by
+//! its `serialize` method.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::field::extension::Extendable;
+use crate::fri::FriParams;
+use crate::gates::selectors::SelectorsInfo;
+use crate::hash::hash_types::RichField;
+use crate::hash::merkle_tree::MerkleCap;
+use crate::plonk::circuit_data::{
+    CircuitConfig, CommonCircuitData, VerifierCircuitData, VerifierOnlyCircuitData,
+};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::proof::ProofWithPublicInputs;
+use crate::plonk::registry::CircuitRegistry;
+use crate::util::serialization::{Buffer, Read, Remaining, Write};
+
+/// The version of the verifier key encodings. It is bumped whenever the encoding of a verifier key
+/// changes, so that keys of another version are rejected instead of misread.
+pub const VERIFIER_KEY_FORMAT_VERSION: u32 = 2;
+
+const MAGIC: &[u8; 8] = b"PLKYVKEY";
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    VerifierCircuitData<F, C, D>
+{
+    /// Encodes this verifier key. Every gate of the circuit must be registered in `registry`.
+    pub fn to_bytes(&self, registry: &CircuitRegistry<F, D>) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        buffer
+            .write_all(MAGIC)
+            .and_then(|_| buffer.write_u32(VERIFIER_KEY_FORMAT_VERSION))
+            .map_err(anyhow::Error::msg)?;
+        registry.write_common_data(&mut buffer, &self.common)?;
+        buffer
+            .write_verifier_only_circuit_data(&self.verifier_only)
+            .map_err(anyhow::Error::msg)?;
+        Ok(buffer)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, registry: &CircuitRegistry<F, D>) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let mut magic = [0; MAGIC.len()];
+        buffer.read_exact(&mut magic).map_err(anyhow::Error::msg)?;
+        ensure!(&magic == MAGIC, "Not a verifier key");
+        let version = buffer.read_u32().map_err(anyhow::Error::msg)?;
+        check_version(version)?;
+        let common = registry.read_common_data(&mut buffer)?;
+        let verifier_only = buffer
+            .read_verifier_only_circuit_data(common.config.fri_config.cap_height)
+            .map_err(anyhow::Error::msg)?;
+        ensure!(buffer.is_empty(), "Trailing bytes after the verifier key");
+        Ok(Self {
+            verifier_only,
+            common,
+        })
+    }
+
+    /// Encodes this verifier key as JSON. Every gate of the circuit must be registered in
+    /// `registry`.
+    pub fn to_json(&self, registry: &CircuitRegistry<F, D>) -> Result<String> {
+        let common = &self.common;
+        let gates = common
+            .gates
+            .iter()
+            .map(|gate| {
+                let mut params = Vec::new();
+                gate.0.serialize(&mut params).map_err(anyhow::Error::msg)?;
+                Ok(GateJson {
+                    id: registry.gate_id(gate)?.into(),
+                    params,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let key = VerifierKeyJson::<F, C, D> {
+            version: VERIFIER_KEY_FORMAT_VERSION,
+            config: common.config.clone(),
+            fri_params: common.fri_params.clone(),
+            gates,
+            selectors_info: common.selectors_info.clone(),
+            quotient_degree_factor: common.quotient_degree_factor,
+            num_gate_constraints: common.num_gate_constraints,
+            num_constants: common.num_constants,
+            num_public_inputs: common.num_public_inputs,
+            k_is: common.k_is.clone(),
+            num_partial_products: common.num_partial_products,
+            num_lookup_slots: common.num_lookup_slots,
+            num_lookup_table_slots: common.num_lookup_table_slots,
+            constants_sigmas_cap: self.verifier_only.constants_sigmas_cap.clone(),
+            circuit_digest: self.verifier_only.circuit_digest,
+        };
+        Ok(serde_json::to_string(&key)?)
+    }
+
+    pub fn from_json(json: &str, registry: &CircuitRegistry<F, D>) -> Result<Self> {
+        let key: VerifierKeyJson<F, C, D> = serde_json::from_str(json)?;
+        check_version(key.version)?;
+        let gates = key
+            .gates
+            .into_iter()
+            .map(|gate| {
+                let mut params = Buffer::new(gate.params);
+                let gate_ref = registry.read_gate_params(&gate.id, &mut params)?;
+                ensure!(params.is_empty(), "Trailing bytes after gate {}", gate.id);
+                Ok(gate_ref)
+            })
+            .collect::<Result<_>>()?;
+        ensure!(
+            key.constants_sigmas_cap.len() == key.config.fri_config.num_cap_elements(),
+            "The constants and sigmas cap has the wrong height"
+        );
+        Ok(Self {
+            verifier_only: VerifierOnlyCircuitData {
+                constants_sigmas_cap: key.constants_sigmas_cap,
+                circuit_digest: key.circuit_digest,
+            },
+            common: CommonCircuitData {
+                config: key.config,
+                fri_params: key.fri_params,
+                gates,
+                selectors_info: key.selectors_info,
+                quotient_degree_factor: key.quotient_degree_factor,
+                num_gate_constraints: key.num_gate_constraints,
+                num_constants: key.num_constants,
+                num_public_inputs: key.num_public_inputs,
+                k_is: key.k_is,
+                num_partial_products: key.num_partial_products,
+                num_lookup_slots: key.num_lookup_slots,
+                num_lookup_table_slots: key.num_lookup_table_slots,
+            },
+        })
+    }
+}
+
+/// Verifies a proof, encoded by `ProofWithPublicInputs::to_bytes`, against a verifier key encoded
+/// by `VerifierCircuitData::to_bytes`. The circuit may only use the gates of this crate; see
+/// [`verify_from_bytes_with_registry`] otherwise.
+pub fn verify_from_bytes<F, C, const D: usize>(
+    vk_bytes: Vec<u8>,
+    proof_bytes: Vec<u8>,
+) -> Result<()>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    verify_from_bytes_with_registry::<F, C, D>(vk_bytes, proof_bytes, &CircuitRegistry::new())
+}
+
+/// Like [`verify_from_bytes`], reading the gates of the verifier key from `registry`.
+pub fn verify_from_bytes_with_registry<F, C, const D: usize>(
+    vk_bytes: Vec<u8>,
+    proof_bytes: Vec<u8>,
+    registry: &CircuitRegistry<F, D>,
+) -> Result<()>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    let data = VerifierCircuitData::<F, C, D>::from_bytes(vk_bytes, registry)?;
+    let proof = ProofWithPublicInputs::from_bytes(proof_bytes, &data.common)?;
+    data.verify(proof)
+}
+
+fn check_version(version: u32) -> Result<()> {
+    ensure!(
+        version == VERIFIER_KEY_FORMAT_VERSION,
+        "Verifier key version {} is not supported, expected version {}",
+        version,
+        VERIFIER_KEY_FORMAT_VERSION
+    );
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+struct GateJson {
+    id: String,
+    params: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+struct VerifierKeyJson<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    version: u32,
+    config: CircuitConfig,
+    fri_params: FriParams,
+    gates: Vec<GateJson>,
+    selectors_info: SelectorsInfo,
+    quotient_degree_factor: usize,
+    num_gate_constraints: usize,
+    num_constants: usize,
+    num_public_inputs: usize,
+    k_is: Vec<F>,
+    num_partial_products: usize,
+    num_lookup_slots: usize,
+    num_lookup_table_slots: usize,
+    constants_sigmas_cap: MerkleCap<F, C::Hasher>,
+    circuit_digest: <C::Hasher as Hasher<F>>::Hash,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::{Field, Sample};
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitData;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn circuit_and_proof() -> Result<(CircuitData<F, C, D>, ProofWithPublicInputs<F, C, D>)> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let x_inv = builder.inverse(x);
+        let y = builder.exp_u64(x_inv, 5);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::rand());
+        let proof = data.prove(pw)?;
+        Ok((data, proof))
+    }
+
+    #[test]
+    fn test_verify_from_bytes() -> Result<()> {
+        let (data, proof) = circuit_and_proof()?;
+        let proof_bytes = proof.to_bytes();
+        let vk_bytes = data.verifier_data().to_bytes(&CircuitRegistry::new())?;
+        verify_from_bytes::<F, C, D>(vk_bytes.clone(), proof_bytes.clone())?;
+
+        let mut wrong_proof = proof;
+        wrong_proof.public_inputs[0] += F::ONE;
+        assert!(verify_from_bytes::<F, C, D>(vk_bytes, wrong_proof.to_bytes()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_round_trip() -> Result<()> {
+        let (data, proof) = circuit_and_proof()?;
+        let registry = CircuitRegistry::new();
+        let verifier_data = data.verifier_data();
+        let json = verifier_data.to_json(&registry)?;
+        let decoded = VerifierCircuitData::<F, C, D>::from_json(&json, &registry)?;
+
+        assert_eq!(decoded.common, verifier_data.common);
+        assert_eq!(decoded.verifier_only, verifier_data.verifier_only);
+        decoded.verify(proof)
+    }
+
+    #[test]
+    fn test_reject_other_version() -> Result<()> {
+        let (data, _) = circuit_and_proof()?;
+        let registry = CircuitRegistry::new();
+        let mut vk_bytes = data.verifier_data().to_bytes(&registry)?;
+        vk_bytes[MAGIC.len()] += 1;
+        assert!(VerifierCircuitData::<F, C, D>::from_bytes(vk_bytes, &registry).is_err());
+        Ok(())
+    }
+}
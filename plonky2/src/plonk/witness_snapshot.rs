@@ -0,0 +1,304 @@
+//! Snapshots of generated witnesses. A [`WitnessSnapshot`] holds the wire values of a proof along
+//! with the `PartialWitness` they were generated from, so that a failing proof can be reproduced,
+//! or committed to on another machine, with `prove_from_witness` and without running the
+//! generators again.
+//!
+//! A snapshot starts with a format version and the digest of its circuit, and is only proved
+//! against the circuit with that digest.
+
+use alloc::vec::Vec;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write as _};
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::RichField;
+use crate::iop::generator::generate_partial_witness;
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{MatrixWitness, PartialWitness, Witness};
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::error::{ensure_shape, ProverResult};
+use crate::plonk::preprocessed::{FileReader, FileWriter};
+use crate::util::serialization::{Buffer, IoError, IoResult, Read, Remaining, Write};
+
+/// Version of the snapshot format, bumped whenever the layout of a snapshot changes.
+pub const WITNESS_SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 8] = b"PLKYWITN";
+
+/// The witness of a proof, as generated from `inputs`.
+#[derive(Clone, Debug)]
+pub struct WitnessSnapshot<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+{
+    /// The digest of the circuit the witness was generated for.
+    pub circuit_digest: <C::Hasher as Hasher<F>>::Hash,
+    pub inputs: PartialWitness<F>,
+    pub public_inputs: Vec<F>,
+    /// The number of rows of the circuit.
+    pub degree: usize,
+    /// The values of the wires, column-major: the value of row `i` of column `j` is at
+    /// `j * degree + i`.
+    pub wire_values: Vec<F>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    WitnessSnapshot<F, C, D>
+{
+    /// Runs the generators on `inputs` and records the resulting witness.
+    pub fn generate(
+        inputs: PartialWitness<F>,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> ProverResult<Self> {
+        let partition_witness = generate_partial_witness(inputs.clone(), prover_data, common_data)?;
+        let public_inputs = partition_witness.get_targets(&prover_data.public_inputs);
+        let wire_values = partition_witness.my_full_witness().my_wire_values;
+        Ok(Self {
+            circuit_digest: prover_data.circuit_digest,
+            inputs,
+            public_inputs,
+            degree: common_data.degree(),
+            wire_values,
+        })
+    }
+
+    /// Fails if the snapshot is not a witness of the circuit of `prover_data`.
+    pub(crate) fn ensure_matches(
+        &self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> ProverResult<()> {
+        ensure_shape!(
+            self.circuit_digest == prover_data.circuit_digest,
+            "The witness snapshot is of another circuit"
+        );
+        ensure_shape!(
+            self.degree == common_data.degree()
+                && self.wire_values.len() == common_data.config.num_wires * self.degree
+                && self.public_inputs.len() == prover_data.public_inputs.len(),
+            "The witness snapshot does not have the shape of the circuit"
+        );
+        Ok(())
+    }
+
+    /// The wire values, in the layout expected by the classic or the flattened commitments.
+    pub(crate) fn matrix_witness(self, flattened: bool) -> MatrixWitness<F> {
+        let degree = self.degree;
+        if flattened {
+            MatrixWitness {
+                wire_values: Vec::new(),
+                my_wire_values: self.wire_values,
+                degree,
+            }
+        } else {
+            MatrixWitness {
+                wire_values: self
+                    .wire_values
+                    .chunks(degree)
+                    .map(|c| c.to_vec())
+                    .collect(),
+                my_wire_values: Vec::new(),
+                degree: 0,
+            }
+        }
+    }
+
+    fn num_wires(&self) -> usize {
+        self.wire_values.len() / self.degree
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.write(&mut buffer)
+            .expect("Writing to a byte-vector cannot fail.");
+        buffer
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, common_data: &CommonCircuitData<F, D>) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let snapshot = Self::read(&mut buffer, common_data)?;
+        ensure!(
+            buffer.is_empty(),
+            "Trailing bytes after the witness snapshot"
+        );
+        Ok(snapshot)
+    }
+
+    pub fn write_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut writer = FileWriter(BufWriter::new(File::create(path)?));
+        self.write(&mut writer).map_err(anyhow::Error::msg)?;
+        writer.0.flush()?;
+        Ok(())
+    }
+
+    pub fn read_file(
+        path: impl AsRef<Path>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<Self> {
+        let mut reader = FileReader(BufReader::new(File::open(path)?));
+        let snapshot = Self::read(&mut reader, common_data)?;
+        ensure!(
+            reader.0.fill_buf()?.is_empty(),
+            "Trailing bytes after the witness snapshot"
+        );
+        Ok(snapshot)
+    }
+
+    fn write<W: Write>(&self, dst: &mut W) -> IoResult<()> {
+        dst.write_all(MAGIC)?;
+        dst.write_u32(WITNESS_SNAPSHOT_FORMAT_VERSION)?;
+        dst.write_hash::<F, C::Hasher>(self.circuit_digest)?;
+        let (num_wires, degree) = (self.num_wires(), self.degree);
+        dst.write_usize(num_wires)?;
+        dst.write_usize(degree)?;
+
+        // Sorted, so that a witness is always written the same way.
+        let mut inputs = self.inputs.target_values.iter().collect::<Vec<_>>();
+        inputs.sort_unstable_by_key(|(t, _)| t.index(num_wires, degree));
+        dst.write_usize(inputs.len())?;
+        for (&target, &value) in inputs {
+            dst.write_target(target)?;
+            dst.write_field(value)?;
+        }
+
+        dst.write_usize(self.public_inputs.len())?;
+        dst.write_field_vec(&self.public_inputs)?;
+        dst.write_field_vec(&self.wire_values)
+    }
+
+    fn read<R: Read>(src: &mut R, common_data: &CommonCircuitData<F, D>) -> Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        src.read_exact(&mut magic).map_err(anyhow::Error::msg)?;
+        ensure!(&magic == MAGIC, "Not a witness snapshot");
+        let version = src.read_u32().map_err(anyhow::Error::msg)?;
+        ensure!(
+            version == WITNESS_SNAPSHOT_FORMAT_VERSION,
+            "Witness snapshot version {} is not supported, expected version {}",
+            version,
+            WITNESS_SNAPSHOT_FORMAT_VERSION
+        );
+        let circuit_digest = src
+            .read_hash::<F, C::Hasher>()
+            .map_err(anyhow::Error::msg)?;
+        let num_wires = src.read_usize().map_err(anyhow::Error::msg)?;
+        let degree = src.read_usize().map_err(anyhow::Error::msg)?;
+        ensure!(
+            num_wires == common_data.config.num_wires && degree == common_data.degree(),
+            "Witness snapshot of a circuit of another shape"
+        );
+        read_snapshot_fields(src, num_wires, degree, common_data.num_public_inputs)
+            .map(|(inputs, public_inputs, wire_values)| Self {
+                circuit_digest,
+                inputs,
+                public_inputs,
+                degree,
+                wire_values,
+            })
+            .map_err(anyhow::Error::msg)
+    }
+}
+
+fn read_snapshot_fields<F: RichField, R: Read>(
+    src: &mut R,
+    num_wires: usize,
+    degree: usize,
+    num_public_inputs: usize,
+) -> IoResult<(PartialWitness<F>, Vec<F>, Vec<F>)> {
+    let num_inputs = src.read_usize()?;
+    let mut inputs = PartialWitness::new();
+    for _ in 0..num_inputs {
+        let target = src.read_target()?;
+        if let Target::Wire(Wire { row, column }) = target {
+            if row >= degree || column >= num_wires {
+                return Err(IoError);
+            }
+        }
+        inputs.target_values.insert(target, src.read_field()?);
+    }
+    if src.read_usize()? != num_public_inputs {
+        return Err(IoError);
+    }
+    let public_inputs = src.read_field_vec(num_public_inputs)?;
+    let wire_values = src.read_field_vec(num_wires * degree)?;
+    Ok((inputs, public_inputs, wire_values))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use std::env;
+
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::iop::witness::WitnessWrite;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::plonk::error::ProverError;
+    use crate::plonk::prover::ProvingStrategy;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn circuit(exponent: u64) -> (CircuitData<F, C, D>, PartialWitness<F>) {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.exp_u64(x, exponent);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::rand());
+        (data, pw)
+    }
+
+    #[test]
+    fn test_prove_from_witness() -> Result<()> {
+        let (data, pw) = circuit(5);
+        let proof = data.prove(pw.clone())?;
+
+        let snapshot = data.generate_witness_snapshot(pw)?;
+        let bytes = snapshot.to_bytes();
+        assert_eq!(bytes, snapshot.to_bytes());
+        let snapshot = WitnessSnapshot::<F, C, D>::from_bytes(bytes, &data.common)?;
+        for strategy in [ProvingStrategy::Cpu, ProvingStrategy::CpuFlat] {
+            let replayed = data.prove_from_witness(snapshot.clone(), strategy)?;
+            assert_eq!(replayed, proof);
+        }
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_file_round_trip() -> Result<()> {
+        let (data, pw) = circuit(3);
+        let snapshot = data.generate_witness_snapshot(pw)?;
+        let path = env::temp_dir().join(format!("plonky2-witness-{}.bin", std::process::id()));
+        snapshot.write_file(&path)?;
+        let read = WitnessSnapshot::<F, C, D>::read_file(&path, &data.common);
+        std::fs::remove_file(&path)?;
+        let read = read?;
+
+        assert_eq!(read.circuit_digest, snapshot.circuit_digest);
+        assert_eq!(read.public_inputs, snapshot.public_inputs);
+        assert_eq!(read.wire_values, snapshot.wire_values);
+        assert_eq!(read.inputs.target_values, snapshot.inputs.target_values);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_other_circuit() -> Result<()> {
+        let (data, pw) = circuit(3);
+        let (other_data, _) = circuit(5);
+        let snapshot = data.generate_witness_snapshot(pw)?;
+        let err = other_data
+            .prove_from_witness(snapshot, ProvingStrategy::Cpu)
+            .unwrap_err();
+        assert!(matches!(err, ProverError::Shape(_)), "{}", err);
+        Ok(())
+    }
+}
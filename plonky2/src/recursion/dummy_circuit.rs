@@ -1,4 +1,5 @@
 use alloc::vec;
+use alloc::vec::Vec;
 
 use hashbrown::HashMap;
 use plonky2_field::extension::Extendable;
@@ -15,6 +16,7 @@ use crate::plonk::circuit_data::{
 };
 use crate::plonk::config::{AlgebraicHasher, GenericConfig};
 use crate::plonk::proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget};
+use crate::util::serialization::{Buffer, IoResult, Read, Write};
 
 /// Creates a dummy proof which is suitable for use as a base proof in a cyclic recursion tree.
 /// Such a base proof will not actually be verified, so most of its data is arbitrary. However, its
@@ -119,41 +121,49 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             circuit_digest: self.add_virtual_hash(),
         };
 
+        let mut values = GeneratedValues::empty();
+        values.set_proof_with_pis_target(&dummy_proof_with_pis_target, &dummy_proof_with_pis);
+        values.set_verifier_data_target(&dummy_verifier_data_target, &dummy_circuit.verifier_only);
         self.add_simple_generator(DummyProofGenerator {
-            proof_with_pis_target: dummy_proof_with_pis_target.clone(),
-            proof_with_pis: dummy_proof_with_pis,
-            verifier_data_target: dummy_verifier_data_target.clone(),
-            verifier_data: dummy_circuit.verifier_only,
+            target_values: values.target_values,
         });
 
         Ok((dummy_proof_with_pis_target, dummy_verifier_data_target))
     }
 }
 
+/// Sets the targets of the dummy proof and verifier data. They are stored as a list of values,
+/// so that the generator can be serialized without the common data of the dummy circuit.
 #[derive(Debug)]
-pub(crate) struct DummyProofGenerator<F, C, const D: usize>
-where
-    F: RichField + Extendable<D>,
-    C: GenericConfig<D, F = F>,
-{
-    pub(crate) proof_with_pis_target: ProofWithPublicInputsTarget<D>,
-    pub(crate) proof_with_pis: ProofWithPublicInputs<F, C, D>,
-    pub(crate) verifier_data_target: VerifierCircuitTarget,
-    pub(crate) verifier_data: VerifierOnlyCircuitData<C, D>,
+pub(crate) struct DummyProofGenerator<F: RichField> {
+    pub(crate) target_values: Vec<(Target, F)>,
 }
 
-impl<F, C, const D: usize> SimpleGenerator<F> for DummyProofGenerator<F, C, D>
-where
-    F: RichField + Extendable<D>,
-    C: GenericConfig<D, F = F> + 'static,
-    C::Hasher: AlgebraicHasher<F>,
-{
+impl<F: RichField> SimpleGenerator<F> for DummyProofGenerator<F> {
     fn dependencies(&self) -> Vec<Target> {
         vec![]
     }
 
     fn run_once(&self, _witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
-        out_buffer.set_proof_with_pis_target(&self.proof_with_pis_target, &self.proof_with_pis);
-        out_buffer.set_verifier_data_target(&self.verifier_data_target, &self.verifier_data);
+        for &(target, value) in &self.target_values {
+            out_buffer.set_target(target, value);
+        }
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_usize(self.target_values.len())?;
+        for &(target, value) in &self.target_values {
+            dst.write_target(target)?;
+            dst.write_field(value)?;
+        }
+        Ok(())
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let len = src.read_usize()?;
+        let target_values = (0..len)
+            .map(|_| Ok((src.read_target()?, src.read_field()?)))
+            .collect::<IoResult<Vec<_>>>()?;
+        Ok(Self { target_values })
     }
 }
@@ -168,14 +168,14 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         common_data: &CommonCircuitData<F, D>,
     ) -> OpeningSetTarget<D> {
         let config = &common_data.config;
-        let num_challenges = config.num_challenges;
-        let total_partial_products = num_challenges * common_data.num_partial_products;
+        let num_zs = common_data.zs_range().len();
+        let total_partial_products = common_data.num_partial_products_polys();
         OpeningSetTarget {
             constants: self.add_virtual_extension_targets(common_data.num_constants),
             plonk_sigmas: self.add_virtual_extension_targets(config.num_routed_wires),
             wires: self.add_virtual_extension_targets(config.num_wires),
-            plonk_zs: self.add_virtual_extension_targets(num_challenges),
-            plonk_zs_next: self.add_virtual_extension_targets(num_challenges),
+            plonk_zs: self.add_virtual_extension_targets(num_zs),
+            plonk_zs_next: self.add_virtual_extension_targets(num_zs),
             partial_products: self.add_virtual_extension_targets(total_partial_products),
             quotient_polys: self.add_virtual_extension_targets(common_data.num_quotient_polys()),
         }
@@ -193,9 +193,11 @@ mod tests {
     use crate::gates::noop::NoopGate;
     use crate::iop::witness::{PartialWitness, WitnessWrite};
     use crate::plonk::circuit_data::{CircuitConfig, VerifierOnlyCircuitData};
-    use crate::plonk::config::{GenericConfig, KeccakGoldilocksConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::config::{
+        GenericConfig, KeccakGoldilocksConfig, Poseidon2GoldilocksConfig, PoseidonGoldilocksConfig,
+    };
     use crate::plonk::proof::{CompressedProofWithPublicInputs, ProofWithPublicInputs};
-    use crate::plonk::prover::prove;
+    use crate::plonk::prover::{prove, ProvingStrategy};
     use crate::util::timing::TimingTree;
 
     #[test]
@@ -320,6 +322,23 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_recursive_verifier_poseidon2() -> Result<()> {
+        init_logger();
+        const D: usize = 2;
+        type C = Poseidon2GoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let (proof, vd, cd) = dummy_proof::<F, C, D>(&config, 4_000)?;
+
+        let (proof, vd, cd) =
+            recursive_proof::<F, C, C, D>(proof, vd, cd, &config, None, true, true)?;
+        test_serialization(&proof, &vd, &cd)?;
+
+        Ok(())
+    }
+
     type Proof<F, C, const D: usize> = (
         ProofWithPublicInputs<F, C, D>,
         VerifierOnlyCircuitData<C, D>,
@@ -395,7 +414,13 @@ mod tests {
         let data = builder.build::<C>();
 
         let mut timing = TimingTree::new("prove", Level::Debug);
-        let proof = prove(&data.prover_only, &data.common, pw, &mut timing)?;
+        let proof = prove(
+            &data.prover_only,
+            &data.common,
+            pw,
+            &mut timing,
+            ProvingStrategy::Cpu,
+        )?;
         if print_timing {
             timing.print();
         }
@@ -13,10 +13,16 @@ use crate::fri::proof::{
     CompressedFriProof, CompressedFriQueryRounds, FriInitialTreeProof, FriProof, FriQueryRound,
     FriQueryStep,
 };
+use crate::fri::reduction_strategies::FriReductionStrategy;
+use crate::fri::{FriConfig, FriParams};
+use crate::gates::selectors::SelectorsInfo;
 use crate::hash::hash_types::RichField;
 use crate::hash::merkle_proofs::MerkleProof;
 use crate::hash::merkle_tree::MerkleCap;
-use crate::plonk::circuit_data::CommonCircuitData;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::target::{BoolTarget, Target};
+use crate::iop::wire::Wire;
+use crate::plonk::circuit_data::{CircuitConfig, CommonCircuitData, VerifierOnlyCircuitData};
 use crate::plonk::config::{GenericConfig, GenericHashOut, Hasher};
 use crate::plonk::plonk_common::salt_size;
 use crate::plonk::proof::{
@@ -68,6 +74,168 @@ pub trait Read {
         Ok(u32::from_le_bytes(buf))
     }
 
+    /// Reads a `u64` value from `self`.
+    #[inline]
+    fn read_u64(&mut self) -> IoResult<u64> {
+        let mut buf = [0; size_of::<u64>()];
+        self.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Reads a `usize` value, written as a `u64`, from `self`.
+    #[inline]
+    fn read_usize(&mut self) -> IoResult<usize> {
+        usize::try_from(self.read_u64()?).map_err(|_| IoError)
+    }
+
+    /// Reads a `bool` value from `self`.
+    #[inline]
+    fn read_bool(&mut self) -> IoResult<bool> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(IoError),
+        }
+    }
+
+    /// Reads a value of type [`FriConfig`] from `self`.
+    #[inline]
+    fn read_fri_config(&mut self) -> IoResult<FriConfig> {
+        let rate_bits = self.read_usize()?;
+        let cap_height = self.read_usize()?;
+        let proof_of_work_bits = self.read_u32()?;
+        let reduction_strategy = match self.read_u8()? {
+            0 => {
+                let length = self.read_usize()?;
+                FriReductionStrategy::Fixed(
+                    (0..length)
+                        .map(|_| self.read_usize())
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            1 => FriReductionStrategy::ConstantArityBits(self.read_usize()?, self.read_usize()?),
+            2 => FriReductionStrategy::MinSize(if self.read_bool()? {
+                Some(self.read_usize()?)
+            } else {
+                None
+            }),
+            _ => return Err(IoError),
+        };
+        let num_query_rounds = self.read_usize()?;
+        Ok(FriConfig {
+            rate_bits,
+            cap_height,
+            proof_of_work_bits,
+            reduction_strategy,
+            num_query_rounds,
+        })
+    }
+
+    /// Reads a value of type [`CircuitConfig`] from `self`.
+    #[inline]
+    fn read_circuit_config(&mut self) -> IoResult<CircuitConfig> {
+        Ok(CircuitConfig {
+            num_wires: self.read_usize()?,
+            num_routed_wires: self.read_usize()?,
+            num_constants: self.read_usize()?,
+            use_base_arithmetic_gate: self.read_bool()?,
+            security_bits: self.read_usize()?,
+            num_challenges: self.read_usize()?,
+            zero_knowledge: self.read_bool()?,
+            max_quotient_degree_factor: self.read_usize()?,
+            fri_config: self.read_fri_config()?,
+        })
+    }
+
+    /// Reads a vector of `usize` values, preceded by its length, from `self`.
+    #[inline]
+    fn read_usize_vec(&mut self) -> IoResult<Vec<usize>> {
+        let length = self.read_usize()?;
+        (0..length).map(|_| self.read_usize()).collect()
+    }
+
+    /// Reads a value of type [`FriParams`] from `self`.
+    #[inline]
+    fn read_fri_params(&mut self) -> IoResult<FriParams> {
+        Ok(FriParams {
+            config: self.read_fri_config()?,
+            hiding: self.read_bool()?,
+            degree_bits: self.read_usize()?,
+            reduction_arity_bits: self.read_usize_vec()?,
+        })
+    }
+
+    /// Reads a value of type [`SelectorsInfo`] from `self`.
+    #[inline]
+    fn read_selectors_info(&mut self) -> IoResult<SelectorsInfo> {
+        let selector_indices = self.read_usize_vec()?;
+        let num_groups = self.read_usize()?;
+        let groups = (0..num_groups)
+            .map(|_| Ok(self.read_usize()?..self.read_usize()?))
+            .collect::<IoResult<Vec<_>>>()?;
+        Ok(SelectorsInfo {
+            selector_indices,
+            groups,
+        })
+    }
+
+    /// Reads a value of type [`Target`] from `self`.
+    #[inline]
+    fn read_target(&mut self) -> IoResult<Target> {
+        match self.read_u8()? {
+            0 => Ok(Target::wire(self.read_usize()?, self.read_usize()?)),
+            1 => Ok(Target::VirtualTarget {
+                index: self.read_usize()?,
+            }),
+            _ => Err(IoError),
+        }
+    }
+
+    /// Reads a vector of [`Target`]s, preceded by its length, from `self`.
+    #[inline]
+    fn read_target_vec(&mut self) -> IoResult<Vec<Target>> {
+        let length = self.read_usize()?;
+        (0..length).map(|_| self.read_target()).collect()
+    }
+
+    /// Reads a value of type [`BoolTarget`] from `self`.
+    #[inline]
+    fn read_bool_target(&mut self) -> IoResult<BoolTarget> {
+        Ok(BoolTarget::new_unsafe(self.read_target()?))
+    }
+
+    /// Reads a vector of [`BoolTarget`]s, preceded by its length, from `self`.
+    #[inline]
+    fn read_bool_target_vec(&mut self) -> IoResult<Vec<BoolTarget>> {
+        let length = self.read_usize()?;
+        (0..length).map(|_| self.read_bool_target()).collect()
+    }
+
+    /// Reads a value of type [`ExtensionTarget`] from `self`.
+    #[inline]
+    fn read_target_ext<const D: usize>(&mut self) -> IoResult<ExtensionTarget<D>> {
+        let mut arr = [Target::VirtualTarget { index: 0 }; D];
+        for a in arr.iter_mut() {
+            *a = self.read_target()?;
+        }
+        Ok(ExtensionTarget(arr))
+    }
+
+    /// Reads a value of type [`VerifierOnlyCircuitData`] from `self` with the given `cap_height`.
+    #[inline]
+    fn read_verifier_only_circuit_data<C, const D: usize>(
+        &mut self,
+        cap_height: usize,
+    ) -> IoResult<VerifierOnlyCircuitData<C, D>>
+    where
+        C: GenericConfig<D>,
+    {
+        Ok(VerifierOnlyCircuitData {
+            constants_sigmas_cap: self.read_merkle_cap(cap_height)?,
+            circuit_digest: self.read_hash::<C::F, C::Hasher>()?,
+        })
+    }
+
     /// Reads a element from the field `F` with size less than `2^64` from `self.`
     #[inline]
     fn read_field<F>(&mut self) -> IoResult<F>
@@ -158,10 +326,10 @@ pub trait Read {
         let constants = self.read_field_ext_vec::<F, D>(common_data.num_constants)?;
         let plonk_sigmas = self.read_field_ext_vec::<F, D>(config.num_routed_wires)?;
         let wires = self.read_field_ext_vec::<F, D>(config.num_wires)?;
-        let plonk_zs = self.read_field_ext_vec::<F, D>(config.num_challenges)?;
-        let plonk_zs_next = self.read_field_ext_vec::<F, D>(config.num_challenges)?;
-        let partial_products = self
-            .read_field_ext_vec::<F, D>(common_data.num_partial_products * config.num_challenges)?;
+        let plonk_zs = self.read_field_ext_vec::<F, D>(common_data.zs_range().len())?;
+        let plonk_zs_next = self.read_field_ext_vec::<F, D>(common_data.zs_range().len())?;
+        let partial_products =
+            self.read_field_ext_vec::<F, D>(common_data.num_partial_products_polys())?;
         let quotient_polys = self.read_field_ext_vec::<F, D>(
             common_data.quotient_degree_factor * config.num_challenges,
         )?;
@@ -214,9 +382,7 @@ pub trait Read {
         let wires_p = self.read_merkle_proof()?;
         evals_proofs.push((wires_v, wires_p));
 
-        let zs_partial_v = self.read_field_vec(
-            config.num_challenges * (1 + common_data.num_partial_products) + salt,
-        )?;
+        let zs_partial_v = self.read_field_vec(common_data.num_zs_partial_products_polys() + salt)?;
         let zs_partial_p = self.read_merkle_proof()?;
         evals_proofs.push((zs_partial_v, zs_partial_p));
 
@@ -487,6 +653,163 @@ pub trait Write {
         self.write_all(&x.to_le_bytes())
     }
 
+    /// Writes a `u64` value `x` to `self`.
+    #[inline]
+    fn write_u64(&mut self, x: u64) -> IoResult<()> {
+        self.write_all(&x.to_le_bytes())
+    }
+
+    /// Writes a `usize` value `x` to `self`, as a `u64`.
+    #[inline]
+    fn write_usize(&mut self, x: usize) -> IoResult<()> {
+        self.write_u64(x as u64)
+    }
+
+    /// Writes a `bool` value `x` to `self`.
+    #[inline]
+    fn write_bool(&mut self, x: bool) -> IoResult<()> {
+        self.write_u8(u8::from(x))
+    }
+
+    /// Writes a value `config` of type [`FriConfig`] to `self`.
+    #[inline]
+    fn write_fri_config(&mut self, config: &FriConfig) -> IoResult<()> {
+        self.write_usize(config.rate_bits)?;
+        self.write_usize(config.cap_height)?;
+        self.write_u32(config.proof_of_work_bits)?;
+        match &config.reduction_strategy {
+            FriReductionStrategy::Fixed(reduction_arity_bits) => {
+                self.write_u8(0)?;
+                self.write_usize(reduction_arity_bits.len())?;
+                for &arity_bits in reduction_arity_bits {
+                    self.write_usize(arity_bits)?;
+                }
+            }
+            &FriReductionStrategy::ConstantArityBits(arity_bits, final_poly_bits) => {
+                self.write_u8(1)?;
+                self.write_usize(arity_bits)?;
+                self.write_usize(final_poly_bits)?;
+            }
+            &FriReductionStrategy::MinSize(opt_max_arity_bits) => {
+                self.write_u8(2)?;
+                self.write_bool(opt_max_arity_bits.is_some())?;
+                if let Some(max_arity_bits) = opt_max_arity_bits {
+                    self.write_usize(max_arity_bits)?;
+                }
+            }
+        }
+        self.write_usize(config.num_query_rounds)
+    }
+
+    /// Writes a value `config` of type [`CircuitConfig`] to `self`.
+    #[inline]
+    fn write_circuit_config(&mut self, config: &CircuitConfig) -> IoResult<()> {
+        self.write_usize(config.num_wires)?;
+        self.write_usize(config.num_routed_wires)?;
+        self.write_usize(config.num_constants)?;
+        self.write_bool(config.use_base_arithmetic_gate)?;
+        self.write_usize(config.security_bits)?;
+        self.write_usize(config.num_challenges)?;
+        self.write_bool(config.zero_knowledge)?;
+        self.write_usize(config.max_quotient_degree_factor)?;
+        self.write_fri_config(&config.fri_config)
+    }
+
+    /// Writes a vector `v` of `usize` values, preceded by its length, to `self`.
+    #[inline]
+    fn write_usize_vec(&mut self, v: &[usize]) -> IoResult<()> {
+        self.write_usize(v.len())?;
+        for &x in v {
+            self.write_usize(x)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a value `params` of type [`FriParams`] to `self`.
+    #[inline]
+    fn write_fri_params(&mut self, params: &FriParams) -> IoResult<()> {
+        self.write_fri_config(&params.config)?;
+        self.write_bool(params.hiding)?;
+        self.write_usize(params.degree_bits)?;
+        self.write_usize_vec(&params.reduction_arity_bits)
+    }
+
+    /// Writes a value `info` of type [`SelectorsInfo`] to `self`.
+    #[inline]
+    fn write_selectors_info(&mut self, info: &SelectorsInfo) -> IoResult<()> {
+        self.write_usize_vec(&info.selector_indices)?;
+        self.write_usize(info.groups.len())?;
+        for group in &info.groups {
+            self.write_usize(group.start)?;
+            self.write_usize(group.end)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a target `x` to `self`.
+    #[inline]
+    fn write_target(&mut self, x: Target) -> IoResult<()> {
+        match x {
+            Target::Wire(Wire { row, column }) => {
+                self.write_u8(0)?;
+                self.write_usize(row)?;
+                self.write_usize(column)
+            }
+            Target::VirtualTarget { index } => {
+                self.write_u8(1)?;
+                self.write_usize(index)
+            }
+        }
+    }
+
+    /// Writes a vector `v` of targets, preceded by its length, to `self`.
+    #[inline]
+    fn write_target_vec(&mut self, v: &[Target]) -> IoResult<()> {
+        self.write_usize(v.len())?;
+        for &x in v {
+            self.write_target(x)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a boolean target `x` to `self`.
+    #[inline]
+    fn write_bool_target(&mut self, x: BoolTarget) -> IoResult<()> {
+        self.write_target(x.target)
+    }
+
+    /// Writes a vector `v` of boolean targets, preceded by its length, to `self`.
+    #[inline]
+    fn write_bool_target_vec(&mut self, v: &[BoolTarget]) -> IoResult<()> {
+        self.write_usize(v.len())?;
+        for &x in v {
+            self.write_bool_target(x)?;
+        }
+        Ok(())
+    }
+
+    /// Writes an extension target `x` to `self`.
+    #[inline]
+    fn write_target_ext<const D: usize>(&mut self, x: ExtensionTarget<D>) -> IoResult<()> {
+        for &a in &x.0 {
+            self.write_target(a)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a value `data` of type [`VerifierOnlyCircuitData`] to `self`.
+    #[inline]
+    fn write_verifier_only_circuit_data<C, const D: usize>(
+        &mut self,
+        data: &VerifierOnlyCircuitData<C, D>,
+    ) -> IoResult<()>
+    where
+        C: GenericConfig<D>,
+    {
+        self.write_merkle_cap(&data.constants_sigmas_cap)?;
+        self.write_hash::<C::F, C::Hasher>(data.circuit_digest)
+    }
+
     /// Writes an element `x` from the field `F` to `self`.
     #[inline]
     fn write_field<F>(&mut self, x: F) -> IoResult<()>
@@ -782,14 +1105,12 @@ impl Write for Vec<u8> {
 }
 
 /// Buffer
-#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct Buffer {
     bytes: Vec<u8>,
     pos: usize,
 }
 
-#[cfg(feature = "std")]
 impl Buffer {
     /// Builds a new [`Buffer`] over `buffer`.
     #[inline]
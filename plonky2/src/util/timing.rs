@@ -1,3 +1,5 @@
+use alloc::string::String;
+use alloc::vec::Vec;
 #[cfg(feature = "timing")]
 use std::time::{Duration, Instant};
 
@@ -21,6 +23,17 @@ pub struct TimingTree {
 #[cfg(not(feature = "timing"))]
 pub struct TimingTree(Level);
 
+/// A scope of a [`TimingTree`], as listed by [`TimingTree::scopes`].
+#[derive(Clone, Debug)]
+pub struct TimingScope {
+    pub name: String,
+    /// The number of enclosing scopes, 0 for the root.
+    pub depth: usize,
+    /// The time from the creation of the root to the creation of this scope.
+    pub start: core::time::Duration,
+    pub duration: core::time::Duration,
+}
+
 #[cfg(feature = "timing")]
 impl Default for TimingTree {
     fn default() -> Self {
@@ -144,6 +157,33 @@ impl TimingTree {
         }
     }
 
+    /// Every scope of this tree, in the order they were created. Scopes which are still open are
+    /// timed until now.
+    #[cfg(feature = "timing")]
+    pub fn scopes(&self) -> Vec<TimingScope> {
+        let mut scopes = Vec::new();
+        self.scopes_helper(self.enter_time, 0, &mut scopes);
+        scopes
+    }
+
+    #[cfg(not(feature = "timing"))]
+    pub fn scopes(&self) -> Vec<TimingScope> {
+        Vec::new()
+    }
+
+    #[cfg(feature = "timing")]
+    fn scopes_helper(&self, root_enter_time: Instant, depth: usize, scopes: &mut Vec<TimingScope>) {
+        scopes.push(TimingScope {
+            name: self.name.clone(),
+            depth,
+            start: self.enter_time.duration_since(root_enter_time),
+            duration: self.duration(),
+        });
+        for child in &self.children {
+            child.scopes_helper(root_enter_time, depth + 1, scopes);
+        }
+    }
+
     #[cfg(feature = "timing")]
     pub fn print(&self) {
         self.print_helper(0);
@@ -178,8 +178,9 @@ where
             &mut challenger,
             &fri_params,
             timing,
+            None,
         )
-    );
+    )?;
     let proof = StarkProof {
         trace_cap,
         permutation_zs_cap,
@@ -0,0 +1,162 @@
+use alloc::vec::Vec;
+
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::target::{BoolTarget, Target};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+/// A byte. Like `U32Target`, it is not range-checked when created; the gadgets taking bytes as
+/// inputs range-check them.
+#[derive(Clone, Copy, Debug)]
+pub struct U8Target(pub Target);
+
+pub trait CircuitBuilderU8<F: RichField + Extendable<D>, const D: usize> {
+    fn add_virtual_u8_target(&mut self) -> U8Target;
+
+    fn add_virtual_u8_targets(&mut self, n: usize) -> Vec<U8Target>;
+
+    fn constant_u8(&mut self, c: u8) -> U8Target;
+
+    fn connect_u8(&mut self, x: U8Target, y: U8Target);
+
+    /// Returns the bits of `x`, least significant first. This range-checks `x`.
+    fn split_u8_le(&mut self, x: U8Target) -> Vec<BoolTarget>;
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilderU8<F, D> for CircuitBuilder<F, D> {
+    fn add_virtual_u8_target(&mut self) -> U8Target {
+        U8Target(self.add_virtual_target())
+    }
+
+    fn add_virtual_u8_targets(&mut self, n: usize) -> Vec<U8Target> {
+        self.add_virtual_targets(n)
+            .into_iter()
+            .map(U8Target)
+            .collect()
+    }
+
+    fn constant_u8(&mut self, c: u8) -> U8Target {
+        U8Target(self.constant(F::from_canonical_u8(c)))
+    }
+
+    fn connect_u8(&mut self, x: U8Target, y: U8Target) {
+        self.connect(x.0, y.0)
+    }
+
+    fn split_u8_le(&mut self, x: U8Target) -> Vec<BoolTarget> {
+        split_le_circuit(self, x.0, 8)
+    }
+}
+
+/// Like `CircuitBuilder::split_le`, but without a gate if `x` is a constant.
+pub(crate) fn split_le_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    x: Target,
+    num_bits: usize,
+) -> Vec<BoolTarget> {
+    match builder.target_as_constant(x) {
+        Some(c) => {
+            let c = c.to_canonical_u64();
+            assert!(
+                num_bits >= 64 || c >> num_bits == 0,
+                "Constant out of range"
+            );
+            constant_bits_circuit(builder, c, num_bits)
+        }
+        None => builder.split_le(x, num_bits),
+    }
+}
+
+/// The `num_bits` low bits of `c`, least significant first.
+pub(crate) fn constant_bits_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    c: u64,
+    num_bits: usize,
+) -> Vec<BoolTarget> {
+    (0..num_bits)
+        .map(|i| builder.constant_bool(i < 64 && (c >> i) & 1 == 1))
+        .collect()
+}
+
+/// The integer with the bits `bits`, least significant first.
+pub(crate) fn le_sum_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bits: &[BoolTarget],
+) -> Target {
+    let zero = builder.zero();
+    bits.iter().rev().fold(zero, |acc, bit| {
+        builder.mul_const_add(F::TWO, acc, bit.target)
+    })
+}
+
+/// `a ^ b`.
+pub(crate) fn xor_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: BoolTarget,
+    b: BoolTarget,
+) -> BoolTarget {
+    for (x, y) in [(a, b), (b, a)] {
+        match builder.target_as_constant(x.target) {
+            Some(c) if c.is_zero() => return y,
+            Some(_) => return builder.not(y),
+            None => {}
+        }
+    }
+    // `a + b - 2 a b`.
+    let sum = builder.add(a.target, b.target);
+    BoolTarget::new_unsafe(builder.arithmetic(-F::TWO, F::ONE, a.target, b.target, sum))
+}
+
+/// `!a & b`.
+pub(crate) fn and_not_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: BoolTarget,
+    b: BoolTarget,
+) -> BoolTarget {
+    // `b - a b`.
+    BoolTarget::new_unsafe(builder.arithmetic(F::NEG_ONE, F::ONE, a.target, b.target, b.target))
+}
+
+/// The flags `len == i` for `i` in `0..=max_len`. This asserts that `len <= max_len`, as exactly
+/// one of them must be set.
+pub(crate) fn len_one_hot_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    len: Target,
+    max_len: usize,
+) -> Vec<BoolTarget> {
+    let flags = (0..=max_len)
+        .map(|i| {
+            let i = builder.constant(F::from_canonical_usize(i));
+            builder.is_equal(len, i)
+        })
+        .collect::<Vec<_>>();
+    let num_set = builder.add_many(flags.iter().map(|flag| flag.target));
+    builder.assert_one(num_set);
+    flags
+}
+
+/// The bytes at positions `0..num_bytes` of the message `input[..len]` followed by its padding,
+/// where `padding(builder, i)` is the padding at position `i` assuming that the message ends before
+/// it. `len_flags` are the flags of `len_one_hot_circuit`.
+pub(crate) fn pad_variable_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    input: &[U8Target],
+    len_flags: &[BoolTarget],
+    num_bytes: usize,
+    mut padding: impl FnMut(&mut CircuitBuilder<F, D>, usize) -> Target,
+) -> Vec<Target> {
+    // `i < len`, i.e. `1 - sum_{j <= i} [len == j]`.
+    let mut in_message = builder.one();
+    (0..num_bytes)
+        .map(|i| {
+            if let Some(flag) = len_flags.get(i) {
+                in_message = builder.sub(in_message, flag.target);
+            }
+            let padding = padding(builder, i);
+            match input.get(i) {
+                Some(byte) => builder.mul_add(in_message, byte.0, padding),
+                None => padding,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,340 @@
+//! Keccak-256 of byte inputs.
+//!
+//! Unlike SHA-256, whose additions modulo 2^32 map onto `U32Target` arithmetic, Keccak-f[1600]
+//! only XORs, ANDs and rotates 64-bit lanes. `U32Target` has no bitwise operations, so each of them
+//! would split its operands into bits and recombine the result. The state is instead kept as bits
+//! for the whole permutation, and is only packed into bytes when absorbing the input and squeezing
+//! the digest.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::array;
+
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::target::{BoolTarget, Target};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::gadgets::bytes::{
+    and_not_circuit, le_sum_circuit, len_one_hot_circuit, pad_variable_circuit, split_le_circuit,
+    xor_circuit, U8Target,
+};
+
+/// The number of bytes absorbed by each permutation of Keccak-256.
+const RATE: usize = 136;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808A,
+    0x8000000080008000,
+    0x000000000000808B,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008A,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000A,
+    0x000000008000808B,
+    0x800000000000008B,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800A,
+    0x800000008000000A,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// The rotation of the lane `x + 5 y` in the rho step.
+const ROTATIONS: [usize; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+/// The 25 lanes of the state, each as its 64 bits, least significant first.
+type KeccakState = Vec<Vec<BoolTarget>>;
+
+pub trait CircuitBuilderKeccak<F: RichField + Extendable<D>, const D: usize> {
+    /// Returns the Keccak-256 digest of `input`, as used by Ethereum, i.e. with the original
+    /// `0x01` padding rather than the `0x06` of SHA3-256. This range-checks `input`.
+    fn keccak256(&mut self, input: &[U8Target]) -> [U8Target; 32];
+
+    /// Returns the Keccak-256 digest of `input[..len]`, for any `len <= input.len()`. The circuit
+    /// costs as much as the digest of `input.len()` bytes.
+    fn keccak256_variable(&mut self, input: &[U8Target], len: Target) -> [U8Target; 32];
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilderKeccak<F, D>
+    for CircuitBuilder<F, D>
+{
+    fn keccak256(&mut self, input: &[U8Target]) -> [U8Target; 32] {
+        let num_blocks = input.len() / RATE + 1;
+        let bytes = (0..num_blocks * RATE)
+            .map(|i| match input.get(i) {
+                Some(byte) => byte.0,
+                None => {
+                    let mut padding = 0u8;
+                    if i == input.len() {
+                        padding |= 0x01;
+                    }
+                    if i == num_blocks * RATE - 1 {
+                        padding |= 0x80;
+                    }
+                    self.constant(F::from_canonical_u8(padding))
+                }
+            })
+            .collect::<Vec<_>>();
+        keccak256_blocks_circuit(self, &bytes, None)
+    }
+
+    fn keccak256_variable(&mut self, input: &[U8Target], len: Target) -> [U8Target; 32] {
+        let max_len = input.len();
+        let len_flags = len_one_hot_circuit(self, len, max_len);
+        let num_blocks = max_len / RATE + 1;
+        let last_block = (0..num_blocks)
+            .map(|k| {
+                let flags = &len_flags[k * RATE..((k + 1) * RATE).min(max_len + 1)];
+                self.add_many(flags.iter().map(|flag| flag.target))
+            })
+            .collect::<Vec<_>>();
+
+        let bytes =
+            pad_variable_circuit(self, input, &len_flags, num_blocks * RATE, |builder, i| {
+                let first = len_flags.get(i).map_or(builder.zero(), |flag| flag.target);
+                if i % RATE == RATE - 1 {
+                    let last = F::from_canonical_u8(0x80);
+                    builder.mul_const_add(last, last_block[i / RATE], first)
+                } else {
+                    first
+                }
+            });
+        keccak256_blocks_circuit(self, &bytes, Some(&last_block))
+    }
+}
+
+/// The digest of the padded message `bytes`. If `last_block` is given, the message ends in the
+/// block `k` for which `last_block[k]` is set, and the blocks after it are ignored.
+fn keccak256_blocks_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bytes: &[Target],
+    last_block: Option<&[Target]>,
+) -> [U8Target; 32] {
+    let mut state: KeccakState = vec![vec![builder._false(); 64]; 25];
+    let mut digest_bits = vec![builder.zero(); 256];
+    for (k, block) in bytes.chunks(RATE).enumerate() {
+        for (i, &byte) in block.iter().enumerate() {
+            let bits = split_le_circuit(builder, byte, 8);
+            for (j, bit) in bits.into_iter().enumerate() {
+                let lane_bit = &mut state[i / 8][(i % 8) * 8 + j];
+                *lane_bit = xor_circuit(builder, *lane_bit, bit);
+            }
+        }
+        keccak_f_circuit(builder, &mut state);
+
+        let bits = state[..4].iter().flatten();
+        match last_block {
+            Some(last_block) => {
+                for (acc, bit) in digest_bits.iter_mut().zip(bits) {
+                    *acc = builder.mul_add(last_block[k], bit.target, *acc);
+                }
+            }
+            None => {
+                for (acc, bit) in digest_bits.iter_mut().zip(bits) {
+                    *acc = bit.target;
+                }
+            }
+        }
+    }
+
+    // The selected bits are boolean, as exactly one block is selected.
+    let digest_bits = digest_bits
+        .into_iter()
+        .map(BoolTarget::new_unsafe)
+        .collect::<Vec<_>>();
+    array::from_fn(|i| U8Target(le_sum_circuit(builder, &digest_bits[8 * i..8 * (i + 1)])))
+}
+
+/// The bits of `lane` rotated left by `n`.
+fn rotate_left(lane: &[BoolTarget], n: usize) -> Vec<BoolTarget> {
+    (0..64).map(|i| lane[(i + 64 - n) % 64]).collect()
+}
+
+fn xor_lanes_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: &[BoolTarget],
+    b: &[BoolTarget],
+) -> Vec<BoolTarget> {
+    a.iter()
+        .zip(b)
+        .map(|(&a, &b)| xor_circuit(builder, a, b))
+        .collect()
+}
+
+/// The Keccak-f[1600] permutation.
+fn keccak_f_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    state: &mut KeccakState,
+) {
+    for round_constant in ROUND_CONSTANTS {
+        // Theta.
+        let parities = (0..5)
+            .map(|x| {
+                (1..5).fold(state[x].clone(), |acc, y| {
+                    xor_lanes_circuit(builder, &acc, &state[x + 5 * y])
+                })
+            })
+            .collect::<Vec<_>>();
+        for x in 0..5 {
+            let d = xor_lanes_circuit(
+                builder,
+                &parities[(x + 4) % 5],
+                &rotate_left(&parities[(x + 1) % 5], 1),
+            );
+            for y in 0..5 {
+                state[x + 5 * y] = xor_lanes_circuit(builder, &state[x + 5 * y], &d);
+            }
+        }
+
+        // Rho and pi.
+        let mut rotated = vec![Vec::new(); 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                rotated[y + 5 * ((2 * x + 3 * y) % 5)] =
+                    rotate_left(&state[x + 5 * y], ROTATIONS[x + 5 * y]);
+            }
+        }
+
+        // Chi.
+        for x in 0..5 {
+            for y in 0..5 {
+                let lane = &rotated[x + 5 * y];
+                let next = &rotated[(x + 1) % 5 + 5 * y];
+                let next_next = &rotated[(x + 2) % 5 + 5 * y];
+                state[x + 5 * y] = (0..64)
+                    .map(|i| {
+                        let t = and_not_circuit(builder, next[i], next_next[i]);
+                        xor_circuit(builder, lane[i], t)
+                    })
+                    .collect();
+            }
+        }
+
+        // Iota.
+        for i in 0..64 {
+            if (round_constant >> i) & 1 == 1 {
+                state[0][i] = builder.not(state[0][i]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use keccak_hash::keccak;
+    use plonky2::field::types::Field;
+    use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use rand::rngs::OsRng;
+    use rand::Rng;
+
+    use super::*;
+    use crate::gadgets::bytes::CircuitBuilderU8;
+    use crate::witness::WitnessU8;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_keccak256_vectors() -> Result<()> {
+        let vectors: [(&[u8], &str); 3] = [
+            (
+                b"",
+                "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+            ),
+            (
+                b"abc",
+                "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+            ),
+            (
+                b"The quick brown fox jumps over the lazy dog",
+                "4d741b6f1eb29cb2a9b9911c82f56fa8d73b04959d3d9d222895df6c0b28aa15",
+            ),
+        ];
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let mut pw = PartialWitness::new();
+        for (message, digest) in vectors {
+            let input = builder.add_virtual_u8_targets(message.len());
+            let output = builder.keccak256(&input);
+            for (&t, &byte) in input.iter().zip(message) {
+                pw.set_u8_target(t, byte);
+            }
+            for (t, byte) in output.into_iter().zip(hex::decode(digest).unwrap()) {
+                let expected = builder.constant_u8(byte);
+                builder.connect_u8(t, expected);
+            }
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_keccak256_variable() -> Result<()> {
+        const MAX_LEN: usize = RATE + 4;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let input = builder.add_virtual_u8_targets(MAX_LEN);
+        let len = builder.add_virtual_target();
+        let output = builder.keccak256_variable(&input, len);
+        let expected = builder.add_virtual_u8_targets(32);
+        for (&t, &e) in output.iter().zip(&expected) {
+            builder.connect_u8(t, e);
+        }
+        let data = builder.build::<C>();
+
+        let mut rng = OsRng;
+        // Lengths padded with a single byte, and lengths ending a block and the input.
+        for message_len in [0, RATE - 1, RATE, MAX_LEN] {
+            let message = (0..MAX_LEN).map(|_| rng.gen()).collect::<Vec<u8>>();
+            let digest = keccak(&message[..message_len]);
+
+            let mut pw = PartialWitness::new();
+            pw.set_target(len, F::from_canonical_usize(message_len));
+            for (&t, &byte) in input.iter().zip(&message) {
+                pw.set_u8_target(t, byte);
+            }
+            for (&t, &byte) in expected.iter().zip(digest.as_bytes()) {
+                pw.set_u8_target(t, byte);
+            }
+            let proof = data.prove(pw)?;
+            data.verify(proof)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_keccak256_variable_rejects_long_len() {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let input = builder.add_virtual_u8_targets(8);
+        let len = builder.add_virtual_target();
+        builder.keccak256_variable(&input, len);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(len, F::from_canonical_usize(9));
+        for &t in &input {
+            pw.set_u8_target(t, 0);
+        }
+        data.prove(pw).unwrap();
+    }
+}
@@ -0,0 +1,426 @@
+use alloc::vec::Vec;
+use core::array;
+
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::target::{BoolTarget, Target};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::gadgets::arithmetic_u32::{CircuitBuilderU32, U32Target};
+use crate::gadgets::bytes::{
+    le_sum_circuit, len_one_hot_circuit, pad_variable_circuit, split_le_circuit, xor_circuit,
+    U8Target,
+};
+
+/// The number of bytes of a block of SHA-256.
+const BLOCK_SIZE: usize = 64;
+
+const INITIAL_HASH: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// A word along with its bits, least significant first.
+#[derive(Clone)]
+struct Word {
+    value: U32Target,
+    bits: Vec<BoolTarget>,
+}
+
+pub trait CircuitBuilderSha256<F: RichField + Extendable<D>, const D: usize> {
+    /// Returns the SHA-256 digest of `input`. This range-checks `input`.
+    fn sha256(&mut self, input: &[U8Target]) -> [U8Target; 32];
+
+    /// Returns the SHA-256 digest of `input[..len]`, for any `len <= input.len()`. The circuit
+    /// costs as much as the digest of `input.len()` bytes.
+    fn sha256_variable(&mut self, input: &[U8Target], len: Target) -> [U8Target; 32];
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilderSha256<F, D>
+    for CircuitBuilder<F, D>
+{
+    fn sha256(&mut self, input: &[U8Target]) -> [U8Target; 32] {
+        let num_blocks = (input.len() + 8) / BLOCK_SIZE + 1;
+        let num_bytes = num_blocks * BLOCK_SIZE;
+        let len_bytes = (8 * input.len() as u64).to_be_bytes();
+        let bytes = (0..num_bytes)
+            .map(|i| match input.get(i) {
+                Some(byte) => byte.0,
+                None => {
+                    let padding = if i == input.len() {
+                        0x80
+                    } else if i >= num_bytes - 8 {
+                        len_bytes[i + 8 - num_bytes]
+                    } else {
+                        0
+                    };
+                    self.constant(F::from_canonical_u8(padding))
+                }
+            })
+            .collect::<Vec<_>>();
+        sha256_blocks_circuit(self, &bytes, None)
+    }
+
+    fn sha256_variable(&mut self, input: &[U8Target], len: Target) -> [U8Target; 32] {
+        let max_len = input.len();
+        let len_flags = len_one_hot_circuit(self, len, max_len);
+        let num_blocks = (max_len + 8) / BLOCK_SIZE + 1;
+        let last_block = (0..num_blocks)
+            .map(|k| {
+                let flags = len_flags
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| (i + 8) / BLOCK_SIZE == k)
+                    .map(|(_, flag)| flag.target);
+                self.add_many(flags)
+            })
+            .collect::<Vec<_>>();
+
+        // The bytes of the bit length, most significant first. `len <= max_len`, so the bits above
+        // those of `8 max_len` are zero.
+        let bit_len = self.mul_const(F::from_canonical_u8(8), len);
+        let num_bits = (u64::BITS - (8 * max_len as u64).leading_zeros()) as usize;
+        let bit_len_bits = split_le_circuit(self, bit_len, num_bits);
+        let len_bytes = (0..8)
+            .rev()
+            .map(|j| {
+                let bits = &bit_len_bits[(8 * j).min(num_bits)..(8 * (j + 1)).min(num_bits)];
+                (!bits.is_empty()).then(|| le_sum_circuit(self, bits))
+            })
+            .collect::<Vec<_>>();
+
+        let bytes = pad_variable_circuit(
+            self,
+            input,
+            &len_flags,
+            num_blocks * BLOCK_SIZE,
+            |builder, i| {
+                let first = match len_flags.get(i) {
+                    Some(flag) => builder.mul_const(F::from_canonical_u8(0x80), flag.target),
+                    None => builder.zero(),
+                };
+                match len_bytes.get((i % BLOCK_SIZE).wrapping_sub(BLOCK_SIZE - 8)) {
+                    Some(&Some(len_byte)) => {
+                        builder.mul_add(last_block[i / BLOCK_SIZE], len_byte, first)
+                    }
+                    _ => first,
+                }
+            },
+        );
+        sha256_blocks_circuit(self, &bytes, Some(&last_block))
+    }
+}
+
+/// The digest of the padded message `bytes`. If `last_block` is given, the message ends in the
+/// block `k` for which `last_block[k]` is set, and the blocks after it are ignored.
+fn sha256_blocks_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bytes: &[Target],
+    last_block: Option<&[Target]>,
+) -> [U8Target; 32] {
+    let mut hash = INITIAL_HASH
+        .iter()
+        .map(|&h| constant_word_circuit(builder, h))
+        .collect::<Vec<_>>();
+    let mut digest = Vec::new();
+    for (k, block) in bytes.chunks(BLOCK_SIZE).enumerate() {
+        let words = block
+            .chunks(4)
+            .map(|word_bytes| {
+                // The bytes are big-endian, so the bits of the last byte come first.
+                let bits = word_bytes
+                    .iter()
+                    .rev()
+                    .flat_map(|&byte| split_le_circuit(builder, byte, 8))
+                    .collect();
+                word_from_bits_circuit(builder, bits)
+            })
+            .collect::<Vec<_>>();
+        hash = compress_circuit(builder, &hash, words);
+
+        digest = match last_block {
+            Some(last_block) if k > 0 => digest
+                .iter()
+                .zip(&hash)
+                .map(|(&acc, h)| builder.mul_add(last_block[k], h.value.0, acc))
+                .collect(),
+            Some(last_block) => hash
+                .iter()
+                .map(|h| builder.mul(last_block[k], h.value.0))
+                .collect(),
+            None => hash.iter().map(|h| h.value.0).collect(),
+        };
+    }
+
+    let digest_bits = match last_block {
+        Some(_) => digest
+            .into_iter()
+            .flat_map(|word| split_le_circuit(builder, word, 32))
+            .collect::<Vec<_>>(),
+        None => hash.into_iter().flat_map(|h| h.bits).collect(),
+    };
+    array::from_fn(|i| {
+        let (word, byte) = (i / 4, 3 - i % 4);
+        let bits = &digest_bits[32 * word + 8 * byte..32 * word + 8 * (byte + 1)];
+        U8Target(le_sum_circuit(builder, bits))
+    })
+}
+
+/// The SHA-256 compression function, for the message schedule starting with `words`.
+fn compress_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    hash: &[Word],
+    mut words: Vec<Word>,
+) -> Vec<Word> {
+    for t in 16..64 {
+        let s0 = xor3_circuit(
+            builder,
+            &rotate_right(&words[t - 15].bits, 7),
+            &rotate_right(&words[t - 15].bits, 18),
+            &shift_right(builder, &words[t - 15].bits, 3),
+        );
+        let s1 = xor3_circuit(
+            builder,
+            &rotate_right(&words[t - 2].bits, 17),
+            &rotate_right(&words[t - 2].bits, 19),
+            &shift_right(builder, &words[t - 2].bits, 10),
+        );
+        let (word, _) = builder.add_many_u32(&[
+            words[t - 16].value,
+            U32Target(s0),
+            words[t - 7].value,
+            U32Target(s1),
+        ]);
+        words.push(word_from_value_circuit(builder, word));
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h]: [Word; 8] =
+        array::from_fn(|i| hash[i].clone());
+    for (word, &round_constant) in words.iter().zip(&ROUND_CONSTANTS) {
+        let s1 = xor3_circuit(
+            builder,
+            &rotate_right(&e.bits, 6),
+            &rotate_right(&e.bits, 11),
+            &rotate_right(&e.bits, 25),
+        );
+        // `(e & f) ^ (!e & g)`, i.e. `g + e (f - g)`.
+        let ch_bits = (0..32)
+            .map(|i| {
+                let diff = builder.sub(f.bits[i].target, g.bits[i].target);
+                BoolTarget::new_unsafe(builder.mul_add(e.bits[i].target, diff, g.bits[i].target))
+            })
+            .collect::<Vec<_>>();
+        let ch = le_sum_circuit(builder, &ch_bits);
+        let round_constant = builder.constant_u32(round_constant);
+        let (temp1, _) = builder.add_many_u32(&[
+            h.value,
+            U32Target(s1),
+            U32Target(ch),
+            round_constant,
+            word.value,
+        ]);
+
+        let s0 = xor3_circuit(
+            builder,
+            &rotate_right(&a.bits, 2),
+            &rotate_right(&a.bits, 13),
+            &rotate_right(&a.bits, 22),
+        );
+        // `(a & b) ^ (a & c) ^ (b & c)`, i.e. `a b + c (a ^ b)`.
+        let maj_bits = (0..32)
+            .map(|i| {
+                let a_xor_b = xor_circuit(builder, a.bits[i], b.bits[i]);
+                let a_and_b = builder.and(a.bits[i], b.bits[i]);
+                BoolTarget::new_unsafe(builder.mul_add(
+                    c.bits[i].target,
+                    a_xor_b.target,
+                    a_and_b.target,
+                ))
+            })
+            .collect::<Vec<_>>();
+        let maj = le_sum_circuit(builder, &maj_bits);
+        let (temp2, _) = builder.add_many_u32(&[temp1, U32Target(s0), U32Target(maj)]);
+        let (new_e, _) = builder.add_u32(d.value, temp1);
+
+        h = g;
+        g = f;
+        f = e;
+        e = word_from_value_circuit(builder, new_e);
+        d = c;
+        c = b;
+        b = a;
+        a = word_from_value_circuit(builder, temp2);
+    }
+
+    [a, b, c, d, e, f, g, h]
+        .iter()
+        .zip(hash)
+        .map(|(x, h)| {
+            let (sum, _) = builder.add_u32(h.value, x.value);
+            word_from_value_circuit(builder, sum)
+        })
+        .collect()
+}
+
+fn constant_word_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    c: u32,
+) -> Word {
+    let value = builder.constant_u32(c);
+    word_from_value_circuit(builder, value)
+}
+
+fn word_from_value_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    value: U32Target,
+) -> Word {
+    let bits = split_le_circuit(builder, value.0, 32);
+    Word { value, bits }
+}
+
+fn word_from_bits_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bits: Vec<BoolTarget>,
+) -> Word {
+    let value = U32Target(le_sum_circuit(builder, &bits));
+    Word { value, bits }
+}
+
+/// The bits of a word rotated right by `n`.
+fn rotate_right(bits: &[BoolTarget], n: usize) -> Vec<BoolTarget> {
+    (0..32).map(|i| bits[(i + n) % 32]).collect()
+}
+
+/// The bits of a word shifted right by `n`.
+fn shift_right<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bits: &[BoolTarget],
+    n: usize,
+) -> Vec<BoolTarget> {
+    (0..32)
+        .map(|i| bits.get(i + n).copied().unwrap_or_else(|| builder._false()))
+        .collect()
+}
+
+/// The word `a ^ b ^ c`.
+fn xor3_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: &[BoolTarget],
+    b: &[BoolTarget],
+    c: &[BoolTarget],
+) -> Target {
+    let bits = (0..32)
+        .map(|i| {
+            let a_xor_b = xor_circuit(builder, a[i], b[i]);
+            xor_circuit(builder, a_xor_b, c[i])
+        })
+        .collect::<Vec<_>>();
+    le_sum_circuit(builder, &bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::field::types::Field;
+    use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use rand::rngs::OsRng;
+    use rand::Rng;
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::gadgets::bytes::CircuitBuilderU8;
+    use crate::witness::WitnessU8;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_sha256_vectors() -> Result<()> {
+        // The examples of FIPS 180-2.
+        let vectors: [(&[u8], &str); 4] = [
+            (
+                b"",
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                b"abc",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmjklmnklmnomnopnopq",
+                "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            ),
+            (
+                b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmn\
+                  hijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+                "cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1",
+            ),
+        ];
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let mut pw = PartialWitness::new();
+        for (message, digest) in vectors {
+            let input = builder.add_virtual_u8_targets(message.len());
+            let output = builder.sha256(&input);
+            for (&t, &byte) in input.iter().zip(message) {
+                pw.set_u8_target(t, byte);
+            }
+            for (t, byte) in output.into_iter().zip(hex::decode(digest).unwrap()) {
+                let expected = builder.constant_u8(byte);
+                builder.connect_u8(t, expected);
+            }
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_sha256_variable() -> Result<()> {
+        const MAX_LEN: usize = 2 * BLOCK_SIZE;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let input = builder.add_virtual_u8_targets(MAX_LEN);
+        let len = builder.add_virtual_target();
+        let output = builder.sha256_variable(&input, len);
+        let expected = builder.add_virtual_u8_targets(32);
+        for (&t, &e) in output.iter().zip(&expected) {
+            builder.connect_u8(t, e);
+        }
+        let data = builder.build::<C>();
+
+        let mut rng = OsRng;
+        // The largest lengths whose padding fits in one and two blocks, the smallest ones needing
+        // one more block, and the longest message.
+        for message_len in [0, 55, 56, 119, 120, MAX_LEN] {
+            let message = (0..MAX_LEN).map(|_| rng.gen()).collect::<Vec<u8>>();
+            let digest = Sha256::digest(&message[..message_len]);
+
+            let mut pw = PartialWitness::new();
+            pw.set_target(len, F::from_canonical_usize(message_len));
+            for (&t, &byte) in input.iter().zip(&message) {
+                pw.set_u8_target(t, byte);
+            }
+            for (&t, &byte) in expected.iter().zip(digest.iter()) {
+                pw.set_u8_target(t, byte);
+            }
+            let proof = data.prove(pw)?;
+            data.verify(proof)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,454 @@
+use alloc::vec::Vec;
+use core::array;
+
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::target::{BoolTarget, Target};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::gadgets::arithmetic_u32::{CircuitBuilderU32, U32Target};
+use crate::gadgets::bytes::{
+    le_sum_circuit, len_one_hot_circuit, pad_variable_circuit, split_le_circuit, xor_circuit,
+    U8Target,
+};
+
+/// The number of bytes of a block of SHA-512.
+const BLOCK_SIZE: usize = 128;
+
+/// The number of bytes of the bit length at the end of the padding.
+const LEN_SIZE: usize = 16;
+
+#[rustfmt::skip]
+const INITIAL_HASH: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+#[rustfmt::skip]
+const ROUND_CONSTANTS: [u64; 80] = [
+    0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
+    0x3956c25bf348b538, 0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
+    0xd807aa98a3030242, 0x12835b0145706fbe, 0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 0xc19bf174cf692694,
+    0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5,
+    0x983e5152ee66dfab, 0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2, 0xd5a79147930aa725, 0x06ca6351e003826f, 0x142929670a0e6e70,
+    0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 0x53380d139d95b3df,
+    0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b,
+    0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30,
+    0xd192e819d6ef5218, 0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec,
+    0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b,
+    0xca273eceea26619c, 0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178,
+    0x06f067aa72176fba, 0x0a637dc5a2c898a6, 0x113f9804bef90dae, 0x1b710b35131c471b,
+    0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817,
+];
+
+/// A 64-bit word, as its low and high halves along with its bits, least significant first. The
+/// field cannot hold the sum of two words, so they are added half by half.
+#[derive(Clone)]
+struct Word {
+    lo: U32Target,
+    hi: U32Target,
+    bits: Vec<BoolTarget>,
+}
+
+pub trait CircuitBuilderSha512<F: RichField + Extendable<D>, const D: usize> {
+    /// Returns the SHA-512 digest of `input`. This range-checks `input`.
+    fn sha512(&mut self, input: &[U8Target]) -> [U8Target; 64];
+
+    /// Returns the SHA-512 digest of `input[..len]`, for any `len <= input.len()`. The circuit
+    /// costs as much as the digest of `input.len()` bytes.
+    fn sha512_variable(&mut self, input: &[U8Target], len: Target) -> [U8Target; 64];
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilderSha512<F, D>
+    for CircuitBuilder<F, D>
+{
+    fn sha512(&mut self, input: &[U8Target]) -> [U8Target; 64] {
+        let num_blocks = (input.len() + LEN_SIZE) / BLOCK_SIZE + 1;
+        let num_bytes = num_blocks * BLOCK_SIZE;
+        let len_bytes = (8 * input.len() as u128).to_be_bytes();
+        let bytes = (0..num_bytes)
+            .map(|i| match input.get(i) {
+                Some(byte) => byte.0,
+                None => {
+                    let padding = if i == input.len() {
+                        0x80
+                    } else if i >= num_bytes - LEN_SIZE {
+                        len_bytes[i + LEN_SIZE - num_bytes]
+                    } else {
+                        0
+                    };
+                    self.constant(F::from_canonical_u8(padding))
+                }
+            })
+            .collect::<Vec<_>>();
+        sha512_blocks_circuit(self, &bytes, None)
+    }
+
+    fn sha512_variable(&mut self, input: &[U8Target], len: Target) -> [U8Target; 64] {
+        let max_len = input.len();
+        let len_flags = len_one_hot_circuit(self, len, max_len);
+        let num_blocks = (max_len + LEN_SIZE) / BLOCK_SIZE + 1;
+        let last_block = (0..num_blocks)
+            .map(|k| {
+                let flags = len_flags
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| (i + LEN_SIZE) / BLOCK_SIZE == k)
+                    .map(|(_, flag)| flag.target);
+                self.add_many(flags)
+            })
+            .collect::<Vec<_>>();
+
+        // The bytes of the bit length, most significant first. `len <= max_len`, so the bits above
+        // those of `8 max_len` are zero.
+        let bit_len = self.mul_const(F::from_canonical_u8(8), len);
+        let num_bits = (u64::BITS - (8 * max_len as u64).leading_zeros()) as usize;
+        let bit_len_bits = split_le_circuit(self, bit_len, num_bits);
+        let len_bytes = (0..LEN_SIZE)
+            .rev()
+            .map(|j| {
+                let bits = &bit_len_bits[(8 * j).min(num_bits)..(8 * (j + 1)).min(num_bits)];
+                (!bits.is_empty()).then(|| le_sum_circuit(self, bits))
+            })
+            .collect::<Vec<_>>();
+
+        let bytes = pad_variable_circuit(
+            self,
+            input,
+            &len_flags,
+            num_blocks * BLOCK_SIZE,
+            |builder, i| {
+                let first = match len_flags.get(i) {
+                    Some(flag) => builder.mul_const(F::from_canonical_u8(0x80), flag.target),
+                    None => builder.zero(),
+                };
+                match len_bytes.get((i % BLOCK_SIZE).wrapping_sub(BLOCK_SIZE - LEN_SIZE)) {
+                    Some(&Some(len_byte)) => {
+                        builder.mul_add(last_block[i / BLOCK_SIZE], len_byte, first)
+                    }
+                    _ => first,
+                }
+            },
+        );
+        sha512_blocks_circuit(self, &bytes, Some(&last_block))
+    }
+}
+
+/// The digest of the padded message `bytes`. If `last_block` is given, the message ends in the
+/// block `k` for which `last_block[k]` is set, and the blocks after it are ignored.
+fn sha512_blocks_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bytes: &[Target],
+    last_block: Option<&[Target]>,
+) -> [U8Target; 64] {
+    let mut hash = INITIAL_HASH
+        .iter()
+        .map(|&h| constant_word_circuit(builder, h))
+        .collect::<Vec<_>>();
+    // The halves of the digest, low half first.
+    let mut digest = Vec::new();
+    for (k, block) in bytes.chunks(BLOCK_SIZE).enumerate() {
+        let words = block
+            .chunks(8)
+            .map(|word_bytes| {
+                // The bytes are big-endian, so the bits of the last byte come first.
+                let bits = word_bytes
+                    .iter()
+                    .rev()
+                    .flat_map(|&byte| split_le_circuit(builder, byte, 8))
+                    .collect();
+                word_from_bits_circuit(builder, bits)
+            })
+            .collect::<Vec<_>>();
+        hash = compress_circuit(builder, &hash, words);
+
+        let halves = hash.iter().flat_map(|h| [h.lo.0, h.hi.0]);
+        digest = match last_block {
+            Some(last_block) if k > 0 => digest
+                .iter()
+                .zip(halves)
+                .map(|(&acc, half)| builder.mul_add(last_block[k], half, acc))
+                .collect(),
+            Some(last_block) => halves
+                .map(|half| builder.mul(last_block[k], half))
+                .collect(),
+            None => halves.collect(),
+        };
+    }
+
+    let digest_bits = match last_block {
+        Some(_) => digest
+            .into_iter()
+            .flat_map(|half| split_le_circuit(builder, half, 32))
+            .collect::<Vec<_>>(),
+        None => hash.into_iter().flat_map(|h| h.bits).collect(),
+    };
+    array::from_fn(|i| {
+        let (word, byte) = (i / 8, 7 - i % 8);
+        let bits = &digest_bits[64 * word + 8 * byte..64 * word + 8 * (byte + 1)];
+        U8Target(le_sum_circuit(builder, bits))
+    })
+}
+
+/// The SHA-512 compression function, for the message schedule starting with `words`.
+fn compress_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    hash: &[Word],
+    mut words: Vec<Word>,
+) -> Vec<Word> {
+    for t in 16..80 {
+        let s0 = xor3_circuit(
+            builder,
+            &rotate_right(&words[t - 15].bits, 1),
+            &rotate_right(&words[t - 15].bits, 8),
+            &shift_right(builder, &words[t - 15].bits, 7),
+        );
+        let s1 = xor3_circuit(
+            builder,
+            &rotate_right(&words[t - 2].bits, 19),
+            &rotate_right(&words[t - 2].bits, 61),
+            &shift_right(builder, &words[t - 2].bits, 6),
+        );
+        let word = add_words_circuit(builder, &[&words[t - 16], &s0, &words[t - 7], &s1]);
+        words.push(word);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h]: [Word; 8] =
+        array::from_fn(|i| hash[i].clone());
+    for (word, &round_constant) in words.iter().zip(&ROUND_CONSTANTS) {
+        let s1 = xor3_circuit(
+            builder,
+            &rotate_right(&e.bits, 14),
+            &rotate_right(&e.bits, 18),
+            &rotate_right(&e.bits, 41),
+        );
+        // `(e & f) ^ (!e & g)`, i.e. `g + e (f - g)`.
+        let ch_bits = (0..64)
+            .map(|i| {
+                let diff = builder.sub(f.bits[i].target, g.bits[i].target);
+                BoolTarget::new_unsafe(builder.mul_add(e.bits[i].target, diff, g.bits[i].target))
+            })
+            .collect::<Vec<_>>();
+        let ch = word_from_bits_circuit(builder, ch_bits);
+        let round_constant = constant_word_circuit(builder, round_constant);
+        let temp1 = add_words_circuit(builder, &[&h, &s1, &ch, &round_constant, word]);
+
+        let s0 = xor3_circuit(
+            builder,
+            &rotate_right(&a.bits, 28),
+            &rotate_right(&a.bits, 34),
+            &rotate_right(&a.bits, 39),
+        );
+        // `(a & b) ^ (a & c) ^ (b & c)`, i.e. `a b + c (a ^ b)`.
+        let maj_bits = (0..64)
+            .map(|i| {
+                let a_xor_b = xor_circuit(builder, a.bits[i], b.bits[i]);
+                let a_and_b = builder.and(a.bits[i], b.bits[i]);
+                BoolTarget::new_unsafe(builder.mul_add(
+                    c.bits[i].target,
+                    a_xor_b.target,
+                    a_and_b.target,
+                ))
+            })
+            .collect::<Vec<_>>();
+        let maj = word_from_bits_circuit(builder, maj_bits);
+        let temp2 = add_words_circuit(builder, &[&temp1, &s0, &maj]);
+        let new_e = add_words_circuit(builder, &[&d, &temp1]);
+
+        h = g;
+        g = f;
+        f = e;
+        e = new_e;
+        d = c;
+        c = b;
+        b = a;
+        a = temp2;
+    }
+
+    [a, b, c, d, e, f, g, h]
+        .iter()
+        .zip(hash)
+        .map(|(x, h)| add_words_circuit(builder, &[h, x]))
+        .collect()
+}
+
+/// The sum of `words` modulo 2^64.
+fn add_words_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    words: &[&Word],
+) -> Word {
+    let los = words.iter().map(|w| w.lo).collect::<Vec<_>>();
+    let his = words.iter().map(|w| w.hi).collect::<Vec<_>>();
+    let (lo, carry) = builder.add_many_u32(&los);
+    let (hi, _) = builder.add_u32s_with_carry(&his, carry);
+    word_from_halves_circuit(builder, lo, hi)
+}
+
+fn constant_word_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    c: u64,
+) -> Word {
+    let lo = builder.constant_u32(c as u32);
+    let hi = builder.constant_u32((c >> 32) as u32);
+    word_from_halves_circuit(builder, lo, hi)
+}
+
+fn word_from_halves_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    lo: U32Target,
+    hi: U32Target,
+) -> Word {
+    let mut bits = split_le_circuit(builder, lo.0, 32);
+    bits.extend(split_le_circuit(builder, hi.0, 32));
+    Word { lo, hi, bits }
+}
+
+fn word_from_bits_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bits: Vec<BoolTarget>,
+) -> Word {
+    let lo = U32Target(le_sum_circuit(builder, &bits[..32]));
+    let hi = U32Target(le_sum_circuit(builder, &bits[32..]));
+    Word { lo, hi, bits }
+}
+
+/// The bits of a word rotated right by `n`.
+fn rotate_right(bits: &[BoolTarget], n: usize) -> Vec<BoolTarget> {
+    (0..64).map(|i| bits[(i + n) % 64]).collect()
+}
+
+/// The bits of a word shifted right by `n`.
+fn shift_right<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bits: &[BoolTarget],
+    n: usize,
+) -> Vec<BoolTarget> {
+    (0..64)
+        .map(|i| bits.get(i + n).copied().unwrap_or_else(|| builder._false()))
+        .collect()
+}
+
+/// The word `a ^ b ^ c`.
+fn xor3_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: &[BoolTarget],
+    b: &[BoolTarget],
+    c: &[BoolTarget],
+) -> Word {
+    let bits = (0..64)
+        .map(|i| {
+            let a_xor_b = xor_circuit(builder, a[i], b[i]);
+            xor_circuit(builder, a_xor_b, c[i])
+        })
+        .collect::<Vec<_>>();
+    word_from_bits_circuit(builder, bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::field::types::Field;
+    use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use rand::rngs::OsRng;
+    use rand::Rng;
+    use sha2::{Digest, Sha512};
+
+    use super::*;
+    use crate::gadgets::bytes::CircuitBuilderU8;
+    use crate::witness::WitnessU8;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_sha512_vectors() -> Result<()> {
+        // The examples of FIPS 180-2.
+        let vectors: [(&[u8], &str); 3] = [
+            (
+                b"abc",
+                concat!(
+                    "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a",
+                    "2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+                ),
+            ),
+            (
+                b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmn\
+                  hijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+                concat!(
+                    "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018",
+                    "501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909",
+                ),
+            ),
+            (
+                b"",
+                concat!(
+                    "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce",
+                    "47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e",
+                ),
+            ),
+        ];
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let mut pw = PartialWitness::new();
+        for (message, digest) in vectors {
+            let input = builder.add_virtual_u8_targets(message.len());
+            let output = builder.sha512(&input);
+            for (&t, &byte) in input.iter().zip(message) {
+                pw.set_u8_target(t, byte);
+            }
+            for (t, byte) in output.into_iter().zip(hex::decode(digest).unwrap()) {
+                let expected = builder.constant_u8(byte);
+                builder.connect_u8(t, expected);
+            }
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_sha512_variable() -> Result<()> {
+        const MAX_LEN: usize = 2 * BLOCK_SIZE;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let input = builder.add_virtual_u8_targets(MAX_LEN);
+        let len = builder.add_virtual_target();
+        let output = builder.sha512_variable(&input, len);
+        let expected = builder.add_virtual_u8_targets(64);
+        for (&t, &e) in output.iter().zip(&expected) {
+            builder.connect_u8(t, e);
+        }
+        let data = builder.build::<C>();
+
+        let mut rng = OsRng;
+        // The largest lengths whose padding fits in one and two blocks, the smallest ones needing
+        // one more block, and the longest message.
+        for message_len in [0, 111, 112, 239, 240, MAX_LEN] {
+            let message = (0..MAX_LEN).map(|_| rng.gen()).collect::<Vec<u8>>();
+            let digest = Sha512::digest(&message[..message_len]);
+
+            let mut pw = PartialWitness::new();
+            pw.set_target(len, F::from_canonical_usize(message_len));
+            for (&t, &byte) in input.iter().zip(&message) {
+                pw.set_u8_target(t, byte);
+            }
+            for (&t, &byte) in expected.iter().zip(digest.iter()) {
+                pw.set_u8_target(t, byte);
+            }
+            let proof = data.prove(pw)?;
+            data.verify(proof)?;
+        }
+        Ok(())
+    }
+}
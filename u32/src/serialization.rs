@@ -0,0 +1,81 @@
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::registry::CircuitRegistry;
+
+use crate::gadgets::arithmetic_u32::SplitToU32Generator;
+use crate::gates::add_many_u32::{U32AddManyGate, U32AddManyGenerator};
+use crate::gates::arithmetic_u32::{U32ArithmeticGate, U32ArithmeticGenerator};
+use crate::gates::comparison::{ComparisonGate, ComparisonGenerator};
+use crate::gates::range_check_u32::{U32RangeCheckGate, U32RangeCheckGenerator};
+use crate::gates::subtraction_u32::{U32SubtractionGate, U32SubtractionGenerator};
+
+/// Registers the gates and generators of this crate, so that circuits using them can be
+/// serialized.
+pub fn register<F: RichField + Extendable<D>, const D: usize>(
+    registry: &mut CircuitRegistry<F, D>,
+) {
+    registry
+        .register_gate::<ComparisonGate<F, D>>("ComparisonGate")
+        .register_gate::<U32AddManyGate<F, D>>("U32AddManyGate")
+        .register_gate::<U32ArithmeticGate<F, D>>("U32ArithmeticGate")
+        .register_gate::<U32RangeCheckGate<F, D>>("U32RangeCheckGate")
+        .register_gate::<U32SubtractionGate<F, D>>("U32SubtractionGate");
+    registry
+        .register_simple_generator::<ComparisonGenerator<F, D>>("ComparisonGenerator")
+        .register_simple_generator::<SplitToU32Generator<F, D>>("SplitToU32Generator")
+        .register_simple_generator::<U32AddManyGenerator<F, D>>("U32AddManyGenerator")
+        .register_simple_generator::<U32ArithmeticGenerator<F, D>>("U32ArithmeticGenerator")
+        .register_simple_generator::<U32RangeCheckGenerator<F, D>>("U32RangeCheckGenerator")
+        .register_simple_generator::<U32SubtractionGenerator<F, D>>("U32SubtractionGenerator");
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use anyhow::Result;
+    use plonky2::iop::witness::PartialWitness;
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::{CircuitConfig, CircuitData};
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use rand::rngs::OsRng;
+    use rand::Rng;
+
+    use super::*;
+    use crate::gadgets::arithmetic_u32::CircuitBuilderU32;
+    use crate::gadgets::multiple_comparison::list_le_u32_circuit;
+    use crate::gadgets::range_check::range_check_u32_circuit;
+    use crate::witness::WitnessU32;
+
+    #[test]
+    fn test_circuit_data_round_trip() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_u32_targets(3);
+        let (low, high) = builder.mul_add_u32(x[0], x[1], x[2]);
+        let (sum, carry) = builder.add_many_u32(&x);
+        let zero = builder.zero_u32();
+        let (diff, borrow) = builder.sub_u32(x[0], x[1], zero);
+        let le = list_le_u32_circuit(&mut builder, vec![low, high], vec![sum, carry]);
+        range_check_u32_circuit(&mut builder, vec![diff, borrow]);
+        builder.register_public_input(le.target);
+        let data = builder.build::<C>();
+
+        let mut registry = CircuitRegistry::new();
+        register(&mut registry);
+        let bytes = data.to_bytes(&registry)?;
+        let deserialized = CircuitData::<F, C, D>::from_bytes(bytes, &registry)?;
+
+        let mut rng = OsRng;
+        let mut pw = PartialWitness::new();
+        for &t in &x {
+            pw.set_u32_target(t, rng.gen());
+        }
+        let proof = data.prove(pw.clone())?;
+        assert_eq!(deserialized.prove(pw)?, proof);
+        deserialized.verify(proof)
+    }
+}
@@ -16,6 +16,7 @@ use plonky2::plonk::vars::{
     EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
     EvaluationVarsBasePacked,
 };
+use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
 use plonky2_field::extension::Extendable;
 use plonky2_field::packed::PackedField;
 use plonky2_field::types::Field;
@@ -77,6 +78,17 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SwitchGate<F,
         format!("{self:?}<D={D}>")
     }
 
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_usize(self.num_copies)?;
+        dst.write_usize(self.chunk_size)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let num_copies = src.read_usize()?;
+        let chunk_size = src.read_usize()?;
+        Ok(Self::new(num_copies, chunk_size))
+    }
+
     fn export_circom_verification_code(&self) -> String {
         todo!()
     }
@@ -217,7 +229,7 @@ impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D> for
 }
 
 #[derive(Debug)]
-struct SwitchGenerator<F: RichField + Extendable<D>, const D: usize> {
+pub(crate) struct SwitchGenerator<F: RichField + Extendable<D>, const D: usize> {
     row: usize,
     gate: SwitchGate<F, D>,
     copy: usize,
@@ -327,6 +339,20 @@ impl<F: RichField + Extendable<D>, const D: usize> WitnessGenerator<F> for Switc
             false
         }
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        self.gate.serialize(dst)?;
+        dst.write_usize(self.copy)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            row: src.read_usize()?,
+            gate: SwitchGate::<F, D>::deserialize(src)?,
+            copy: src.read_usize()?,
+        })
+    }
 }
 
 #[cfg(test)]
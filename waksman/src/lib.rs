@@ -8,4 +8,5 @@
 pub mod bimap;
 pub mod gates;
 pub mod permutation;
+pub mod serialization;
 pub mod sorting;
@@ -8,6 +8,7 @@ use plonky2::iop::generator::{GeneratedValues, SimpleGenerator};
 use plonky2::iop::target::Target;
 use plonky2::iop::witness::{PartitionWitness, Witness, WitnessWrite};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
 
 use crate::bimap::bimap_from_lists;
 use crate::gates::switch::SwitchGate;
@@ -333,7 +334,7 @@ fn route<F: Field>(
 }
 
 #[derive(Debug)]
-struct PermutationGenerator<F: Field> {
+pub(crate) struct PermutationGenerator<F: Field> {
     a: Vec<Vec<Target>>,
     b: Vec<Vec<Target>>,
     a_switches: Vec<Target>,
@@ -366,6 +367,36 @@ impl<F: Field> SimpleGenerator<F> for PermutationGenerator<F> {
             out_buffer,
         );
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        write_chunks(dst, &self.a)?;
+        write_chunks(dst, &self.b)?;
+        dst.write_target_vec(&self.a_switches)?;
+        dst.write_target_vec(&self.b_switches)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            a: read_chunks(src)?,
+            b: read_chunks(src)?,
+            a_switches: src.read_target_vec()?,
+            b_switches: src.read_target_vec()?,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+fn write_chunks(dst: &mut Vec<u8>, chunks: &[Vec<Target>]) -> IoResult<()> {
+    dst.write_usize(chunks.len())?;
+    for chunk in chunks {
+        dst.write_target_vec(chunk)?;
+    }
+    Ok(())
+}
+
+fn read_chunks(src: &mut Buffer) -> IoResult<Vec<Vec<Target>>> {
+    let len = src.read_usize()?;
+    (0..len).map(|_| src.read_target_vec()).collect()
 }
 
 #[cfg(test)]
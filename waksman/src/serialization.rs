@@ -0,0 +1,23 @@
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::registry::CircuitRegistry;
+use plonky2_field::extension::Extendable;
+
+use crate::gates::assert_le::{AssertLessThanGate, AssertLessThanGenerator};
+use crate::gates::switch::{SwitchGate, SwitchGenerator};
+use crate::permutation::PermutationGenerator;
+use crate::sorting::MemoryOpSortGenerator;
+
+/// Registers the gates and generators of this crate, so that circuits using them can be
+/// serialized.
+pub fn register<F: RichField + Extendable<D>, const D: usize>(
+    registry: &mut CircuitRegistry<F, D>,
+) {
+    registry
+        .register_gate::<AssertLessThanGate<F, D>>("AssertLessThanGate")
+        .register_gate::<SwitchGate<F, D>>("SwitchGate");
+    registry
+        .register_simple_generator::<AssertLessThanGenerator<F, D>>("AssertLessThanGenerator")
+        .register_simple_generator::<MemoryOpSortGenerator<F, D>>("MemoryOpSortGenerator")
+        .register_simple_generator::<PermutationGenerator<F>>("PermutationGenerator")
+        .register_generator::<SwitchGenerator<F, D>>("SwitchGenerator");
+}
@@ -8,6 +8,7 @@ use plonky2::iop::generator::{GeneratedValues, SimpleGenerator};
 use plonky2::iop::target::{BoolTarget, Target};
 use plonky2::iop::witness::{PartitionWitness, Witness, WitnessWrite};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
 use plonky2_util::ceil_div_usize;
 
 use crate::gates::assert_le::AssertLessThanGate;
@@ -127,7 +128,7 @@ pub fn sort_memory_ops_circuit<F: RichField + Extendable<D>, const D: usize>(
 }
 
 #[derive(Debug)]
-struct MemoryOpSortGenerator<F: RichField + Extendable<D>, const D: usize> {
+pub(crate) struct MemoryOpSortGenerator<F: RichField + Extendable<D>, const D: usize> {
     input_ops: Vec<MemoryOpTarget>,
     output_ops: Vec<MemoryOpTarget>,
     _phantom: PhantomData<F>,
@@ -178,6 +179,44 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
             out_buffer.set_target(out_op.value, op.value);
         }
     }
+
+    fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        write_memory_ops(dst, &self.input_ops)?;
+        write_memory_ops(dst, &self.output_ops)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self {
+            input_ops: read_memory_ops(src)?,
+            output_ops: read_memory_ops(src)?,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+fn write_memory_ops(dst: &mut Vec<u8>, ops: &[MemoryOpTarget]) -> IoResult<()> {
+    dst.write_usize(ops.len())?;
+    for op in ops {
+        dst.write_bool_target(op.is_write)?;
+        dst.write_target(op.address)?;
+        dst.write_target(op.timestamp)?;
+        dst.write_target(op.value)?;
+    }
+    Ok(())
+}
+
+fn read_memory_ops(src: &mut Buffer) -> IoResult<Vec<MemoryOpTarget>> {
+    let len = src.read_usize()?;
+    (0..len)
+        .map(|_| {
+            Ok(MemoryOpTarget {
+                is_write: src.read_bool_target()?,
+                address: src.read_target()?,
+                timestamp: src.read_target()?,
+                value: src.read_target()?,
+            })
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -186,10 +225,13 @@ mod tests {
     use plonky2::field::types::{Field, PrimeField64, Sample};
     use plonky2::iop::witness::PartialWitness;
     use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::circuit_data::CircuitData;
     use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2::plonk::registry::CircuitRegistry;
     use rand::{thread_rng, Rng};
 
     use super::*;
+    use crate::serialization::register;
 
     fn test_sorting(size: usize, address_bits: usize, timestamp_bits: usize) -> Result<()> {
         const D: usize = 2;
@@ -274,4 +316,33 @@ mod tests {
 
         test_sorting(size, address_bits, timestamp_bits)
     }
+
+    #[test]
+    fn test_sorting_circuit_round_trip() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let mut rng = thread_rng();
+        let input_ops: Vec<MemoryOpTarget> = (0..8)
+            .map(|_| MemoryOpTarget {
+                is_write: builder.constant_bool(rng.gen()),
+                address: builder.constant(F::from_canonical_u64(rng.gen_range(0..1 << 10))),
+                timestamp: builder.constant(F::from_canonical_u64(rng.gen_range(0..1 << 10))),
+                value: builder.constant(F::rand()),
+            })
+            .collect();
+        sort_memory_ops_circuit(&mut builder, &input_ops, 10, 10);
+        let data = builder.build::<C>();
+
+        let mut registry = CircuitRegistry::new();
+        register(&mut registry);
+        let bytes = data.to_bytes(&registry)?;
+        let deserialized = CircuitData::<F, C, D>::from_bytes(bytes, &registry)?;
+
+        let proof = data.prove(PartialWitness::new())?;
+        assert_eq!(deserialized.prove(PartialWitness::new())?, proof);
+        deserialized.verify(proof)
+    }
 }